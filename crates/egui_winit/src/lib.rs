@@ -1122,7 +1122,8 @@ fn key_from_named_key(named_key: winit::keyboard::NamedKey) -> Option<egui::Key>
     })
 }
 
-fn key_from_key_code(key: winit::keyboard::KeyCode) -> Option<egui::Key> {
+/// Key at a physical position, independent of the keyboard layout
+pub fn key_from_key_code(key: winit::keyboard::KeyCode) -> Option<egui::Key> {
     use egui::Key;
     use winit::keyboard::KeyCode;
 
@@ -1,15 +1,30 @@
+use crate::inserts::VstRemote;
+use crate::ui::file::Export;
+use crate::ui::editor::{self, PluginEditor};
 use crate::window::{Render, Window};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use log::{error, warn};
 use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use voxea_alloc::perf;
 use voxea_alloc::perf::PerfTrace;
+use voxea_audio::engine::Engine;
+use voxea_audio::mixer::{InsertId, Mixer};
 use winit::application::ApplicationHandler;
 use winit::event::{StartCause, WindowEvent};
+use voxea_vst::view::PlugView;
+use voxea_vst::ViewRect;
+use winit::dpi::PhysicalSize;
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
-use winit::platform::windows::WindowExtWindows;
-use winit::window::{WindowAttributes, WindowId};
+use winit::window::{Window as WinitWindow, WindowAttributes, WindowId};
+use crate::platform;
+use crate::history::{Checkpoint, Document, History};
+use crate::preferences::Preferences;
+use crate::shortcuts::{KeyBinding, ShortcutContext, Shortcuts};
+use crate::project::Project;
+use crate::project_file::ProjectFile;
 use crate::renderer::RenderContext;
 
 #[derive(Default)]
@@ -17,18 +32,66 @@ pub struct App {
     pub(crate) windows: FxHashMap<WindowId, Option<Window>>,
     pub(crate) on_start_callback: Option<Box<dyn FnOnce(&mut App, &ActiveEventLoop)>>,
     pub(crate) wait_cancelled: bool,
-    pub(crate) render_context: Option<RenderContext>
+    pub(crate) render_context: Option<RenderContext>,
+    pub(crate) audio: Option<Engine>,
+    /// Mixer processed by the audio engine, only there while it runs
+    pub(crate) mixer: Option<Mixer>,
+    /// UI side of the VST3 inserts, to open their editors and report their failures
+    pub(crate) plugins: FxHashMap<InsertId, VstRemote>,
+    /// Open editor windows of inserts
+    pub(crate) editors: FxHashMap<InsertId, WindowId>,
+    /// Tracks and clips shown in the arrangement of the main window
+    pub(crate) project: Project,
+    /// File the project was opened from or last saved to, `None` until it is saved
+    pub(crate) project_path: Option<PathBuf>,
+    /// Project as it was saved, there are unsaved changes when it differs
+    pub(crate) saved: ProjectFile,
+    /// Undo and redo of edits to the project, mixer and plugins
+    pub(crate) history: History,
+    /// Project and mixer as of the last recorded edit, the UI records what changed since then
+    pub(crate) checkpoint: Checkpoint,
+    /// Settings of the user kept between runs
+    pub(crate) preferences: Preferences,
+    /// Actions and the keys bound to them
+    pub(crate) shortcuts: Shortcuts,
+    /// Export rendering in the background, its progress is shown in the main windows
+    pub(crate) export: Option<Export>,
+    /// Modal window of every window that is disabled by one, keyed by the owner
+    pub(crate) modals: FxHashMap<WindowId, WindowId>,
 }
 
 const WAIT_TIME: Duration = Duration::from_micros(16666);
 
 impl App {
     pub fn new() -> Self {
+        let preferences = Preferences::default_path()
+            .map(|path| Preferences::load(&path))
+            .transpose()
+            .unwrap_or_else(|e| {
+                error!("Could not load preferences: {e}");
+                None
+            })
+            .unwrap_or_default();
+        let shortcuts = Shortcuts::new(&preferences);
+
         Self {
             windows: FxHashMap::default(),
             on_start_callback: None,
             wait_cancelled: false,
-            render_context: None
+            render_context: None,
+            audio: None,
+            mixer: None,
+            plugins: FxHashMap::default(),
+            editors: FxHashMap::default(),
+            project: Project::default(),
+            project_path: None,
+            saved: ProjectFile::default(),
+            history: History::default(),
+            checkpoint: Checkpoint::default(),
+            preferences,
+            shortcuts,
+            export: None,
+            modals: FxHashMap::default(),
         }
     }
 
@@ -48,6 +111,223 @@ impl App {
         Ok(id)
     }
 
+    /// Number of open main windows, without the one currently handling an event
+    pub fn open_menus(&self) -> usize {
+        self.windows
+            .values()
+            .flatten()
+            .filter(|window| window.running)
+            .filter(|window| {
+                window
+                    .view
+                    .as_ref()
+                    .is_some_and(|view| view.shortcut_context() == ShortcutContext::Menu)
+            })
+            .count()
+    }
+
+    /// Whether the project or mixer changed since they were saved, opened or created
+    pub fn is_dirty(&self) -> bool {
+        ProjectFile::new(&self.project, self.mixer.as_ref()) != self.saved
+    }
+
+    /// Takes the current project as the saved one
+    pub fn mark_saved(&mut self) {
+        self.saved = ProjectFile::new(&self.project, self.mixer.as_ref());
+    }
+
+    /// Writes the preferences, including the key bindings, to the config directory
+    pub fn save_preferences(&mut self) {
+        self.shortcuts.save(&mut self.preferences);
+
+        let Some(path) = Preferences::default_path() else {
+            warn!("No config directory to save the preferences in");
+            return;
+        };
+
+        if let Err(e) = self.preferences.save(&path) {
+            error!("Could not save preferences to {}: {e}", path.display());
+        }
+    }
+
+    /// Reverts the last edit
+    pub fn undo(&mut self) {
+        let overrides = self.shortcuts.overrides().clone();
+        let mut document = Document {
+            project: &mut self.project,
+            mixer: self.mixer.as_mut(),
+            shortcuts: &mut self.shortcuts,
+        };
+
+        if let Err(e) = self.history.undo(&mut document) {
+            error!("Could not undo: {e}");
+        }
+
+        self.history_changed(&overrides);
+    }
+
+    /// Applies the last undone edit again
+    pub fn redo(&mut self) {
+        let overrides = self.shortcuts.overrides().clone();
+        let mut document = Document {
+            project: &mut self.project,
+            mixer: self.mixer.as_mut(),
+            shortcuts: &mut self.shortcuts,
+        };
+
+        if let Err(e) = self.history.redo(&mut document) {
+            error!("Could not redo: {e}");
+        }
+
+        self.history_changed(&overrides);
+    }
+
+    /// Takes what undoing or redoing changed as the state to record further edits from
+    fn history_changed(&mut self, overrides: &BTreeMap<String, Option<KeyBinding>>) {
+        self.checkpoint.reset(&self.project, self.mixer.as_ref());
+
+        if self.shortcuts.overrides() != overrides {
+            self.save_preferences();
+        }
+
+        self.request_redraw();
+    }
+
+    /// Redraws every window, e.g. after an edit that any of them may show
+    pub fn request_redraw(&self) {
+        for window in self.windows.values().flatten() {
+            window.window.request_redraw();
+        }
+    }
+
+    /// Opens a window owned by `owner` and disables the owner until the new window is closed
+    pub fn open_modal(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        owner: &WinitWindow,
+        window_attributes: WindowAttributes,
+        view: Option<Box<dyn Render + 'static>>,
+    ) -> Result<WindowId> {
+        let window_attributes = platform::with_owner(window_attributes, owner);
+        let id = self.open_window(event_loop, Some(window_attributes), view)?;
+
+        self.modals.insert(owner.id(), id);
+        platform::set_enabled(owner, false);
+
+        Ok(id)
+    }
+
+    /// Re-enables the owner of a modal window once it is closed
+    fn close_modal(&mut self, modal: WindowId) {
+        let Some(owner) = self
+            .modals
+            .iter()
+            .find(|(_, &m)| m == modal)
+            .map(|(&owner, _)| owner)
+        else {
+            return;
+        };
+
+        self.modals.remove(&owner);
+
+        if let Some(window) = self.get_window(&owner) {
+            platform::set_enabled(&window.window, true);
+            window.window.focus_window();
+        }
+    }
+
+    /// Opens a window and embeds the editor of a plugin into it
+    pub fn open_plugin_editor(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        title: &str,
+        mut view: PlugView,
+    ) -> Result<WindowId> {
+        perf::begin_perf!("app::open_plugin_editor");
+
+        let size = view.size().unwrap_or(ViewRect::new(800, 600));
+
+        let window_attributes = WindowAttributes::default()
+            .with_title(title)
+            .with_inner_size(PhysicalSize::new(size.width().max(1) as u32, size.height().max(1) as u32))
+            .with_resizable(view.can_resize());
+
+        let id = self.open_window(event_loop, Some(window_attributes), None)?;
+        let window = self.get_window(&id).unwrap();
+
+        let attached = editor::parent_window(&window.window).and_then(|parent| view.attach(parent));
+        if let Err(e) = attached {
+            self.windows.remove(&id);
+            return Err(e);
+        }
+
+        window.view = Some(Box::new(PluginEditor::new(view)));
+
+        Ok(id)
+    }
+
+    /// Opens the editor of a VST3 insert, or focuses it if it is already open
+    pub fn open_insert_editor(&mut self, event_loop: &ActiveEventLoop, id: InsertId) -> Result<()> {
+        if let Some(window) = self.editors.get(&id).copied().and_then(|window| self.get_window(&window)) {
+            window.window.focus_window();
+            return Ok(());
+        }
+
+        let Some(plugin) = self.plugins.get_mut(&id).and_then(VstRemote::handle_mut) else {
+            return Err(anyhow!("The insert has no editor"));
+        };
+
+        let title = plugin.class().name.clone();
+        let view = plugin.create_view()?;
+        let window = self.open_plugin_editor(event_loop, &title, view)?;
+        self.editors.insert(id, window);
+
+        Ok(())
+    }
+
+    /// Lets go of the handles of inserts the mixer freed, closes the editors of removed inserts and reports
+    /// inserts that failed on the audio thread
+    fn sync_plugins(&mut self) {
+        let slots = self
+            .mixer
+            .iter()
+            .flat_map(|mixer| mixer.strips())
+            .flat_map(|strip| strip.inserts())
+            .collect::<Vec<_>>();
+        let inserts = slots.iter().map(|slot| slot.id).collect::<Vec<_>>();
+
+        // Removed inserts keep their handle while undoing can still put them back
+        let detached = self
+            .mixer
+            .iter()
+            .flat_map(|mixer| mixer.detached_inserts())
+            .map(|slot| slot.id)
+            .collect::<Vec<_>>();
+        self.plugins
+            .retain(|id, _| inserts.contains(id) || detached.contains(id));
+
+        for slot in slots {
+            if self.plugins.get_mut(&slot.id).is_some_and(VstRemote::take_failure) {
+                warn!("Bypassing VST3 insert {}, it failed to process", slot.name);
+            }
+        }
+
+        let closed = self
+            .editors
+            .iter()
+            .filter(|(id, _)| !inserts.contains(id))
+            .map(|(&id, &window)| (id, window))
+            .collect::<Vec<_>>();
+
+        for (id, window) in closed {
+            self.editors.remove(&id);
+            self.windows.remove(&window);
+        }
+
+        let windows = &self.windows;
+        self.editors.retain(|_, window| windows.contains_key(window));
+    }
+
     pub fn get_window(&mut self, window_id: &WindowId) -> Option<&mut Window> {
         let window = self.windows.get_mut(window_id);
 
@@ -58,7 +338,7 @@ impl App {
         for window in self.windows.values_mut() {
             if window.is_some() && &window.as_ref().unwrap().window.id() != keep {
                 error!("{:?}", window.as_ref().unwrap().window);
-                window.as_mut().unwrap().window.set_enable(enable);
+                platform::set_enabled(&window.as_mut().unwrap().window, enable);
             }
         }
         // self.windows
@@ -103,6 +383,19 @@ impl ApplicationHandler for App {
     ) {
         perf::begin_perf!("app::window_event");
 
+        // Windows with an open modal only get input natively disabled on Windows, so it is filtered here
+        if let Some(&modal) = self.modals.get(&window_id) {
+            if let WindowEvent::Focused(true) = event {
+                if let Some(modal) = self.get_window(&modal) {
+                    modal.window.focus_window();
+                }
+            }
+
+            if is_user_input(&event) {
+                return;
+            }
+        }
+
         // Moves ownership of the window to the current scope to avoid double borrowing
         let Some(mut window) = self
             .windows
@@ -132,6 +425,7 @@ impl ApplicationHandler for App {
         } else {
             // Drops the reference to the window which closes it
             self.windows.remove(&window_id);
+            self.close_modal(window_id);
         }
 
         // Exits the event loop when all windows have been closed
@@ -141,6 +435,22 @@ impl ApplicationHandler for App {
     }
 
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Merged here so the audio thread never has to
+        perf::collect();
+
+        if let Some(audio) = self.audio.as_mut() {
+            audio.stats().publish();
+        }
+
+        if let Some(mixer) = self.mixer.as_mut() {
+            mixer.update();
+
+            if let Some(audio) = self.audio.as_ref() {
+                mixer.publish_load(audio.sample_rate());
+            }
+        }
+        self.sync_plugins();
+
         if !self.wait_cancelled {
             for window in self
                 .windows
@@ -157,3 +467,26 @@ impl ApplicationHandler for App {
         }
     }
 }
+
+/// Events caused directly by the user, which a disabled window should ignore
+fn is_user_input(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::CloseRequested
+            | WindowEvent::DroppedFile(_)
+            | WindowEvent::HoveredFile(_)
+            | WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Ime(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorEntered { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::PinchGesture { .. }
+            | WindowEvent::PanGesture { .. }
+            | WindowEvent::DoubleTapGesture { .. }
+            | WindowEvent::RotationGesture { .. }
+            | WindowEvent::TouchpadPressure { .. }
+            | WindowEvent::Touch(_)
+    )
+}
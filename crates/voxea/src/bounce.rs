@@ -0,0 +1,121 @@
+//! Renders a project into an audio file through an offline copy of the mixer
+
+use crate::project::{Clip, Project};
+use anyhow::{anyhow, Result};
+use std::ops::Range;
+use std::path::Path;
+use voxea_audio::file::AudioFile;
+use voxea_audio::mixer::{self, InsertSlot, Mixer, MixerNode, Processor, StripKind, StripState};
+
+/// Frames rendered per call into the mixer
+const BLOCK_FRAMES: usize = 1024;
+const CHANNELS: usize = 2;
+
+/// Plays the clips of a track as the source of its strip
+struct ClipPlayer {
+    clips: Vec<Clip>,
+    /// Timeline frame of the next block
+    position: u64,
+}
+
+impl Processor for ClipPlayer {
+    fn process(&mut self, buffer: &mut [f32], channels: usize) {
+        let frames = (buffer.len() / channels) as u64;
+        let block = self.position..self.position + frames;
+
+        for clip in &self.clips {
+            let source = &clip.source;
+
+            for frame in clip.start.max(block.start)..clip.end().min(block.end) {
+                let from = (frame - clip.start + clip.offset) as usize * source.channels;
+                let Some(input) = source.samples.get(from..from + source.channels) else {
+                    break;
+                };
+
+                let to = (frame - block.start) as usize * channels;
+                for (channel, sample) in buffer[to..to + channels].iter_mut().enumerate() {
+                    // Mono sources play on every channel
+                    *sample += input[channel.min(input.len() - 1)];
+                }
+            }
+        }
+
+        self.position = block.end;
+    }
+}
+
+/// Part of the timeline a bounce renders, the loop region if looping is on and otherwise up to the last clip
+fn range(project: &Project) -> Range<u64> {
+    match &project.loop_region {
+        Some(region) if project.loop_enabled => region.clone(),
+        _ => 0..project.end(),
+    }
+}
+
+/// An offline copy of the mixer playing the project, set up on the UI thread and rendered on any other
+pub struct Bounce {
+    /// Kept until the end, the node sends what it lets go of to it
+    _mixer: Mixer,
+    node: MixerNode,
+    range: Range<u64>,
+    sample_rate: u32,
+}
+
+impl Bounce {
+    /// Plays the clips of every track through the strips of the mixer. Their inserts get new processors from
+    /// `instantiate`, since the ones of the running mixer belong to the audio thread.
+    pub fn new(
+        project: &Project,
+        strips: &[StripState],
+        instantiate: impl FnMut(&InsertSlot) -> Result<Box<dyn Processor>>,
+    ) -> Result<Self> {
+        let range = range(project);
+        if range.is_empty() {
+            return Err(anyhow!("There is nothing to bounce"));
+        }
+
+        let (mut mixer, node) = mixer::offline(strips, instantiate)?;
+
+        for track in &project.tracks {
+            // Tracks added while no audio engine was running have no strip yet
+            let strip = match track.strip.filter(|&strip| mixer.strip(strip).is_some()) {
+                Some(strip) => strip,
+                None => mixer.add_strip(&track.name, StripKind::Track)?,
+            };
+
+            let player = ClipPlayer {
+                clips: track.clips.clone(),
+                position: range.start,
+            };
+            mixer.set_source(strip, Some(Box::new(player)))?;
+        }
+
+        Ok(Self {
+            _mixer: mixer,
+            node,
+            range,
+            sample_rate: project.sample_rate,
+        })
+    }
+
+    /// Writes a stereo file at the project sample rate, `progress` gets the share rendered after every block
+    pub fn render(mut self, path: &Path, mut progress: impl FnMut(f32)) -> Result<()> {
+        let frames = (self.range.end - self.range.start) as usize;
+        let mut samples = vec![0.0; frames * CHANNELS];
+
+        let blocks = frames.div_ceil(BLOCK_FRAMES);
+        for (index, block) in samples.chunks_mut(BLOCK_FRAMES * CHANNELS).enumerate() {
+            self.node.process(block, CHANNELS);
+            progress((index + 1) as f32 / blocks as f32);
+        }
+
+        let file = AudioFile {
+            path: path.to_path_buf(),
+            sample_rate: self.sample_rate,
+            channels: CHANNELS,
+            samples,
+        };
+
+        file.save(path)
+    }
+}
@@ -0,0 +1,818 @@
+//! Undo and redo of edits.
+//!
+//! Every edit is a [`Command`] that can be applied to and reverted from a [`Document`]. Commands recorded in
+//! quick succession merge when they belong to the same gesture, e.g. the frames of a fader drag, and
+//! transactions group several commands into one step. Steps are dropped oldest first once they use more
+//! memory than the budget.
+
+use crate::plugin;
+use crate::project::{Project, Track};
+use crate::shortcuts::{KeyBinding, Shortcuts};
+use anyhow::{anyhow, Result};
+use std::any::Any;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+use voxea_audio::mixer::{InsertSlot, Mixer, StripId, StripKind, StripState};
+
+/// Memory the undo steps may use before the oldest are dropped
+pub const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Commands recorded within this time of the first command of a step can merge into it, so a gesture that goes on
+/// longer is split into several steps
+const MERGE_WINDOW: Duration = Duration::from_millis(500);
+
+/// What commands edit
+pub struct Document<'a> {
+    pub project: &'a mut Project,
+    pub mixer: Option<&'a mut Mixer>,
+    pub shortcuts: &'a mut Shortcuts,
+}
+
+impl Document<'_> {
+    fn mixer(&mut self) -> Result<&mut Mixer> {
+        self.mixer.as_deref_mut().ok_or_else(|| anyhow!("No audio stream is running"))
+    }
+}
+
+/// A reversible edit
+pub trait Command: Any {
+    /// Shown in the Edit menu, e.g. "Move Clip"
+    fn name(&self) -> &str;
+
+    fn apply(&mut self, document: &mut Document) -> Result<()>;
+
+    fn revert(&mut self, document: &mut Document) -> Result<()>;
+
+    /// Takes over a command that follows this one in the same gesture, keeping the state from before this one
+    fn merge(&mut self, _next: &dyn Command) -> bool {
+        false
+    }
+
+    /// Rough number of bytes the command keeps alive
+    fn size(&self) -> usize;
+}
+
+/// Commands undone and redone together
+struct Step {
+    name: String,
+    commands: Vec<Box<dyn Command>>,
+    time: Instant,
+}
+
+impl Step {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            commands: Vec::new(),
+            time: Instant::now(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.commands.iter().map(|command| command.size()).sum()
+    }
+
+    fn apply(&mut self, document: &mut Document) -> Result<()> {
+        self.commands.iter_mut().try_for_each(|command| command.apply(document))
+    }
+
+    fn revert(&mut self, document: &mut Document) -> Result<()> {
+        self.commands.iter_mut().rev().try_for_each(|command| command.revert(document))
+    }
+}
+
+pub struct History {
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+    /// Step being built and how deeply transactions are nested
+    transaction: Option<(Step, usize)>,
+    budget: usize,
+    used: usize,
+    /// Set when a gesture ends, so the next command starts a new step
+    sealed: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_budget(DEFAULT_BUDGET)
+    }
+}
+
+impl History {
+    pub fn with_budget(budget: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            transaction: None,
+            budget,
+            used: 0,
+            sealed: false,
+        }
+    }
+
+    /// Applies a command and records it
+    pub fn execute(&mut self, document: &mut Document, mut command: Box<dyn Command>) -> Result<()> {
+        command.apply(document)?;
+        self.record(command);
+        Ok(())
+    }
+
+    /// Records a command whose change was already made, e.g. by a widget editing the value directly
+    pub fn record(&mut self, command: Box<dyn Command>) {
+        self.clear_redo();
+
+        if let Some((step, _)) = &mut self.transaction {
+            step.commands.push(command);
+            return;
+        }
+
+        if !self.sealed {
+            if let Some(last) = self.undo.back_mut() {
+                let mergeable = last.commands.len() == 1 && last.time.elapsed() < MERGE_WINDOW;
+
+                if mergeable {
+                    let size = last.size();
+                    if last.commands[0].merge(command.as_ref()) {
+                        self.used = self.used - size + last.size();
+                        self.evict();
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.sealed = false;
+
+        let mut step = Step::new(command.name());
+        step.commands.push(command);
+        self.push(step);
+    }
+
+    /// Ends the current gesture, the next command is not merged into the last one
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Starts grouping commands into one step named `name`, transactions can be nested
+    pub fn begin(&mut self, name: &str) {
+        match &mut self.transaction {
+            Some((_, depth)) => *depth += 1,
+            None => self.transaction = Some((Step::new(name), 1)),
+        }
+    }
+
+    /// Ends a transaction, the outermost one records its commands as one step
+    pub fn commit(&mut self) {
+        let Some((_, depth)) = &mut self.transaction else {
+            return;
+        };
+
+        *depth -= 1;
+        if *depth > 0 {
+            return;
+        }
+
+        if let Some((step, _)) = self.transaction.take() {
+            if !step.commands.is_empty() {
+                self.sealed = true;
+                self.push(step);
+            }
+        }
+    }
+
+    /// Reverts the commands of the open transaction and ends it
+    pub fn cancel(&mut self, document: &mut Document) -> Result<()> {
+        match self.transaction.take() {
+            Some((mut step, _)) => step.revert(document),
+            None => Ok(()),
+        }
+    }
+
+    pub fn undo_name(&self) -> Option<&str> {
+        self.undo.back().map(|step| step.name.as_str())
+    }
+
+    pub fn redo_name(&self) -> Option<&str> {
+        self.redo.last().map(|step| step.name.as_str())
+    }
+
+    /// Reverts the last step. A step that fails halfway is dropped, it can not be redone reliably.
+    pub fn undo(&mut self, document: &mut Document) -> Result<()> {
+        self.ensure_idle()?;
+
+        let Some(mut step) = self.undo.pop_back() else {
+            return Ok(());
+        };
+        self.used -= step.size();
+
+        if let Err(e) = step.revert(document) {
+            self.clear_redo();
+            return Err(e);
+        }
+
+        self.used += step.size();
+        self.redo.push(step);
+        self.sealed = true;
+        Ok(())
+    }
+
+    /// Applies the last undone step again
+    pub fn redo(&mut self, document: &mut Document) -> Result<()> {
+        self.ensure_idle()?;
+
+        let Some(mut step) = self.redo.pop() else {
+            return Ok(());
+        };
+        self.used -= step.size();
+
+        if let Err(e) = step.apply(document) {
+            self.clear_redo();
+            return Err(e);
+        }
+
+        step.time = Instant::now();
+        self.sealed = true;
+        self.push(step);
+        Ok(())
+    }
+
+    /// Forgets every step, e.g. when another project is opened
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.transaction = None;
+        self.used = 0;
+    }
+
+    fn ensure_idle(&self) -> Result<()> {
+        match &self.transaction {
+            Some((step, _)) => Err(anyhow!("{} is still in progress", step.name)),
+            None => Ok(()),
+        }
+    }
+
+    fn clear_redo(&mut self) {
+        self.used -= self.redo.iter().map(Step::size).sum::<usize>();
+        self.redo.clear();
+    }
+
+    fn push(&mut self, step: Step) {
+        self.used += step.size();
+        self.undo.push_back(step);
+        self.evict();
+    }
+
+    /// Drops the oldest steps until the rest fit into the budget
+    fn evict(&mut self) {
+        // The latest step is kept even when it alone is over the budget
+        while self.used > self.budget && self.undo.len() > 1 {
+            if let Some(step) = self.undo.pop_front() {
+                self.used -= step.size();
+            }
+        }
+    }
+}
+
+/// Snapshot of the project before and after an edit. Clips share their audio, so snapshots stay small.
+pub struct ProjectEdit {
+    name: String,
+    before: Project,
+    after: Project,
+}
+
+impl ProjectEdit {
+    pub fn new(name: &str, before: Project, after: Project) -> Self {
+        Self {
+            name: name.to_string(),
+            before,
+            after,
+        }
+    }
+
+    /// Replaces the project, the playhead stays where it is
+    fn restore(document: &mut Document, project: &Project) {
+        let playhead = document.project.playhead;
+        *document.project = project.clone();
+        document.project.playhead = playhead;
+    }
+}
+
+impl Command for ProjectEdit {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, document: &mut Document) -> Result<()> {
+        Self::restore(document, &self.after);
+        Ok(())
+    }
+
+    fn revert(&mut self, document: &mut Document) -> Result<()> {
+        Self::restore(document, &self.before);
+        Ok(())
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        let Some(next) = (next as &dyn Any).downcast_ref::<Self>() else {
+            return false;
+        };
+
+        if next.name != self.name {
+            return false;
+        }
+
+        self.after = next.after.clone();
+        true
+    }
+
+    fn size(&self) -> usize {
+        project_size(&self.before) + project_size(&self.after)
+    }
+}
+
+fn project_size(project: &Project) -> usize {
+    let tracks = project.tracks.iter().map(|track| {
+        let clips = track
+            .clips
+            .iter()
+            .map(|clip| size_of_val(clip) + clip.name.len())
+            .sum::<usize>();
+
+        size_of::<Track>() + track.name.len() + clips
+    });
+
+    size_of::<Project>() + tracks.sum::<usize>()
+}
+
+/// Snapshot of the mixer strips before and after an edit, named after what changed
+pub struct MixerEdit {
+    name: String,
+    /// Strip the edit changed, edits only merge with edits of the same strip
+    strip: Option<StripId>,
+    before: Vec<StripState>,
+    after: Vec<StripState>,
+}
+
+impl MixerEdit {
+    /// Describes the change between two [`Mixer::state`]s, `None` if there is none
+    pub fn new(before: Vec<StripState>, after: Vec<StripState>) -> Option<Self> {
+        let added = after.iter().find(|state| !before.iter().any(|other| other.id == state.id));
+        let removed = before.iter().find(|state| !after.iter().any(|other| other.id == state.id));
+
+        let (name, strip) = if let Some(state) = added {
+            (format!("Add {}", kind_name(state.kind)), Some(state.id))
+        } else if let Some(state) = removed {
+            (format!("Remove {}", kind_name(state.kind)), Some(state.id))
+        } else {
+            let (old, new) = before.iter().zip(&after).find(|(old, new)| old != new)?;
+
+            let name = if old.gain != new.gain {
+                "Change Volume"
+            } else if old.pan != new.pan {
+                "Change Pan"
+            } else if old.mute != new.mute {
+                "Mute"
+            } else if old.solo != new.solo {
+                "Solo"
+            } else if old.arm != new.arm {
+                "Arm"
+            } else if old.output != new.output {
+                "Change Output"
+            } else if old.sends != new.sends {
+                "Change Sends"
+            } else if old.inserts.len() < new.inserts.len() {
+                "Add Insert"
+            } else if old.inserts.len() > new.inserts.len() {
+                "Remove Insert"
+            } else if old.inserts.iter().zip(&new.inserts).any(|(old, new)| old.bypassed != new.bypassed) {
+                "Bypass Insert"
+            } else if old.inserts != new.inserts {
+                "Change Inserts"
+            } else {
+                "Rename Strip"
+            };
+
+            (name.to_string(), Some(new.id))
+        };
+
+        Some(Self {
+            name,
+            strip,
+            before,
+            after,
+        })
+    }
+}
+
+fn kind_name(kind: StripKind) -> &'static str {
+    match kind {
+        StripKind::Track => "Track",
+        StripKind::Bus => "Bus",
+        StripKind::Master => "Master",
+    }
+}
+
+impl Command for MixerEdit {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, document: &mut Document) -> Result<()> {
+        document.mixer()?.restore(&self.after)
+    }
+
+    fn revert(&mut self, document: &mut Document) -> Result<()> {
+        document.mixer()?.restore(&self.before)
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        let Some(next) = (next as &dyn Any).downcast_ref::<Self>() else {
+            return false;
+        };
+
+        if next.name != self.name || next.strip != self.strip {
+            return false;
+        }
+
+        self.after = next.after.clone();
+        true
+    }
+
+    fn size(&self) -> usize {
+        let states = self.before.iter().chain(&self.after);
+        states
+            .map(|state| {
+                let inserts = state
+                    .inserts
+                    .iter()
+                    .map(|slot| size_of::<InsertSlot>() + slot.name.len())
+                    .sum::<usize>();
+
+                size_of::<StripState>() + state.name.len() + size_of_val(state.sends.as_slice()) + inserts
+            })
+            .sum()
+    }
+}
+
+/// Change of a plugin parameter, sent to the plugin when undone or redone
+pub struct ParameterEdit {
+    name: String,
+    plugin: String,
+    parameter: u32,
+    before: f32,
+    after: f32,
+}
+
+impl ParameterEdit {
+    pub fn new(plugin: &str, parameter: u32, parameter_name: &str, before: f32, after: f32) -> Self {
+        Self {
+            name: format!("Change {parameter_name}"),
+            plugin: plugin.to_string(),
+            parameter,
+            before,
+            after,
+        }
+    }
+
+    fn set(&self, value: f32) -> Result<()> {
+        plugin::with_parameters(&self.plugin, |parameters| parameters.set(self.parameter, value))
+            .ok_or_else(|| anyhow!("{} is not loaded", self.plugin))?;
+
+        plugin::flush_parameter_changes(&self.plugin)
+    }
+}
+
+impl Command for ParameterEdit {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, _document: &mut Document) -> Result<()> {
+        self.set(self.after)
+    }
+
+    fn revert(&mut self, _document: &mut Document) -> Result<()> {
+        self.set(self.before)
+    }
+
+    fn merge(&mut self, next: &dyn Command) -> bool {
+        let Some(next) = (next as &dyn Any).downcast_ref::<Self>() else {
+            return false;
+        };
+
+        if next.plugin != self.plugin || next.parameter != self.parameter {
+            return false;
+        }
+
+        self.after = next.after;
+        true
+    }
+
+    fn size(&self) -> usize {
+        size_of::<Self>() + self.name.len() + self.plugin.len()
+    }
+}
+
+/// Key bindings before and after a change in the settings
+pub struct SettingsEdit {
+    name: String,
+    before: BTreeMap<String, Option<KeyBinding>>,
+    after: BTreeMap<String, Option<KeyBinding>>,
+}
+
+impl SettingsEdit {
+    /// Takes [`Shortcuts::overrides`] from before and after the change
+    pub fn new(
+        name: &str,
+        before: BTreeMap<String, Option<KeyBinding>>,
+        after: BTreeMap<String, Option<KeyBinding>>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            before,
+            after,
+        }
+    }
+}
+
+impl Command for SettingsEdit {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, document: &mut Document) -> Result<()> {
+        document.shortcuts.set_overrides(self.after.clone());
+        Ok(())
+    }
+
+    fn revert(&mut self, document: &mut Document) -> Result<()> {
+        document.shortcuts.set_overrides(self.before.clone());
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        let bindings = self.before.keys().chain(self.after.keys());
+        size_of::<Self>() + self.name.len() + bindings.map(|id| id.len() + size_of::<Option<KeyBinding>>()).sum::<usize>()
+    }
+}
+
+/// Project and mixer as of the last recorded edit, see [`Checkpoint::record`]
+#[derive(Default)]
+pub struct Checkpoint {
+    project: Project,
+    /// [`Mixer::revision`] and the state it had then
+    mixer: Option<(u64, Vec<StripState>)>,
+}
+
+impl Checkpoint {
+    pub fn new(project: &Project, mixer: Option<&Mixer>) -> Self {
+        Self {
+            project: project.clone(),
+            mixer: mixer.map(|mixer| (mixer.revision(), mixer.state())),
+        }
+    }
+
+    /// Takes the current project and mixer without recording, e.g. after undoing or opening a project
+    pub fn reset(&mut self, project: &Project, mixer: Option<&Mixer>) {
+        *self = Self::new(project, mixer);
+    }
+
+    /// Records what changed since the last call as one step. Project edits are called `name`, mixer edits are
+    /// named after what changed. Moving the playhead is not an edit. Only copies the project and mixer when they
+    /// changed, so it can run every frame.
+    pub fn record(&mut self, history: &mut History, name: Option<&str>, project: &Project, mixer: Option<&Mixer>) {
+        self.project.playhead = project.playhead;
+
+        let project_edit = (self.project != *project).then(|| {
+            let before = std::mem::replace(&mut self.project, project.clone());
+            ProjectEdit::new(name.unwrap_or("Edit Project"), before, project.clone())
+        });
+
+        let mixer_edit = match (&mut self.mixer, mixer) {
+            (Some((revision, before)), Some(mixer)) if *revision != mixer.revision() => {
+                let after = mixer.state();
+                *revision = mixer.revision();
+                MixerEdit::new(std::mem::replace(before, after.clone()), after)
+            }
+            (Some(_), Some(_)) => None,
+            // The audio stream started or stopped, which is not an edit
+            (checkpoint, mixer) => {
+                *checkpoint = mixer.map(|mixer| (mixer.revision(), mixer.state()));
+                None
+            }
+        };
+
+        match (project_edit, mixer_edit) {
+            (Some(project_edit), Some(mixer_edit)) => {
+                history.begin(&project_edit.name);
+                history.record(Box::new(project_edit));
+                history.record(Box::new(mixer_edit));
+                history.commit();
+            }
+            (Some(project_edit), None) => history.record(Box::new(project_edit)),
+            (None, Some(mixer_edit)) => history.record(Box::new(mixer_edit)),
+            (None, None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, Document, History, MERGE_WINDOW};
+    use crate::project::Project;
+    use crate::shortcuts::Shortcuts;
+    use anyhow::Result;
+    use std::any::Any;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Changes the tempo, the changes of one gesture merge
+    struct SetTempo {
+        before: f64,
+        after: f64,
+        size: usize,
+    }
+
+    impl Command for SetTempo {
+        fn name(&self) -> &str {
+            "Change Tempo"
+        }
+
+        fn apply(&mut self, document: &mut Document) -> Result<()> {
+            document.project.tempo = self.after;
+            Ok(())
+        }
+
+        fn revert(&mut self, document: &mut Document) -> Result<()> {
+            document.project.tempo = self.before;
+            Ok(())
+        }
+
+        fn merge(&mut self, next: &dyn Command) -> bool {
+            let Some(next) = (next as &dyn Any).downcast_ref::<Self>() else {
+                return false;
+            };
+
+            self.after = next.after;
+            self.size = self.size.max(next.size);
+            true
+        }
+
+        fn size(&self) -> usize {
+            self.size
+        }
+    }
+
+    fn set_tempo(history: &mut History, document: &mut Document, tempo: f64, size: usize) {
+        let command = SetTempo {
+            before: document.project.tempo,
+            after: tempo,
+            size,
+        };
+        history.execute(document, Box::new(command)).unwrap();
+    }
+
+    /// Runs `test` with a new project at 120 bpm
+    fn with_document(test: impl FnOnce(&mut Document)) {
+        let mut project = Project::new(48000);
+        let mut shortcuts = Shortcuts::default();
+
+        test(&mut Document {
+            project: &mut project,
+            mixer: None,
+            shortcuts: &mut shortcuts,
+        });
+    }
+
+    #[test]
+    fn merge_within_window() {
+        with_document(|document| {
+            let mut history = History::default();
+            set_tempo(&mut history, document, 130.0, 1);
+            set_tempo(&mut history, document, 140.0, 1);
+
+            assert_eq!(history.undo.len(), 1);
+            history.undo(document).unwrap();
+            assert_eq!(document.project.tempo, 120.0);
+            assert_eq!(history.undo_name(), None);
+
+            history.redo(document).unwrap();
+            assert_eq!(document.project.tempo, 140.0);
+        });
+    }
+
+    #[test]
+    fn no_merge_after_seal_or_window() {
+        with_document(|document| {
+            let mut history = History::default();
+            set_tempo(&mut history, document, 130.0, 1);
+            history.seal();
+            set_tempo(&mut history, document, 140.0, 1);
+            thread::sleep(MERGE_WINDOW + Duration::from_millis(50));
+            set_tempo(&mut history, document, 150.0, 1);
+
+            assert_eq!(history.undo.len(), 3);
+            history.undo(document).unwrap();
+            assert_eq!(document.project.tempo, 140.0);
+            history.undo(document).unwrap();
+            assert_eq!(document.project.tempo, 130.0);
+        });
+    }
+
+    #[test]
+    fn window_starts_at_first_command() {
+        with_document(|document| {
+            let mut history = History::default();
+            set_tempo(&mut history, document, 130.0, 1);
+            thread::sleep(MERGE_WINDOW / 2 + Duration::from_millis(50));
+            set_tempo(&mut history, document, 140.0, 1);
+            thread::sleep(MERGE_WINDOW / 2 + Duration::from_millis(50));
+            set_tempo(&mut history, document, 150.0, 1);
+
+            assert_eq!(history.undo.len(), 2);
+            history.undo(document).unwrap();
+            assert_eq!(document.project.tempo, 140.0);
+        });
+    }
+
+    #[test]
+    fn evict_after_merge() {
+        with_document(|document| {
+            let mut history = History::with_budget(250);
+            set_tempo(&mut history, document, 130.0, 100);
+            history.seal();
+            set_tempo(&mut history, document, 140.0, 100);
+            set_tempo(&mut history, document, 150.0, 200);
+
+            assert_eq!(history.undo.len(), 1);
+            assert_eq!(history.used, 200);
+        });
+    }
+
+    #[test]
+    fn evict_over_budget() {
+        with_document(|document| {
+            let mut history = History::with_budget(250);
+            for tempo in [130.0, 140.0, 150.0] {
+                set_tempo(&mut history, document, tempo, 100);
+                history.seal();
+            }
+
+            // The oldest step is dropped, undoing stops at its result
+            assert_eq!(history.undo.len(), 2);
+            assert_eq!(history.used, 200);
+            history.undo(document).unwrap();
+            history.undo(document).unwrap();
+            assert_eq!(document.project.tempo, 130.0);
+            assert_eq!(history.undo_name(), None);
+
+            // The latest step stays even when it alone is over the budget
+            set_tempo(&mut history, document, 160.0, 1000);
+            assert_eq!(history.undo.len(), 1);
+            assert_eq!(history.used, 1000);
+        });
+    }
+
+    #[test]
+    fn nested_transactions() {
+        with_document(|document| {
+            let mut history = History::default();
+            history.begin("Change Tempo Twice");
+            set_tempo(&mut history, document, 130.0, 1);
+            history.begin("Inner");
+            set_tempo(&mut history, document, 140.0, 1);
+            history.commit();
+
+            // Nothing is recorded until the outermost transaction ends
+            assert_eq!(history.undo_name(), None);
+            assert!(history.undo(document).is_err());
+
+            history.commit();
+            assert_eq!(history.undo.len(), 1);
+            assert_eq!(history.undo_name(), Some("Change Tempo Twice"));
+
+            history.undo(document).unwrap();
+            assert_eq!(document.project.tempo, 120.0);
+            history.redo(document).unwrap();
+            assert_eq!(document.project.tempo, 140.0);
+        });
+    }
+
+    #[test]
+    fn cancel() {
+        with_document(|document| {
+            let mut history = History::default();
+            set_tempo(&mut history, document, 130.0, 1);
+            history.seal();
+
+            history.begin("Change Tempo Twice");
+            set_tempo(&mut history, document, 140.0, 1);
+            set_tempo(&mut history, document, 150.0, 1);
+            history.cancel(document).unwrap();
+
+            assert_eq!(document.project.tempo, 130.0);
+            assert_eq!(history.undo.len(), 1);
+            assert_eq!(history.undo_name(), Some("Change Tempo"));
+        });
+    }
+}
@@ -0,0 +1,249 @@
+//! Processors for the insert slots of the mixer
+
+use anyhow::{anyhow, Result};
+use log::warn;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use voxea_audio::mixer::{InsertId, InsertKind, InsertSlot, Processor};
+use voxea_vst::database::PluginDatabase;
+use voxea_vst::module::ClassInfo;
+use voxea_vst::node::{Deadline, PluginNode};
+use voxea_vst::plugin::PluginHandle;
+use voxea_vst::preset::Preset;
+use voxea_vst::scanner::{self, Scanner};
+
+/// Frames the plugin is set up for, larger buffers are processed in blocks of this size
+const MAX_BLOCK: usize = 8192;
+
+/// Category of VST3 classes that process audio
+const AUDIO_EFFECT_CLASS: &str = "Audio Module Class";
+
+/// How long bridged plugins may take for a block when rendering offline. Helpers that take longer are considered
+/// hung and bypassed anyway.
+const OFFLINE_DEADLINE: Duration = Duration::from_secs(1);
+
+/// A VST3 plugin on a strip, the first two channels are split into one buffer each for it
+pub struct VstInsert {
+    node: PluginNode,
+    inputs: [Vec<f32>; 2],
+    outputs: [Vec<f32>; 2],
+    failed: Arc<AtomicBool>,
+}
+
+impl VstInsert {
+    /// Instantiates the first audio effect of a bundle, hosted the way the plugin database says
+    pub fn load(bundle: &Path, sample_rate: f64) -> Result<(InsertSlot, Self)> {
+        let info = Scanner::new().scan(bundle)?;
+        let class = info
+            .classes
+            .into_iter()
+            .find(|class| class.category == AUDIO_EFFECT_CLASS)
+            .ok_or_else(|| anyhow!("{} has no audio effect", bundle.display()))?;
+
+        Self::open(bundle, class, sample_rate)
+    }
+
+    /// Instantiates the class `cid` of a bundle, e.g. the one a saved insert was
+    pub fn load_class(bundle: &Path, cid: &str, sample_rate: f64) -> Result<(InsertSlot, Self)> {
+        let info = Scanner::new().scan(bundle)?;
+        let class = info
+            .classes
+            .into_iter()
+            .find(|class| class.cid.eq_ignore_ascii_case(cid))
+            .ok_or_else(|| anyhow!("{} has no class {cid}", bundle.display()))?;
+
+        Self::open(bundle, class, sample_rate)
+    }
+
+    fn open(bundle: &Path, class: ClassInfo, sample_rate: f64) -> Result<(InsertSlot, Self)> {
+        let mode = PluginDatabase::default_path()
+            .and_then(|path| PluginDatabase::load(&path).ok())
+            .map(|database| database.hosting_mode(bundle))
+            .unwrap_or_default();
+
+        let node = PluginNode::new(
+            mode,
+            &scanner::default_helper(),
+            bundle,
+            &class.cid,
+            sample_rate,
+            MAX_BLOCK,
+        )?;
+
+        let slot = InsertSlot {
+            id: InsertId::default(),
+            name: class.name,
+            kind: InsertKind::Vst3 {
+                path: bundle.to_path_buf(),
+                cid: class.cid,
+            },
+            bypassed: false,
+        };
+
+        let insert = Self {
+            node,
+            inputs: [vec![0.0; MAX_BLOCK], vec![0.0; MAX_BLOCK]],
+            outputs: [vec![0.0; MAX_BLOCK], vec![0.0; MAX_BLOCK]],
+            failed: Arc::new(AtomicBool::new(false)),
+        };
+
+        Ok((slot, insert))
+    }
+
+    /// Lets bridged plugins take up to [`OFFLINE_DEADLINE`] for a block when not real time, e.g. for a bounce
+    pub fn set_realtime(&mut self, realtime: bool) {
+        let deadline = if realtime { Deadline::Share } else { Deadline::Fixed(OFFLINE_DEADLINE) };
+        self.node.set_deadline(deadline);
+    }
+
+    /// Restores a state saved with [`VstRemote::preset`], before the insert is handed to a mixer
+    pub fn apply_preset(&mut self, preset: &Preset) -> Result<()> {
+        let mut handle = self
+            .node
+            .handle()
+            .ok_or_else(|| anyhow!("The state of bridged plugins can not be restored"))?;
+
+        preset.apply(&mut handle)
+    }
+
+    /// What the UI thread keeps of the insert once it is handed to the mixer
+    pub fn remote(&self) -> VstRemote {
+        VstRemote {
+            handle: self.node.handle(),
+            failed: self.failed.clone(),
+            reported: false,
+        }
+    }
+}
+
+/// UI side of a [`VstInsert`], to open its editor and to report failures the audio thread can not log
+pub struct VstRemote {
+    handle: Option<PluginHandle>,
+    failed: Arc<AtomicBool>,
+    reported: bool,
+}
+
+impl VstRemote {
+    /// Reference to the plugin, `None` for bridged plugins
+    pub fn handle_mut(&mut self) -> Option<&mut PluginHandle> {
+        self.handle.as_mut()
+    }
+
+    pub fn has_editor(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Current state of the plugin, to save it or to set up another instance the same way
+    pub fn preset(&mut self) -> Result<Preset> {
+        let handle = self
+            .handle
+            .as_mut()
+            .ok_or_else(|| anyhow!("The state of bridged plugins can not be saved"))?;
+
+        Preset::from_plugin(handle)
+    }
+
+    /// Whether the plugin failed since the last call, it stays bypassed afterwards
+    pub fn take_failure(&mut self) -> bool {
+        if self.reported || !self.failed.load(Ordering::Acquire) {
+            return false;
+        }
+
+        self.reported = true;
+        true
+    }
+}
+
+impl Processor for VstInsert {
+    fn process(&mut self, buffer: &mut [f32], channels: usize) {
+        // A failed plugin is bypassed instead of silencing the strip
+        if self.failed.load(Ordering::Relaxed) {
+            return;
+        }
+        if self.node.is_failed() {
+            self.failed.store(true, Ordering::Release);
+            return;
+        }
+
+        for block in buffer.chunks_mut(MAX_BLOCK * channels) {
+            let frames = block.len() / channels;
+
+            for (frame, samples) in block.chunks_exact(channels).enumerate() {
+                self.inputs[0][frame] = samples[0];
+                self.inputs[1][frame] = samples[1.min(channels - 1)];
+            }
+
+            let [left, right] = &self.inputs;
+            let [out_left, out_right] = &mut self.outputs;
+
+            // Logging would block the audio thread, the UI reports the failure instead
+            if self
+                .node
+                .process(
+                    &[&left[..frames], &right[..frames]],
+                    &mut [&mut out_left[..frames], &mut out_right[..frames]],
+                    &[],
+                    &[],
+                )
+                .is_err()
+            {
+                self.failed.store(true, Ordering::Release);
+                return;
+            }
+
+            for (frame, samples) in block.chunks_exact_mut(channels).enumerate() {
+                samples[0] = self.outputs[0][frame];
+                if channels > 1 {
+                    samples[1] = self.outputs[1][frame];
+                }
+            }
+        }
+    }
+}
+
+/// Placeholder for WASM plugins on a strip. The plugin API has no audio processing yet, so the signal passes
+/// through unchanged and the slot only gives access to the plugin's UI.
+pub struct WasmInsert;
+
+impl WasmInsert {
+    pub fn new(id: &str) -> (InsertSlot, Self) {
+        let slot = InsertSlot {
+            id: InsertId::default(),
+            name: id.to_string(),
+            kind: InsertKind::Wasm(id.to_string()),
+            bypassed: false,
+        };
+
+        (slot, Self)
+    }
+}
+
+impl Processor for WasmInsert {
+    fn process(&mut self, _buffer: &mut [f32], _channels: usize) {}
+}
+
+/// Processor of a saved insert, set up with its saved state if it is a VST3 plugin. VST3 inserts also return
+/// their [`VstRemote`]. A state that can not be restored is only logged, the plugin keeps its defaults.
+pub fn instantiate(
+    slot: &InsertSlot,
+    preset: Option<&Preset>,
+    sample_rate: f64,
+    realtime: bool,
+) -> Result<(Box<dyn Processor>, Option<VstRemote>)> {
+    match &slot.kind {
+        InsertKind::Wasm(id) => Ok((Box::new(WasmInsert::new(id).1), None)),
+        InsertKind::Vst3 { path, cid } => {
+            let (_, mut insert) = VstInsert::load_class(path, cid, sample_rate)?;
+            insert.set_realtime(realtime);
+
+            if let Some(Err(e)) = preset.map(|preset| insert.apply_preset(preset)) {
+                warn!("Could not restore the state of {}: {e}", slot.name);
+            }
+
+            let remote = insert.remote();
+            Ok((Box::new(insert), Some(remote)))
+        }
+    }
+}
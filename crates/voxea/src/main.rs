@@ -1,18 +1,30 @@
 #![feature(once_cell_get_mut)]
 
 mod app;
+mod bounce;
 mod config;
+mod history;
+mod inserts;
+mod platform;
 mod plugin;
+mod preferences;
+mod project;
+mod project_file;
 mod renderer;
+mod shortcuts;
 mod ui;
 mod window;
 
 use crate::app::App;
+use crate::project::Project;
+use crate::renderer::RendererConfig;
 use crate::ui::menu;
-use log::info;
+use log::{info, warn};
 use tracing_subscriber::fmt::time::LocalTime;
 use voxea_alloc::perf;
 use voxea_alloc::perf::PerfTrace;
+use voxea_audio::engine::Engine;
+use voxea_audio::mixer;
 use winit::event_loop::EventLoop;
 use anyhow::Result;
 
@@ -30,7 +42,7 @@ fn main() -> Result<()> {
         .init();
 
     plugin::init()?;
-    renderer::init();
+    renderer::init(RendererConfig::from_env())?;
 
     let event_loop = EventLoop::builder()
         .build()
@@ -40,11 +52,34 @@ fn main() -> Result<()> {
     app.run(event_loop, |cx, event_loop| {
         menu::init(cx, event_loop);
 
+        // Outputs silence until tracks are given a source
+        let (mixer, mut node) = mixer::mixer();
+        match Engine::start_default(Box::new(move |buffer, channels| node.process(buffer, channels))) {
+            Ok(engine) => {
+                cx.project = Project::new(engine.sample_rate());
+                cx.audio = Some(engine);
+                cx.mixer = Some(mixer);
+            }
+            Err(e) => warn!("Could not start audio engine: {e}"),
+        }
+
+        // An empty project has nothing to save
+        cx.mark_saved();
+
         std::thread::spawn(|| {
             plugin::load_plugins().unwrap();
         });
     });
 
+    if let Some(path) = perf::trace::finish()? {
+        info!("Wrote perf trace to {}", path.display());
+    }
+
+    let leaks = perf::leaks::report();
+    if !leaks.is_empty() {
+        warn!("Leak report:\n{leaks}");
+    }
+
     info!("Bye bye!");
 
     Ok(())
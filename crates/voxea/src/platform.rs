@@ -0,0 +1,51 @@
+//! Platform specific window behaviour that winit does not abstract over, i.e. owned (modal) windows and
+//! disabling a window while one of its modals is open.
+//!
+//! Windows supports both natively. On Linux, X11 only gets a dialog hint and Wayland gets nothing,
+//! so the [`App`](crate::app::App) additionally blocks input to disabled windows itself.
+
+use winit::window::{Window as WinitWindow, WindowAttributes};
+
+/// Makes a window created with these attributes owned by `owner`, so it stays on top of it like a dialog
+#[cfg(windows)]
+pub fn with_owner(window_attributes: WindowAttributes, owner: &WinitWindow) -> WindowAttributes {
+    use winit::platform::windows::WindowAttributesExtWindows;
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let hwnd = match owner.window_handle().map(|h| h.as_raw()) {
+        Ok(RawWindowHandle::Win32(handle)) => handle.hwnd.get(),
+        _ => return window_attributes,
+    };
+
+    window_attributes
+        .with_owner_window(hwnd)
+        .with_clip_children(false)
+}
+
+/// Makes a window created with these attributes owned by `owner`, so it stays on top of it like a dialog
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn with_owner(window_attributes: WindowAttributes, _owner: &WinitWindow) -> WindowAttributes {
+    use winit::platform::x11::{WindowAttributesExtX11, WindowType};
+
+    // winit can't set WM_TRANSIENT_FOR, the dialog type makes most window managers keep it on top.
+    // Ignored on Wayland.
+    window_attributes.with_x11_window_type(vec![WindowType::Dialog])
+}
+
+/// Makes a window created with these attributes owned by `owner`, so it stays on top of it like a dialog
+#[cfg(not(any(windows, all(unix, not(target_os = "macos")))))]
+pub fn with_owner(window_attributes: WindowAttributes, _owner: &WinitWindow) -> WindowAttributes {
+    window_attributes
+}
+
+/// Enables or disables user input to a window natively, where the platform supports it
+#[cfg(windows)]
+pub fn set_enabled(window: &WinitWindow, enabled: bool) {
+    use winit::platform::windows::WindowExtWindows;
+
+    window.set_enable(enabled);
+}
+
+/// Enables or disables user input to a window natively, where the platform supports it
+#[cfg(not(windows))]
+pub fn set_enabled(_window: &WinitWindow, _enabled: bool) {}
@@ -0,0 +1,9 @@
+//! Actions plugins offer for key bindings, converted from the generated bindings like the [layout](super::layout)
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginAction {
+    pub id: String,
+    pub name: String,
+    /// Default binding in the format of [`KeyBinding::parse`](crate::shortcuts::KeyBinding::parse)
+    pub shortcut: Option<String>,
+}
@@ -0,0 +1,57 @@
+//! Host side of the declarative plugin UI in `world.wit`, converted from the generated bindings so the
+//! UI does not depend on wasmtime types.
+
+/// Controls of a plugin UI, parameters are referenced by id
+#[derive(Debug, Clone, PartialEq)]
+pub enum Widget {
+    Row,
+    Column,
+    Group(String),
+    Knob(u32),
+    Slider(u32),
+    Toggle(u32),
+    Meter(u32),
+    Label(String),
+    Image(String),
+}
+
+impl Widget {
+    pub fn is_container(&self) -> bool {
+        matches!(self, Widget::Row | Widget::Column | Widget::Group(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub widget: Widget,
+    pub parent: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub nodes: Vec<Node>,
+    pub images: Vec<Image>,
+}
+
+impl Layout {
+    /// Indices of the nodes directly inside `parent`, or the top level nodes for `None`, in declaration order.
+    /// Nodes whose parent is not a container declared before them are treated as top level, which also rules out cycles.
+    pub fn children(&self, parent: Option<u32>) -> impl Iterator<Item = usize> + '_ {
+        self.nodes.iter().enumerate().filter_map(move |(index, node)| {
+            let node_parent = node
+                .parent
+                .filter(|&p| (p as usize) < index && self.nodes[p as usize].widget.is_container());
+
+            (node_parent == parent).then_some(index)
+        })
+    }
+}
@@ -1,14 +1,23 @@
+pub mod actions;
+pub mod layout;
+pub mod params;
+
 use std::fs;
 use anyhow::{anyhow, Result};
 use std::sync::OnceLock;
 use log::{info, warn};
 use wasmtime::component::{Component, Linker, ResourceTable};
-use wasmtime::{Config, Engine, Store};
+use wasmtime::{Config, Engine, Store, StoreContextMut};
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
 use crate::renderer;
+use crate::renderer::textures::TextureOwner;
+use crate::plugin::actions::PluginAction;
+use crate::plugin::layout::{Image, Layout, Node, Widget};
+use crate::plugin::params::{ParameterInfo, ParameterStore};
+use rustc_hash::FxHashMap;
 
 pub struct PluginContext {
-    pub(crate) plugins: Vec<Plugin>,
+    pub(crate) plugins: Vec<LoadedPlugin>,
     pub(crate) engine: Engine,
     pub(crate) linker: Linker<MyState>,
 
@@ -16,6 +25,15 @@ pub struct PluginContext {
     pub(crate) signal: Vec<f64>,
 }
 
+/// An instantiated plugin and the id its resources are owned by
+pub struct LoadedPlugin {
+    /// File name of the plugin without extension
+    pub(crate) id: String,
+    pub(crate) instance: Plugin,
+    /// Actions the plugin offers for key bindings
+    pub(crate) actions: Vec<PluginAction>,
+}
+
 static mut CONTEXT: OnceLock<PluginContext> = OnceLock::new();
 
 pub fn init() -> Result<()> {
@@ -58,6 +76,22 @@ pub fn init() -> Result<()> {
         Ok((*signal,))
     })?;
 
+    registry.func_wrap("get-parameter", |store: StoreContextMut<'_, MyState>, (id,): (u32,)| {
+        let value = store
+            .data()
+            .current_parameters()
+            .and_then(|parameters| parameters.get(id))
+            .unwrap_or(0.0);
+        Ok((value,))
+    })?;
+
+    registry.func_wrap("set-parameter", |mut store: StoreContextMut<'_, MyState>, (id, value): (u32, f32)| {
+        if let Some(parameters) = store.data_mut().current_parameters_mut() {
+            parameters.set_from_plugin(id, value);
+        }
+        Ok(())
+    })?;
+
     // All wasm objects operate within the context of a "store". Each
     // `Store` has a type parameter to store host-specific data, which in
     // this case we're using `4` for.
@@ -67,6 +101,8 @@ pub fn init() -> Result<()> {
         MyState {
             ctx: wasi,
             table: ResourceTable::new(),
+            parameters: FxHashMap::default(),
+            current: None,
         },
     );
 
@@ -101,13 +137,36 @@ pub fn load_plugins() -> Result<()> {
         .filter(|path| path.extension().map_or(false, |ext| ext == "wasm"));
 
     for plugin in plugins {
+        let id = plugin.file_stem().unwrap_or_default().to_string_lossy().to_string();
+
         info!("Loading {}!", plugin.to_str().unwrap());
         let component = Component::from_file(&cx.engine, plugin.to_str().unwrap())?;
         let instance = Plugin::instantiate(&mut cx.store, &component, &cx.linker)?;
 
+        // Parameter imports called from now on belong to this plugin
+        cx.store.data_mut().current = Some(id.clone());
+
         let icon = instance.sdk_component_plugin_api().call_icon(&mut cx.store)?;
 
-        renderer::get_mut().create_texture_from_memory(&icon);
+        let parameters = instance
+            .sdk_component_plugin_api()
+            .call_parameters(&mut cx.store)?
+            .into_iter()
+            .map(ParameterInfo::from)
+            .collect();
+        cx.store.data_mut().parameters.insert(id.clone(), ParameterStore::new(parameters));
+
+        let actions = instance
+            .sdk_component_plugin_api()
+            .call_actions(&mut cx.store)?
+            .into_iter()
+            .map(PluginAction::from)
+            .collect();
+
+        // A broken icon should not keep the plugin from loading
+        if let Err(e) = renderer::get_mut().load_texture(TextureOwner::Plugin(id.clone()), "icon", &icon) {
+            warn!("Could not load icon of {id}: {e}");
+        }
 
         info!("Enabling {}!", plugin.to_str().unwrap());
         let result = instance
@@ -117,13 +176,143 @@ pub fn load_plugins() -> Result<()> {
         println!("{:?}", result);
 
         unsafe {
-            cx.plugins.push(instance);
+            cx.plugins.push(LoadedPlugin { id, instance, actions });
         }
     }
 
     Ok(())
 }
 
+/// Disables a plugin, drops its instance and releases its textures
+pub fn unload_plugin(id: &str) -> Result<()> {
+    let Some(cx) = (unsafe { CONTEXT.get_mut() }) else {
+        return Err(anyhow!("Plugin Context not initialized!"));
+    };
+
+    let index = cx
+        .plugins
+        .iter()
+        .position(|plugin| plugin.id == id)
+        .ok_or_else(|| anyhow!("Plugin {id} is not loaded"))?;
+
+    let plugin = cx.plugins.remove(index);
+    cx.store.data_mut().current = Some(id.to_string());
+
+    info!("Disabling {id}!");
+    let result = plugin
+        .instance
+        .sdk_component_plugin_api()
+        .call_disable(&mut cx.store);
+
+    cx.store.data_mut().parameters.remove(id);
+    cx.store.data_mut().current = None;
+
+    let released = renderer::get_mut().release_textures(&TextureOwner::Plugin(id.to_string()));
+    info!("Unloaded {id}, released {released} texture(s)");
+
+    result?;
+    Ok(())
+}
+
+/// Index of a loaded plugin, imports called from now on act on its parameters
+fn enter_plugin(cx: &mut PluginContext, id: &str) -> Result<usize> {
+    let index = cx
+        .plugins
+        .iter()
+        .position(|plugin| plugin.id == id)
+        .ok_or_else(|| anyhow!("Plugin {id} is not loaded"))?;
+
+    cx.store.data_mut().current = Some(id.to_string());
+    Ok(index)
+}
+
+/// UI a plugin describes for its window, `None` if it has none
+pub fn layout(id: &str) -> Result<Option<Layout>> {
+    let Some(cx) = (unsafe { CONTEXT.get_mut() }) else {
+        return Err(anyhow!("Plugin Context not initialized!"));
+    };
+
+    let index = enter_plugin(cx, id)?;
+    let layout = cx.plugins[index]
+        .instance
+        .sdk_component_plugin_api()
+        .call_layout(&mut cx.store)?;
+
+    Ok(layout.map(Layout::from))
+}
+
+/// Runs `f` with the parameters of a plugin, `None` if it is not loaded
+pub fn with_parameters<R>(id: &str, f: impl FnOnce(&mut ParameterStore) -> R) -> Option<R> {
+    let cx = unsafe { CONTEXT.get_mut() }?;
+    cx.store.data_mut().parameters.get_mut(id).map(f)
+}
+
+/// Sends the parameters changed in the UI to the plugin
+pub fn flush_parameter_changes(id: &str) -> Result<()> {
+    let Some(cx) = (unsafe { CONTEXT.get_mut() }) else {
+        return Err(anyhow!("Plugin Context not initialized!"));
+    };
+
+    let changes = match cx.store.data_mut().parameters.get_mut(id) {
+        Some(parameters) => parameters.take_changes(),
+        None => return Ok(()),
+    };
+
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let index = enter_plugin(cx, id)?;
+    for (parameter, value) in changes {
+        cx.plugins[index]
+            .instance
+            .sdk_component_plugin_api()
+            .call_parameter_changed(&mut cx.store, parameter, value)?;
+    }
+
+    Ok(())
+}
+
+/// Actions of all loaded plugins with the id of the plugin offering them
+pub fn actions() -> Vec<(String, PluginAction)> {
+    unsafe {
+        CONTEXT
+            .get()
+            .map(|cx| {
+                cx.plugins
+                    .iter()
+                    .flat_map(|plugin| plugin.actions.iter().map(|action| (plugin.id.clone(), action.clone())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Runs an action of a plugin, e.g. when the user pressed its key binding
+pub fn run_action(plugin: &str, id: &str) -> Result<()> {
+    let Some(cx) = (unsafe { CONTEXT.get_mut() }) else {
+        return Err(anyhow!("Plugin Context not initialized!"));
+    };
+
+    let index = enter_plugin(cx, plugin)?;
+    cx.plugins[index]
+        .instance
+        .sdk_component_plugin_api()
+        .call_run_action(&mut cx.store, id)?;
+
+    Ok(())
+}
+
+/// Ids of the loaded plugins
+pub fn plugin_ids() -> Vec<String> {
+    unsafe {
+        CONTEXT
+            .get()
+            .map(|cx| cx.plugins.iter().map(|plugin| plugin.id.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
 pub fn process_signal() {
     unsafe {
         let Some(mut cx) = CONTEXT.get_mut() else {
@@ -136,6 +325,7 @@ pub fn process_signal() {
 
         for plugin in &cx.plugins {
             // plugin
+            //     .instance
             //     .sdk_component_plugin_api()
             //     .call_process_signal(&mut cx.store, signal)
             //     .unwrap();
@@ -152,6 +342,20 @@ pub fn get_plugins() -> usize {
 struct MyState {
     ctx: WasiCtx,
     table: ResourceTable,
+    /// Parameters of every plugin by id
+    parameters: FxHashMap<String, ParameterStore>,
+    /// Plugin that is currently called into, imports like `get-parameter` act on its parameters
+    current: Option<String>,
+}
+
+impl MyState {
+    fn current_parameters(&self) -> Option<&ParameterStore> {
+        self.current.as_ref().and_then(|id| self.parameters.get(id))
+    }
+
+    fn current_parameters_mut(&mut self) -> Option<&mut ParameterStore> {
+        self.current.as_ref().and_then(|id| self.parameters.get_mut(id))
+    }
 }
 
 impl WasiView for MyState {
@@ -168,3 +372,68 @@ wasmtime::component::bindgen!({
     world: "plugin",
     async: false
 });
+
+impl From<sdk::component::ui::Parameter> for ParameterInfo {
+    fn from(parameter: sdk::component::ui::Parameter) -> Self {
+        Self {
+            id: parameter.id,
+            name: parameter.name,
+            min: parameter.min,
+            max: parameter.max,
+            default_value: parameter.default_value,
+            unit: parameter.unit,
+        }
+    }
+}
+
+impl From<sdk::component::ui::Layout> for Layout {
+    fn from(layout: sdk::component::ui::Layout) -> Self {
+        use sdk::component::ui::Widget as W;
+
+        let nodes = layout
+            .nodes
+            .into_iter()
+            .map(|node| Node {
+                widget: match node.widget {
+                    W::Row => Widget::Row,
+                    W::Column => Widget::Column,
+                    W::Group(title) => Widget::Group(title),
+                    W::Knob(id) => Widget::Knob(id),
+                    W::Slider(id) => Widget::Slider(id),
+                    W::Toggle(id) => Widget::Toggle(id),
+                    W::Meter(id) => Widget::Meter(id),
+                    W::Label(text) => Widget::Label(text),
+                    W::Image(name) => Widget::Image(name),
+                },
+                parent: node.parent,
+            })
+            .collect();
+
+        let images = layout
+            .images
+            .into_iter()
+            .map(|image| Image {
+                name: image.name,
+                data: image.data,
+            })
+            .collect();
+
+        Self {
+            title: layout.title,
+            width: layout.width,
+            height: layout.height,
+            nodes,
+            images,
+        }
+    }
+}
+
+impl From<exports::sdk::component::plugin_api::Action> for PluginAction {
+    fn from(action: exports::sdk::component::plugin_api::Action) -> Self {
+        Self {
+            id: action.id,
+            name: action.name,
+            shortcut: action.shortcut,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use rustc_hash::FxHashMap;
+
+/// A parameter as declared by a plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterInfo {
+    pub id: u32,
+    pub name: String,
+    pub min: f32,
+    pub max: f32,
+    pub default_value: f32,
+    pub unit: String,
+}
+
+impl ParameterInfo {
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.min.min(self.max), self.max.max(self.min))
+    }
+
+    /// Position of `value` in the range, from 0 to 1
+    pub fn normalize(&self, value: f32) -> f32 {
+        let range = self.max - self.min;
+        if range == 0.0 {
+            0.0
+        } else {
+            ((value - self.min) / range).clamp(0.0, 1.0)
+        }
+    }
+
+    pub fn denormalize(&self, normalized: f32) -> f32 {
+        self.min + normalized.clamp(0.0, 1.0) * (self.max - self.min)
+    }
+}
+
+/// Current values of the parameters of one plugin.
+/// Changes made by the user are queued until they are sent to the plugin, see [`ParameterStore::take_changes`].
+#[derive(Debug, Default, Clone)]
+pub struct ParameterStore {
+    infos: Vec<ParameterInfo>,
+    values: FxHashMap<u32, f32>,
+    changes: Vec<(u32, f32)>,
+}
+
+impl ParameterStore {
+    pub fn new(infos: Vec<ParameterInfo>) -> Self {
+        let values = infos
+            .iter()
+            .map(|info| (info.id, info.clamp(info.default_value)))
+            .collect();
+
+        Self {
+            infos,
+            values,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn infos(&self) -> &[ParameterInfo] {
+        &self.infos
+    }
+
+    pub fn info(&self, id: u32) -> Option<&ParameterInfo> {
+        self.infos.iter().find(|info| info.id == id)
+    }
+
+    pub fn get(&self, id: u32) -> Option<f32> {
+        self.values.get(&id).copied()
+    }
+
+    /// Sets a parameter from the UI and queues the change for the plugin, returns `false` for unknown ids
+    pub fn set(&mut self, id: u32, value: f32) -> bool {
+        let Some(value) = self.info(id).map(|info| info.clamp(value)) else {
+            return false;
+        };
+
+        if self.values.insert(id, value) != Some(value) {
+            // Only the latest value of a parameter is sent
+            self.changes.retain(|(changed, _)| *changed != id);
+            self.changes.push((id, value));
+        }
+
+        true
+    }
+
+    /// Sets a parameter from the plugin itself, e.g. a meter, without sending it back
+    pub fn set_from_plugin(&mut self, id: u32, value: f32) -> bool {
+        let Some(value) = self.info(id).map(|info| info.clamp(value)) else {
+            return false;
+        };
+
+        self.values.insert(id, value);
+        true
+    }
+
+    pub fn reset(&mut self, id: u32) -> bool {
+        match self.info(id).map(|info| info.default_value) {
+            Some(value) => self.set(id, value),
+            None => false,
+        }
+    }
+
+    /// Changes made with [`ParameterStore::set`] since the last call
+    pub fn take_changes(&mut self) -> Vec<(u32, f32)> {
+        std::mem::take(&mut self.changes)
+    }
+}
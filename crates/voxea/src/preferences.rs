@@ -0,0 +1,53 @@
+//! User preferences persisted as json in the config directory
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Projects kept in the recent list
+const MAX_RECENT_PROJECTS: usize = 10;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Preferences {
+    /// Key bindings changed from their defaults by action id, `None` when the user removed the binding
+    #[serde(default)]
+    pub shortcuts: BTreeMap<String, Option<String>>,
+    /// Last opened or saved projects, most recent first
+    #[serde(default)]
+    pub recent_projects: Vec<PathBuf>,
+}
+
+impl Preferences {
+    /// Default location of the preferences in the user's config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("Voxea").join("preferences.json"))
+    }
+
+    /// Loads the preferences from disk, or returns the defaults if there are none yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Moves a project to the top of the recent list
+    pub fn add_recent_project(&mut self, path: &Path) {
+        self.recent_projects.retain(|recent| recent != path);
+        self.recent_projects.insert(0, path.to_path_buf());
+        self.recent_projects.truncate(MAX_RECENT_PROJECTS);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
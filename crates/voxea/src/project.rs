@@ -0,0 +1,438 @@
+//! Project data model edited by the arrangement view: tracks with audio clips on a timeline. Positions are in
+//! frames at the project sample rate.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::Arc;
+use voxea_audio::file::AudioFile;
+use voxea_audio::mixer::StripId;
+
+/// Shortest clip trimming and splitting can leave
+pub const MIN_CLIP_FRAMES: u64 = 64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TrackId(u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ClipId(u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSignature {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            numerator: 4,
+            denominator: 4,
+        }
+    }
+}
+
+/// Grid positions snap to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Snap {
+    Off,
+    Bar,
+    Beat,
+    /// A fraction of a whole note, e.g. 16 for sixteenths
+    Division(u32),
+}
+
+impl Snap {
+    pub const ALL: [Snap; 6] = [
+        Self::Off,
+        Self::Bar,
+        Self::Beat,
+        Self::Division(8),
+        Self::Division(16),
+        Self::Division(32),
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Off => "Off".to_string(),
+            Self::Bar => "Bar".to_string(),
+            Self::Beat => "Beat".to_string(),
+            Self::Division(division) => format!("1/{division}"),
+        }
+    }
+}
+
+/// Part of an audio file placed on a track
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub id: ClipId,
+    pub name: String,
+    pub source: Arc<AudioFile>,
+    /// Timeline frame the clip starts at
+    pub start: u64,
+    /// Frame of the source played at `start`
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// Clips are the same if they play the same part of the same source, without comparing the audio
+impl PartialEq for Clip {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.name == other.name
+            && Arc::ptr_eq(&self.source, &other.source)
+            && self.start == other.start
+            && self.offset == other.offset
+            && self.length == other.length
+    }
+}
+
+impl Clip {
+    pub fn end(&self) -> u64 {
+        self.start + self.length
+    }
+
+    fn source_frames(&self) -> u64 {
+        self.source.frames() as u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub id: TrackId,
+    pub name: String,
+    /// Mixer strip the track plays through
+    pub strip: Option<StripId>,
+    /// Clips sorted by start
+    pub clips: Vec<Clip>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    pub sample_rate: u32,
+    /// Beats per minute
+    pub tempo: f64,
+    pub time_signature: TimeSignature,
+    pub tracks: Vec<Track>,
+    pub loop_region: Option<Range<u64>>,
+    pub loop_enabled: bool,
+    pub playhead: u64,
+    next_id: u32,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self::new(48000)
+    }
+}
+
+impl Project {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            tempo: 120.0,
+            time_signature: TimeSignature::default(),
+            tracks: Vec::new(),
+            loop_region: None,
+            loop_enabled: false,
+            playhead: 0,
+            next_id: 0,
+        }
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Length of a beat in frames, a beat being one note of the time signature's denominator
+    pub fn frames_per_beat(&self) -> f64 {
+        let quarter = self.sample_rate as f64 * 60.0 / self.tempo.max(1.0);
+        quarter * 4.0 / self.time_signature.denominator.max(1) as f64
+    }
+
+    pub fn frames_per_bar(&self) -> f64 {
+        self.frames_per_beat() * self.time_signature.numerator.max(1) as f64
+    }
+
+    pub fn seconds(&self, frame: u64) -> f64 {
+        frame as f64 / self.sample_rate as f64
+    }
+
+    /// Length of one grid step in frames, `None` when snapping is off
+    pub fn grid(&self, snap: Snap) -> Option<f64> {
+        match snap {
+            Snap::Off => None,
+            Snap::Bar => Some(self.frames_per_bar()),
+            Snap::Beat => Some(self.frames_per_beat()),
+            Snap::Division(division) => {
+                let whole = self.sample_rate as f64 * 60.0 / self.tempo.max(1.0) * 4.0;
+                Some(whole / division.max(1) as f64)
+            }
+        }
+    }
+
+    /// Nearest grid position to `frame`
+    pub fn snap(&self, frame: f64, snap: Snap) -> u64 {
+        let frame = match self.grid(snap) {
+            Some(step) => (frame / step).round() * step,
+            None => frame,
+        };
+
+        frame.max(0.0).round() as u64
+    }
+
+    /// Frame after the last clip
+    pub fn end(&self) -> u64 {
+        self.tracks
+            .iter()
+            .flat_map(|track| &track.clips)
+            .map(Clip::end)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn add_track(&mut self, name: &str, strip: Option<StripId>) -> TrackId {
+        let id = TrackId(self.next_id());
+
+        self.tracks.push(Track {
+            id,
+            name: name.to_string(),
+            strip,
+            clips: Vec::new(),
+        });
+
+        id
+    }
+
+    pub fn remove_track(&mut self, id: TrackId) -> Option<Track> {
+        let index = self.tracks.iter().position(|track| track.id == id)?;
+        Some(self.tracks.remove(index))
+    }
+
+    pub fn track(&self, id: TrackId) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    pub fn track_mut(&mut self, id: TrackId) -> Option<&mut Track> {
+        self.tracks.iter_mut().find(|track| track.id == id)
+    }
+
+    /// Places a whole audio file on a track
+    pub fn add_clip(&mut self, track: TrackId, source: Arc<AudioFile>, start: u64) -> Result<ClipId> {
+        let length = source.frames() as u64;
+        self.add_clip_part(track, source, start, 0, length)
+    }
+
+    /// Places the part of an audio file from `offset` on a track, limited to the end of the file
+    pub fn add_clip_part(&mut self, track: TrackId, source: Arc<AudioFile>, start: u64, offset: u64, length: u64) -> Result<ClipId> {
+        let frames = source.frames() as u64;
+        if offset >= frames {
+            return Err(anyhow!("{} ends before frame {offset}", source.path.display()));
+        }
+
+        let id = ClipId(self.next_id());
+        let name = source
+            .path
+            .file_stem()
+            .map_or("Audio".to_string(), |name| name.to_string_lossy().into_owned());
+
+        let clip = Clip {
+            id,
+            name,
+            start,
+            offset,
+            length: length.min(frames - offset),
+            source,
+        };
+
+        self.insert_clip(track, clip)?;
+        Ok(id)
+    }
+
+    fn insert_clip(&mut self, track: TrackId, clip: Clip) -> Result<()> {
+        let track = self.track_mut(track).ok_or_else(|| anyhow!("No track {track:?}"))?;
+        let index = track.clips.partition_point(|other| other.start <= clip.start);
+        track.clips.insert(index, clip);
+        Ok(())
+    }
+
+    fn take_clip(&mut self, id: ClipId) -> Result<(TrackId, Clip)> {
+        for track in &mut self.tracks {
+            if let Some(index) = track.clips.iter().position(|clip| clip.id == id) {
+                return Ok((track.id, track.clips.remove(index)));
+            }
+        }
+
+        Err(anyhow!("No clip {id:?}"))
+    }
+
+    pub fn clip(&self, id: ClipId) -> Option<(TrackId, &Clip)> {
+        self.tracks.iter().find_map(|track| {
+            track
+                .clips
+                .iter()
+                .find(|clip| clip.id == id)
+                .map(|clip| (track.id, clip))
+        })
+    }
+
+    pub fn remove_clip(&mut self, id: ClipId) -> Result<Clip> {
+        self.take_clip(id).map(|(_, clip)| clip)
+    }
+
+    /// Moves a clip to another position and track
+    pub fn move_clip(&mut self, id: ClipId, track: TrackId, start: u64) -> Result<()> {
+        if self.track(track).is_none() {
+            return Err(anyhow!("No track {track:?}"));
+        }
+
+        let (_, mut clip) = self.take_clip(id)?;
+        clip.start = start;
+        self.insert_clip(track, clip)
+    }
+
+    /// Moves the start of a clip, revealing or hiding the beginning of its source
+    pub fn trim_start(&mut self, id: ClipId, start: u64) -> Result<()> {
+        let (track, mut clip) = self.take_clip(id)?;
+
+        // Limited by the beginning of the source and the shortest clip, a source shorter than that is shown whole
+        let end = clip.end();
+        let first = clip.start.saturating_sub(clip.offset);
+        let start = start.clamp(first, end.saturating_sub(MIN_CLIP_FRAMES).max(first));
+
+        clip.offset = (clip.offset + start).saturating_sub(clip.start);
+        clip.start = start;
+        clip.length = end - start;
+
+        self.insert_clip(track, clip)
+    }
+
+    /// Moves the end of a clip, limited by the end of its source
+    pub fn trim_end(&mut self, id: ClipId, end: u64) -> Result<()> {
+        let clip = self
+            .tracks
+            .iter_mut()
+            .flat_map(|track| &mut track.clips)
+            .find(|clip| clip.id == id)
+            .ok_or_else(|| anyhow!("No clip {id:?}"))?;
+
+        let max = clip.start + clip.source_frames().saturating_sub(clip.offset);
+        let end = end.clamp(clip.start + MIN_CLIP_FRAMES, max.max(clip.start + MIN_CLIP_FRAMES));
+        clip.length = end - clip.start;
+
+        Ok(())
+    }
+
+    /// Splits a clip in two at a timeline frame, returns the id of the right part
+    pub fn split_clip(&mut self, id: ClipId, at: u64) -> Result<ClipId> {
+        let (track, clip) = self.clip(id).ok_or_else(|| anyhow!("No clip {id:?}"))?;
+
+        if at < clip.start + MIN_CLIP_FRAMES || at + MIN_CLIP_FRAMES > clip.end() {
+            return Err(anyhow!("Can not split {} this close to its edges", clip.name));
+        }
+
+        let mut right = clip.clone();
+        right.id = ClipId(self.next_id());
+        right.offset += at - right.start;
+        right.length = right.end() - at;
+        right.start = at;
+
+        let right_id = right.id;
+        self.trim_end(id, at)?;
+        self.insert_clip(track, right)?;
+
+        Ok(right_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Project, Snap, MIN_CLIP_FRAMES};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use voxea_audio::file::AudioFile;
+
+    fn source(frames: usize) -> Arc<AudioFile> {
+        Arc::new(AudioFile {
+            path: PathBuf::from("loop.wav"),
+            sample_rate: 48000,
+            channels: 1,
+            samples: vec![0.0; frames],
+        })
+    }
+
+    #[test]
+    fn snap() {
+        // 120 bpm in 4/4 at 48 kHz: a beat is 24000 frames, a bar 96000
+        let project = Project::new(48000);
+
+        assert_eq!(project.snap(13000.4, Snap::Off), 13000);
+        assert_eq!(project.snap(13000.0, Snap::Beat), 24000);
+        assert_eq!(project.snap(11000.0, Snap::Beat), 0);
+        assert_eq!(project.snap(50000.0, Snap::Bar), 96000);
+        assert_eq!(project.snap(7000.0, Snap::Division(16)), 6000);
+        assert_eq!(project.snap(-500.0, Snap::Beat), 0);
+    }
+
+    #[test]
+    fn trim_start() {
+        let mut project = Project::new(48000);
+        let track = project.add_track("Audio", None);
+        let id = project.add_clip_part(track, source(1000), 500, 200, 600).unwrap();
+
+        // Reveals the source up to its first frame
+        project.trim_start(id, 0).unwrap();
+        let (_, clip) = project.clip(id).unwrap();
+        assert_eq!((clip.start, clip.offset, clip.length), (300, 0, 800));
+
+        // Keeps the shortest clip
+        project.trim_start(id, 2000).unwrap();
+        let (_, clip) = project.clip(id).unwrap();
+        assert_eq!((clip.start, clip.offset, clip.length), (1100 - MIN_CLIP_FRAMES, 800 - MIN_CLIP_FRAMES, MIN_CLIP_FRAMES));
+    }
+
+    #[test]
+    fn trim_start_of_short_source() {
+        let mut project = Project::new(48000);
+        let track = project.add_track("Audio", None);
+        let id = project.add_clip(track, source(40), 100).unwrap();
+
+        project.trim_start(id, 130).unwrap();
+        let (_, clip) = project.clip(id).unwrap();
+        assert_eq!((clip.start, clip.offset, clip.length), (100, 0, 40));
+    }
+
+    #[test]
+    fn trim_end() {
+        let mut project = Project::new(48000);
+        let track = project.add_track("Audio", None);
+        let id = project.add_clip_part(track, source(1000), 0, 200, 300).unwrap();
+
+        // Limited by the end of the source
+        project.trim_end(id, 5000).unwrap();
+        assert_eq!(project.clip(id).unwrap().1.length, 800);
+
+        project.trim_end(id, 10).unwrap();
+        assert_eq!(project.clip(id).unwrap().1.length, MIN_CLIP_FRAMES);
+    }
+
+    #[test]
+    fn split_clip() {
+        let mut project = Project::new(48000);
+        let track = project.add_track("Audio", None);
+        let left = project.add_clip_part(track, source(1000), 100, 50, 800).unwrap();
+
+        let right = project.split_clip(left, 400).unwrap();
+        let (_, left_clip) = project.clip(left).unwrap();
+        let (_, right_clip) = project.clip(right).unwrap();
+        assert_eq!((left_clip.start, left_clip.offset, left_clip.length), (100, 50, 300));
+        assert_eq!((right_clip.start, right_clip.offset, right_clip.length), (400, 350, 500));
+
+        let clips = &project.track(track).unwrap().clips;
+        assert_eq!(clips.iter().map(|clip| clip.id).collect::<Vec<_>>(), [left, right]);
+
+        assert!(project.split_clip(right, 400 + MIN_CLIP_FRAMES - 1).is_err());
+        assert!(project.split_clip(right, 900 - MIN_CLIP_FRAMES + 1).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+//! Projects saved as json. Audio stays in its own files, referenced relative to the project if it is next to it,
+//! and the states of VST3 inserts are saved as .vstpreset files next to the project.
+
+use crate::project::{Project, TimeSignature};
+use anyhow::{anyhow, Result};
+use log::warn;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use voxea_audio::file::AudioFile;
+use voxea_audio::mixer::{InsertId, Mixer, StripId, StripState};
+use voxea_vst::preset::Preset;
+
+pub const EXTENSION: &str = "voxea";
+
+/// Written into every file, files of newer versions are not opened
+const VERSION: u32 = 1;
+
+const PRESET_EXTENSION: &str = "vstpreset";
+
+/// Everything saved of a project. Compared to the last saved one to tell whether there are unsaved changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectFile {
+    pub version: u32,
+    pub sample_rate: u32,
+    pub tempo: f64,
+    pub time_signature: TimeSignature,
+    pub loop_region: Option<Range<u64>>,
+    pub loop_enabled: bool,
+    pub tracks: Vec<TrackFile>,
+    /// Strips of the mixer with their inserts
+    #[serde(default)]
+    pub mixer: Vec<StripState>,
+    /// States of the VST3 inserts, only filled in when saving, see [`ProjectFile::save`]
+    #[serde(default)]
+    pub presets: Vec<PresetFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackFile {
+    pub name: String,
+    pub strip: Option<StripId>,
+    pub clips: Vec<ClipFile>,
+}
+
+/// State of an insert saved in a .vstpreset file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetFile {
+    pub insert: InsertId,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClipFile {
+    pub source: PathBuf,
+    pub start: u64,
+    pub offset: u64,
+    pub length: u64,
+}
+
+impl Default for ProjectFile {
+    fn default() -> Self {
+        Self::new(&Project::default(), None)
+    }
+}
+
+impl ProjectFile {
+    pub fn new(project: &Project, mixer: Option<&Mixer>) -> Self {
+        let tracks = project
+            .tracks
+            .iter()
+            .map(|track| TrackFile {
+                name: track.name.clone(),
+                strip: track.strip,
+                clips: track
+                    .clips
+                    .iter()
+                    .map(|clip| ClipFile {
+                        source: clip.source.path.clone(),
+                        start: clip.start,
+                        offset: clip.offset,
+                        length: clip.length,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            version: VERSION,
+            sample_rate: project.sample_rate,
+            tempo: project.tempo,
+            time_signature: project.time_signature,
+            loop_region: project.loop_region.clone(),
+            loop_enabled: project.loop_enabled,
+            tracks,
+            mixer: mixer.map(Mixer::state).unwrap_or_default(),
+            presets: Vec::new(),
+        }
+    }
+
+    /// Reads a project, sources and presets are resolved against the directory of the file
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut file: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        if file.version > VERSION {
+            return Err(anyhow!("{} was saved by a newer version of Voxea", path.display()));
+        }
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for reference in file.files_mut() {
+            if reference.is_relative() {
+                *reference = dir.join(&*reference);
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Writes the project and the states of its VST3 inserts, which go into a directory next to the project and
+    /// replace the ones saved before. Sources and presets in the directory of the file or below it are saved
+    /// relative to it.
+    ///
+    /// Everything is written to temporary files first and renamed into place with the project last, so a save that
+    /// fails halfway leaves the previous project as it was.
+    pub fn save(&mut self, path: &Path, presets: Vec<(InsertId, Preset)>) -> Result<()> {
+        let dir = path.with_extension("presets");
+
+        let mut staged = Vec::new();
+        let result = self
+            .stage(path, &dir, presets, &mut staged)
+            .and_then(|()| staged.iter().try_for_each(|(temporary, path)| Ok(fs::rename(temporary, path)?)));
+
+        if let Err(e) = result {
+            for (temporary, _) in &staged {
+                let _ = fs::remove_file(temporary);
+            }
+            return Err(e);
+        }
+
+        // Presets of inserts that were removed since
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let saved = self.presets.iter().any(|preset| preset.path == path);
+
+            if !saved && path.extension().is_some_and(|extension| extension == PRESET_EXTENSION) {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the presets and then the project to temporary files next to where they belong. `staged` gets the
+    /// temporary and the final path of every file, in the order they are renamed in.
+    fn stage(
+        &mut self,
+        path: &Path,
+        dir: &Path,
+        presets: Vec<(InsertId, Preset)>,
+        staged: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !presets.is_empty() {
+            fs::create_dir_all(dir)?;
+        }
+
+        self.presets.clear();
+        for (index, (insert, preset)) in presets.into_iter().enumerate() {
+            let path = dir.join(format!("{index}.{PRESET_EXTENSION}"));
+            let temporary = temporary_path(&path);
+
+            staged.push((temporary.clone(), path.clone()));
+            preset.save(&temporary)?;
+            self.presets.push(PresetFile { insert, path });
+        }
+
+        let mut file = self.clone();
+        if let Some(dir) = path.parent() {
+            for reference in file.files_mut() {
+                if let Ok(relative) = reference.strip_prefix(dir) {
+                    *reference = relative.to_path_buf();
+                }
+            }
+        }
+
+        let temporary = temporary_path(path);
+        staged.push((temporary.clone(), path.to_path_buf()));
+        fs::write(&temporary, serde_json::to_string_pretty(&file)?)?;
+
+        Ok(())
+    }
+
+    /// Saved state of an insert, `None` if there is none
+    pub fn preset(&self, insert: InsertId) -> Option<Result<Preset>> {
+        let preset = self.presets.iter().find(|preset| preset.insert == insert)?;
+        Some(Preset::load(&preset.path))
+    }
+
+    /// Paths of the audio and presets the project refers to
+    fn files_mut(&mut self) -> impl Iterator<Item = &mut PathBuf> {
+        let sources = self
+            .tracks
+            .iter_mut()
+            .flat_map(|track| &mut track.clips)
+            .map(|clip| &mut clip.source);
+
+        sources.chain(self.presets.iter_mut().map(|preset| &mut preset.path))
+    }
+
+    /// Reads the audio and builds the project. Clips of files that can't be read are left out.
+    pub fn project(&self) -> Project {
+        let mut project = Project::new(self.sample_rate);
+        project.tempo = self.tempo;
+        project.time_signature = self.time_signature;
+        project.loop_region = self.loop_region.clone();
+        project.loop_enabled = self.loop_enabled;
+
+        // Every file is only decoded once, however many clips play it
+        let mut sources: FxHashMap<&Path, Option<Arc<AudioFile>>> = FxHashMap::default();
+
+        for track in &self.tracks {
+            let id = project.add_track(&track.name, track.strip);
+
+            for clip in &track.clips {
+                let source = sources.entry(&clip.source).or_insert_with(|| match AudioFile::open(&clip.source) {
+                    Ok(file) => Some(Arc::new(file)),
+                    Err(e) => {
+                        warn!("Could not read {}: {e}", clip.source.display());
+                        None
+                    }
+                });
+
+                let Some(source) = source.clone() else {
+                    continue;
+                };
+
+                if let Err(e) = project.add_clip_part(id, source, clip.start, clip.offset, clip.length) {
+                    warn!("Left out a clip of {}: {e}", track.name);
+                }
+            }
+        }
+
+        project
+    }
+}
+
+/// Where a file is written before it is renamed to `path`
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".tmp");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClipFile, ProjectFile, TrackFile, VERSION};
+    use crate::project::Project;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use voxea_audio::file::AudioFile;
+    use voxea_audio::mixer::InsertId;
+    use voxea_vst::preset::{Preset, COMPONENT_STATE};
+
+    /// Empty directory for the files of a test
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("voxea_project_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_audio(path: &Path, frames: usize) -> Arc<AudioFile> {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let file = AudioFile {
+            path: path.to_path_buf(),
+            sample_rate: 48000,
+            channels: 1,
+            samples: vec![0.0; frames],
+        };
+        file.save(path).unwrap();
+
+        Arc::new(file)
+    }
+
+    #[test]
+    fn save_and_load() {
+        let other = dir("save_and_load_other");
+        let dir = dir("save_and_load");
+        let inside = dir.join("audio").join("loop.wav");
+        let outside = other.join("kick.wav");
+
+        let mut project = Project::new(48000);
+        project.tempo = 93.0;
+        project.loop_region = Some(100..5000);
+        let track = project.add_track("Drums", None);
+        project.add_clip_part(track, write_audio(&inside, 1000), 0, 10, 500).unwrap();
+        project.add_clip(track, write_audio(&outside, 200), 2000).unwrap();
+
+        let mut file = ProjectFile::new(&project, None);
+        let path = dir.join("song.voxea");
+        file.save(&path, Vec::new()).unwrap();
+
+        // Sources next to the project are saved relative to it, others as they are
+        let saved: ProjectFile = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let sources = saved.tracks[0].clips.iter().map(|clip| clip.source.clone()).collect::<Vec<_>>();
+        assert_eq!(sources, [Path::new("audio").join("loop.wav"), outside.clone()]);
+
+        let loaded = ProjectFile::load(&path).unwrap();
+        assert_eq!(loaded, file);
+        assert_eq!(ProjectFile::new(&loaded.project(), None), file);
+
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(other).unwrap();
+    }
+
+    #[test]
+    fn save_presets() {
+        let dir = dir("save_presets");
+        let path = dir.join("song.voxea");
+
+        let mut preset = Preset::new("E831FF31F2D54301928EBBEE25697802");
+        preset.set_chunk(COMPONENT_STATE, vec![1, 2, 3]);
+
+        let mut file = ProjectFile::default();
+        file.save(&path, vec![(InsertId::default(), preset.clone())]).unwrap();
+
+        let loaded = ProjectFile::load(&path).unwrap();
+        assert_eq!(loaded.presets, file.presets);
+        assert_eq!(loaded.preset(InsertId::default()).unwrap().unwrap(), preset);
+
+        // Presets of removed inserts are deleted with the next save
+        let stale = file.presets[0].path.clone();
+        file.save(&path, Vec::new()).unwrap();
+        assert!(!stale.exists());
+        assert!(file.preset(InsertId::default()).is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_save_keeps_previous_project() {
+        let dir = dir("failed_save_keeps_previous_project");
+        let path = dir.join("song.voxea");
+
+        let mut preset = Preset::new("E831FF31F2D54301928EBBEE25697802");
+        preset.set_chunk(COMPONENT_STATE, vec![1, 2, 3]);
+
+        let mut file = ProjectFile::default();
+        file.save(&path, vec![(InsertId::default(), preset.clone())]).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+
+        // The project can not be written once the presets are, as a directory is in the way
+        fs::create_dir(dir.join("song.voxea.tmp")).unwrap();
+        let mut changed = preset.clone();
+        changed.set_chunk(COMPONENT_STATE, vec![4, 5, 6]);
+        file.tempo = 90.0;
+        assert!(file.save(&path, vec![(InsertId::default(), changed)]).is_err());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+        let loaded = ProjectFile::load(&path).unwrap();
+        assert_eq!(loaded.preset(InsertId::default()).unwrap().unwrap(), preset);
+        assert!(!dir.join("song.presets").join("0.vstpreset.tmp").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuse_newer_versions() {
+        let dir = dir("refuse_newer_versions");
+        let path = dir.join("song.voxea");
+
+        let mut file = ProjectFile::default();
+        file.save(&path, Vec::new()).unwrap();
+        assert!(ProjectFile::load(&path).is_ok());
+
+        file.version = VERSION + 1;
+        file.save(&path, Vec::new()).unwrap();
+        assert!(ProjectFile::load(&path).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn clips_beyond_their_source() {
+        let dir = dir("clips_beyond_their_source");
+        let source = dir.join("short.wav");
+        write_audio(&source, 100);
+
+        let clip = |source: &Path, offset, length| ClipFile {
+            source: source.to_path_buf(),
+            start: 0,
+            offset,
+            length,
+        };
+
+        let mut file = ProjectFile::default();
+        file.tracks.push(TrackFile {
+            name: "Track".to_string(),
+            strip: None,
+            clips: vec![
+                // Shortened to the end of the source
+                clip(&source, 40, 1000),
+                // Left out, the source ends before it starts or can not be read
+                clip(&source, 100, 10),
+                clip(&dir.join("missing.wav"), 0, 10),
+            ],
+        });
+
+        let project = file.project();
+        let clips = &project.tracks[0].clips;
+        assert_eq!(clips.len(), 1);
+        assert_eq!((clips[0].offset, clips[0].length), (40, 60));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
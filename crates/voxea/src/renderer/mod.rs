@@ -0,0 +1,176 @@
+pub mod offscreen;
+pub mod plot;
+pub mod target;
+pub mod textures;
+pub mod waveform;
+
+use crate::renderer::plot::PlotPipeline;
+use crate::renderer::textures::{TextureHandle, TextureManager, TextureOwner};
+use crate::renderer::waveform::WaveformPipeline;
+use log::{info, warn};
+use std::borrow::Cow;
+use std::sync::{Arc, OnceLock};
+use winit::window::Window as WinitWindow;
+use anyhow::{anyhow, Result};
+
+static mut CONTEXT: OnceLock<RenderContext> = OnceLock::new();
+
+pub fn init(config: RendererConfig) -> Result<()> {
+    let cx = RenderContext::new(&config)?;
+
+    unsafe {
+        CONTEXT.get_or_init(|| cx);
+    }
+
+    Ok(())
+}
+
+pub fn get() -> &'static RenderContext {
+    unsafe {
+        CONTEXT.get().expect("Could not get Render Context!")
+    }
+}
+
+pub fn get_mut() -> &'static mut RenderContext {
+    unsafe {
+        CONTEXT.get_mut().expect("Could not get Render Context!")
+    }
+}
+
+/// Which adapters the [`RenderContext`] may use
+#[derive(Debug, Clone)]
+pub struct RendererConfig {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Only uses a software adapter, e.g. on machines without a GPU
+    pub force_fallback_adapter: bool,
+}
+
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::PRIMARY,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+        }
+    }
+}
+
+impl RendererConfig {
+    /// Reads `WGPU_BACKEND` (e.g. `vulkan,gl`), `WGPU_POWER_PREF` and `VOXEA_FALLBACK_ADAPTER`
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or(default.backends),
+            power_preference: wgpu::util::power_preference_from_env().unwrap_or(default.power_preference),
+            force_fallback_adapter: std::env::var("VOXEA_FALLBACK_ADAPTER")
+                .is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true")),
+        }
+    }
+}
+
+pub struct RenderContext {
+    pub(crate) instance: wgpu::Instance,
+    pub(crate) adapter: wgpu::Adapter,
+    pub(crate) device: wgpu::Device,
+    pub(crate) queue: wgpu::Queue,
+    pub(crate) shader: wgpu::ShaderModule,
+    pub(crate) renderer: egui_wgpu::Renderer,
+    pub(crate) waveform: WaveformPipeline,
+    pub(crate) plot: PlotPipeline,
+
+    pub(crate) textures: TextureManager,
+}
+
+impl RenderContext {
+    pub fn new(config: &RendererConfig) -> Result<Self> {
+        let (instance, adapter) = Self::request_adapter(config)?;
+        info!("Using adapter {:?}", adapter.get_info());
+
+        // Create the logical device and command queue
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
+                // Waveforms need storage buffers, which the WebGL2 limits don't have.
+                required_limits:
+                    wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+            },
+            None,
+        ))?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
+        });
+
+        let mut renderer =
+            egui_wgpu::Renderer::new(&device, wgpu::TextureFormat::Bgra8UnormSrgb, None, 1);
+        let waveform = WaveformPipeline::new(&device);
+        let plot = PlotPipeline::new(&device);
+
+        Ok(Self {
+            instance,
+            adapter,
+            device,
+            queue,
+            shader,
+            renderer,
+            waveform,
+            plot,
+
+            textures: TextureManager::default(),
+        })
+    }
+
+    /// Finds an adapter for the configured backends, falling back to a software adapter on any backend
+    fn request_adapter(config: &RendererConfig) -> Result<(wgpu::Instance, wgpu::Adapter)> {
+        let request = |backends: wgpu::Backends, force_fallback_adapter: bool| {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends,
+                ..Default::default()
+            });
+
+            let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: config.power_preference,
+                force_fallback_adapter,
+                // Surfaces are created per window later, every adapter of the backends can present to them
+                compatible_surface: None,
+            }));
+
+            adapter.map(|adapter| (instance, adapter))
+        };
+
+        if let Some(found) = request(config.backends, config.force_fallback_adapter) {
+            return Ok(found);
+        }
+
+        warn!("No adapter found for {:?}, trying a fallback adapter", config.backends);
+
+        request(wgpu::Backends::all(), true)
+            .ok_or_else(|| anyhow!("Failed to find an appropriate adapter for {:?}", config.backends))
+    }
+
+    pub fn create_surface(&self, window: Arc<WinitWindow>) -> Result<wgpu::Surface<'static>> {
+        let surface = self.instance.create_surface(window)?;
+
+        Ok(surface)
+    }
+
+    /// Decodes and uploads an image, see [`TextureManager::load`]
+    pub fn load_texture(&mut self, owner: TextureOwner, key: &str, data: &[u8]) -> Result<TextureHandle> {
+        self.textures
+            .load(&self.device, &self.queue, &mut self.renderer, owner, key, data)
+    }
+
+    pub fn remove_texture(&mut self, handle: TextureHandle) -> bool {
+        self.textures.remove(&mut self.renderer, handle)
+    }
+
+    /// Removes every texture of an owner, e.g. when a plugin is unloaded
+    pub fn release_textures(&mut self, owner: &TextureOwner) -> usize {
+        self.textures.release(&mut self.renderer, owner)
+    }
+}
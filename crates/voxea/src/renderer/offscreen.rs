@@ -0,0 +1,265 @@
+//! Renders egui views into offscreen textures and reads them back, so views can be drawn without a window,
+//! e.g. to compare screenshots of the UI in tests.
+
+use crate::renderer::RenderContext;
+use anyhow::{anyhow, Result};
+use image::RgbaImage;
+
+/// Format of the offscreen texture, the same as the window surfaces use
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+/// Render target with its own egui context that is not attached to a window
+pub struct Offscreen {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    width: u32,
+    height: u32,
+    egui_ctx: egui::Context,
+}
+
+impl Offscreen {
+    pub fn new(render_context: &RenderContext, width: u32, height: u32) -> Self {
+        let texture = render_context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("offscreen"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            width,
+            height,
+            egui_ctx: egui::Context::default(),
+        }
+    }
+
+    /// Runs `f` as the egui view, renders it and reads the result back
+    pub fn render<F>(&mut self, render_context: &mut RenderContext, pixels_per_point: f32, mut f: F) -> Result<RgbaImage>
+    where
+        F: FnMut(&egui::Context),
+    {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(self.width as f32, self.height as f32) / pixels_per_point,
+            )),
+            ..Default::default()
+        };
+        self.egui_ctx.set_pixels_per_point(pixels_per_point);
+
+        // The first pass only lays out the view, egui needs the sizes from it to place everything correctly
+        let first = self.egui_ctx.run(input.clone(), &mut f);
+        let full_output = self.egui_ctx.run(input, &mut f);
+
+        let clipped_primitives = self
+            .egui_ctx
+            .tessellate(full_output.shapes, full_output.pixels_per_point);
+
+        for (id, image_delta) in first.textures_delta.set.iter().chain(&full_output.textures_delta.set) {
+            render_context
+                .renderer
+                .update_texture(&render_context.device, &render_context.queue, *id, image_delta);
+        }
+
+        let desc = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.width, self.height],
+            pixels_per_point,
+        };
+
+        let mut encoder = render_context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        render_context.renderer.update_buffers(
+            &render_context.device,
+            &render_context.queue,
+            &mut encoder,
+            &clipped_primitives,
+            &desc,
+        );
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_context.renderer.render(&mut rpass, &clipped_primitives, &desc);
+        }
+
+        for id in first.textures_delta.free.iter().chain(&full_output.textures_delta.free) {
+            render_context.renderer.free_texture(id);
+        }
+
+        render_context.queue.submit(Some(encoder.finish()));
+
+        self.read_back(render_context)
+    }
+
+    /// Copies the texture into a buffer and waits for the GPU to map it
+    fn read_back(&self, render_context: &RenderContext) -> Result<RgbaImage> {
+        // Rows of a texture copy have to be aligned to 256 bytes
+        let unpadded = self.width * 4;
+        let padded = unpadded.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = render_context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("offscreen readback"),
+            size: padded as u64 * self.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = render_context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            self.texture.size(),
+        );
+
+        render_context.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        render_context.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded * self.height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks_exact(padded as usize) {
+                // BGRA to RGBA
+                for pixel in row[..unpadded as usize].chunks_exact(4) {
+                    pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                }
+            }
+        }
+        buffer.unmap();
+
+        RgbaImage::from_raw(self.width, self.height, pixels).ok_or_else(|| anyhow!("Readback has the wrong size"))
+    }
+}
+
+/// Compares two screenshots and returns the share of pixels that differ by more than `tolerance` in any channel
+pub fn compare_images(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> Result<f64> {
+    if a.dimensions() != b.dimensions() {
+        return Err(anyhow!(
+            "Screenshots have different sizes, {:?} and {:?}",
+            a.dimensions(),
+            b.dimensions()
+        ));
+    }
+
+    let differing = a
+        .pixels()
+        .zip(b.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance))
+        .count();
+
+    Ok(differing as f64 / (a.width() as f64 * a.height() as f64).max(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::RendererConfig;
+
+    /// Screenshot tests need an adapter, which CI machines without a GPU or software renderer don't have
+    fn context() -> Option<RenderContext> {
+        let config = RendererConfig {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        };
+
+        match RenderContext::new(&config) {
+            Ok(cx) => Some(cx),
+            Err(err) => {
+                eprintln!("Skipping screenshot test: {err}");
+                None
+            }
+        }
+    }
+
+    fn panel(cx: &egui::Context) {
+        egui::CentralPanel::default().show(cx, |ui| {
+            ui.heading("Voxea");
+            ui.label("Offscreen");
+        });
+    }
+
+    #[test]
+    fn renders_deterministically() {
+        let Some(mut render_context) = context() else {
+            return;
+        };
+
+        let mut offscreen = Offscreen::new(&render_context, 200, 100);
+        let first = offscreen.render(&mut render_context, 1.0, panel).unwrap();
+        let second = offscreen.render(&mut render_context, 1.0, panel).unwrap();
+
+        assert_eq!(first.dimensions(), (200, 100));
+        assert_eq!(compare_images(&first, &second, 0).unwrap(), 0.0);
+        // The panel background is opaque
+        assert!(first.pixels().all(|p| p.0[3] == 255));
+    }
+
+    #[test]
+    fn detects_changes() {
+        let Some(mut render_context) = context() else {
+            return;
+        };
+
+        let mut offscreen = Offscreen::new(&render_context, 200, 100);
+        let before = offscreen.render(&mut render_context, 1.0, panel).unwrap();
+        let after = offscreen
+            .render(&mut render_context, 1.0, |cx| {
+                egui::CentralPanel::default().show(cx, |ui| ui.heading("Changed"));
+            })
+            .unwrap();
+
+        assert!(compare_images(&before, &after, 8).unwrap() > 0.0);
+    }
+
+    #[test]
+    fn compare_rejects_different_sizes() {
+        let a = RgbaImage::new(2, 2);
+        let b = RgbaImage::new(2, 3);
+
+        assert!(compare_images(&a, &b, 0).is_err());
+        assert_eq!(compare_images(&a, &a.clone(), 0).unwrap(), 0.0);
+    }
+}
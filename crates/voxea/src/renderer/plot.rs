@@ -0,0 +1,172 @@
+//! Line plots drawn on the GPU into a texture that egui shows as an image, used for the spectrum analyzer
+//! and oscilloscope.
+
+use crate::renderer::target::{RenderTarget, FORMAT};
+use crate::renderer::RenderContext;
+use bytemuck::{Pod, Zeroable};
+use std::borrow::Cow;
+use std::ops::Range;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+/// A line through points in plot space, where (0, 0) is the bottom left and (1, 1) the top right corner
+pub struct Series<'a> {
+    pub points: &'a [[f32; 2]],
+    pub color: egui::Color32,
+}
+
+/// Pipeline shared by all plots, owned by the [`RenderContext`]
+pub struct PlotPipeline {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl PlotPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("plot"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("plot.wgsl"))),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("plot"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("plot"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Vertex>() as u64,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineStrip,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self { pipeline }
+    }
+}
+
+/// Texture with line plots, registered with egui
+pub struct Plot {
+    target: RenderTarget,
+
+    vertices: Vec<Vertex>,
+    ranges: Vec<Range<u32>>,
+    buffer: Option<wgpu::Buffer>,
+}
+
+impl Plot {
+    pub fn new(cx: &mut RenderContext, width: u32, height: u32) -> Self {
+        Self {
+            target: RenderTarget::new(cx, "plot", width, height),
+
+            vertices: Vec::new(),
+            ranges: Vec::new(),
+            buffer: None,
+        }
+    }
+
+    pub fn texture_id(&self) -> egui::TextureId {
+        self.target.texture_id()
+    }
+
+    pub fn resize(&mut self, cx: &mut RenderContext, width: u32, height: u32) {
+        self.target.resize(cx, width, height);
+    }
+
+    /// Clears the texture to `background` and draws the series on top of each other
+    pub fn draw(&mut self, cx: &RenderContext, background: egui::Color32, series: &[Series]) {
+        self.vertices.clear();
+        self.ranges.clear();
+
+        for series in series {
+            let color = egui::Rgba::from(series.color).to_array();
+            let start = self.vertices.len() as u32;
+
+            self.vertices
+                .extend(series.points.iter().map(|&position| Vertex { position, color }));
+            self.ranges.push(start..self.vertices.len() as u32);
+        }
+
+        let size = (self.vertices.len() * size_of::<Vertex>()) as u64;
+        if self.buffer.as_ref().is_none_or(|buffer| buffer.size() < size) {
+            // Grows in powers of two so the buffer is not recreated every time a series gets longer
+            self.buffer = Some(cx.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("plot vertices"),
+                size: size.next_power_of_two().max(1024),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        let buffer = self.buffer.as_ref().unwrap();
+        cx.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.vertices));
+
+        let background = egui::Rgba::from(background);
+
+        let mut encoder = cx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("plot") });
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("plot"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: self.target.view(),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: background.r() as f64,
+                            g: background.g() as f64,
+                            b: background.b() as f64,
+                            a: background.a() as f64,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            rpass.set_pipeline(&cx.plot.pipeline);
+            rpass.set_vertex_buffer(0, buffer.slice(..));
+
+            for range in &self.ranges {
+                if range.len() > 1 {
+                    rpass.draw(range.clone(), 0..1);
+                }
+            }
+        }
+
+        cx.queue.submit(Some(encoder.finish()));
+    }
+}
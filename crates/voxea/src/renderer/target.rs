@@ -0,0 +1,74 @@
+//! Textures the GPU draws into and egui shows as images, e.g. plots and clip waveforms
+
+use crate::renderer::{self, RenderContext};
+
+/// Format of render targets, the same as the window FBOs
+pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+/// A texture registered with egui that can be rendered to, freed in egui when dropped
+pub struct RenderTarget {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    id: egui::TextureId,
+}
+
+impl RenderTarget {
+    pub fn new(cx: &mut RenderContext, label: &str, width: u32, height: u32) -> Self {
+        let (texture, view) = create_texture(cx, label, width, height);
+        let id = cx
+            .renderer
+            .register_native_texture(&cx.device, &view, wgpu::FilterMode::Linear);
+
+        Self { texture, view, id }
+    }
+
+    pub fn texture_id(&self) -> egui::TextureId {
+        self.id
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.texture.width(), self.texture.height())
+    }
+
+    /// Recreates the texture if the size changed, the egui id stays the same
+    pub fn resize(&mut self, cx: &mut RenderContext, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if self.size() == (width, height) {
+            return;
+        }
+
+        (self.texture, self.view) = create_texture(cx, "render target", width, height);
+        cx.renderer
+            .update_egui_texture_from_wgpu_texture(&cx.device, &self.view, wgpu::FilterMode::Linear, self.id);
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        renderer::get_mut().renderer.free_texture(&self.id);
+    }
+}
+
+fn create_texture(cx: &RenderContext, label: &str, width: u32, height: u32) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = cx.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    (texture, view)
+}
@@ -0,0 +1,186 @@
+//! Textures shown by egui, e.g. plugin icons. Every texture belongs to an owner and is looked up by a key
+//! unique to it, so all textures of a plugin can be released when it is unloaded.
+
+use anyhow::{anyhow, Context, Result};
+use rustc_hash::FxHashMap;
+use wgpu::util::{DeviceExt, TextureDataOrder};
+
+/// Format textures are uploaded in, images are converted to it when decoded
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextureOwner {
+    App,
+    /// A plugin by its id, see [`crate::plugin::unload_plugin`]
+    Plugin(String),
+}
+
+/// Stable reference to a texture, stays invalid once the texture is removed even when its slot is reused
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    index: u32,
+    generation: u32,
+}
+
+pub struct Texture {
+    pub(crate) texture: wgpu::Texture,
+    pub(crate) view: wgpu::TextureView,
+    pub(crate) id: egui::TextureId,
+    pub(crate) owner: TextureOwner,
+    pub(crate) key: String,
+}
+
+impl Texture {
+    pub fn size(&self) -> [u32; 2] {
+        [self.texture.width(), self.texture.height()]
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    generation: u32,
+    texture: Option<Texture>,
+}
+
+#[derive(Default)]
+pub struct TextureManager {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    keys: FxHashMap<(TextureOwner, String), TextureHandle>,
+}
+
+impl TextureManager {
+    /// Decodes an image in any format the `image` crate supports and uploads it.
+    /// A texture with the same owner and key is replaced, its handle becomes invalid.
+    pub fn load(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        renderer: &mut egui_wgpu::Renderer,
+        owner: TextureOwner,
+        key: &str,
+        data: &[u8],
+    ) -> Result<TextureHandle> {
+        let format = image::guess_format(data).context("Unknown image format")?;
+        let image = image::load_from_memory_with_format(data, format)
+            .with_context(|| format!("Failed to decode {format:?} image '{key}'"))?
+            .to_rgba8();
+
+        let (width, height) = image.dimensions();
+        let max = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max || height > max {
+            return Err(anyhow!("Image '{key}' is {width}x{height}, textures must be between 1 and {max} pixels"));
+        }
+
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(key),
+                format: FORMAT,
+                usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::TEXTURE_BINDING,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                dimension: wgpu::TextureDimension::D2,
+                mip_level_count: 1,
+                sample_count: 1,
+                view_formats: &[FORMAT],
+            },
+            TextureDataOrder::LayerMajor,
+            image.as_raw(),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let id = renderer.register_native_texture(device, &view, wgpu::FilterMode::Linear);
+
+        if let Some(previous) = self.find(&owner, key) {
+            self.remove(renderer, previous);
+        }
+
+        let index = self.free.pop().unwrap_or_else(|| {
+            self.slots.push(Slot::default());
+            (self.slots.len() - 1) as u32
+        });
+
+        let slot = &mut self.slots[index as usize];
+        let handle = TextureHandle {
+            index,
+            generation: slot.generation,
+        };
+
+        slot.texture = Some(Texture {
+            texture,
+            view,
+            id,
+            owner: owner.clone(),
+            key: key.to_string(),
+        });
+        self.keys.insert((owner, key.to_string()), handle);
+
+        Ok(handle)
+    }
+
+    pub fn get(&self, handle: TextureHandle) -> Option<&Texture> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.texture.as_ref())
+    }
+
+    pub fn find(&self, owner: &TextureOwner, key: &str) -> Option<TextureHandle> {
+        self.keys.get(&(owner.clone(), key.to_string())).copied()
+    }
+
+    pub fn texture_id(&self, handle: TextureHandle) -> Option<egui::TextureId> {
+        self.get(handle).map(|texture| texture.id)
+    }
+
+    /// Frees a texture in egui and on the GPU, returns `false` if the handle was already invalid
+    pub fn remove(&mut self, renderer: &mut egui_wgpu::Renderer, handle: TextureHandle) -> bool {
+        let Some(slot) = self
+            .slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+        else {
+            return false;
+        };
+
+        let Some(texture) = slot.texture.take() else {
+            return false;
+        };
+
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.keys.remove(&(texture.owner, texture.key));
+
+        renderer.free_texture(&texture.id);
+        texture.texture.destroy();
+
+        true
+    }
+
+    /// Removes every texture of an owner, returns how many were removed
+    pub fn release(&mut self, renderer: &mut egui_wgpu::Renderer, owner: &TextureOwner) -> usize {
+        let handles = self
+            .keys
+            .iter()
+            .filter(|((o, _), _)| o == owner)
+            .map(|(_, &handle)| handle)
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .filter(|&handle| self.remove(renderer, handle))
+            .count()
+    }
+
+    /// Number of live textures
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
@@ -0,0 +1,387 @@
+//! Waveforms drawn on the GPU from multi-resolution min/max peaks.
+//!
+//! A [`PeakPyramid`] stores the min/max of every [`BASE_BLOCK`] samples and halves the resolution with
+//! every level above. For any zoom the level with at most two buckets per pixel is drawn, so drawing
+//! costs the same for a clip of a second and one of an hour.
+
+use crate::renderer::RenderContext;
+use anyhow::{anyhow, Result};
+use bytemuck::{Pod, Zeroable};
+use std::borrow::Cow;
+use voxea_audio::file::AudioFile;
+use wgpu::util::DeviceExt;
+
+/// Samples per bucket of the finest level
+pub const BASE_BLOCK: usize = 16;
+
+/// Largest zoom, in pixels per sample
+const MAX_PIXELS_PER_SAMPLE: f64 = 16.0;
+
+/// Format of the targets waveforms are drawn into, the same as the window FBOs
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+/// Buckets of one resolution, stored channel after channel in [`PeakPyramid::peaks`]
+#[derive(Debug, Copy, Clone)]
+pub struct Level {
+    pub offset: usize,
+    /// Buckets per channel
+    pub len: usize,
+    pub samples_per_peak: usize,
+}
+
+/// Min/max peaks of an audio file at decreasing resolutions
+#[derive(Debug, Clone)]
+pub struct PeakPyramid {
+    pub channels: usize,
+    pub frames: usize,
+    pub levels: Vec<Level>,
+    pub peaks: Vec<[f32; 2]>,
+}
+
+impl PeakPyramid {
+    pub fn from_file(file: &AudioFile) -> Self {
+        Self::from_interleaved(&file.samples, file.channels)
+    }
+
+    pub fn from_interleaved(samples: &[f32], channels: usize) -> Self {
+        let channels = channels.max(1);
+        let frames = samples.len() / channels;
+
+        let len = frames.div_ceil(BASE_BLOCK).max(1);
+        let mut peaks = Vec::with_capacity(len * channels * 2);
+
+        for channel in 0..channels {
+            for bucket in 0..len {
+                let start = bucket * BASE_BLOCK;
+                let end = (start + BASE_BLOCK).min(frames);
+
+                let peak = (start..end)
+                    .map(|frame| samples[frame * channels + channel])
+                    .fold([f32::MAX, f32::MIN], |[min, max], sample| [min.min(sample), max.max(sample)]);
+
+                // Empty files still get a silent bucket
+                peaks.push(if start < end { peak } else { [0.0, 0.0] });
+            }
+        }
+
+        let mut levels = vec![Level {
+            offset: 0,
+            len,
+            samples_per_peak: BASE_BLOCK,
+        }];
+
+        while let Some(&previous) = levels.last().filter(|level| level.len > 1) {
+            let level = Level {
+                offset: peaks.len(),
+                len: previous.len.div_ceil(2),
+                samples_per_peak: previous.samples_per_peak * 2,
+            };
+
+            for channel in 0..channels {
+                let below = previous.offset + channel * previous.len;
+
+                for bucket in 0..level.len {
+                    let left = peaks[below + bucket * 2];
+
+                    // An odd number of buckets leaves the last one without a neighbour
+                    peaks.push(if bucket * 2 + 1 < previous.len {
+                        let right = peaks[below + bucket * 2 + 1];
+                        [left[0].min(right[0]), left[1].max(right[1])]
+                    } else {
+                        left
+                    });
+                }
+            }
+
+            levels.push(level);
+        }
+
+        Self {
+            channels,
+            frames,
+            levels,
+            peaks,
+        }
+    }
+
+    /// Coarsest level whose buckets are not wider than a pixel, or the finest level when zoomed in further
+    pub fn level_for(&self, samples_per_pixel: f64) -> &Level {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.samples_per_peak as f64 <= samples_per_pixel)
+            .unwrap_or(&self.levels[0])
+    }
+}
+
+/// Visible part of a waveform, in frames and physical pixels
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WaveformView {
+    /// Frame at the left edge
+    pub start: f64,
+    pub samples_per_pixel: f64,
+    pub width: u32,
+    pub height: u32,
+    /// Frames of the waveform, scrolling and zooming is limited to them
+    pub frames: usize,
+}
+
+impl WaveformView {
+    /// View showing all `frames` in `width` pixels
+    pub fn fit(frames: usize, width: u32, height: u32) -> Self {
+        let width = width.max(1);
+
+        Self {
+            start: 0.0,
+            samples_per_pixel: (frames as f64 / width as f64).max(1.0 / MAX_PIXELS_PER_SAMPLE),
+            width,
+            height: height.max(1),
+            frames,
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.clamp();
+    }
+
+    /// Zooms by `factor`, values above 1 zoom in, keeping the frame under pixel `anchor` in place
+    pub fn zoom(&mut self, factor: f64, anchor: f64) {
+        let frame = self.start + anchor * self.samples_per_pixel;
+
+        self.samples_per_pixel = (self.samples_per_pixel / factor).clamp(1.0 / MAX_PIXELS_PER_SAMPLE, self.max_samples_per_pixel());
+        self.start = frame - anchor * self.samples_per_pixel;
+        self.clamp();
+    }
+
+    /// Scrolls by a number of pixels, positive values scroll to the right
+    pub fn scroll(&mut self, pixels: f64) {
+        self.start += pixels * self.samples_per_pixel;
+        self.clamp();
+    }
+
+    /// Zooming out stops when the whole waveform fits twice
+    fn max_samples_per_pixel(&self) -> f64 {
+        (self.frames as f64 * 2.0 / self.width as f64).max(1.0)
+    }
+
+    fn clamp(&mut self) {
+        let visible = self.width as f64 * self.samples_per_pixel;
+        self.start = self.start.min(self.frames as f64 - visible * 0.5).max(0.0);
+    }
+}
+
+/// Uniforms of `waveform.wgsl`
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+struct ViewUniform {
+    color: [f32; 4],
+    background: [f32; 4],
+    base: u32,
+    offset: f32,
+    buckets_per_pixel: f32,
+    level_offset: u32,
+    level_len: u32,
+    channels: u32,
+    height: f32,
+    _padding: u32,
+}
+
+/// Pipeline shared by all waveforms, owned by the [`RenderContext`]
+pub struct WaveformPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl WaveformPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("waveform"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("waveform.wgsl"))),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("waveform"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("waveform"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("waveform"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+/// Peaks of an audio file uploaded to the GPU
+pub struct Waveform {
+    pub(crate) pyramid: PeakPyramid,
+    pub(crate) color: egui::Color32,
+    pub(crate) background: egui::Color32,
+
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Waveform {
+    pub fn new(cx: &RenderContext, pyramid: PeakPyramid) -> Result<Self> {
+        let contents: &[u8] = bytemuck::cast_slice(&pyramid.peaks);
+
+        let limit = cx.device.limits().max_storage_buffer_binding_size as usize;
+        if contents.len() > limit {
+            return Err(anyhow!(
+                "Peaks of {} frames need {} bytes, the adapter supports {limit}",
+                pyramid.frames,
+                contents.len()
+            ));
+        }
+
+        let peaks = cx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("waveform peaks"),
+            contents,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let uniforms = cx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("waveform view"),
+            size: size_of::<ViewUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = cx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("waveform"),
+            layout: &cx.waveform.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: peaks.as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(Self {
+            pyramid,
+            color: egui::Color32::from_rgb(100, 180, 255),
+            background: egui::Color32::TRANSPARENT,
+
+            uniforms,
+            bind_group,
+        })
+    }
+
+    pub fn from_file(cx: &RenderContext, file: &AudioFile) -> Result<Self> {
+        Self::new(cx, PeakPyramid::from_file(file))
+    }
+
+    pub fn frames(&self) -> usize {
+        self.pyramid.frames
+    }
+
+    /// Draws the visible part of the waveform into `target`, which must be `view.width` by `view.height` pixels
+    pub fn draw(&self, cx: &RenderContext, target: &wgpu::TextureView, view: &WaveformView) {
+        let level = self.pyramid.level_for(view.samples_per_pixel);
+
+        // Split into integer and fraction on the CPU, f32 can't address every bucket of long files
+        let start = view.start.max(0.0) / level.samples_per_peak as f64;
+        let base = start.floor();
+
+        let uniform = ViewUniform {
+            color: egui::Rgba::from(self.color).to_array(),
+            background: egui::Rgba::from(self.background).to_array(),
+            base: base as u32,
+            offset: (start - base) as f32,
+            buckets_per_pixel: (view.samples_per_pixel / level.samples_per_peak as f64) as f32,
+            level_offset: level.offset as u32,
+            level_len: level.len as u32,
+            channels: self.pyramid.channels as u32,
+            height: view.height as f32,
+            _padding: 0,
+        };
+
+        cx.queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniform));
+
+        let mut encoder = cx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("waveform") });
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("waveform"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            rpass.set_pipeline(&cx.waveform.pipeline);
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+
+        cx.queue.submit(Some(encoder.finish()));
+    }
+}
@@ -0,0 +1,74 @@
+// Draws min/max peaks of one level of a peak pyramid, one column of buckets per pixel.
+// Bucket positions are split into an integer base and a small float offset to stay precise in long files.
+
+struct View {
+    color: vec4<f32>,
+    background: vec4<f32>,
+    // First visible bucket, the fractional part is in `offset`
+    base: u32,
+    offset: f32,
+    buckets_per_pixel: f32,
+    // Start of the level in `peaks` and number of buckets per channel in it
+    level_offset: u32,
+    level_len: u32,
+    channels: u32,
+    height: f32,
+    _padding: u32,
+}
+
+// Largest number of buckets combined into one pixel, the level is chosen so there are at most two
+const MAX_BUCKETS: u32 = 4u;
+
+@group(0) @binding(0) var<uniform> view: View;
+@group(0) @binding(1) var<storage, read> peaks: array<vec2<f32>>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // Fullscreen triangle
+    let x = f32(i32(in_vertex_index & 1u) * 4 - 1);
+    let y = f32(i32(in_vertex_index >> 1u) * 4 - 1);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let channels = max(view.channels, 1u);
+    let lane_height = view.height / f32(channels);
+    let channel = min(u32(position.y / lane_height), channels - 1u);
+
+    // -1 at the bottom of the lane, 1 at the top
+    let y = 1.0 - 2.0 * (position.y - f32(channel) * lane_height) / lane_height;
+    let pixel = 2.0 / lane_height;
+
+    let x = floor(position.x);
+    let start = view.offset + x * view.buckets_per_pixel;
+    let end = start + view.buckets_per_pixel;
+
+    let first = view.base + u32(max(floor(start), 0.0));
+    let last = min(view.base + u32(max(ceil(end), 1.0)), view.level_len);
+
+    var low = 1.0;
+    var high = -1.0;
+
+    for (var i = 0u; i < MAX_BUCKETS; i++) {
+        let bucket = first + i;
+        if bucket >= last {
+            break;
+        }
+
+        let peak = peaks[view.level_offset + channel * view.level_len + bucket];
+        low = min(low, peak.x);
+        high = max(high, peak.y);
+    }
+
+    // Silence and very quiet parts are still drawn as a line at least one pixel thick
+    if low <= high && y >= low - pixel && y <= high + pixel {
+        return view.color;
+    }
+
+    if first < view.level_len && abs(y) <= pixel * 0.5 {
+        return view.color * vec4<f32>(1.0, 1.0, 1.0, 0.4);
+    }
+
+    return view.background;
+}
@@ -0,0 +1,473 @@
+//! Named actions and the keys bound to them.
+//!
+//! Every action belongs to a [`ShortcutContext`]: global actions work in every window, the others only in
+//! windows of that context, see [`Render::shortcut_context`](crate::window::Render::shortcut_context).
+//! Bindings the user changed are stored in the [`Preferences`], plugins add their own actions.
+
+use crate::plugin::actions::PluginAction;
+use crate::preferences::Preferences;
+use anyhow::{anyhow, Result};
+use egui::{Key, KeyboardShortcut, Modifiers};
+use log::warn;
+use std::collections::BTreeMap;
+use std::fmt;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+pub const UNDO: &str = "edit.undo";
+pub const REDO: &str = "edit.redo";
+pub const NEW_WINDOW: &str = "window.new";
+pub const CLOSE_WINDOW: &str = "window.close";
+pub const SETTINGS: &str = "window.settings";
+pub const PROFILER: &str = "window.profiler";
+pub const ANALYZER: &str = "window.analyzer";
+pub const NEW_PROJECT: &str = "file.new";
+pub const OPEN_PROJECT: &str = "file.open";
+pub const SAVE_PROJECT: &str = "file.save";
+pub const SAVE_PROJECT_AS: &str = "file.save_as";
+pub const IMPORT_AUDIO: &str = "file.import_audio";
+pub const EXPORT_AUDIO: &str = "file.export_audio";
+pub const QUIT: &str = "file.quit";
+pub const TOGGLE_MIXER: &str = "view.mixer";
+pub const SPLIT_CLIP: &str = "arrangement.split";
+pub const DELETE_CLIP: &str = "arrangement.delete";
+pub const RESET_PARAMETERS: &str = "plugin_ui.reset";
+
+/// Prefix of the ids of actions contributed by plugins, followed by the plugin id and the action id
+const PLUGIN_PREFIX: &str = "plugin";
+
+/// Windows a binding works in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ShortcutContext {
+    Global,
+    /// The main window with the arrangement
+    Menu,
+    /// Windows of plugin UIs
+    Plugin,
+}
+
+impl ShortcutContext {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Global => "Global",
+            Self::Menu => "Main Window",
+            Self::Plugin => "Plugin Windows",
+        }
+    }
+
+    /// Whether the same keys would trigger actions of both contexts somewhere
+    fn overlaps(self, other: Self) -> bool {
+        self == other || self == Self::Global || other == Self::Global
+    }
+}
+
+/// A key with modifiers. The command modifier is Ctrl, or Cmd on macOS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub key: Key,
+    pub command: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    pub const fn new(key: Key) -> Self {
+        Self {
+            key,
+            command: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    pub const fn command(mut self) -> Self {
+        self.command = true;
+        self
+    }
+
+    pub const fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    /// Binding of a key pressed in a window, `None` for keys egui does not know
+    pub fn from_winit(key: KeyCode, modifiers: ModifiersState) -> Option<Self> {
+        let command = if cfg!(target_os = "macos") {
+            modifiers.super_key()
+        } else {
+            modifiers.control_key()
+        };
+
+        Some(Self {
+            key: egui_winit::key_from_key_code(key)?,
+            command,
+            alt: modifiers.alt_key(),
+            shift: modifiers.shift_key(),
+        })
+    }
+
+    pub fn from_egui(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            command: modifiers.command,
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+        }
+    }
+
+    /// Parses the format written by [`fmt::Display`], e.g. "Ctrl+Shift+Z"
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parts = text.split('+').map(str::trim).collect::<Vec<_>>();
+
+        // "Ctrl++" binds the plus key
+        if text.ends_with("++") {
+            parts.truncate(parts.len().saturating_sub(2));
+            parts.push("Plus");
+        }
+
+        let (key, modifiers) = parts.split_last().ok_or_else(|| anyhow!("Empty key binding"))?;
+        let key = Key::from_name(key).ok_or_else(|| anyhow!("Unknown key {key} in {text}"))?;
+
+        let mut binding = Self::new(key);
+        for modifier in modifiers {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "cmd" | "command" => binding.command = true,
+                "alt" | "option" => binding.alt = true,
+                "shift" => binding.shift = true,
+                _ => return Err(anyhow!("Unknown modifier {modifier} in {text}")),
+            }
+        }
+
+        Ok(binding)
+    }
+
+    /// For showing the binding the way the platform does, see [`egui::Context::format_shortcut`]
+    pub fn shortcut(&self) -> KeyboardShortcut {
+        let modifiers = Modifiers {
+            alt: self.alt,
+            shift: self.shift,
+            ..if self.command { Modifiers::COMMAND } else { Modifiers::NONE }
+        };
+
+        KeyboardShortcut::new(modifiers, self.key)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.command {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key.name())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Action {
+    pub id: String,
+    pub name: String,
+    pub context: ShortcutContext,
+    /// Binding until the user changes it
+    pub default: Option<KeyBinding>,
+    /// Plugin that contributed the action
+    pub plugin: Option<String>,
+}
+
+impl Action {
+    fn builtin(id: &str, name: &str, context: ShortcutContext, default: Option<KeyBinding>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            context,
+            default,
+            plugin: None,
+        }
+    }
+}
+
+/// Id of an action of a plugin, see [`parse_plugin_action`]
+pub fn plugin_action_id(plugin: &str, action: &str) -> String {
+    format!("{PLUGIN_PREFIX}:{plugin}:{action}")
+}
+
+/// Plugin and action id of an action contributed by a plugin
+pub fn parse_plugin_action(id: &str) -> Option<(&str, &str)> {
+    let rest = id.strip_prefix(PLUGIN_PREFIX)?.strip_prefix(':')?;
+    rest.split_once(':')
+}
+
+/// Registry of actions and their bindings
+pub struct Shortcuts {
+    actions: Vec<Action>,
+    /// Bindings changed by the user by action id, kept for actions of plugins that are not loaded
+    overrides: BTreeMap<String, Option<KeyBinding>>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self::new(&Preferences::default())
+    }
+}
+
+impl Shortcuts {
+    pub fn new(preferences: &Preferences) -> Self {
+        use ShortcutContext::*;
+
+        let actions = vec![
+            Action::builtin(UNDO, "Undo", Global, Some(KeyBinding::new(Key::Z).command())),
+            Action::builtin(REDO, "Redo", Global, Some(KeyBinding::new(Key::Z).command().shift())),
+            Action::builtin(NEW_WINDOW, "New Window", Global, Some(KeyBinding::new(Key::G))),
+            Action::builtin(CLOSE_WINDOW, "Close Window", Global, Some(KeyBinding::new(Key::W).command())),
+            Action::builtin(SETTINGS, "Settings", Global, Some(KeyBinding::new(Key::Comma).command())),
+            Action::builtin(PROFILER, "Profiler", Global, None),
+            Action::builtin(ANALYZER, "Analyzer", Global, None),
+            Action::builtin(NEW_PROJECT, "New Project", Menu, Some(KeyBinding::new(Key::N).command())),
+            Action::builtin(OPEN_PROJECT, "Open Project", Menu, Some(KeyBinding::new(Key::O).command())),
+            Action::builtin(SAVE_PROJECT, "Save Project", Menu, Some(KeyBinding::new(Key::S).command())),
+            Action::builtin(SAVE_PROJECT_AS, "Save Project As", Menu, Some(KeyBinding::new(Key::S).command().shift())),
+            Action::builtin(IMPORT_AUDIO, "Import Audio", Menu, Some(KeyBinding::new(Key::I).command())),
+            Action::builtin(EXPORT_AUDIO, "Export Audio", Menu, Some(KeyBinding::new(Key::E).command())),
+            Action::builtin(QUIT, "Quit", Menu, Some(KeyBinding::new(Key::Q).command())),
+            Action::builtin(TOGGLE_MIXER, "Show Mixer", Menu, Some(KeyBinding::new(Key::M).command())),
+            Action::builtin(SPLIT_CLIP, "Split Clip", Menu, Some(KeyBinding::new(Key::S))),
+            Action::builtin(DELETE_CLIP, "Delete Clip", Menu, Some(KeyBinding::new(Key::Delete))),
+            Action::builtin(RESET_PARAMETERS, "Reset Parameters", Plugin, None),
+        ];
+
+        let overrides = preferences
+            .shortcuts
+            .iter()
+            .filter_map(|(id, binding)| match binding.as_deref().map(KeyBinding::parse).transpose() {
+                Ok(binding) => Some((id.clone(), binding)),
+                Err(e) => {
+                    warn!("Ignoring the binding of {id}: {e}");
+                    None
+                }
+            })
+            .collect();
+
+        Self { actions, overrides }
+    }
+
+    /// Writes the bindings the user changed into the preferences
+    pub fn save(&self, preferences: &mut Preferences) {
+        preferences.shortcuts = self
+            .overrides
+            .iter()
+            .map(|(id, binding)| (id.clone(), binding.map(|binding| binding.to_string())))
+            .collect();
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    pub fn action(&self, id: &str) -> Option<&Action> {
+        self.actions.iter().find(|action| action.id == id)
+    }
+
+    /// Replaces the actions of plugins with the ones they offer now, e.g. after plugins were loaded
+    pub fn set_plugin_actions(&mut self, actions: Vec<(String, PluginAction)>) {
+        let unchanged = actions.len() == self.actions.iter().filter(|action| action.plugin.is_some()).count()
+            && actions
+                .iter()
+                .all(|(plugin, action)| self.action(&plugin_action_id(plugin, &action.id)).is_some());
+        if unchanged {
+            return;
+        }
+
+        self.actions.retain(|action| action.plugin.is_none());
+
+        for (plugin, action) in actions {
+            let default = action.shortcut.as_deref().and_then(|text| match KeyBinding::parse(text) {
+                Ok(binding) => Some(binding),
+                Err(e) => {
+                    warn!("{plugin} has an invalid shortcut for {}: {e}", action.id);
+                    None
+                }
+            });
+
+            self.actions.push(Action {
+                id: plugin_action_id(&plugin, &action.id),
+                name: action.name,
+                context: ShortcutContext::Global,
+                default,
+                plugin: Some(plugin),
+            });
+        }
+    }
+
+    /// Current binding of an action
+    pub fn binding(&self, id: &str) -> Option<KeyBinding> {
+        match self.overrides.get(id) {
+            Some(binding) => *binding,
+            None => self.action(id)?.default,
+        }
+    }
+
+    /// Binding of an action the way the platform shows it, empty if it has none
+    pub fn format(&self, ctx: &egui::Context, id: &str) -> String {
+        self.binding(id)
+            .map(|binding| ctx.format_shortcut(&binding.shortcut()))
+            .unwrap_or_default()
+    }
+
+    pub fn is_default(&self, id: &str) -> bool {
+        !self.overrides.contains_key(id)
+    }
+
+    /// Binds an action to a key, `None` removes its binding. Conflicts are allowed, see [`Shortcuts::conflicts`].
+    pub fn set_binding(&mut self, id: &str, binding: Option<KeyBinding>) {
+        if self.action(id).is_some_and(|action| action.default == binding) {
+            self.overrides.remove(id);
+        } else {
+            self.overrides.insert(id.to_string(), binding);
+        }
+    }
+
+    pub fn reset(&mut self, id: &str) {
+        self.overrides.remove(id);
+    }
+
+    pub fn reset_all(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// Bindings the user changed, to put them back with [`Shortcuts::set_overrides`]
+    pub fn overrides(&self) -> &BTreeMap<String, Option<KeyBinding>> {
+        &self.overrides
+    }
+
+    pub fn set_overrides(&mut self, overrides: BTreeMap<String, Option<KeyBinding>>) {
+        self.overrides = overrides;
+    }
+
+    /// Other actions `binding` would trigger in the windows `id` works in
+    pub fn conflicts(&self, id: &str, binding: KeyBinding) -> Vec<&Action> {
+        let Some(context) = self.action(id).map(|action| action.context) else {
+            return Vec::new();
+        };
+
+        self.actions
+            .iter()
+            .filter(|action| action.id != id && action.context.overlaps(context))
+            .filter(|action| self.binding(&action.id) == Some(binding))
+            .collect()
+    }
+
+    /// Action bound to a key in a window, the window's own actions win over global ones
+    pub fn lookup(&self, context: ShortcutContext, binding: KeyBinding) -> Option<&Action> {
+        let bound = |action: &&Action| self.binding(&action.id) == Some(binding);
+
+        self.actions
+            .iter()
+            .filter(|action| action.context == context)
+            .find(bound)
+            .or_else(|| {
+                self.actions
+                    .iter()
+                    .filter(|action| action.context == ShortcutContext::Global)
+                    .find(bound)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(text: &str) -> KeyBinding {
+        KeyBinding::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let redo = binding("Ctrl+Shift+Z");
+        assert_eq!(redo, KeyBinding::new(Key::Z).command().shift());
+        assert_eq!(redo.to_string(), "Ctrl+Shift+Z");
+
+        // Modifiers in any order and case, with the names of other platforms
+        assert_eq!(binding("shift + cmd + Z"), redo);
+        assert!(binding("Option+A").alt);
+
+        assert_eq!(binding("Ctrl++"), KeyBinding::new(Key::Plus).command());
+        assert_eq!(binding("Ctrl++").to_string(), "Ctrl+Plus");
+        assert_eq!(binding("Ctrl+Plus"), binding("Ctrl++"));
+
+        assert!(KeyBinding::parse("").is_err());
+        assert!(KeyBinding::parse("Ctrl+Nope").is_err());
+        assert!(KeyBinding::parse("Hyper+Z").is_err());
+    }
+
+    #[test]
+    fn lookup_prefers_window_actions() {
+        let mut shortcuts = Shortcuts::default();
+        shortcuts.set_binding(NEW_WINDOW, Some(binding("S")));
+
+        let found = |context| shortcuts.lookup(context, binding("S")).map(|action| action.id.as_str());
+        assert_eq!(found(ShortcutContext::Menu), Some(SPLIT_CLIP));
+        assert_eq!(found(ShortcutContext::Plugin), Some(NEW_WINDOW));
+        assert_eq!(found(ShortcutContext::Global), Some(NEW_WINDOW));
+
+        assert!(shortcuts.lookup(ShortcutContext::Menu, binding("Ctrl+Alt+F12")).is_none());
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut shortcuts = Shortcuts::default();
+        shortcuts.set_binding(NEW_WINDOW, Some(binding("S")));
+        shortcuts.set_binding(RESET_PARAMETERS, Some(binding("Ctrl+S")));
+
+        let ids = |id, binding| {
+            shortcuts
+                .conflicts(id, binding)
+                .iter()
+                .map(|action| action.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Global actions conflict with every window, other windows only with themselves
+        assert_eq!(ids(SPLIT_CLIP, binding("S")), [NEW_WINDOW]);
+        assert_eq!(ids(NEW_WINDOW, binding("S")), [SPLIT_CLIP]);
+        assert!(ids(RESET_PARAMETERS, binding("Ctrl+S")).is_empty());
+        assert!(ids(SAVE_PROJECT, binding("Ctrl+S")).is_empty());
+        assert!(ids("missing", binding("S")).is_empty());
+    }
+
+    #[test]
+    fn overrides_round_trip() {
+        let plugin_action = plugin_action_id("gain", "bypass");
+
+        let mut preferences = Preferences::default();
+        preferences.shortcuts.insert(plugin_action.clone(), Some("Ctrl+B".to_string()));
+        preferences.shortcuts.insert(QUIT.to_string(), Some("Ctrl+Nope".to_string()));
+
+        let mut shortcuts = Shortcuts::new(&preferences);
+        assert!(shortcuts.is_default(QUIT));
+
+        shortcuts.set_binding(UNDO, Some(binding("Ctrl+Y")));
+        shortcuts.set_binding(REDO, None);
+        // Setting the default binding is not an override
+        shortcuts.set_binding(SPLIT_CLIP, Some(binding("S")));
+        shortcuts.save(&mut preferences);
+
+        let expected = [
+            (REDO.to_string(), None),
+            (UNDO.to_string(), Some("Ctrl+Y".to_string())),
+            (plugin_action.clone(), Some("Ctrl+B".to_string())),
+        ];
+        assert_eq!(preferences.shortcuts, BTreeMap::from(expected));
+
+        // Bindings of plugins that are not loaded are kept
+        let shortcuts = Shortcuts::new(&preferences);
+        assert_eq!(shortcuts.binding(UNDO), Some(binding("Ctrl+Y")));
+        assert_eq!(shortcuts.binding(REDO), None);
+        assert!(shortcuts.is_default(SPLIT_CLIP));
+        assert_eq!(shortcuts.binding(&plugin_action), Some(binding("Ctrl+B")));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::app::App;
+use crate::renderer;
+use crate::renderer::plot::{Plot, Series};
+use crate::window::{Render, WindowContext};
+use egui::{pos2, vec2, Color32};
+use std::time::Duration;
+use voxea_alloc::perf;
+use voxea_alloc::perf::PerfTrace;
+use voxea_audio::analyzer::{Analyzer, WindowFunction};
+use winit::dpi::PhysicalSize;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::WindowAttributes;
+
+const FFT_SIZES: [usize; 7] = [256, 512, 1024, 2048, 4096, 8192, 16384];
+
+/// Range shown by the spectrum
+const MIN_FREQUENCY: f32 = 20.0;
+const MIN_DB: f32 = -96.0;
+
+const BACKGROUND_COLOR: Color32 = Color32::from_gray(20);
+const GRID_COLOR: Color32 = Color32::from_gray(60);
+const SPECTRUM_COLOR: Color32 = Color32::from_rgb(100, 180, 255);
+const PEAK_COLOR: Color32 = Color32::from_rgb(230, 180, 80);
+const SCOPE_COLOR: Color32 = Color32::from_rgb(120, 220, 120);
+
+pub fn init(cx: &mut App, event_loop: &ActiveEventLoop) {
+    let window_attributes = WindowAttributes::default()
+        .with_title("Analyzer")
+        .with_inner_size(PhysicalSize::new(900, 700));
+
+    cx.open_window(
+        event_loop,
+        Some(window_attributes),
+        Some(Box::new(AnalyzerView::default())),
+    )
+    .expect("Failed to open analyzer");
+}
+
+/// A plot texture and the size it is shown at, in physical pixels
+#[derive(Default)]
+struct PlotArea {
+    plot: Option<Plot>,
+    size: (u32, u32),
+}
+
+impl PlotArea {
+    fn draw(&mut self, series: &[Series]) {
+        let cx = renderer::get_mut();
+        let (width, height) = self.size;
+
+        let plot = self.plot.get_or_insert_with(|| Plot::new(cx, width, height));
+        plot.resize(cx, width, height);
+        plot.draw(cx, BACKGROUND_COLOR, series);
+    }
+
+    /// Shows the plot at the full width of `ui` and remembers its size to draw the next frame at
+    fn show(&mut self, ui: &mut egui::Ui, height: f32) -> egui::Rect {
+        let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), height), egui::Sense::hover());
+        let pixels_per_point = ui.ctx().pixels_per_point();
+
+        self.size = (
+            (rect.width() * pixels_per_point) as u32,
+            (rect.height() * pixels_per_point) as u32,
+        );
+
+        if let Some(plot) = &self.plot {
+            let uv = egui::Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+            ui.painter().image(plot.texture_id(), rect, uv, Color32::WHITE);
+        }
+
+        rect
+    }
+}
+
+/// Window with the spectrum and a triggered oscilloscope of the audio output
+#[derive(Default)]
+pub struct AnalyzerView {
+    spectrum: PlotArea,
+    scope: PlotArea,
+
+    spectrum_points: Vec<[f32; 2]>,
+    peak_points: Vec<[f32; 2]>,
+    scope_points: Vec<[f32; 2]>,
+}
+
+impl AnalyzerView {
+    /// Converts the spectrum to plot space with a logarithmic frequency axis
+    fn spectrum_points(analyzer: &Analyzer, levels: &[f32], points: &mut Vec<[f32; 2]>) {
+        let nyquist = analyzer.sample_rate() as f32 / 2.0;
+        let range = (nyquist / MIN_FREQUENCY).ln();
+
+        points.clear();
+        points.extend(levels.iter().enumerate().skip(1).filter_map(|(bin, &db)| {
+            let frequency = analyzer.bin_frequency(bin);
+            (frequency >= MIN_FREQUENCY).then(|| {
+                let x = (frequency / MIN_FREQUENCY).ln() / range;
+                let y = ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0);
+                [x, y]
+            })
+        }));
+    }
+
+    fn frequency_x(analyzer: &Analyzer, frequency: f32) -> f32 {
+        let nyquist = analyzer.sample_rate() as f32 / 2.0;
+        (frequency / MIN_FREQUENCY).ln() / (nyquist / MIN_FREQUENCY).ln()
+    }
+
+    fn draw(&mut self, analyzer: &Analyzer) {
+        Self::spectrum_points(analyzer, analyzer.spectrum(), &mut self.spectrum_points);
+        Self::spectrum_points(analyzer, analyzer.peaks(), &mut self.peak_points);
+
+        let mut grid = Vec::new();
+        for frequency in [100.0, 1000.0, 10000.0] {
+            let x = Self::frequency_x(analyzer, frequency);
+            grid.push([[x, 0.0], [x, 1.0]]);
+        }
+        for db in (MIN_DB as i32..0).step_by(12) {
+            let y = (db as f32 - MIN_DB) / -MIN_DB;
+            grid.push([[0.0, y], [1.0, y]]);
+        }
+
+        let mut series = grid
+            .iter()
+            .map(|line| Series {
+                points: line,
+                color: GRID_COLOR,
+            })
+            .collect::<Vec<_>>();
+        series.push(Series {
+            points: &self.peak_points,
+            color: PEAK_COLOR,
+        });
+        series.push(Series {
+            points: &self.spectrum_points,
+            color: SPECTRUM_COLOR,
+        });
+
+        self.spectrum.draw(&series);
+
+        let scope = analyzer.scope();
+        let step = 1.0 / (scope.len().max(2) - 1) as f32;
+        self.scope_points.clear();
+        self.scope_points.extend(
+            scope
+                .iter()
+                .enumerate()
+                .map(|(i, &sample)| [i as f32 * step, (sample * 0.5 + 0.5).clamp(0.0, 1.0)]),
+        );
+
+        self.scope.draw(&[
+            Series {
+                points: &[[0.0, 0.5], [1.0, 0.5]],
+                color: GRID_COLOR,
+            },
+            Series {
+                points: &self.scope_points,
+                color: SCOPE_COLOR,
+            },
+        ]);
+    }
+}
+
+fn settings(ui: &mut egui::Ui, analyzer: &mut Analyzer) {
+    let mut config = *analyzer.config();
+
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_label("FFT Size")
+            .selected_text(config.fft_size.to_string())
+            .show_ui(ui, |ui| {
+                for size in FFT_SIZES {
+                    ui.selectable_value(&mut config.fft_size, size, size.to_string());
+                }
+            });
+
+        egui::ComboBox::from_label("Window")
+            .selected_text(config.window.name())
+            .show_ui(ui, |ui| {
+                for window in WindowFunction::ALL {
+                    ui.selectable_value(&mut config.window, window, window.name());
+                }
+            });
+
+        ui.add(egui::Slider::new(&mut config.averaging, 0.0..=0.95).text("Averaging"));
+
+        let mut hold = config.peak_hold.as_secs_f32();
+        if ui.add(egui::Slider::new(&mut hold, 0.0..=5.0).suffix(" s").text("Peak Hold")).changed() {
+            config.peak_hold = Duration::from_secs_f32(hold);
+        }
+
+        if ui.button("Reset Peaks").clicked() {
+            analyzer.reset_peaks();
+        }
+    });
+
+    if config != *analyzer.config() {
+        analyzer.set_config(config);
+    }
+}
+
+impl Render for AnalyzerView {
+    fn render(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop) {
+        perf::begin_perf!("analyzer::render");
+
+        let window = &mut cx.window;
+        let mut analyzer = cx.app.audio.as_mut().map(|engine| engine.analyzer());
+
+        // Plots are drawn with the sizes of the previous frame, the UI below shows them
+        if let Some(analyzer) = analyzer.as_deref_mut() {
+            analyzer.update();
+            self.draw(analyzer);
+        }
+
+        window.ui2(|cx| {
+            egui::CentralPanel::default().show(cx, |ui| {
+                let Some(analyzer) = analyzer.as_deref_mut() else {
+                    ui.weak("No audio stream is running");
+                    return;
+                };
+
+                settings(ui, analyzer);
+                ui.separator();
+
+                let height = ui.available_height() - ui.spacing().item_spacing.y * 2.0;
+
+                ui.strong("Spectrum");
+                let rect = self.spectrum.show(ui, height * 0.6);
+
+                for (frequency, label) in [(100.0, "100 Hz"), (1000.0, "1 kHz"), (10000.0, "10 kHz")] {
+                    let x = rect.left() + Self::frequency_x(analyzer, frequency) * rect.width();
+                    ui.painter().text(
+                        pos2(x + 2.0, rect.bottom() - 2.0),
+                        egui::Align2::LEFT_BOTTOM,
+                        label,
+                        egui::FontId::monospace(10.0),
+                        ui.visuals().weak_text_color(),
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    ui.strong("Oscilloscope");
+                    if !analyzer.is_triggered() {
+                        ui.weak("(untriggered)");
+                    }
+                });
+                self.scope.show(ui, ui.available_height());
+            });
+
+            // Keeps the plots moving even without input
+            cx.request_repaint();
+        });
+    }
+}
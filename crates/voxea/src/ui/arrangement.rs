@@ -0,0 +1,688 @@
+use crate::project::{ClipId, Project, Snap, TrackId};
+use crate::renderer;
+use crate::renderer::target::RenderTarget;
+use crate::renderer::waveform::{Waveform, WaveformView};
+use anyhow::Result;
+use egui::{pos2, vec2, Align2, Color32, CursorIcon, FontId, Rect, Sense, Stroke};
+use log::error;
+use rustc_hash::FxHashMap;
+use std::path::PathBuf;
+use voxea_audio::mixer::{Mixer, StripKind};
+
+const HEADER_WIDTH: f32 = 160.0;
+const RULER_HEIGHT: f32 = 36.0;
+const TRACK_HEIGHT: f32 = 84.0;
+/// Width of the clip edges that trim instead of move
+const TRIM_HANDLE: f32 = 6.0;
+const LINE_WIDTH: f32 = 1.0;
+
+/// Zoom range in frames per point
+const MIN_FRAMES_PER_POINT: f64 = 1.0;
+const MAX_FRAMES_PER_POINT: f64 = 65536.0;
+
+/// Smallest distance between labels on the ruler, in points
+const MIN_LABEL_SPACING: f32 = 64.0;
+const MIN_TICK_SPACING: f32 = 8.0;
+
+/// Second intervals time labels are placed at, the first one far enough apart is used
+const TIME_STEPS: [f64; 14] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0];
+
+const CLIP_COLOR: Color32 = Color32::from_rgb(48, 72, 110);
+const CLIP_SELECTED_COLOR: Color32 = Color32::from_rgb(70, 100, 150);
+const LOOP_COLOR: Color32 = Color32::from_rgba_premultiplied(60, 90, 40, 60);
+const PLAYHEAD_COLOR: Color32 = Color32::from_rgb(240, 90, 70);
+
+/// What a drag that started on the ruler or a clip changes until the pointer is released
+#[derive(Debug, Copy, Clone)]
+enum Drag {
+    Playhead,
+    Loop { anchor: u64 },
+    /// `grab` is the distance from the start of the clip to the pointer in frames
+    Move { clip: ClipId, grab: f64 },
+    TrimStart(ClipId),
+    TrimEnd(ClipId),
+}
+
+/// Texture a clip's waveform is drawn into and the view it was laid out with in the previous frame
+struct ClipWaveform {
+    target: RenderTarget,
+    view: WaveformView,
+}
+
+/// Tracks on a timeline with their clips, edits go straight to the [`Project`]
+pub struct ArrangementView {
+    /// Timeline frame at the left edge of the lanes
+    start: f64,
+    frames_per_point: f64,
+    /// Vertical scroll of the tracks in points
+    scroll: f32,
+    snap: Snap,
+    selected: Option<ClipId>,
+    drag: Option<Drag>,
+    /// Name of the last edit made in this frame, for the undo history
+    edit: Option<&'static str>,
+
+    /// Peaks of every source by path, `None` if they could not be uploaded
+    waveforms: FxHashMap<PathBuf, Option<Waveform>>,
+    clips: FxHashMap<ClipId, ClipWaveform>,
+}
+
+impl Default for ArrangementView {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            frames_per_point: 1000.0,
+            scroll: 0.0,
+            snap: Snap::Beat,
+            selected: None,
+            drag: None,
+            edit: None,
+
+            waveforms: FxHashMap::default(),
+            clips: FxHashMap::default(),
+        }
+    }
+}
+
+impl ArrangementView {
+    /// Draws the waveforms of the clips that were visible in the previous frame, call before the UI
+    pub fn draw_waveforms(&mut self, project: &Project) {
+        self.clips.retain(|id, _| project.clip(*id).is_some());
+
+        let cx = renderer::get_mut();
+
+        for (id, clip) in &mut self.clips {
+            let Some((_, source)) = project.clip(*id) else {
+                continue;
+            };
+
+            let waveform = self
+                .waveforms
+                .entry(source.source.path.clone())
+                .or_insert_with(|| match Waveform::from_file(cx, &source.source) {
+                    Ok(waveform) => Some(waveform),
+                    Err(e) => {
+                        error!("Could not draw {}: {e}", source.source.path.display());
+                        None
+                    }
+                });
+
+            if let Some(waveform) = waveform {
+                clip.target.resize(cx, clip.view.width, clip.view.height);
+                waveform.draw(cx, clip.target.view(), &clip.view);
+            }
+        }
+    }
+
+    fn x(&self, rect: Rect, frame: f64) -> f32 {
+        rect.left() + ((frame - self.start) / self.frames_per_point) as f32
+    }
+
+    fn frame(&self, rect: Rect, x: f32) -> f64 {
+        (self.start + (x - rect.left()) as f64 * self.frames_per_point).max(0.0)
+    }
+
+    /// Shows the arrangement and returns the name of the edit it made to the project, if any
+    pub fn show(&mut self, ui: &mut egui::Ui, project: &mut Project, mut mixer: Option<&mut Mixer>) -> Option<&'static str> {
+        self.toolbar(ui, project, mixer.as_deref_mut());
+        ui.separator();
+
+        let (rect, _) = ui.allocate_exact_size(ui.available_size(), Sense::hover());
+        let headers = Rect::from_min_max(pos2(rect.left(), rect.top() + RULER_HEIGHT), pos2(rect.left() + HEADER_WIDTH, rect.bottom()));
+        let ruler = Rect::from_min_max(pos2(headers.right(), rect.top()), pos2(rect.right(), rect.top() + RULER_HEIGHT));
+        let lanes = Rect::from_min_max(pos2(headers.right(), headers.top()), rect.max);
+
+        let content = project.tracks.len() as f32 * TRACK_HEIGHT;
+        self.scroll = self.scroll.clamp(0.0, (content - lanes.height()).max(0.0));
+
+        self.navigate(ui, lanes);
+        self.lanes(ui, project, lanes);
+        self.clips(ui, project, lanes);
+        self.ruler(ui, project, ruler);
+        self.headers(ui, project, mixer, headers);
+        self.apply_drag(ui, project, lanes);
+
+        // Playhead and loop region over everything
+        let painter = ui.painter_at(Rect::from_min_max(ruler.min, lanes.max));
+        let playhead = self.x(lanes, project.playhead as f64);
+        painter.vline(playhead, ruler.top()..=lanes.bottom(), Stroke::new(LINE_WIDTH, PLAYHEAD_COLOR));
+
+        if let Some(region) = &project.loop_region {
+            let area = Rect::from_x_y_ranges(
+                self.x(lanes, region.start as f64)..=self.x(lanes, region.end as f64),
+                ruler.top()..=lanes.bottom(),
+            );
+            let color = if project.loop_enabled { LOOP_COLOR } else { LOOP_COLOR.gamma_multiply(0.4) };
+            painter.rect_filled(area, 0.0, color);
+        }
+
+        self.edit.take()
+    }
+
+    /// Selected clip, the selection may have been undone
+    fn selected(&self, project: &Project) -> Option<ClipId> {
+        self.selected.filter(|clip| project.clip(*clip).is_some())
+    }
+
+    /// Splits the selected clip at the playhead, returns the name of the edit if there was a clip to split
+    pub fn split_selected(&mut self, project: &mut Project) -> Option<&'static str> {
+        let clip = self.selected(project)?;
+        report(project.split_clip(clip, project.playhead).map(|_| ()));
+        Some("Split Clip")
+    }
+
+    /// Removes the selected clip, returns the name of the edit if there was one selected
+    pub fn delete_selected(&mut self, project: &mut Project) -> Option<&'static str> {
+        let clip = self.selected(project)?;
+        let _ = project.remove_clip(clip);
+        self.selected = None;
+        Some("Delete Clip")
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui, project: &mut Project, mixer: Option<&mut Mixer>) {
+        ui.horizontal(|ui| {
+            if ui.button("Add Track").clicked() {
+                let name = format!("Track {}", project.tracks.len() + 1);
+                add_track(project, mixer, &name);
+                self.edit = Some("Add Track");
+            }
+
+            ui.separator();
+
+            let tempo = ui.add(
+                egui::DragValue::new(&mut project.tempo)
+                    .range(20.0..=400.0)
+                    .speed(0.5)
+                    .suffix(" BPM"),
+            );
+            if tempo.changed() {
+                self.edit = Some("Change Tempo");
+            }
+
+            let signature = &mut project.time_signature;
+            let before = *signature;
+            ui.add(egui::DragValue::new(&mut signature.numerator).range(1..=32));
+            ui.label("/");
+            egui::ComboBox::from_id_source("denominator")
+                .width(40.0)
+                .selected_text(signature.denominator.to_string())
+                .show_ui(ui, |ui| {
+                    for denominator in [2, 4, 8, 16] {
+                        ui.selectable_value(&mut signature.denominator, denominator, denominator.to_string());
+                    }
+                });
+            if *signature != before {
+                self.edit = Some("Change Time Signature");
+            }
+
+            ui.separator();
+
+            egui::ComboBox::from_label("Snap")
+                .selected_text(self.snap.name())
+                .show_ui(ui, |ui| {
+                    for snap in Snap::ALL {
+                        ui.selectable_value(&mut self.snap, snap, snap.name());
+                    }
+                });
+
+            ui.add_enabled_ui(project.loop_region.is_some(), |ui| {
+                let toggle = ui
+                    .toggle_value(&mut project.loop_enabled, "Loop")
+                    .on_disabled_hover_text("Shift + drag on the ruler to set a loop region");
+                if toggle.changed() {
+                    self.edit = Some("Toggle Loop");
+                }
+            });
+
+            ui.separator();
+
+            if self.selected(project).is_some() {
+                if ui.button("Split").on_hover_text("Split at the playhead").clicked() {
+                    self.edit = self.split_selected(project);
+                }
+
+                if ui.button("Delete").clicked() {
+                    self.edit = self.delete_selected(project);
+                }
+            }
+
+            ui.label(format!(
+                "{} | {}",
+                format_position(project, project.playhead),
+                format_time(project.seconds(project.playhead)),
+            ));
+        });
+    }
+
+    /// Ctrl + scroll zooms around the pointer, shift + scroll or horizontal scrolling moves through time
+    fn navigate(&mut self, ui: &mut egui::Ui, lanes: Rect) {
+        let hovered = ui.rect_contains_pointer(Rect::from_min_max(pos2(lanes.left(), lanes.top() - RULER_HEIGHT), lanes.max));
+        if !hovered {
+            return;
+        }
+
+        let (scroll, zoom, shift, pointer) = ui.input(|i| {
+            (i.smooth_scroll_delta, i.zoom_delta(), i.modifiers.shift, i.pointer.hover_pos())
+        });
+
+        if zoom != 1.0 {
+            let x = pointer.map_or(lanes.left(), |pointer| pointer.x);
+            let anchor = self.frame(lanes, x);
+
+            self.frames_per_point = (self.frames_per_point / zoom as f64).clamp(MIN_FRAMES_PER_POINT, MAX_FRAMES_PER_POINT);
+            self.start = (anchor - (x - lanes.left()) as f64 * self.frames_per_point).max(0.0);
+        } else if shift {
+            self.start = (self.start - (scroll.x + scroll.y) as f64 * self.frames_per_point).max(0.0);
+        } else {
+            self.start = (self.start - scroll.x as f64 * self.frames_per_point).max(0.0);
+            self.scroll -= scroll.y;
+        }
+    }
+
+    fn track_rect(&self, lanes: Rect, index: usize) -> Rect {
+        let top = lanes.top() + index as f32 * TRACK_HEIGHT - self.scroll;
+        Rect::from_min_size(pos2(lanes.left(), top), vec2(lanes.width(), TRACK_HEIGHT))
+    }
+
+    /// Index of the track under a y coordinate, clamped to the existing tracks
+    fn track_at(&self, lanes: Rect, y: f32, tracks: usize) -> usize {
+        let index = ((y - lanes.top() + self.scroll) / TRACK_HEIGHT).floor().max(0.0) as usize;
+        index.min(tracks.saturating_sub(1))
+    }
+
+    /// Lane backgrounds and the bar and beat grid
+    fn lanes(&self, ui: &egui::Ui, project: &Project, lanes: Rect) {
+        let painter = ui.painter_at(lanes);
+        let visuals = ui.visuals();
+
+        painter.rect_filled(lanes, 0.0, visuals.extreme_bg_color);
+
+        for index in 0..project.tracks.len() {
+            let rect = self.track_rect(lanes, index);
+            if index % 2 == 1 {
+                painter.rect_filled(rect, 0.0, visuals.faint_bg_color);
+            }
+            painter.hline(lanes.x_range(), rect.bottom(), Stroke::new(LINE_WIDTH, visuals.widgets.noninteractive.bg_stroke.color));
+        }
+
+        let beat = project.frames_per_beat();
+        let beats_per_bar = project.time_signature.numerator.max(1) as u64;
+
+        if (beat / self.frames_per_point) as f32 >= MIN_TICK_SPACING {
+            let first = (self.start / beat).floor() as u64;
+            let last = (self.frame(lanes, lanes.right()) / beat).ceil() as u64;
+
+            for index in first..=last {
+                let x = self.x(lanes, index as f64 * beat);
+                let color = if index % beats_per_bar == 0 {
+                    visuals.widgets.noninteractive.bg_stroke.color
+                } else {
+                    visuals.faint_bg_color
+                };
+                painter.vline(x, lanes.y_range(), Stroke::new(LINE_WIDTH, color));
+            }
+        } else {
+            let step = bar_step(project, self.frames_per_point, MIN_TICK_SPACING);
+            for bar in self.visible_bars(project, lanes, step) {
+                let x = self.x(lanes, bar as f64 * project.frames_per_bar());
+                painter.vline(x, lanes.y_range(), Stroke::new(LINE_WIDTH, visuals.widgets.noninteractive.bg_stroke.color));
+            }
+        }
+    }
+
+    fn visible_bars(&self, project: &Project, rect: Rect, step: u64) -> impl Iterator<Item = u64> {
+        let bar = project.frames_per_bar();
+        let first = (self.start / bar).floor() as u64 / step * step;
+        let last = (self.frame(rect, rect.right()) / bar).ceil() as u64;
+
+        (first..=last).step_by(step as usize)
+    }
+
+    fn clips(&mut self, ui: &mut egui::Ui, project: &mut Project, lanes: Rect) {
+        let pixels_per_point = ui.ctx().pixels_per_point();
+        let painter = ui.painter_at(lanes);
+        let (pointer, alt) = ui.input(|i| (i.pointer.hover_pos(), i.modifiers.alt));
+
+        let mut split = None;
+        let mut delete = None;
+        let mut visible_clips = Vec::new();
+
+        for (index, track) in project.tracks.iter().enumerate() {
+            let lane = self.track_rect(lanes, index);
+            if !lane.intersects(lanes) {
+                continue;
+            }
+
+            for clip in &track.clips {
+                let rect = Rect::from_x_y_ranges(
+                    self.x(lanes, clip.start as f64)..=self.x(lanes, clip.end() as f64),
+                    lane.top() + 2.0..=lane.bottom() - 2.0,
+                );
+                let visible = rect.intersect(lanes);
+                if !visible.is_positive() {
+                    continue;
+                }
+
+                visible_clips.push(clip.id);
+
+                let response = ui.interact(visible, ui.id().with(("clip", clip.id)), Sense::click_and_drag());
+                let selected = self.selected == Some(clip.id);
+
+                painter.rect_filled(rect, 3.0, if selected { CLIP_SELECTED_COLOR } else { CLIP_COLOR });
+
+                // Lays out the waveform for the next frame and shows the one drawn for this frame
+                let waveform_rect = Rect::from_min_max(pos2(visible.left(), rect.top() + 14.0), pos2(visible.right(), rect.bottom()));
+                let view = WaveformView {
+                    start: clip.offset as f64 + self.frame(lanes, waveform_rect.left()) - clip.start as f64,
+                    samples_per_pixel: self.frames_per_point / pixels_per_point as f64,
+                    width: (waveform_rect.width() * pixels_per_point).max(1.0) as u32,
+                    height: (waveform_rect.height() * pixels_per_point).max(1.0) as u32,
+                    frames: clip.source.frames(),
+                };
+
+                match self.clips.get_mut(&clip.id) {
+                    Some(waveform) => {
+                        if waveform.view == view {
+                            let uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+                            painter.image(waveform.target.texture_id(), waveform_rect, uv, Color32::WHITE);
+                        } else {
+                            waveform.view = view;
+                            ui.ctx().request_repaint();
+                        }
+                    }
+                    None => {
+                        let target = RenderTarget::new(renderer::get_mut(), "clip", view.width, view.height);
+                        self.clips.insert(clip.id, ClipWaveform { target, view });
+                        ui.ctx().request_repaint();
+                    }
+                }
+
+                painter.text(
+                    pos2(visible.left() + 4.0, rect.top() + 2.0),
+                    Align2::LEFT_TOP,
+                    &clip.name,
+                    FontId::proportional(11.0),
+                    ui.visuals().strong_text_color(),
+                );
+                if selected {
+                    painter.rect_stroke(rect, 3.0, ui.visuals().selection.stroke);
+                }
+
+                // Trimming takes the edges, the rest of the clip moves it
+                let edge = pointer.filter(|_| response.hovered()).and_then(|pointer| {
+                    if (pointer.x - rect.left()).abs() <= TRIM_HANDLE {
+                        Some(Drag::TrimStart(clip.id))
+                    } else if (rect.right() - pointer.x).abs() <= TRIM_HANDLE {
+                        Some(Drag::TrimEnd(clip.id))
+                    } else {
+                        None
+                    }
+                });
+
+                if edge.is_some() {
+                    ui.ctx().set_cursor_icon(CursorIcon::ResizeHorizontal);
+                }
+
+                if response.clicked() {
+                    self.selected = Some(clip.id);
+
+                    if alt {
+                        if let Some(pointer) = response.interact_pointer_pos() {
+                            split = Some((clip.id, project.snap(self.frame(lanes, pointer.x), self.snap)));
+                        }
+                    }
+                }
+
+                if response.drag_started() {
+                    self.selected = Some(clip.id);
+                    self.drag = edge.or_else(|| {
+                        let pointer = response.interact_pointer_pos()?;
+                        Some(Drag::Move {
+                            clip: clip.id,
+                            grab: self.frame(lanes, pointer.x) - clip.start as f64,
+                        })
+                    });
+                }
+
+                response.context_menu(|ui| {
+                    if ui.button("Split at Playhead").clicked() {
+                        split = Some((clip.id, project.playhead));
+                        ui.close_menu();
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = Some(clip.id);
+                        ui.close_menu();
+                    }
+                });
+            }
+        }
+
+        // Clips scrolled out of view give up their textures
+        self.clips.retain(|id, _| visible_clips.contains(id));
+
+        if let Some((clip, at)) = split {
+            report(project.split_clip(clip, at).map(|_| ()));
+            self.edit = Some("Split Clip");
+        }
+
+        if let Some(clip) = delete {
+            let _ = project.remove_clip(clip);
+            self.edit = Some("Delete Clip");
+            if self.selected == Some(clip) {
+                self.selected = None;
+            }
+        }
+    }
+
+    /// Bars and beats on top, time below. Dragging moves the playhead, shift + dragging sets the loop region.
+    fn ruler(&mut self, ui: &mut egui::Ui, project: &Project, ruler: Rect) {
+        let response = ui.interact(ruler, ui.id().with("ruler"), Sense::click_and_drag());
+        let painter = ui.painter_at(ruler);
+        let visuals = ui.visuals();
+        let middle = ruler.center().y;
+
+        painter.rect_filled(ruler, 0.0, visuals.window_fill);
+        painter.hline(ruler.x_range(), ruler.bottom(), Stroke::new(LINE_WIDTH, visuals.widgets.noninteractive.bg_stroke.color));
+
+        let step = bar_step(project, self.frames_per_point, MIN_LABEL_SPACING);
+        for bar in self.visible_bars(project, ruler, step) {
+            let x = self.x(ruler, bar as f64 * project.frames_per_bar());
+            painter.vline(x, ruler.top()..=middle, Stroke::new(LINE_WIDTH, visuals.text_color()));
+            painter.text(
+                pos2(x + 3.0, ruler.top() + 1.0),
+                Align2::LEFT_TOP,
+                (bar + 1).to_string(),
+                FontId::monospace(11.0),
+                visuals.text_color(),
+            );
+        }
+
+        let seconds_per_point = self.frames_per_point / project.sample_rate as f64;
+        let time_step = TIME_STEPS
+            .into_iter()
+            .find(|step| (step / seconds_per_point) as f32 >= MIN_LABEL_SPACING)
+            .unwrap_or(600.0);
+
+        let first = (project.seconds(self.start as u64) / time_step).floor() as u64;
+        let last = (project.seconds(self.frame(ruler, ruler.right()) as u64) / time_step).ceil() as u64;
+        for index in first..=last {
+            let seconds = index as f64 * time_step;
+            let x = self.x(ruler, seconds * project.sample_rate as f64);
+
+            painter.vline(x, middle + 6.0..=ruler.bottom(), Stroke::new(LINE_WIDTH, visuals.weak_text_color()));
+            painter.text(
+                pos2(x + 3.0, middle + 2.0),
+                Align2::LEFT_TOP,
+                format_time(seconds),
+                FontId::monospace(10.0),
+                visuals.weak_text_color(),
+            );
+        }
+
+        if response.drag_started() || response.clicked() {
+            let shift = ui.input(|i| i.modifiers.shift);
+            let frame = response
+                .interact_pointer_pos()
+                .map_or(0, |pointer| project.snap(self.frame(ruler, pointer.x), self.snap));
+
+            self.drag = Some(if shift { Drag::Loop { anchor: frame } } else { Drag::Playhead });
+        }
+    }
+
+    fn headers(&mut self, ui: &mut egui::Ui, project: &mut Project, mut mixer: Option<&mut Mixer>, headers: Rect) {
+        ui.painter().rect_filled(headers, 0.0, ui.visuals().panel_fill);
+
+        let mut remove = None;
+
+        for index in 0..project.tracks.len() {
+            let rect = self.track_rect(headers, index).shrink(4.0);
+            if !rect.intersects(headers) {
+                continue;
+            }
+
+            let track = &mut project.tracks[index];
+            let mut ui = ui.child_ui_with_id_source(rect, egui::Layout::top_down(egui::Align::Min), track.id, None);
+            ui.set_clip_rect(headers);
+
+            ui.horizontal(|ui| {
+                let name = ui.add(egui::TextEdit::singleline(&mut track.name).desired_width(HEADER_WIDTH - 40.0));
+                if name.changed() {
+                    self.edit = Some("Rename Track");
+                }
+                if ui.small_button("x").on_hover_text("Remove track").clicked() {
+                    remove = Some(track.id);
+                }
+            });
+
+            let state = track
+                .strip
+                .and_then(|id| mixer.as_deref()?.strip(id))
+                .map(|strip| (strip.id(), strip.is_muted(), strip.is_soloed(), strip.is_armed()));
+
+            if let (Some((id, mut mute, mut solo, mut arm)), Some(mixer)) = (state, mixer.as_deref_mut()) {
+                ui.horizontal(|ui| {
+                    if ui.toggle_value(&mut mute, "M").on_hover_text("Mute").changed() {
+                        report(mixer.set_mute(id, mute));
+                    }
+                    if ui.toggle_value(&mut solo, "S").on_hover_text("Solo").changed() {
+                        report(mixer.set_solo(id, solo));
+                    }
+                    if ui.toggle_value(&mut arm, "R").on_hover_text("Arm for recording").changed() {
+                        report(mixer.set_arm(id, arm));
+                    }
+                });
+            }
+        }
+
+        if let Some(id) = remove {
+            if let Some(track) = project.remove_track(id) {
+                if let (Some(strip), Some(mixer)) = (track.strip, mixer) {
+                    report(mixer.remove_strip(strip));
+                }
+                self.edit = Some("Remove Track");
+            }
+        }
+    }
+
+    /// Applies the drag started on the ruler or a clip, snapped to the grid
+    fn apply_drag(&mut self, ui: &mut egui::Ui, project: &mut Project, lanes: Rect) {
+        let Some(drag) = self.drag else {
+            return;
+        };
+
+        let (down, pointer) = ui.input(|i| (i.pointer.primary_down(), i.pointer.interact_pos()));
+        let Some(pointer) = pointer else {
+            return;
+        };
+
+        let frame = self.frame(lanes, pointer.x);
+        let snapped = project.snap(frame, self.snap);
+
+        match drag {
+            Drag::Playhead => project.playhead = snapped,
+            Drag::Loop { anchor } => {
+                let region = (anchor != snapped).then(|| anchor.min(snapped)..anchor.max(snapped));
+                if region != project.loop_region {
+                    project.loop_enabled = region.is_some();
+                    project.loop_region = region;
+                    self.edit = Some("Set Loop");
+                }
+            }
+            Drag::Move { clip, grab } => {
+                let index = self.track_at(lanes, pointer.y, project.tracks.len());
+                let track: Option<TrackId> = project.tracks.get(index).map(|track| track.id);
+                let start = project.snap(frame - grab, self.snap);
+
+                // Moving in place would still reorder clips that start together
+                let moved = project
+                    .clip(clip)
+                    .is_some_and(|(current, old)| Some(current) != track || old.start != start);
+
+                if let (Some(track), true) = (track, moved) {
+                    report(project.move_clip(clip, track, start));
+                    self.edit = Some("Move Clip");
+                }
+                ui.ctx().set_cursor_icon(CursorIcon::Grabbing);
+            }
+            Drag::TrimStart(clip) => {
+                if project.clip(clip).is_some_and(|(_, old)| old.start != snapped) {
+                    report(project.trim_start(clip, snapped));
+                    self.edit = Some("Trim Clip");
+                }
+                ui.ctx().set_cursor_icon(CursorIcon::ResizeHorizontal);
+            }
+            Drag::TrimEnd(clip) => {
+                report(project.trim_end(clip, snapped));
+                self.edit = Some("Trim Clip");
+                ui.ctx().set_cursor_icon(CursorIcon::ResizeHorizontal);
+            }
+        }
+
+        if !down {
+            self.drag = None;
+        }
+    }
+}
+
+/// Adds a track with its own mixer strip
+pub fn add_track(project: &mut Project, mixer: Option<&mut Mixer>, name: &str) -> TrackId {
+    let strip = mixer.and_then(|mixer| match mixer.add_strip(name, StripKind::Track) {
+        Ok(strip) => Some(strip),
+        Err(e) => {
+            error!("Could not add a mixer strip for {name}: {e}");
+            None
+        }
+    });
+
+    project.add_track(name, strip)
+}
+
+fn report(result: Result<()>) {
+    if let Err(e) = result {
+        error!("Arrangement: {e}");
+    }
+}
+
+/// Smallest power of two number of bars that are at least `spacing` points apart
+fn bar_step(project: &Project, frames_per_point: f64, spacing: f32) -> u64 {
+    let bar = (project.frames_per_bar() / frames_per_point) as f32;
+    let mut step = 1;
+    while bar * (step as f32) < spacing && step < 1 << 16 {
+        step *= 2;
+    }
+    step
+}
+
+/// Position as bar.beat, both counted from 1
+fn format_position(project: &Project, frame: u64) -> String {
+    let beats = frame as f64 / project.frames_per_beat();
+    let numerator = project.time_signature.numerator.max(1) as u64;
+    let beat = beats.floor() as u64;
+
+    format!("{}.{}", beat / numerator + 1, beat % numerator + 1)
+}
+
+fn format_time(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).floor();
+    format!("{}:{:05.2}", minutes, seconds - minutes * 60.0)
+}
@@ -0,0 +1,63 @@
+use crate::window::{Render, WindowContext};
+use anyhow::{anyhow, Result};
+use std::ffi::c_void;
+use voxea_vst::view::{ParentWindow, PlugView};
+use voxea_vst::ViewRect;
+use winit::dpi::PhysicalSize;
+use winit::event::WindowEvent;
+use winit::event_loop::ActiveEventLoop;
+use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use winit::window::Window as WinitWindow;
+
+/// Hosts the native editor of a VST3 plugin embedded into the window
+pub struct PluginEditor {
+    pub(crate) view: PlugView,
+}
+
+impl PluginEditor {
+    pub fn new(view: PlugView) -> Self {
+        Self { view }
+    }
+}
+
+impl Render for PluginEditor {
+    fn window_event(&mut self, cx: &mut WindowContext, _event_loop: &ActiveEventLoop, event: &WindowEvent) {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.view.set_size(ViewRect::new(size.width as i32, size.height as i32));
+            }
+
+            WindowEvent::Focused(focused) => self.view.set_focus(*focused),
+
+            WindowEvent::CloseRequested => self.view.detach(),
+
+            // Redraws are requested every frame, so the run loop of the plugin is serviced here
+            // instead of in `render` which only runs when egui wants to repaint
+            WindowEvent::RedrawRequested => {
+                self.view.poll();
+
+                if let Some(rect) = self.view.take_resize_request() {
+                    let size = PhysicalSize::new(rect.width().max(1) as u32, rect.height().max(1) as u32);
+
+                    // The view is told about its new size once the window is resized
+                    let _ = cx.window.window.request_inner_size(size);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, _cx: &mut WindowContext, _event_loop: &ActiveEventLoop) {}
+}
+
+/// Native handle of a window in the form plugin editors are embedded into
+pub fn parent_window(window: &WinitWindow) -> Result<ParentWindow> {
+    match window.window_handle()?.as_raw() {
+        RawWindowHandle::Win32(handle) => Ok(ParentWindow::Hwnd(handle.hwnd.get() as *mut c_void)),
+        RawWindowHandle::Xlib(handle) => Ok(ParentWindow::X11(handle.window as u64)),
+        RawWindowHandle::Xcb(handle) => Ok(ParentWindow::X11(handle.window.get() as u64)),
+        RawWindowHandle::AppKit(handle) => Ok(ParentWindow::NsView(handle.ns_view.as_ptr())),
+        handle => Err(anyhow!("Plugin editors can not be embedded into {handle:?}")),
+    }
+}
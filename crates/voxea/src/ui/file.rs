@@ -0,0 +1,396 @@
+//! Commands of the File menu: creating, opening and saving projects, importing audio and bouncing
+
+use crate::app::App;
+use crate::bounce::Bounce;
+use crate::inserts::{self, VstRemote};
+use crate::project::Project;
+use crate::project_file::{self, ProjectFile};
+use crate::ui::arrangement;
+use anyhow::{anyhow, Result};
+use log::{error, info, warn};
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use voxea_audio::file::AudioFile;
+use voxea_audio::mixer::{InsertId, InsertSlot, Mixer, StripKind, StripState};
+use voxea_vst::preset::Preset;
+
+/// Name of the project shown in the title bar
+pub fn project_name(app: &App) -> String {
+    app.project_path
+        .as_deref()
+        .and_then(Path::file_stem)
+        .map_or("Untitled".to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// Asks whether to save unsaved changes first, returns false if the user cancelled or saving failed
+pub fn confirm_discard(app: &mut App) -> bool {
+    if !app.is_dirty() {
+        return true;
+    }
+
+    let answer = MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("Save changes?")
+        .set_description(format!("Do you want to save the changes to {}?", project_name(app)))
+        .set_buttons(MessageButtons::YesNoCancel)
+        .show();
+
+    match answer {
+        MessageDialogResult::Yes => save(app, false),
+        MessageDialogResult::No => true,
+        _ => false,
+    }
+}
+
+/// Replaces the project with an empty one, returns false if the user kept the current one
+pub fn new_project(app: &mut App) -> bool {
+    if !confirm_discard(app) {
+        return false;
+    }
+
+    let sample_rate = app.audio.as_ref().map_or(app.project.sample_rate, |engine| engine.sample_rate());
+
+    if let Some(mixer) = app.mixer.as_mut() {
+        report("Could not reset the mixer", mixer.clear());
+    }
+
+    app.project = Project::new(sample_rate);
+    app.project_path = None;
+    app.history.clear();
+    app.checkpoint.reset(&app.project, app.mixer.as_ref());
+    app.mark_saved();
+
+    true
+}
+
+/// Opens a project, asking for the file if there is no `path`. Returns whether the project was replaced.
+pub fn open_project(app: &mut App, path: Option<PathBuf>) -> bool {
+    if !confirm_discard(app) {
+        return false;
+    }
+
+    let Some(path) = path.or_else(|| {
+        rfd::FileDialog::new()
+            .add_filter("Voxea Project", &[project_file::EXTENSION])
+            .pick_file()
+    }) else {
+        return false;
+    };
+
+    let file = match ProjectFile::load(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            // A project that was moved or deleted should not stay in the list, one that is unreadable for now should
+            if !path.exists() {
+                app.preferences.recent_projects.retain(|recent| recent != &path);
+                app.save_preferences();
+            }
+
+            show_error(&format!("Could not open {}", path.display()), e);
+            return false;
+        }
+    };
+
+    let mut project = file.project();
+
+    if let Some(engine) = app.audio.as_ref() {
+        if engine.sample_rate() != project.sample_rate {
+            warn!(
+                "{} is at {} Hz and plays at the engine rate of {} Hz",
+                path.display(),
+                project.sample_rate,
+                engine.sample_rate()
+            );
+        }
+    }
+
+    if let Some(mixer) = app.mixer.as_mut() {
+        let sample_rate = app.audio.as_ref().map_or(project.sample_rate, |engine| engine.sample_rate());
+        restore_mixer(mixer, &mut app.plugins, sample_rate as f64, &mut project, &file);
+    }
+
+    info!("Opened {}", path.display());
+
+    app.project = project;
+    app.project_path = Some(path.clone());
+    app.history.clear();
+    app.checkpoint.reset(&app.project, app.mixer.as_ref());
+    app.mark_saved();
+
+    app.preferences.add_recent_project(&path);
+    app.save_preferences();
+
+    true
+}
+
+/// Replaces the strips of the mixer with the saved ones, tracks saved without a strip get a new one
+fn restore_mixer(
+    mixer: &mut Mixer,
+    plugins: &mut FxHashMap<InsertId, VstRemote>,
+    sample_rate: f64,
+    project: &mut Project,
+    file: &ProjectFile,
+) {
+    report("Could not reset the mixer", mixer.clear());
+
+    let strips = file
+        .mixer
+        .iter()
+        .map(|state| StripState {
+            inserts: Vec::new(),
+            ..state.clone()
+        })
+        .collect::<Vec<_>>();
+    if !strips.is_empty() {
+        report("Could not restore the mixer", mixer.restore(&strips));
+    }
+
+    // Saved inserts get new processors and with them new ids
+    for state in &file.mixer {
+        for slot in &state.inserts {
+            let preset = file.preset(slot.id).and_then(|preset| {
+                preset
+                    .inspect_err(|e| warn!("Could not read the state of {}: {e}", slot.name))
+                    .ok()
+            });
+
+            let result = inserts::instantiate(slot, preset.as_ref(), sample_rate, true).and_then(|(processor, remote)| {
+                let insert = mixer.add_insert(state.id, slot.clone(), processor)?;
+                if let Some(remote) = remote {
+                    plugins.insert(insert, remote);
+                }
+                Ok(())
+            });
+            report(&format!("Could not load {}", slot.name), result);
+        }
+    }
+
+    for track in &mut project.tracks {
+        if track.strip.is_some_and(|strip| mixer.strip(strip).is_some()) {
+            continue;
+        }
+
+        track.strip = match mixer.add_strip(&track.name, StripKind::Track) {
+            Ok(strip) => Some(strip),
+            Err(e) => {
+                error!("Could not add a mixer strip for {}: {e}", track.name);
+                None
+            }
+        };
+    }
+}
+
+/// Saves the project to its file, asking for one if it has none yet or `save_as` is set. Returns whether it was saved.
+pub fn save(app: &mut App, save_as: bool) -> bool {
+    let path = match app.project_path.clone().filter(|_| !save_as) {
+        Some(path) => path,
+        None => {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("Voxea Project", &[project_file::EXTENSION])
+                .set_file_name(format!("{}.{}", project_name(app), project_file::EXTENSION))
+                .save_file()
+            else {
+                return false;
+            };
+
+            path
+        }
+    };
+
+    let mut file = ProjectFile::new(&app.project, app.mixer.as_ref());
+    let presets = presets(app);
+    if let Err(e) = file.save(&path, presets) {
+        show_error(&format!("Could not save {}", path.display()), e);
+        return false;
+    }
+
+    info!("Saved {}", path.display());
+
+    app.mark_saved();
+    app.project_path = Some(path.clone());
+
+    app.preferences.add_recent_project(&path);
+    app.save_preferences();
+
+    true
+}
+
+/// States of the VST3 inserts of the mixer, inserts whose state can not be read are saved without it
+fn presets(app: &mut App) -> Vec<(InsertId, Preset)> {
+    let slots = app
+        .mixer
+        .iter()
+        .flat_map(|mixer| mixer.strips())
+        .flat_map(|strip| strip.inserts());
+
+    slots
+        .filter_map(|slot| match app.plugins.get_mut(&slot.id)?.preset() {
+            Ok(preset) => Some((slot.id, preset)),
+            Err(e) => {
+                warn!("Saving {} without its state: {e}", slot.name);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Puts an audio file on a new track at the playhead, returns whether one was imported
+pub fn import_audio(app: &mut App) -> bool {
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("Audio", &["wav"])
+        .pick_file()
+    else {
+        return false;
+    };
+
+    let file = match AudioFile::open(&path) {
+        Ok(file) => Arc::new(file),
+        Err(err) => {
+            error!("Failed to import {}: {err}", path.display());
+            return false;
+        }
+    };
+
+    info!("Loaded {} with {} frames", path.display(), file.frames());
+
+    if file.sample_rate != app.project.sample_rate {
+        warn!(
+            "{} is at {} Hz and plays at the project rate of {} Hz",
+            path.display(),
+            file.sample_rate,
+            app.project.sample_rate
+        );
+    }
+
+    let name = path
+        .file_stem()
+        .map_or("Audio".to_string(), |name| name.to_string_lossy().into_owned());
+    let track = arrangement::add_track(&mut app.project, app.mixer.as_mut(), &name);
+
+    let playhead = app.project.playhead;
+    if let Err(err) = app.project.add_clip(track, file, playhead) {
+        error!("Failed to import {}: {err}", path.display());
+    }
+
+    true
+}
+
+/// Bounce rendering in the background, see [`export_audio`]
+pub struct Export {
+    path: PathBuf,
+    /// Share rendered so far, the bits of an `f32`
+    progress: Arc<AtomicU32>,
+    thread: JoinHandle<Result<()>>,
+}
+
+impl Export {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn progress(&self) -> f32 {
+        f32::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+}
+
+/// Bounces the project into a WAV file in the background. The inserts are new instances with the state of the
+/// running ones, see [`finish_export`] for the result.
+pub fn export_audio(app: &mut App) {
+    if let Some(export) = &app.export {
+        warn!("Still exporting {}", export.path.display());
+        return;
+    }
+
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter("WAV", &["wav"])
+        .set_file_name(format!("{}.wav", project_name(app)))
+        .save_file()
+    else {
+        return;
+    };
+
+    let strips = app.mixer.as_ref().map(Mixer::state).unwrap_or_default();
+    let sample_rate = app.project.sample_rate as f64;
+    let plugins = &mut app.plugins;
+
+    let instantiate = |slot: &InsertSlot| {
+        let preset = match plugins.get_mut(&slot.id).map(VstRemote::preset).transpose() {
+            Ok(preset) => preset,
+            Err(e) => {
+                warn!("Bouncing {} without its state: {e}", slot.name);
+                None
+            }
+        };
+
+        let (processor, _) = inserts::instantiate(slot, preset.as_ref(), sample_rate, false)?;
+        Ok(processor)
+    };
+
+    let bounce = match Bounce::new(&app.project, &strips, instantiate) {
+        Ok(bounce) => bounce,
+        Err(e) => {
+            show_error(&format!("Could not export {}", path.display()), e);
+            return;
+        }
+    };
+
+    let progress = Arc::new(AtomicU32::new(0));
+    let thread = {
+        let progress = progress.clone();
+        let path = path.clone();
+        thread::spawn(move || bounce.render(&path, |share| progress.store(share.to_bits(), Ordering::Relaxed)))
+    };
+
+    app.export = Some(Export { path, progress, thread });
+}
+
+/// Tells the user how the export went once it is done
+pub fn finish_export(app: &mut App) {
+    if !app.export.as_ref().is_some_and(|export| export.thread.is_finished()) {
+        return;
+    }
+    let Some(export) = app.export.take() else {
+        return;
+    };
+
+    let result = export
+        .thread
+        .join()
+        .unwrap_or_else(|_| Err(anyhow!("The bounce panicked")));
+
+    match result {
+        Ok(()) => {
+            info!("Exported {}", export.path.display());
+
+            MessageDialog::new()
+                .set_level(MessageLevel::Info)
+                .set_title("Voxea")
+                .set_description(format!("Exported {}", export.path.display()))
+                .set_buttons(MessageButtons::Ok)
+                .show();
+        }
+        Err(e) => show_error(&format!("Could not export {}", export.path.display()), e),
+    }
+}
+
+fn report(message: &str, result: Result<()>) {
+    if let Err(e) = result {
+        error!("{message}: {e}");
+    }
+}
+
+/// Tells the user about a failed file operation, not just the log
+fn show_error(message: &str, e: anyhow::Error) {
+    error!("{message}: {e}");
+
+    MessageDialog::new()
+        .set_level(MessageLevel::Error)
+        .set_title("Voxea")
+        .set_description(format!("{message}: {e}"))
+        .set_buttons(MessageButtons::Ok)
+        .show();
+}
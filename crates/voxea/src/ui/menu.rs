@@ -1,12 +1,13 @@
-use crate::ui::settings;
+use crate::ui::mixer::{MixerAction, MixerPanel};
+use crate::ui::{analyzer, file, plugin_ui, profiler, settings};
 use crate::window::{Render, WindowContext};
-use crate::{plugin, renderer, App};
-use egui::{pos2, Color32};
-use egui::load::SizedTexture;
-use log::info;
+use crate::ui::arrangement::ArrangementView;
+use crate::shortcuts::{self, ShortcutContext};
+use crate::App;
+use log::error;
+use std::path::PathBuf;
 use winit::dpi::PhysicalSize;
 use winit::event_loop::ActiveEventLoop;
-use winit::platform::windows::WindowExtWindows;
 use winit::window::WindowAttributes;
 
 pub fn init(cx: &mut App, event_loop: &ActiveEventLoop) {
@@ -23,7 +24,53 @@ pub fn init(cx: &mut App, event_loop: &ActiveEventLoop) {
 }
 
 #[derive(Default)]
-pub struct Menu {}
+pub struct Menu {
+    arrangement: ArrangementView,
+    mixer: MixerPanel,
+    show_mixer: bool,
+    /// Title of the window, updated with the project name and whether it has unsaved changes
+    title: String,
+}
+
+/// Menu entry showing the key bound to an action
+fn item(cx: &egui::Context, app: &App, text: &str, id: &str) -> egui::Button<'static> {
+    egui::Button::new(text.to_string()).shortcut_text(app.shortcuts.format(cx, id))
+}
+
+impl Menu {
+    /// Runs an action that edits the project as one undo step
+    fn edit(&mut self, cx: &mut WindowContext, id: &str) -> bool {
+        let app = &mut cx.app;
+
+        // Changes made since the last frame, e.g. by another window, are recorded on their own first
+        app.checkpoint.record(&mut app.history, None, &app.project, app.mixer.as_ref());
+
+        let edit = match id {
+            shortcuts::IMPORT_AUDIO => file::import_audio(app).then_some("Import Audio"),
+            shortcuts::TOGGLE_MIXER => {
+                self.show_mixer = !self.show_mixer;
+                None
+            }
+            shortcuts::SPLIT_CLIP => self.arrangement.split_selected(&mut app.project),
+            shortcuts::DELETE_CLIP => self.arrangement.delete_selected(&mut app.project),
+            _ => return false,
+        };
+
+        // Every key press is an undo step of its own
+        app.checkpoint.record(&mut app.history, edit, &app.project, app.mixer.as_ref());
+        app.history.seal();
+
+        cx.window.request_redraw();
+        true
+    }
+
+    /// Forgets the selection and drag, whose clips belonged to the previous project
+    fn project_replaced(&mut self, replaced: bool) {
+        if replaced {
+            self.arrangement = ArrangementView::default();
+        }
+    }
+}
 
 impl Render for Menu {
     fn render(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop) {
@@ -33,11 +80,29 @@ impl Render for Menu {
 
         let app = &mut cx.app;
 
-        window.draw_triangle();
-        let texture_id = window.fbo_id;
+        // Drawn with the layout of the previous frame, changes show up in the next one
+        self.arrangement.draw_waveforms(&app.project);
+
+        file::finish_export(app);
 
         let parent = window.window.clone();
 
+        let title = format!("{}{} - Voxea 0.1", file::project_name(app), if app.is_dirty() { "*" } else { "" });
+        if title != self.title {
+            window.window.set_title(&title);
+            self.title = title;
+        }
+
+        // Whatever the UI changes below becomes one undo step, changes made elsewhere since the last frame one of
+        // their own
+        app.checkpoint.record(&mut app.history, None, &app.project, app.mixer.as_ref());
+        let mut edit = None;
+        let mut released = false;
+        let mut undo = None;
+        // Commands of the File menu run after the edits of this frame are recorded, some replace the project
+        let mut command = None;
+        let mut recent: Option<PathBuf> = None;
+
         window.ui2(|cx| {
             egui::TopBottomPanel::top("top_panel")
                 .exact_height(40.0)
@@ -46,41 +111,195 @@ impl Render for Menu {
                     ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                         ui.visuals_mut().button_frame = false;
 
-                        let _ = ui.button("File");
+                        ui.menu_button("File", |ui| {
+                            let mut entry = |ui: &mut egui::Ui, app: &App, text: &str, id: &'static str| {
+                                if ui.add(item(cx, app, text, id)).clicked() {
+                                    ui.close_menu();
+                                    command = Some(id);
+                                }
+                            };
+
+                            entry(ui, app, "New", shortcuts::NEW_PROJECT);
+                            entry(ui, app, "Open...", shortcuts::OPEN_PROJECT);
+
+                            ui.menu_button("Recent Projects", |ui| {
+                                let recent_projects = &app.preferences.recent_projects;
+                                if recent_projects.is_empty() {
+                                    ui.weak("No recent projects");
+                                }
+
+                                for path in recent_projects {
+                                    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                                    if ui.button(name).on_hover_text(path.display().to_string()).clicked() {
+                                        ui.close_menu();
+                                        recent = Some(path.clone());
+                                    }
+                                }
+
+                                ui.separator();
+
+                                if ui.add_enabled(!recent_projects.is_empty(), egui::Button::new("Clear")).clicked() {
+                                    ui.close_menu();
+                                    app.preferences.recent_projects.clear();
+                                    app.save_preferences();
+                                }
+                            });
+
+                            ui.separator();
+                            entry(ui, app, "Save", shortcuts::SAVE_PROJECT);
+                            entry(ui, app, "Save As...", shortcuts::SAVE_PROJECT_AS);
+                            ui.separator();
+
+                            entry(ui, app, "Import Audio...", shortcuts::IMPORT_AUDIO);
+                            entry(ui, app, "Export Audio...", shortcuts::EXPORT_AUDIO);
+                            ui.separator();
+                            entry(ui, app, "Quit", shortcuts::QUIT);
+                        });
+
+                        ui.menu_button("Edit", |ui| {
+                            let name = app.history.undo_name().map_or("Undo".to_string(), |name| format!("Undo {name}"));
+                            if ui.add_enabled(app.history.undo_name().is_some(), item(cx, app, &name, shortcuts::UNDO)).clicked() {
+                                ui.close_menu();
+                                undo = Some(true);
+                            }
+
+                            let name = app.history.redo_name().map_or("Redo".to_string(), |name| format!("Redo {name}"));
+                            if ui.add_enabled(app.history.redo_name().is_some(), item(cx, app, &name, shortcuts::REDO)).clicked() {
+                                ui.close_menu();
+                                undo = Some(false);
+                            }
+                        });
                         let button = ui.button("Settings");
+                        let profile = ui.button("Profiler");
+                        let analyze = ui.button("Analyzer");
+                        ui.toggle_value(&mut self.show_mixer, "Mixer")
+                            .on_hover_text(app.shortcuts.format(cx, shortcuts::TOGGLE_MIXER));
                         let help = ui.button("Help");
 
+                        if let Some(export) = &app.export {
+                            let name = export.path().file_name().unwrap_or_default().to_string_lossy();
+                            ui.add(
+                                egui::ProgressBar::new(export.progress())
+                                    .desired_width(200.0)
+                                    .text(format!("Exporting {name}")),
+                            );
+
+                            // Polled until the bounce is done
+                            ui.ctx().request_repaint();
+                        }
+
                         if button.clicked() {
                             settings::init(app, event_loop, &parent);
+                        }
 
-                            // Disables the parent window
-                            parent.set_enable(false);
+                        if profile.clicked() {
+                            profiler::init(app, event_loop);
+                        }
+
+                        if analyze.clicked() {
+                            analyzer::init(app, event_loop);
                         }
                     });
                 });
 
+            if self.show_mixer {
+                egui::TopBottomPanel::bottom("mixer")
+                    .resizable(true)
+                    .default_height(420.0)
+                    .show(cx, |ui| {
+                        let sample_rate = app.audio.as_ref().map_or(48000, |engine| engine.sample_rate());
+
+                        let Some(mixer) = app.mixer.as_mut() else {
+                            ui.weak("No audio stream is running");
+                            return;
+                        };
+
+                        match self.mixer.show(ui, mixer, &mut app.plugins, sample_rate) {
+                            Some(MixerAction::OpenPlugin(id)) => {
+                                if let Err(e) = plugin_ui::init(app, event_loop, &id) {
+                                    error!("Could not open {id}: {e}");
+                                }
+                            }
+                            Some(MixerAction::OpenEditor(id)) => {
+                                if let Err(e) = app.open_insert_editor(event_loop, id) {
+                                    error!("Could not open the plugin editor: {e}");
+                                }
+                            }
+                            None => {}
+                        }
+                    });
+            }
+
             egui::CentralPanel::default().show(cx, |ui| {
-                let rect = egui::Rect {
-                    min: ui.cursor().left_top(),
-                    max: (ui.cursor().left_top() + ui.available_size()),
-                };
-                let uv = egui::Rect {
-                    min: pos2(0.0, 0.0),
-                    max: pos2(1.0, 1.0),
-                };
+                if let Some(name) = self.arrangement.show(ui, &mut app.project, app.mixer.as_mut()) {
+                    edit = Some(name);
+                }
+            });
 
-                let rend = renderer::get();
+            released = cx.input(|i| i.pointer.any_released());
+        });
 
-                if rend.textures.len() > 0 {
-                    let img = SizedTexture::new(rend.textures.get(0).unwrap().2, ui.available_size());
+        app.checkpoint.record(&mut app.history, edit, &app.project, app.mixer.as_ref());
 
-                    // info!("{:?}", rend.textures.get(0).unwrap());
+        // A drag ends with the button, the next one is a new undo step
+        if released {
+            app.history.seal();
+        }
 
-                    ui.add(egui::Image::new(img));
-                    // ui.painter().image(rend.textures.get(0).unwrap().2, rect, uv, Color32::from_rgba_premultiplied(255, 255, 255, 1));
+        // After recording, so undoing is not recorded as an edit itself
+        match undo {
+            Some(true) => app.undo(),
+            Some(false) => app.redo(),
+            None => {}
+        }
 
+        if let Some(path) = recent {
+            let replaced = file::open_project(app, Some(path));
+            self.project_replaced(replaced);
+        }
+
+        if let Some(id) = command {
+            self.action(cx, event_loop, id);
+        }
+    }
+
+    fn close_requested(&mut self, cx: &mut WindowContext, _event_loop: &ActiveEventLoop) -> bool {
+        // The project stays open in the other main windows
+        cx.app.open_menus() > 0 || file::confirm_discard(cx.app)
+    }
+
+    fn shortcut_context(&self) -> ShortcutContext {
+        ShortcutContext::Menu
+    }
+
+    fn action(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop, id: &str) -> bool {
+        let app = &mut cx.app;
+
+        match id {
+            shortcuts::NEW_PROJECT => {
+                let replaced = file::new_project(app);
+                self.project_replaced(replaced);
+            }
+            shortcuts::OPEN_PROJECT => {
+                let replaced = file::open_project(app, None);
+                self.project_replaced(replaced);
+            }
+            shortcuts::SAVE_PROJECT => {
+                file::save(app, false);
+            }
+            shortcuts::SAVE_PROJECT_AS => {
+                file::save(app, true);
+            }
+            shortcuts::EXPORT_AUDIO => file::export_audio(app),
+            shortcuts::QUIT => {
+                if file::confirm_discard(app) {
+                    event_loop.exit();
                 }
-            });
-        });
+            }
+            _ => return self.edit(cx, id),
+        }
+
+        app.request_redraw();
+        true
     }
 }
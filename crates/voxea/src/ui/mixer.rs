@@ -0,0 +1,401 @@
+use crate::inserts::{VstInsert, VstRemote, WasmInsert};
+use crate::plugin;
+use anyhow::Result;
+use egui::{vec2, Color32, Stroke};
+use log::error;
+use rustc_hash::FxHashMap;
+use voxea_audio::mixer::{self, InsertId, InsertKind, Meter, Mixer, Strip, StripId, StripKind, MAX_DB, MIN_DB};
+
+const STRIP_WIDTH: f32 = 110.0;
+const FADER_HEIGHT: f32 = 160.0;
+const METER_WIDTH: f32 = 6.0;
+const LINE_WIDTH: f32 = 1.0;
+
+const RMS_COLOR: Color32 = Color32::from_rgb(80, 180, 100);
+const PEAK_COLOR: Color32 = Color32::from_rgb(160, 220, 120);
+const HOLD_COLOR: Color32 = Color32::from_rgb(230, 200, 80);
+const CLIP_COLOR: Color32 = Color32::from_rgb(220, 60, 60);
+
+/// Something the panel can not do by itself because it needs the app
+pub enum MixerAction {
+    /// Opens the window of a WASM plugin by id
+    OpenPlugin(String),
+    /// Opens the editor of a VST3 insert
+    OpenEditor(InsertId),
+}
+
+/// Channel strips of the [`Mixer`], shown in the main window
+#[derive(Default)]
+pub struct MixerPanel {
+    tracks: usize,
+    buses: usize,
+}
+
+impl MixerPanel {
+    /// `plugins` gets the UI side of the VST3 inserts added in the panel
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        mixer: &mut Mixer,
+        plugins: &mut FxHashMap<InsertId, VstRemote>,
+        sample_rate: u32,
+    ) -> Option<MixerAction> {
+        let mut action = None;
+
+        ui.horizontal(|ui| {
+            if ui.button("Add Track").clicked() {
+                self.tracks += 1;
+                report(mixer.add_strip(&format!("Track {}", self.tracks), StripKind::Track).map(|_| ()));
+            }
+
+            if ui.button("Add Bus").clicked() {
+                self.buses += 1;
+                report(mixer.add_strip(&format!("Bus {}", self.buses), StripKind::Bus).map(|_| ()));
+            }
+
+            if mixer.is_soloed() {
+                ui.colored_label(HOLD_COLOR, "Solo active");
+            }
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                let ids = mixer.strips().iter().map(Strip::id).collect::<Vec<_>>();
+
+                // The master goes last, like on a console
+                for id in ids.iter().skip(1).chain(ids.first()) {
+                    ui.group(|ui| {
+                        ui.set_width(STRIP_WIDTH);
+                        ui.vertical(|ui| {
+                            if let Some(a) = strip(ui, mixer, plugins, *id, sample_rate) {
+                                action = Some(a);
+                            }
+                        });
+                    });
+                }
+            });
+        });
+
+        // Meters keep falling without input
+        ui.ctx().request_repaint();
+
+        action
+    }
+}
+
+fn report(result: Result<()>) {
+    if let Err(e) = result {
+        error!("Mixer: {e}");
+    }
+}
+
+fn strip(
+    ui: &mut egui::Ui,
+    mixer: &mut Mixer,
+    plugins: &mut FxHashMap<InsertId, VstRemote>,
+    id: StripId,
+    sample_rate: u32,
+) -> Option<MixerAction> {
+    let kind = mixer.strip(id)?.kind();
+
+    ui.horizontal(|ui| {
+        ui.strong(mixer.strip(id).map_or("", Strip::name));
+        if kind != StripKind::Master && ui.small_button("x").on_hover_text("Remove").clicked() {
+            report(mixer.remove_strip(id));
+        }
+    });
+
+    // Removed above, it is gone from the next frame on
+    mixer.strip(id)?;
+
+    ui.weak("Inserts");
+    let action = inserts(ui, mixer, plugins, id, sample_rate);
+
+    if kind == StripKind::Track {
+        ui.weak("Sends");
+        sends(ui, mixer, id);
+    }
+
+    if let Some(output) = mixer.strip(id)?.output() {
+        output_selector(ui, mixer, id, output);
+    }
+
+    let mut pan = mixer.strip(id)?.pan();
+    let pan_response = ui.add(egui::Slider::new(&mut pan, -1.0..=1.0).show_value(false).text("Pan"));
+    if pan_response.double_clicked() {
+        report(mixer.set_pan(id, 0.0));
+    } else if pan_response.changed() {
+        report(mixer.set_pan(id, pan));
+    }
+
+    let strip = mixer.strip(id)?;
+    let (mut mute, mut solo, mut arm) = (strip.is_muted(), strip.is_soloed(), strip.is_armed());
+
+    ui.horizontal(|ui| {
+        if ui.toggle_value(&mut mute, "M").on_hover_text("Mute").changed() {
+            report(mixer.set_mute(id, mute));
+        }
+
+        if kind == StripKind::Track {
+            if ui.toggle_value(&mut solo, "S").on_hover_text("Solo").changed() {
+                report(mixer.set_solo(id, solo));
+            }
+            if ui.toggle_value(&mut arm, "R").on_hover_text("Arm for recording").changed() {
+                report(mixer.set_arm(id, arm));
+            }
+        }
+    });
+
+    let strip = mixer.strip(id)?;
+    let mut gain = strip.gain();
+    let levels = *strip.meter();
+
+    ui.horizontal(|ui| {
+        // Vertical sliders are as long as horizontal ones are wide
+        ui.spacing_mut().slider_width = FADER_HEIGHT;
+
+        let fader = ui.add(
+            egui::Slider::new(&mut gain, MIN_DB..=MAX_DB)
+                .vertical()
+                .show_value(false)
+                .custom_formatter(|db, _| format_db(db as f32)),
+        );
+
+        if fader.double_clicked() {
+            report(mixer.set_gain(id, 0.0));
+        } else if fader.changed() {
+            report(mixer.set_gain(id, gain));
+        }
+
+        if meter(ui, &levels).clicked() {
+            report(mixer.reset_clip(id));
+        }
+    });
+
+    ui.monospace(format_db(gain));
+
+    action
+}
+
+fn output_selector(ui: &mut egui::Ui, mixer: &mut Mixer, id: StripId, output: StripId) {
+    let Some(kind) = mixer.strip(id).map(Strip::kind) else {
+        return;
+    };
+    let mut selected = output;
+
+    egui::ComboBox::from_id_source(("output", id))
+        .width(STRIP_WIDTH - 8.0)
+        .selected_text(mixer.strip(output).map_or("?", Strip::name))
+        .show_ui(ui, |ui| {
+            for target in mixer.strips() {
+                // Buses only go to the master so every strip is processed after its inputs
+                let allowed = matches!(
+                    (kind, target.kind()),
+                    (StripKind::Track, StripKind::Bus | StripKind::Master) | (StripKind::Bus, StripKind::Master)
+                );
+
+                if allowed {
+                    ui.selectable_value(&mut selected, target.id(), target.name());
+                }
+            }
+        });
+
+    if selected != output {
+        report(mixer.set_output(id, selected));
+    }
+}
+
+fn inserts(
+    ui: &mut egui::Ui,
+    mixer: &mut Mixer,
+    plugins: &mut FxHashMap<InsertId, VstRemote>,
+    id: StripId,
+    sample_rate: u32,
+) -> Option<MixerAction> {
+    let slots = mixer.strip(id)?.inserts().to_vec();
+    let mut action = None;
+
+    for (index, slot) in slots.iter().enumerate() {
+        ui.horizontal(|ui| {
+            let mut active = !slot.bypassed;
+            if ui.checkbox(&mut active, "").on_hover_text("Active").changed() {
+                report(mixer.set_bypass(id, index, !active));
+            }
+
+            let has_editor = match slot.kind {
+                InsertKind::Wasm(_) => true,
+                InsertKind::Vst3 { .. } => plugins.get(&slot.id).is_some_and(VstRemote::has_editor),
+            };
+
+            let name = ui
+                .add_enabled(has_editor, egui::Button::new(&slot.name).small().truncate())
+                .on_disabled_hover_text("Bridged plugins have no editor");
+            if name.clicked() {
+                action = Some(match &slot.kind {
+                    InsertKind::Wasm(plugin) => MixerAction::OpenPlugin(plugin.clone()),
+                    InsertKind::Vst3 { .. } => MixerAction::OpenEditor(slot.id),
+                });
+            }
+
+            if ui.small_button("x").clicked() {
+                report(mixer.remove_insert(id, index).map(|_| ()));
+            }
+        });
+    }
+
+    if slots.len() < mixer::MAX_INSERTS {
+        ui.menu_button("+ Insert", |ui| {
+            for plugin in plugin::plugin_ids() {
+                if ui.button(&plugin).clicked() {
+                    ui.close_menu();
+
+                    let (slot, insert) = WasmInsert::new(&plugin);
+                    report(mixer.add_insert(id, slot, Box::new(insert)).map(|_| ()));
+                }
+            }
+
+            ui.separator();
+
+            if ui.button("VST3...").clicked() {
+                ui.close_menu();
+
+                if let Some(bundle) = rfd::FileDialog::new().pick_folder() {
+                    let result = VstInsert::load(&bundle, sample_rate as f64).and_then(|(slot, insert)| {
+                        let remote = insert.remote();
+                        let insert = mixer.add_insert(id, slot, Box::new(insert))?;
+                        plugins.insert(insert, remote);
+                        Ok(())
+                    });
+                    report(result);
+                }
+            }
+        });
+    }
+
+    action
+}
+
+fn sends(ui: &mut egui::Ui, mixer: &mut Mixer, id: StripId) {
+    let Some(strip) = mixer.strip(id) else {
+        return;
+    };
+    let sends = strip.sends().to_vec();
+
+    for (index, send) in sends.iter().enumerate() {
+        ui.horizontal(|ui| {
+            let target = mixer.strip(send.target).map_or("?", Strip::name).to_string();
+            ui.label(target);
+
+            let mut level = send.level;
+            let mut pre_fader = send.pre_fader;
+
+            let level_changed = ui
+                .add(
+                    egui::DragValue::new(&mut level)
+                        .range(MIN_DB..=MAX_DB)
+                        .speed(0.2)
+                        .custom_formatter(|db, _| format_db(db as f32)),
+                )
+                .changed();
+            let pre_changed = ui.toggle_value(&mut pre_fader, "Pre").changed();
+
+            if level_changed || pre_changed {
+                report(mixer.set_send(id, index, level, pre_fader));
+            }
+
+            if ui.small_button("x").clicked() {
+                report(mixer.remove_send(id, index));
+            }
+        });
+    }
+
+    let buses = mixer
+        .strips()
+        .iter()
+        .filter(|strip| strip.kind() == StripKind::Bus)
+        .map(|strip| (strip.id(), strip.name().to_string()))
+        .collect::<Vec<_>>();
+
+    if sends.len() < mixer::MAX_SENDS {
+        ui.menu_button("+ Send", |ui| {
+            if buses.is_empty() {
+                ui.weak("Add a bus first");
+            }
+
+            for (bus, name) in buses {
+                if ui.button(name).clicked() {
+                    ui.close_menu();
+                    report(mixer.add_send(id, bus));
+                }
+            }
+        });
+    }
+}
+
+fn format_db(db: f32) -> String {
+    if db <= MIN_DB {
+        "-inf dB".to_string()
+    } else {
+        format!("{db:+.1} dB")
+    }
+}
+
+/// Height of a level in a meter of `height` points, from 0 at `MIN_DB` to `height` at `MAX_DB`
+fn meter_height(db: f32, height: f32) -> f32 {
+    ((db - MIN_DB) / (MAX_DB - MIN_DB)).clamp(0.0, 1.0) * height
+}
+
+/// Left and right peak/RMS meters with held peaks and a clip light, clicking resets the clip light
+fn meter(ui: &mut egui::Ui, meter: &Meter) -> egui::Response {
+    let size = vec2(METER_WIDTH * 2.0 + 2.0, FADER_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+    let painter = ui.painter();
+
+    let clip = egui::Rect::from_min_size(rect.min, vec2(rect.width(), METER_WIDTH));
+    painter.rect_filled(
+        clip,
+        1.0,
+        if meter.clipped { CLIP_COLOR } else { ui.visuals().extreme_bg_color },
+    );
+
+    let bars = egui::Rect::from_min_max(rect.min + vec2(0.0, METER_WIDTH + 2.0), rect.max);
+    let height = bars.height();
+
+    for channel in 0..2 {
+        let left = bars.left() + channel as f32 * (METER_WIDTH + 2.0);
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left, bars.top()),
+            egui::pos2(left + METER_WIDTH, bars.bottom()),
+        );
+        painter.rect_filled(bar, 1.0, ui.visuals().extreme_bg_color);
+
+        let level = |db| bar.bottom() - meter_height(db, height);
+
+        let mut rms = bar;
+        rms.set_top(level(meter.rms[channel]));
+        painter.rect_filled(rms, 1.0, RMS_COLOR);
+
+        let peak = level(meter.peak[channel]);
+        painter.hline(bar.x_range(), peak, Stroke::new(LINE_WIDTH, PEAK_COLOR));
+
+        let hold = level(meter.hold[channel]);
+        painter.hline(bar.x_range(), hold, Stroke::new(LINE_WIDTH, HOLD_COLOR));
+    }
+
+    // 0 dB mark
+    painter.hline(
+        bars.x_range(),
+        bars.bottom() - meter_height(0.0, height),
+        Stroke::new(LINE_WIDTH, ui.visuals().weak_text_color()),
+    );
+
+    response.on_hover_text(format!(
+        "Peak {} / {}\nRMS {} / {}",
+        format_db(meter.peak[0]),
+        format_db(meter.peak[1]),
+        format_db(meter.rms[0]),
+        format_db(meter.rms[1]),
+    ))
+}
@@ -1,2 +1,9 @@
+mod arrangement;
+pub mod analyzer;
+pub mod editor;
+pub mod file;
 pub mod menu;
-mod settings;
+mod mixer;
+pub mod plugin_ui;
+pub mod profiler;
+pub mod settings;
@@ -0,0 +1,301 @@
+use crate::app::App;
+use crate::history::{History, ParameterEdit};
+use crate::plugin;
+use crate::plugin::layout::{Layout, Widget};
+use crate::plugin::params::{ParameterInfo, ParameterStore};
+use crate::renderer;
+use crate::renderer::textures::TextureOwner;
+use crate::shortcuts::{self, ShortcutContext};
+use crate::window::{Render, WindowContext};
+use anyhow::{anyhow, Result};
+use egui::load::SizedTexture;
+use egui::{vec2, Color32, Stroke};
+use log::{error, warn};
+use std::f32::consts::PI;
+use std::time::Duration;
+use voxea_alloc::perf;
+use voxea_alloc::perf::PerfTrace;
+use winit::dpi::LogicalSize;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::WindowAttributes;
+
+const KNOB_SIZE: f32 = 48.0;
+const METER_SIZE: egui::Vec2 = vec2(12.0, 80.0);
+
+/// Angle of a knob at its minimum, the maximum is mirrored
+const KNOB_START: f32 = 0.75 * PI;
+const KNOB_SWEEP: f32 = 1.5 * PI;
+const KNOB_ARC_WIDTH: f32 = 3.0;
+
+/// How often windows with meters are redrawn, meters are set by the plugin at any time
+const METER_REFRESH: Duration = Duration::from_millis(33);
+
+/// Opens the window of a plugin with the UI from its layout
+pub fn init(cx: &mut App, event_loop: &ActiveEventLoop, plugin_id: &str) -> Result<()> {
+    let layout = plugin::layout(plugin_id)?.ok_or_else(|| anyhow!("{plugin_id} has no UI"))?;
+
+    let owner = TextureOwner::Plugin(plugin_id.to_string());
+    for image in &layout.images {
+        if let Err(e) = renderer::get_mut().load_texture(owner.clone(), &image.name, &image.data) {
+            warn!("Failed to load image {} of {plugin_id}: {e}", image.name);
+        }
+    }
+
+    let window_attributes = WindowAttributes::default()
+        .with_title(&layout.title)
+        .with_inner_size(LogicalSize::new(layout.width.max(100), layout.height.max(100)));
+
+    cx.open_window(
+        event_loop,
+        Some(window_attributes),
+        Some(Box::new(PluginUi {
+            id: plugin_id.to_string(),
+            meters: layout.nodes.iter().any(|node| matches!(node.widget, Widget::Meter(_))),
+            layout,
+        })),
+    )?;
+
+    Ok(())
+}
+
+/// Window showing the declarative UI of a plugin, changes go to its [`ParameterStore`]
+pub struct PluginUi {
+    id: String,
+    layout: Layout,
+    /// Whether the layout has meters, which need the window to be redrawn without input
+    meters: bool,
+}
+
+impl PluginUi {
+    /// Sets every parameter back to its default as one undo step
+    fn reset_parameters(&self, history: &mut History) {
+        history.begin("Reset Parameters");
+
+        plugin::with_parameters(&self.id, |parameters| {
+            for info in parameters.infos().to_vec() {
+                let Some(before) = parameters.get(info.id) else {
+                    continue;
+                };
+
+                if parameters.reset(info.id) {
+                    let after = parameters.get(info.id).unwrap_or(info.default_value);
+                    history.record(Box::new(ParameterEdit::new(&self.id, info.id, &info.name, before, after)));
+                }
+            }
+        });
+
+        history.commit();
+
+        if let Err(e) = plugin::flush_parameter_changes(&self.id) {
+            error!("Failed to send parameter changes to {}: {e}", self.id);
+        }
+    }
+
+    fn show_children(
+        &self,
+        ui: &mut egui::Ui,
+        parameters: &mut ParameterStore,
+        history: &mut History,
+        parent: Option<u32>,
+    ) {
+        for index in self.layout.children(parent) {
+            self.show_node(ui, parameters, history, index);
+        }
+    }
+
+    fn show_node(&self, ui: &mut egui::Ui, parameters: &mut ParameterStore, history: &mut History, index: usize) {
+        let parent = Some(index as u32);
+
+        match &self.layout.nodes[index].widget {
+            Widget::Row => {
+                ui.horizontal(|ui| self.show_children(ui, parameters, history, parent));
+            }
+            Widget::Column => {
+                ui.vertical(|ui| self.show_children(ui, parameters, history, parent));
+            }
+            Widget::Group(title) => {
+                ui.group(|ui| {
+                    ui.vertical(|ui| {
+                        ui.strong(title);
+                        self.show_children(ui, parameters, history, parent);
+                    });
+                });
+            }
+            Widget::Knob(id) => self.parameter(ui, parameters, history, *id, knob),
+            Widget::Slider(id) => self.parameter(ui, parameters, history, *id, |ui, info, value| {
+                ui.add(egui::Slider::new(value, info.min..=info.max).text(&info.name).suffix(unit(info)))
+            }),
+            Widget::Toggle(id) => self.parameter(ui, parameters, history, *id, |ui, info, value| {
+                let mut on = info.normalize(*value) >= 0.5;
+                let response = ui.checkbox(&mut on, &info.name);
+                if response.changed() {
+                    *value = if on { info.max } else { info.min };
+                }
+                response
+            }),
+            Widget::Meter(id) => self.parameter(ui, parameters, history, *id, meter),
+            Widget::Label(text) => {
+                ui.label(text);
+            }
+            Widget::Image(name) => {
+                let textures = &renderer::get().textures;
+                let texture = textures
+                    .find(&TextureOwner::Plugin(self.id.clone()), name)
+                    .and_then(|handle| textures.get(handle));
+
+                match texture {
+                    Some(texture) => {
+                        let [width, height] = texture.size();
+                        ui.image(SizedTexture::new(texture.id, vec2(width as f32, height as f32)));
+                    }
+                    None => {
+                        ui.weak(format!("Missing image {name}"));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Shows a widget for a parameter, writes the value back when it changes and records the edit
+    fn parameter(
+        &self,
+        ui: &mut egui::Ui,
+        parameters: &mut ParameterStore,
+        history: &mut History,
+        id: u32,
+        widget: impl FnOnce(&mut egui::Ui, &ParameterInfo, &mut f32) -> egui::Response,
+    ) {
+        let (Some(info), Some(before)) = (parameters.info(id), parameters.get(id)) else {
+            ui.weak(format!("Unknown parameter {id}"));
+            return;
+        };
+
+        let mut value = before;
+        let response = widget(ui, info, &mut value);
+
+        let changed = if response.double_clicked() {
+            parameters.reset(id)
+        } else {
+            response.changed() && parameters.set(id, value)
+        };
+
+        let after = parameters.get(id).filter(|&after| after != before);
+        if let (true, Some(info), Some(after)) = (changed, parameters.info(id), after) {
+            history.record(Box::new(ParameterEdit::new(&self.id, id, &info.name, before, after)));
+        }
+    }
+}
+
+fn unit(info: &ParameterInfo) -> String {
+    if info.unit.is_empty() {
+        String::new()
+    } else {
+        format!(" {}", info.unit)
+    }
+}
+
+/// Rotary control dragged vertically, double click resets it
+fn knob(ui: &mut egui::Ui, info: &ParameterInfo, value: &mut f32) -> egui::Response {
+    ui.vertical_centered(|ui| {
+        let (rect, mut response) = ui.allocate_exact_size(vec2(KNOB_SIZE, KNOB_SIZE), egui::Sense::click_and_drag());
+
+        if response.dragged() {
+            // The full range is one knob height per 200 points dragged, shift for fine adjustments
+            let speed = if ui.input(|i| i.modifiers.shift) { 1000.0 } else { 200.0 };
+            let normalized = info.normalize(*value) - response.drag_delta().y / speed;
+            *value = info.denormalize(normalized);
+            response.mark_changed();
+        }
+
+        let visuals = ui.style().interact(&response);
+        let center = rect.center();
+        let radius = rect.width() / 2.0 - 2.0;
+        let angle = KNOB_START + info.normalize(*value) * KNOB_SWEEP;
+
+        let painter = ui.painter();
+        painter.circle(center, radius, visuals.bg_fill, visuals.bg_stroke);
+
+        let arc = (0..=32)
+            .map(|i| {
+                let a = KNOB_START + (angle - KNOB_START) * i as f32 / 32.0;
+                center + radius * vec2(a.cos(), a.sin())
+            })
+            .collect();
+        painter.add(egui::Shape::line(arc, Stroke::new(KNOB_ARC_WIDTH, ui.visuals().selection.bg_fill)));
+        painter.line_segment(
+            [center, center + radius * 0.8 * vec2(angle.cos(), angle.sin())],
+            visuals.fg_stroke,
+        );
+
+        ui.label(&info.name);
+        ui.weak(format!("{:.2}{}", value, unit(info)));
+
+        response.on_hover_text(&info.name)
+    })
+    .inner
+}
+
+/// Vertical bar showing a value set by the plugin
+fn meter(ui: &mut egui::Ui, info: &ParameterInfo, value: &mut f32) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(METER_SIZE, egui::Sense::hover());
+
+    let mut level = rect;
+    level.set_top(rect.bottom() - rect.height() * info.normalize(*value));
+
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    painter.rect_filled(level, 2.0, Color32::from_rgb(100, 200, 120));
+
+    response.on_hover_text(format!("{}: {:.2}{}", info.name, value, unit(info)))
+}
+
+impl Render for PluginUi {
+    fn render(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop) {
+        perf::begin_perf!("plugin_ui::render");
+
+        let window = &mut cx.window;
+        let history = &mut cx.app.history;
+        let mut released = false;
+
+        window.ui2(|cx| {
+            egui::CentralPanel::default().show(cx, |ui| {
+                let shown = plugin::with_parameters(&self.id, |parameters| {
+                    self.show_children(ui, parameters, history, None);
+                });
+
+                if shown.is_none() {
+                    ui.weak(format!("{} is not loaded", self.id));
+                }
+            });
+
+            released = cx.input(|i| i.pointer.any_released());
+
+            if self.meters {
+                cx.request_repaint_after(METER_REFRESH);
+            }
+        });
+
+        if released {
+            history.seal();
+        }
+
+        if let Err(e) = plugin::flush_parameter_changes(&self.id) {
+            error!("Failed to send parameter changes to {}: {e}", self.id);
+        }
+    }
+
+    fn shortcut_context(&self) -> ShortcutContext {
+        ShortcutContext::Plugin
+    }
+
+    fn action(&mut self, cx: &mut WindowContext, _event_loop: &ActiveEventLoop, id: &str) -> bool {
+        match id {
+            shortcuts::RESET_PARAMETERS => {
+                self.reset_parameters(&mut cx.app.history);
+                cx.app.history.seal();
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::app::App;
+use crate::window::{Render, WindowContext};
+use egui::{pos2, vec2, Color32, Stroke};
+use log::error;
+use std::collections::VecDeque;
+use std::time::Instant;
+use voxea_alloc::perf;
+use voxea_alloc::perf::metrics;
+use voxea_alloc::perf::PerfTrace;
+use winit::dpi::PhysicalSize;
+use winit::event_loop::ActiveEventLoop;
+use winit::window::WindowAttributes;
+
+const GRAPH_HEIGHT: f32 = 80.0;
+
+const FRAME_COLOR: Color32 = Color32::from_rgb(100, 180, 255);
+const CALLBACK_COLOR: Color32 = Color32::from_rgb(120, 220, 120);
+const DEADLINE_COLOR: Color32 = Color32::from_rgb(230, 80, 80);
+const MEMORY_COLOR: Color32 = Color32::from_rgb(230, 180, 80);
+
+pub fn init(cx: &mut App, event_loop: &ActiveEventLoop) {
+    let window_attributes = WindowAttributes::default()
+        .with_title("Profiler")
+        .with_inner_size(PhysicalSize::new(600, 800));
+
+    if let Err(e) = cx.open_window(
+        event_loop,
+        Some(window_attributes),
+        Some(Box::new(Profiler::default())),
+    ) {
+        error!("Could not open the profiler: {e}");
+    }
+}
+
+/// Window with scrolling graphs of the frame time, audio callback, DSP load and memory usage
+#[derive(Default)]
+pub struct Profiler {
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<f64>,
+    physical_memory: VecDeque<f64>,
+    live_memory: VecDeque<f64>,
+}
+
+impl Profiler {
+    /// Samples the values that are measured by the profiler itself rather than published as metrics
+    fn sample(&mut self) {
+        let now = Instant::now();
+
+        if let Some(last_frame) = self.last_frame {
+            push(&mut self.frame_times, (now - last_frame).as_secs_f64() * 1000.0);
+        }
+        self.last_frame = Some(now);
+
+        let (physical, _) = perf::total_memory();
+        push(&mut self.physical_memory, physical as f64 / 1_000_000.0);
+        push(&mut self.live_memory, perf::leaks::totals().live as f64 / 1_000_000.0);
+    }
+}
+
+fn push(history: &mut VecDeque<f64>, value: f64) {
+    if history.len() == metrics::HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+/// Draws a scrolling line graph of one or more series, scaled to the largest value or `min_max`
+fn graph(ui: &mut egui::Ui, title: &str, unit: &str, series: &[(&str, &[f64], Color32)], min_max: f64) {
+    let max = series
+        .iter()
+        .flat_map(|(_, values, _)| values.iter().copied())
+        .fold(min_max, f64::max);
+
+    ui.horizontal(|ui| {
+        ui.strong(title);
+
+        for (name, values, color) in series {
+            if let Some(last) = values.last() {
+                ui.colored_label(*color, format!("{name}: {last:.2} {unit}"));
+            }
+        }
+    });
+
+    let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), GRAPH_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    painter.text(
+        rect.right_top() + vec2(-4.0, 2.0),
+        egui::Align2::RIGHT_TOP,
+        format!("{max:.1} {unit}"),
+        egui::FontId::monospace(10.0),
+        ui.visuals().weak_text_color(),
+    );
+
+    let step = rect.width() / (metrics::HISTORY - 1) as f32;
+
+    for (_, values, color) in series {
+        // Newest values are drawn at the right edge so the graph scrolls to the left
+        let offset = metrics::HISTORY.saturating_sub(values.len());
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                let x = rect.left() + (offset + i) as f32 * step;
+                let y = rect.bottom() - (value / max) as f32 * rect.height();
+                pos2(x, y.clamp(rect.top(), rect.bottom()))
+            })
+            .collect::<Vec<_>>();
+
+        if points.len() > 1 {
+            painter.add(egui::Shape::line(points, Stroke::new(1.5f32, *color)));
+        }
+    }
+
+    ui.add_space(4.0);
+}
+
+impl Render for Profiler {
+    fn render(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop) {
+        perf::begin_perf!("profiler::render");
+
+        self.sample();
+
+        let window = &mut cx.window;
+
+        window.ui2(|cx| {
+            egui::CentralPanel::default().show(cx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let frame_times = self.frame_times.make_contiguous();
+                    graph(ui, "Frame Time", "ms", &[("Frame", frame_times, FRAME_COLOR)], 16.7);
+
+                    ui.separator();
+
+                    let callback = metrics::history(metrics::AUDIO_CALLBACK);
+                    let deadline = metrics::history(metrics::AUDIO_DEADLINE);
+
+                    if callback.is_empty() {
+                        ui.strong("Audio");
+                        ui.weak("No audio stream is running");
+                    } else {
+                        graph(
+                            ui,
+                            "Audio Callback",
+                            "ms",
+                            &[("Callback", &callback, CALLBACK_COLOR), ("Deadline", &deadline, DEADLINE_COLOR)],
+                            1.0,
+                        );
+
+                        let xruns = metrics::history(metrics::AUDIO_XRUNS);
+                        graph(ui, "Xruns", "", &[("Total", &xruns, DEADLINE_COLOR)], 1.0);
+                    }
+
+                    for (title, prefix) in [("DSP Load per Node", metrics::DSP_NODE), ("DSP Load per Plugin", metrics::DSP_PLUGIN)] {
+                        let names = metrics::names(prefix);
+                        if names.is_empty() {
+                            continue;
+                        }
+
+                        ui.separator();
+                        ui.heading(title);
+
+                        for name in names {
+                            let load = metrics::history(&name);
+                            let label = name.strip_prefix(prefix).unwrap_or(&name);
+                            graph(ui, label, "%", &[("Load", &load, CALLBACK_COLOR)], 100.0);
+                        }
+                    }
+
+                    ui.separator();
+
+                    let physical = self.physical_memory.make_contiguous();
+                    graph(ui, "Process Memory", "MB", &[("Physical", physical, MEMORY_COLOR)], 1.0);
+
+                    let live = self.live_memory.make_contiguous();
+                    graph(ui, "Heap Memory", "MB", &[("Live", live, MEMORY_COLOR)], 1.0);
+                });
+            });
+
+            // Keeps the graphs scrolling even without input
+            cx.request_repaint();
+        });
+    }
+}
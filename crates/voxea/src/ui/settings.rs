@@ -2,35 +2,26 @@ use crate::app::App;
 use crate::window::{Render, WindowContext};
 use cpal::traits::DeviceTrait;
 use egui::vec2;
-use log::info;
+use log::{error, info};
 use voxea_alloc::perf;
 use voxea_alloc::perf::PerfTrace;
 use winit::dpi::PhysicalSize;
-use winit::event::WindowEvent;
 use winit::event_loop::ActiveEventLoop;
-use winit::platform::windows::{WindowAttributesExtWindows, WindowExtWindows};
-use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use winit::window::{Window as WinitWindow, WindowAttributes, WindowId};
+use winit::window::{Window as WinitWindow, WindowAttributes};
 use crate::plugin;
+use crate::ui::plugin_ui;
+use crate::renderer;
+use crate::renderer::textures::TextureOwner;
+use crate::history::SettingsEdit;
+use crate::shortcuts::{self, KeyBinding};
+use egui::load::SizedTexture;
 
 pub fn init(cx: &mut App, event_loop: &ActiveEventLoop, parent: &WinitWindow) {
-    let window_handle = parent.window_handle().unwrap().as_raw();
-
-    let mut window_attributes = WindowAttributes::default()
+    let window_attributes = WindowAttributes::default()
         .with_title("Settings")
         .with_inner_size(PhysicalSize::new(800.0, 600.0))
         .with_visible(true);
 
-    let hwnd = match window_handle {
-        RawWindowHandle::Win32(handle) => handle.hwnd.get(),
-        _ => todo!("Not running on Windows"),
-    };
-
-    // Sets the owner window to the parent, so it becomes a modal
-    window_attributes = window_attributes
-        .with_owner_window(hwnd)
-        .with_clip_children(false);
-
     let hosts = voxea_audio::enumerate_hosts();
 
     let inputs = voxea_audio::enumerate_input_devices(hosts.get(0).unwrap())
@@ -66,23 +57,18 @@ pub fn init(cx: &mut App, event_loop: &ActiveEventLoop, parent: &WinitWindow) {
         selected_output,
         plugins_path: dirs::config_dir()
             .unwrap()
-            .join("Voxea\\Plugins")
+            .join("Voxea")
+            .join("Plugins")
             .to_str()
             .unwrap()
             .to_string(),
         // plugins_path: "C:\\Users\\William\\AppData\\Roaming\\Voxea\\Plugins".to_string(),
-        parent_window: Some(parent.id()),
+        recording: None,
     };
 
-    let window_id = cx
-        .open_window(
-            event_loop,
-            Some(window_attributes),
-            Some(Box::new(settings)),
-        )
+    // Opens as a modal, so the parent is disabled until the settings are closed
+    cx.open_modal(event_loop, parent, window_attributes, Some(Box::new(settings)))
         .unwrap();
-
-    // cx.set_enable_all_other_windows(&window_id, false);
 }
 
 #[derive(Default)]
@@ -94,7 +80,8 @@ pub struct Settings {
     pub(crate) outputs: Vec<String>,
     pub(crate) selected_output: String,
     pub(crate) plugins_path: String,
-    pub(crate) parent_window: Option<WindowId>,
+    /// Action waiting for the key to bind it to
+    pub(crate) recording: Option<String>,
 }
 
 impl Settings {
@@ -126,29 +113,121 @@ impl Settings {
     }
 }
 
-impl Render for Settings {
-    fn window_event(
-        &mut self,
-        cx: &mut WindowContext,
-        event_loop: &ActiveEventLoop,
-        event: &WindowEvent,
-    ) {
-        match event {
-            WindowEvent::CloseRequested => {
-                // Re-enables the parent window
-                cx.app
-                    .get_window(&self.parent_window.unwrap())
-                    .unwrap()
-                    .window
-                    .set_enable(true);
-                // cx.app.set_enable_all_other_windows(&cx.window.window.id(), true);
+impl Settings {
+    /// Lists every action with its binding. Clicking a binding records the next key pressed. Changes are undo
+    /// steps of their own.
+    fn shortcuts(&mut self, ui: &mut egui::Ui, app: &mut App) {
+        app.shortcuts.set_plugin_actions(plugin::actions());
+
+        // Name of the change and the bindings from before it
+        let mut changed = None;
+        let actions = app.shortcuts.actions().to_vec();
+
+        egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+            for action in &actions {
+                ui.label(&action.name).on_hover_text(&action.id);
+                ui.weak(action.plugin.as_deref().unwrap_or(action.context.name()));
+
+                let binding = app.shortcuts.binding(&action.id);
+
+                if self.recording.as_ref() == Some(&action.id) {
+                    let response = ui.add(egui::Button::new("Press a key...").selected(true));
+
+                    // While focused the key goes to the editor instead of triggering actions
+                    response.request_focus();
+                    ui.memory_mut(|memory| {
+                        memory.set_focus_lock_filter(
+                            response.id,
+                            egui::EventFilter {
+                                tab: true,
+                                horizontal_arrows: true,
+                                vertical_arrows: true,
+                                escape: true,
+                            },
+                        )
+                    });
+
+                    let pressed = ui.input(|i| {
+                        i.events.iter().find_map(|event| match event {
+                            egui::Event::Key {
+                                key,
+                                physical_key,
+                                pressed: true,
+                                repeat: false,
+                                modifiers,
+                            } => Some((physical_key.unwrap_or(*key), *modifiers)),
+                            _ => None,
+                        })
+                    });
+
+                    match pressed {
+                        Some((egui::Key::Escape, modifiers)) if modifiers.is_none() => {
+                            self.recording = None;
+                        }
+                        Some((key, modifiers)) => {
+                            changed = Some(("Change Shortcut", app.shortcuts.overrides().clone()));
+                            app.shortcuts.set_binding(&action.id, Some(KeyBinding::from_egui(key, modifiers)));
+                            self.recording = None;
+                        }
+                        None if response.clicked_elsewhere() => self.recording = None,
+                        None => {}
+                    }
+
+                    if self.recording.is_none() {
+                        response.surrender_focus();
+                    }
+                } else {
+                    let text = binding.map_or("None".to_string(), |binding| ui.ctx().format_shortcut(&binding.shortcut()));
+                    if ui.button(text).on_hover_text("Click and press a key to change").clicked() {
+                        self.recording = Some(action.id.clone());
+                    }
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!app.shortcuts.is_default(&action.id), egui::Button::new("Reset").small()).clicked() {
+                        changed = Some(("Reset Shortcut", app.shortcuts.overrides().clone()));
+                        app.shortcuts.reset(&action.id);
+                    }
+
+                    if ui.add_enabled(binding.is_some(), egui::Button::new("Clear").small()).clicked() {
+                        changed = Some(("Clear Shortcut", app.shortcuts.overrides().clone()));
+                        app.shortcuts.set_binding(&action.id, None);
+                    }
+                });
+
+                let conflicts = binding
+                    .map(|binding| app.shortcuts.conflicts(&action.id, binding))
+                    .unwrap_or_default();
+                if !conflicts.is_empty() {
+                    let names = conflicts.iter().map(|action| action.name.as_str()).collect::<Vec<_>>();
+                    ui.colored_label(ui.visuals().error_fg_color, format!("Also bound to {}", names.join(", ")));
+                }
+
+                ui.end_row();
             }
-            _ => {}
+        });
+
+        if ui.button("Reset All").clicked() {
+            changed = Some(("Reset All Shortcuts", app.shortcuts.overrides().clone()));
+            app.shortcuts.reset_all();
+        }
+
+        if let Some((name, before)) = changed {
+            let after = app.shortcuts.overrides().clone();
+            if before != after {
+                app.history.record(Box::new(SettingsEdit::new(name, before, after)));
+                app.history.seal();
+            }
+
+            app.save_preferences();
         }
     }
+}
 
+impl Render for Settings {
     fn render(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop) {
         let window = &mut cx.window;
+        let app = &mut cx.app;
         let inner_size = window
             .window
             .inner_size()
@@ -260,6 +339,39 @@ impl Render for Settings {
                             }
                         });
                         ui.label(format!("{} plugins loaded", plugin::get_plugins()));
+
+                        for id in plugin::plugin_ids() {
+                            ui.horizontal(|ui| {
+                                let textures = &renderer::get().textures;
+                                let icon = textures
+                                    .find(&TextureOwner::Plugin(id.clone()), "icon")
+                                    .and_then(|handle| textures.get(handle));
+
+                                if let Some(icon) = icon {
+                                    ui.image(SizedTexture::new(icon.id, vec2(16.0, 16.0)));
+                                }
+
+                                ui.label(&id);
+
+                                if ui.small_button("Open").clicked() {
+                                    if let Err(e) = plugin_ui::init(app, event_loop, &id) {
+                                        error!("Could not open {id}: {e}");
+                                    }
+                                }
+
+                                if ui.small_button("Unload").clicked() {
+                                    if let Err(e) = plugin::unload_plugin(&id) {
+                                        error!("Could not unload {id}: {e}");
+                                    }
+                                }
+                            });
+                        }
+                    });
+
+                    ui.group(|ui| {
+                        ui.heading("Shortcuts:");
+                        self.shortcuts(ui, app);
+                        ui.allocate_space(vec2(ui.available_width(), 0.0));
                     });
 
                     // StripBuilder::new(ui)
@@ -277,29 +389,78 @@ impl Render for Settings {
                     ui.group(|ui| {
                         ui.heading("Debug Info:");
 
-                        for entry in perf::registry() {
-                            let stats = entry.1;
-
-                            ui.strong(*entry.0);
-
-                            ui.label(format!(
-                                "- Time Elapsed: {:.2} ms",
-                                stats.timer.last_elapsed as f64 / 1000.0
-                            ));
-                            ui.label(format!(
-                                "- Memory Allocated: {:.2} KB",
-                                stats.memory.allocated as f64 / 1000.0
-                            ));
-                            ui.label(format!(
-                                "- Memory Freed: {:.2} KB",
-                                stats.memory.freed as f64 / 1000.0
-                            ));
-                            ui.label(format!(
-                                "- Peak Memory Usage: {:.2} KB",
-                                stats.memory.peak_usage as f64 / 1000.0
-                            ));
+                        for root in perf::tree() {
+                            for node in root.iter() {
+                                ui.indent(node.depth, |ui| {
+                                    ui.strong(node.region);
+
+                                    ui.label(format!(
+                                        "- Calls: {}, Mean: {:.2} ms, P95: {:.2} ms, Max: {:.2} ms",
+                                        node.calls,
+                                        node.timing.mean / 1000.0,
+                                        node.timing.p95 as f64 / 1000.0,
+                                        node.timing.max as f64 / 1000.0
+                                    ));
+                                    ui.label(format!(
+                                        "- Time Inclusive: {:.2} ms, Exclusive: {:.2} ms",
+                                        node.inclusive_time as f64 / 1000.0,
+                                        node.exclusive_time as f64 / 1000.0
+                                    ));
+                                    ui.label(format!(
+                                        "- Memory Allocated Inclusive: {:.2} KB, Exclusive: {:.2} KB",
+                                        node.inclusive.allocated as f64 / 1000.0,
+                                        node.exclusive.allocated as f64 / 1000.0
+                                    ));
+                                    ui.label(format!(
+                                        "- Memory Freed Inclusive: {:.2} KB, Exclusive: {:.2} KB",
+                                        node.inclusive.freed as f64 / 1000.0,
+                                        node.exclusive.freed as f64 / 1000.0
+                                    ));
+                                    ui.label(format!(
+                                        "- Peak Memory Usage: {:.2} KB, Allocations: {}",
+                                        node.inclusive.peak_usage as f64 / 1000.0,
+                                        node.inclusive.allocations
+                                    ));
+                                });
+                            }
                         }
 
+                        ui.horizontal(|ui| {
+                            if perf::trace::is_recording() {
+                                ui.label(format!("Recording trace, {} events", perf::trace::len()));
+
+                                if ui.button("Stop and Save Trace").clicked() {
+                                    perf::trace::stop();
+
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .set_file_name("trace.json")
+                                        .add_filter("Chrome Trace", &["json"])
+                                        .save_file()
+                                    {
+                                        if let Err(e) = perf::trace::write(&path) {
+                                            error!("Could not write perf trace: {e}");
+                                        }
+                                    }
+                                }
+                            } else if ui.button("Record Trace").clicked() {
+                                perf::trace::start(perf::trace::DEFAULT_CAPACITY);
+                            }
+                        });
+
+                        let totals = perf::leaks::totals();
+                        ui.label(format!(
+                            "Live: {:.2} KB, Peak: {:.2} KB, Allocations: {}, Deallocations: {}",
+                            totals.live as f64 / 1000.0,
+                            totals.peak as f64 / 1000.0,
+                            totals.allocations,
+                            totals.deallocations
+                        ));
+                        ui.collapsing("Allocation Sizes", |ui| {
+                            for (class, count) in totals.histogram.classes() {
+                                ui.label(format!("{class}: {count}"));
+                            }
+                        });
+
                         let stats = perf::total_memory();
                         ui.label(format!("{}, {}", stats.0, stats.1));
                         ui.allocate_space(vec2(ui.available_width(), 0.0));
@@ -310,4 +471,9 @@ impl Render for Settings {
             });
         });
     }
+
+    fn action(&mut self, _cx: &mut WindowContext, _event_loop: &ActiveEventLoop, id: &str) -> bool {
+        // Already open
+        id == shortcuts::SETTINGS
+    }
 }
@@ -1,14 +1,15 @@
 use crate::renderer::RenderContext;
-use crate::ui::menu;
-use crate::{renderer, App};
+use crate::shortcuts::{self, KeyBinding, ShortcutContext};
+use crate::ui::{analyzer, menu, profiler, settings};
+use crate::{plugin, renderer, App};
 use anyhow::Result;
 use egui::ViewportBuilder;
 use egui_winit::{apply_viewport_builder_to_window, create_winit_window_attributes};
-use log::{info, warn};
+use log::{error, info, warn};
 use std::sync::Arc;
 use winit::event::{ElementState, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
-use winit::keyboard::KeyCode;
+use winit::keyboard::{ModifiersState, PhysicalKey};
 use winit::window::WindowAttributes;
 use winit::{dpi::PhysicalSize, window::Window as WinitWindow};
 
@@ -26,6 +27,21 @@ pub trait Render: 'static {
     ) {
     }
     fn render(&mut self, cx: &mut WindowContext, event_loop: &ActiveEventLoop);
+
+    /// Called when the user closes the window, returning false keeps it open, e.g. to save changes first
+    fn close_requested(&mut self, _cx: &mut WindowContext, _event_loop: &ActiveEventLoop) -> bool {
+        true
+    }
+
+    /// Which key bindings apply in the window besides the global ones
+    fn shortcut_context(&self) -> ShortcutContext {
+        ShortcutContext::Global
+    }
+
+    /// Runs an action of [`Render::shortcut_context`], returns false if the window does not handle it
+    fn action(&mut self, _cx: &mut WindowContext, _event_loop: &ActiveEventLoop, _id: &str) -> bool {
+        false
+    }
 }
 
 /// A wrapper around the winit window
@@ -44,6 +60,8 @@ pub struct Window {
     pub(crate) egui_state: egui_winit::State,
 
     pub(crate) running: bool,
+    /// Modifier keys held down, for shortcuts
+    pub(crate) modifiers: ModifiersState,
 
 
     // winit bug where random resize events are sent on startup. https://github.com/rust-windowing/winit/issues/2094
@@ -177,6 +195,7 @@ impl Window {
             egui_state,
 
             running: true,
+            modifiers: ModifiersState::empty(),
             init: false,
         })
     }
@@ -203,7 +222,7 @@ impl Window {
 
         match event {
             WindowEvent::CloseRequested => {
-                self.running = false;
+                self.close(cx, event_loop);
             }
 
             WindowEvent::Resized(size) => {
@@ -215,17 +234,35 @@ impl Window {
                 }
             }
 
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+
             WindowEvent::KeyboardInput {
                 event,
                 is_synthetic,
                 ..
             } => {
-                if !is_synthetic
-                    && !event.repeat
-                    && event.state == ElementState::Pressed
-                    && event.physical_key == KeyCode::KeyG
-                {
-                    menu::init(cx, event_loop);
+                // Text fields keep their own keys, e.g. for undo, and so does the shortcut editor
+                let typing = self.egui_state.egui_ctx().wants_keyboard_input();
+
+                if !is_synthetic && !event.repeat && !typing && event.state == ElementState::Pressed {
+                    let binding = match event.physical_key {
+                        PhysicalKey::Code(key) => KeyBinding::from_winit(key, self.modifiers),
+                        PhysicalKey::Unidentified(_) => None,
+                    };
+
+                    // Plugins load in the background and can be unloaded at any time
+                    cx.shortcuts.set_plugin_actions(plugin::actions());
+
+                    let context = self.view.as_ref().map_or(ShortcutContext::Global, |view| view.shortcut_context());
+                    let action = binding
+                        .and_then(|binding| cx.shortcuts.lookup(context, binding))
+                        .map(|action| action.id.clone());
+
+                    if let Some(action) = action {
+                        self.run_action(cx, event_loop, &action);
+                    }
                 }
             }
 
@@ -250,6 +287,60 @@ impl Window {
         response
     }
 
+    /// Closes the window unless its view wants to keep it open
+    pub fn close(&mut self, cx: &mut App, event_loop: &ActiveEventLoop) {
+        if let Some(mut view) = self.view.take() {
+            let mut cx = WindowContext {
+                app: cx,
+                window: self,
+            };
+
+            let close = view.close_requested(&mut cx, event_loop);
+            self.view = Some(view);
+
+            if !close {
+                return;
+            }
+        }
+
+        self.running = false;
+    }
+
+    /// Runs an action bound to a key, the view gets the first chance to handle it
+    pub fn run_action(&mut self, cx: &mut App, event_loop: &ActiveEventLoop, id: &str) {
+        if let Some(mut view) = self.view.take() {
+            let mut cx = WindowContext {
+                app: cx,
+                window: self,
+            };
+
+            let handled = view.action(&mut cx, event_loop, id);
+            self.view = Some(view);
+
+            if handled {
+                return;
+            }
+        }
+
+        match id {
+            shortcuts::UNDO => cx.undo(),
+            shortcuts::REDO => cx.redo(),
+            shortcuts::NEW_WINDOW => menu::init(cx, event_loop),
+            shortcuts::CLOSE_WINDOW => self.close(cx, event_loop),
+            shortcuts::SETTINGS => settings::init(cx, event_loop, &self.window),
+            shortcuts::PROFILER => profiler::init(cx, event_loop),
+            shortcuts::ANALYZER => analyzer::init(cx, event_loop),
+            _ => match shortcuts::parse_plugin_action(id) {
+                Some((plugin, action)) => {
+                    if let Err(e) = plugin::run_action(plugin, action) {
+                        error!("Could not run {action} of {plugin}: {e}");
+                    }
+                }
+                None => warn!("No window handles the action {id}"),
+            },
+        }
+    }
+
     pub fn resize(&mut self, new_size: &PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             let cx = renderer::get();
@@ -373,6 +464,41 @@ impl Window {
         render_context.queue.submit(Some(encoder.finish()));
     }
 
+    /// Recreates the FBO with a new size, keeping its egui texture id
+    pub fn resize_fbo(&mut self, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if self.fbo.width() == width && self.fbo.height() == height {
+            return;
+        }
+
+        let render_context = renderer::get_mut();
+
+        self.fbo = render_context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("FBO Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[wgpu::TextureFormat::Bgra8UnormSrgb],
+        });
+        self.fbo_view = self.fbo.create_view(&wgpu::TextureViewDescriptor::default());
+
+        render_context.renderer.update_egui_texture_from_wgpu_texture(
+            &render_context.device,
+            &self.fbo_view,
+            wgpu::FilterMode::Linear,
+            self.fbo_id,
+        );
+    }
+
     pub fn render(&mut self, window: &WinitWindow) {
         let render_context = renderer::get_mut();
         let input = self.egui_state.take_egui_input(&window);
@@ -1,11 +1,11 @@
-#![feature(once_cell_get_mut)]
-
 pub mod perf;
+pub mod ring;
+pub mod rt;
 
 use std::alloc::{GlobalAlloc, Layout};
 
 /// Custom global allocator which uses the [`MiMalloc`] allocator internally.
-/// Tracks memory usage, times, etc. for regions of code
+/// Tracks memory usage, times, etc. for regions of code and reports allocations on real-time threads, see [`rt`]
 pub struct MemAllocator {
     pub(crate) inner: mimalloc::MiMalloc,
 }
@@ -22,12 +22,19 @@ unsafe impl GlobalAlloc for MemAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         // MIGHT HAVE PERFORMANCE COSTS DUE TO OVERHEAD
         // WILL FIND A BETTER SOLUTION MAYBE
-        perf::alloc(layout.size());
-        self.inner.alloc(layout)
+        rt::check(rt::ViolationKind::Alloc, layout.size());
+
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            perf::alloc(ptr, layout.size());
+        }
+
+        ptr
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        perf::dealloc(layout.size());
+        rt::check(rt::ViolationKind::Dealloc, layout.size());
+        perf::dealloc(ptr, layout.size());
         self.inner.dealloc(ptr, layout)
     }
 }
@@ -0,0 +1,72 @@
+/// Number of size classes, powers of two from 16 bytes up to everything above 256 KiB
+pub const SIZE_CLASSES: usize = 16;
+
+/// Smallest size class, allocations up to this size land in the first class
+const MIN_CLASS: usize = 16;
+
+/// Allocation counts by size class
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Histogram(pub [u64; SIZE_CLASSES]);
+
+impl Histogram {
+    /// Index of the size class an allocation of `size` bytes belongs to
+    #[inline]
+    pub const fn class(size: usize) -> usize {
+        if size <= MIN_CLASS {
+            return 0;
+        }
+
+        let class = (usize::BITS - (size - 1).leading_zeros()) as usize - MIN_CLASS.trailing_zeros() as usize;
+        if class < SIZE_CLASSES {
+            class
+        } else {
+            SIZE_CLASSES - 1
+        }
+    }
+
+    /// Largest allocation in a size class, `None` for the last class which has no upper bound
+    pub const fn upper_bound(class: usize) -> Option<usize> {
+        if class + 1 < SIZE_CLASSES {
+            Some(MIN_CLASS << class)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn record(&mut self, size: usize) {
+        self.0[Self::class(size)] += 1;
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, other) in self.0.iter_mut().zip(other.0) {
+            *count += other;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.iter().sum()
+    }
+
+    /// Non empty size classes with a label like `<= 64 B` and their count
+    pub fn classes(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(class, &count)| (label(class), count))
+    }
+}
+
+fn label(class: usize) -> String {
+    let format = |bytes: usize| match bytes {
+        b if b >= 1 << 20 => format!("{} MiB", b >> 20),
+        b if b >= 1 << 10 => format!("{} KiB", b >> 10),
+        b => format!("{b} B"),
+    };
+
+    match Histogram::upper_bound(class) {
+        Some(bound) => format!("<= {}", format(bound)),
+        None => format!("> {}", format(MIN_CLASS << (SIZE_CLASSES - 2))),
+    }
+}
@@ -0,0 +1,227 @@
+use crate::perf::histogram::{Histogram, SIZE_CLASSES};
+use crate::perf::{guarded, region, tree, NodeSnapshot};
+use rustc_hash::FxHashMap;
+use std::backtrace::Backtrace;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Environment variable with the sampling interval for leak backtraces, see [`set_sampling`]
+pub const SAMPLING_ENV: &str = "VOXEA_LEAK_SAMPLING";
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static HISTOGRAM: [AtomicU64; SIZE_CLASSES] = [const { AtomicU64::new(0) }; SIZE_CLASSES];
+
+static SAMPLING: AtomicUsize = AtomicUsize::new(0);
+static SAMPLED: AtomicUsize = AtomicUsize::new(0);
+static SAMPLES: OnceLock<Mutex<FxHashMap<usize, SampledAllocation>>> = OnceLock::new();
+
+/// Process wide allocation totals
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Totals {
+    /// Bytes currently allocated
+    pub live: usize,
+    /// Highest number of bytes allocated at once
+    pub peak: usize,
+    pub allocations: u64,
+    pub deallocations: u64,
+    pub histogram: Histogram,
+}
+
+/// An allocation whose backtrace was captured and that has not been freed yet
+#[derive(Debug)]
+pub struct SampledAllocation {
+    pub size: usize,
+    pub region: Option<&'static str>,
+    pub backtrace: Backtrace,
+}
+
+/// A region whose allocations and deallocations don't add up
+#[derive(Debug, Clone)]
+pub struct LeakedRegion {
+    /// Regions from the outermost one, separated by `/`
+    pub path: String,
+    pub allocated: usize,
+    pub freed: usize,
+    pub allocations: usize,
+    pub deallocations: usize,
+}
+
+impl LeakedRegion {
+    pub fn live(&self) -> isize {
+        self.allocated as isize - self.freed as isize
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LeakReport {
+    pub totals: Totals,
+    pub regions: Vec<LeakedRegion>,
+    /// Sampled allocations that are still live, only filled when sampling is enabled
+    pub sampled: Vec<(usize, String)>,
+}
+
+impl LeakReport {
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty() && self.sampled.is_empty()
+    }
+}
+
+impl Display for LeakReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} bytes still allocated, {} allocations and {} deallocations, peak {} bytes",
+            self.totals.live, self.totals.allocations, self.totals.deallocations, self.totals.peak
+        )?;
+
+        for region in &self.regions {
+            writeln!(
+                f,
+                "- {}: {} bytes live ({} allocated in {} allocations, {} freed in {} deallocations)",
+                region.path,
+                region.live(),
+                region.allocated,
+                region.allocations,
+                region.freed,
+                region.deallocations
+            )?;
+        }
+
+        for (size, backtrace) in &self.sampled {
+            writeln!(f, "Sampled allocation of {size} bytes:\n{backtrace}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Initializes sampling from [`SAMPLING_ENV`] if it is set
+pub(crate) fn init_from_env() {
+    if let Some(every) = std::env::var(SAMPLING_ENV).ok().and_then(|v| v.parse().ok()) {
+        set_sampling(every);
+    }
+}
+
+/// Captures the backtrace of every `every`th allocation until it is freed, `0` disables sampling
+pub fn set_sampling(every: usize) {
+    SAMPLING.store(every, Ordering::Relaxed);
+}
+
+pub fn totals() -> Totals {
+    let mut histogram = Histogram::default();
+    for (count, class) in histogram.0.iter_mut().zip(&HISTOGRAM) {
+        *count = class.load(Ordering::Relaxed);
+    }
+
+    Totals {
+        live: LIVE.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed),
+        deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+        histogram,
+    }
+}
+
+#[inline]
+pub(crate) fn alloc(ptr: *mut u8, size: usize) {
+    let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+    let count = ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    HISTOGRAM[Histogram::class(size)].fetch_add(1, Ordering::Relaxed);
+
+    let every = SAMPLING.load(Ordering::Relaxed);
+    if every > 0 && count.is_multiple_of(every as u64) {
+        sample(ptr, size);
+    }
+}
+
+#[inline]
+pub(crate) fn dealloc(ptr: *mut u8, size: usize) {
+    LIVE.fetch_sub(size, Ordering::Relaxed);
+    DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+    if SAMPLED.load(Ordering::Relaxed) > 0 {
+        guarded(|| {
+            if samples_lock().remove(&(ptr as usize)).is_some() {
+                SAMPLED.fetch_sub(1, Ordering::Relaxed);
+            }
+        });
+    }
+}
+
+#[cold]
+fn sample(ptr: *mut u8, size: usize) {
+    let region = region();
+
+    guarded(|| {
+        let sample = SampledAllocation {
+            size,
+            region,
+            backtrace: Backtrace::force_capture(),
+        };
+
+        if samples_lock().insert(ptr as usize, sample).is_none() {
+            SAMPLED.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+}
+
+fn samples_lock() -> std::sync::MutexGuard<'static, FxHashMap<usize, SampledAllocation>> {
+    SAMPLES
+        .get_or_init(|| Mutex::new(FxHashMap::default()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lists every region of the call tree whose own allocations were not all freed inside it,
+/// and the sampled allocations that are still live. Meant to be called at shutdown.
+pub fn report() -> LeakReport {
+    let mut regions = Vec::new();
+
+    for root in tree() {
+        collect(&root, String::new(), &mut regions);
+    }
+
+    let sampled = guarded(|| {
+        samples_lock()
+            .values()
+            .map(|s| {
+                let region = s.region.unwrap_or("<no region>");
+                (s.size, format!("in {region}\n{}", s.backtrace))
+            })
+            .collect()
+    })
+    .unwrap_or_default();
+
+    LeakReport {
+        totals: totals(),
+        regions,
+        sampled,
+    }
+}
+
+fn collect(node: &NodeSnapshot, parent: String, regions: &mut Vec<LeakedRegion>) {
+    let path = if parent.is_empty() {
+        node.region.to_string()
+    } else {
+        format!("{parent}/{}", node.region)
+    };
+
+    if node.exclusive.live() != 0 {
+        regions.push(LeakedRegion {
+            path: path.clone(),
+            allocated: node.exclusive.allocated,
+            freed: node.exclusive.freed,
+            allocations: node.exclusive.allocations,
+            deallocations: node.exclusive.deallocations,
+        });
+    }
+
+    for child in &node.children {
+        collect(child, path.clone(), regions);
+    }
+}
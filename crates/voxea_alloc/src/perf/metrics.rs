@@ -0,0 +1,82 @@
+use crate::perf::guarded;
+use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// Number of values kept per metric
+pub const HISTORY: usize = 512;
+
+/// Time spent in the audio callback, in milliseconds
+pub const AUDIO_CALLBACK: &str = "audio::callback";
+/// Time available to the audio callback, i.e. the buffer period, in milliseconds
+pub const AUDIO_DEADLINE: &str = "audio::deadline";
+/// Total number of underruns and overruns of the audio stream
+pub const AUDIO_XRUNS: &str = "audio::xruns";
+/// Prefix of the DSP load of a graph node, in percent of the deadline
+pub const DSP_NODE: &str = "dsp::node::";
+/// Prefix of the DSP load of a plugin, in percent of the deadline
+pub const DSP_PLUGIN: &str = "dsp::plugin::";
+
+static METRICS: OnceLock<Mutex<FxHashMap<String, VecDeque<f64>>>> = OnceLock::new();
+
+fn metrics_lock() -> std::sync::MutexGuard<'static, FxHashMap<String, VecDeque<f64>>> {
+    METRICS
+        .get_or_init(|| Mutex::new(FxHashMap::default()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Appends a value to the history of a metric, dropping the oldest value once [`HISTORY`] is reached.
+/// Takes a lock, so real-time threads should publish through a lock-free channel that is drained elsewhere.
+pub fn record(metric: &str, value: f64) {
+    guarded(|| {
+        let mut metrics = metrics_lock();
+
+        let history = match metrics.get_mut(metric) {
+            Some(history) => history,
+            None => metrics
+                .entry(metric.to_string())
+                .or_insert_with(|| VecDeque::with_capacity(HISTORY)),
+        };
+
+        if history.len() == HISTORY {
+            history.pop_front();
+        }
+        history.push_back(value);
+    });
+}
+
+/// History of a metric from oldest to newest
+pub fn history(metric: &str) -> Vec<f64> {
+    guarded(|| {
+        metrics_lock()
+            .get(metric)
+            .map(|h| h.iter().copied().collect())
+            .unwrap_or_default()
+    })
+    .unwrap_or_default()
+}
+
+pub fn latest(metric: &str) -> Option<f64> {
+    guarded(|| metrics_lock().get(metric).and_then(|h| h.back().copied())).flatten()
+}
+
+/// Names of every recorded metric starting with `prefix`, sorted
+pub fn names(prefix: &str) -> Vec<String> {
+    let mut names = guarded(|| {
+        metrics_lock()
+            .keys()
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect::<Vec<_>>()
+    })
+    .unwrap_or_default();
+
+    names.sort();
+    names
+}
+
+/// Removes a metric, e.g. when the node it belongs to is removed
+pub fn remove(metric: &str) {
+    guarded(|| metrics_lock().remove(metric));
+}
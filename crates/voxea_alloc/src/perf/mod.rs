@@ -0,0 +1,639 @@
+mod histogram;
+pub mod leaks;
+pub mod metrics;
+pub mod trace;
+mod tree;
+
+pub use histogram::{Histogram, SIZE_CLASSES};
+pub use tree::{NodeSnapshot, Timing, WINDOW};
+
+use crate::perf;
+use crate::ring::{self, Consumer, Handoff, Producer};
+use crate::perf::tree::{CallTree, Sample};
+use rustc_hash::{FxHashMap, FxHasher};
+use std::cell::{Cell, RefCell, UnsafeCell};
+use std::hash::{Hash, Hasher};
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// Statistics and call tree of every region, merged from the samples of all threads by [`collect`]
+static COLLECTOR: OnceLock<Mutex<Collector>> = OnceLock::new();
+
+/// Sample buffers of the threads that ended their first region since the last [`collect`]
+static SOURCES: Handoff<Source> = Handoff::new();
+
+/// Samples lost because the buffer of their thread was full or it ran out of paths
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Maximum nesting of regions per thread. Deeper regions are still matched up but not recorded.
+pub const MAX_DEPTH: usize = 64;
+
+/// Samples a thread buffers until they are collected, later ones are dropped
+const SAMPLE_CAPACITY: usize = 1024;
+
+/// Different paths of regions per thread, regions on new paths are not recorded once they are used up
+const MAX_PATHS: usize = 1024;
+
+/// Parent of outermost regions and empty slot of a path index
+const NONE: u32 = u32::MAX;
+
+thread_local! {
+    static REGION_STACK: RefCell<RegionStack> = const { RefCell::new(RegionStack::new()) };
+
+    /// Buffer of the samples of the current thread, created when it begins its first region
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+
+    /// Set while perf itself is running, so allocations made by the registry are not tracked
+    static IN_PERF: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Timer {
+    pub instant: Instant,
+    pub last_elapsed: u128,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Memory {
+    pub allocated: usize,
+    pub freed: usize,
+    /// Highest number of live bytes, i.e. allocated but not yet freed, at any point
+    pub peak_usage: usize,
+    pub allocations: usize,
+    pub deallocations: usize,
+    pub histogram: Histogram,
+}
+
+impl Memory {
+    const EMPTY: Self = Self {
+        allocated: 0,
+        freed: 0,
+        peak_usage: 0,
+        allocations: 0,
+        deallocations: 0,
+        histogram: Histogram([0; SIZE_CLASSES]),
+    };
+
+    /// Bytes allocated but not freed, negative if memory from outside was freed
+    pub fn live(&self) -> isize {
+        self.allocated as isize - self.freed as isize
+    }
+
+    /// Adds the counts of `other`, the peak is kept as the highest of both
+    pub fn merge(&mut self, other: &Memory) {
+        self.allocated += other.allocated;
+        self.freed += other.freed;
+        self.peak_usage = self.peak_usage.max(other.peak_usage);
+        self.allocations += other.allocations;
+        self.deallocations += other.deallocations;
+        self.histogram.merge(&other.histogram);
+    }
+
+    #[inline]
+    fn alloc(&mut self, size: usize) {
+        self.allocated += size;
+        self.allocations += 1;
+        self.histogram.record(size);
+    }
+
+    #[inline]
+    fn dealloc(&mut self, size: usize) {
+        self.freed += size;
+        self.deallocations += 1;
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Statistics {
+    pub timer: Timer,
+    pub memory: Memory,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self {
+            instant: Instant::now(),
+            last_elapsed: 0,
+        }
+    }
+}
+
+/// A region that has begun on the current thread but not ended yet
+#[derive(Debug, Copy, Clone)]
+struct Frame {
+    region: &'static str,
+    start: Option<Instant>,
+    /// Memory of this region only
+    exclusive: Memory,
+    /// Memory of this region and the children that already ended
+    inclusive: Memory,
+    /// Live bytes and their peak, relative to the start of the region
+    exclusive_live: isize,
+    exclusive_peak: isize,
+    inclusive_live: isize,
+    inclusive_peak: isize,
+    /// Time of the children that already ended, used to split inclusive from exclusive
+    child_time: u128,
+    /// Id of the path from the outermost region in the thread's [`Paths`], `None` if it is not recorded
+    path: Option<u32>,
+}
+
+impl Frame {
+    const EMPTY: Self = Self {
+        path: None,
+        region: "",
+        start: None,
+        exclusive: Memory::EMPTY,
+        inclusive: Memory::EMPTY,
+        exclusive_live: 0,
+        exclusive_peak: 0,
+        inclusive_live: 0,
+        inclusive_peak: 0,
+        child_time: 0,
+    };
+}
+
+/// Fixed size stack of the regions of a thread, so it can be used from inside the allocator
+struct RegionStack {
+    frames: [Frame; MAX_DEPTH],
+    len: usize,
+    /// Regions begun while the stack was full
+    overflow: usize,
+}
+
+impl RegionStack {
+    const fn new() -> Self {
+        Self {
+            frames: [Frame::EMPTY; MAX_DEPTH],
+            len: 0,
+            overflow: 0,
+        }
+    }
+
+    fn current(&mut self) -> Option<&mut Frame> {
+        self.len.checked_sub(1).map(|i| &mut self.frames[i])
+    }
+}
+
+/// Runs `f` with allocation tracking disabled on the current thread.
+/// Returns `None` if perf is already running, e.g. when perf itself allocates.
+fn guarded<R>(f: impl FnOnce() -> R) -> Option<R> {
+    let entered = IN_PERF.try_with(|in_perf| !in_perf.replace(true)).unwrap_or(false);
+    if !entered {
+        return None;
+    }
+
+    let result = f();
+    let _ = IN_PERF.try_with(|in_perf| in_perf.set(false));
+
+    Some(result)
+}
+
+/// Whether perf itself is running on the current thread, see [`guarded`]
+pub(crate) fn is_running() -> bool {
+    IN_PERF.try_with(Cell::get).unwrap_or(false)
+}
+
+/// A finished region as buffered by its thread
+#[derive(Copy, Clone)]
+struct Record {
+    path: u32,
+    sample: Sample,
+    ended: Instant,
+}
+
+#[derive(Copy, Clone)]
+struct PathEntry {
+    parent: u32,
+    region: &'static str,
+}
+
+/// Paths of the regions of one thread, from the outermost region. Only the thread itself appends to them and
+/// entries never change once added, so [`collect`] can read every entry a sample refers to without locking.
+struct Paths {
+    entries: Box<[UnsafeCell<MaybeUninit<PathEntry>>]>,
+}
+
+unsafe impl Sync for Paths {}
+
+impl Paths {
+    /// Safety: `id` has to be added already and its addition has to be visible to the calling thread
+    unsafe fn get(&self, id: u32) -> PathEntry {
+        (*self.entries[id as usize].get()).assume_init()
+    }
+}
+
+/// Recording side of the sample buffer of a thread, nothing in it allocates or locks once it is created
+struct Recorder {
+    samples: Producer<Record>,
+    paths: Arc<Paths>,
+    len: usize,
+    /// Open addressing index of `paths` by parent and region, twice their size so it always has an empty slot
+    index: Box<[u32]>,
+}
+
+impl Recorder {
+    fn new() -> Self {
+        let (samples, consumer) = ring::ring(SAMPLE_CAPACITY);
+        let paths = Arc::new(Paths {
+            entries: (0..MAX_PATHS)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+        });
+
+        SOURCES.push(Source {
+            samples: consumer,
+            paths: paths.clone(),
+            nodes: Vec::new(),
+        });
+
+        Self {
+            samples,
+            paths,
+            len: 0,
+            index: vec![NONE; MAX_PATHS * 2].into_boxed_slice(),
+        }
+    }
+
+    /// Id of the path of `region` below the path `parent`, `None` once every path is used up
+    fn intern(&mut self, parent: u32, region: &'static str) -> Option<u32> {
+        let mut hasher = FxHasher::default();
+        parent.hash(&mut hasher);
+        region.hash(&mut hasher);
+
+        let mut slot = hasher.finish() as usize % self.index.len();
+
+        while self.index[slot] != NONE {
+            let id = self.index[slot];
+            let entry = unsafe { self.paths.get(id) };
+
+            if entry.parent == parent && entry.region == region {
+                return Some(id);
+            }
+
+            slot = (slot + 1) % self.index.len();
+        }
+
+        if self.len == MAX_PATHS {
+            return None;
+        }
+
+        let id = self.len as u32;
+        unsafe { (*self.paths.entries[self.len].get()).write(PathEntry { parent, region }) };
+        self.index[slot] = id;
+        self.len += 1;
+
+        Some(id)
+    }
+}
+
+/// Runs `f` with the recorder of the current thread, creating it first if needed
+fn with_recorder<R>(f: impl FnOnce(&mut Recorder) -> R) -> Option<R> {
+    RECORDER
+        .try_with(|recorder| {
+            let mut recorder = recorder.try_borrow_mut().ok()?;
+
+            if recorder.is_none() {
+                *recorder = Some(guarded(Recorder::new)?);
+            }
+
+            recorder.as_mut().map(f)
+        })
+        .ok()
+        .flatten()
+}
+
+/// Sample buffer of a thread as seen by [`collect`]
+struct Source {
+    samples: Consumer<Record>,
+    paths: Arc<Paths>,
+    /// Node of the call tree by path id, [`NONE`] if not looked up yet
+    nodes: Vec<u32>,
+}
+
+impl Source {
+    /// Node of the call tree a path of this thread belongs to, adding it and its parents if they are new
+    fn node(&mut self, tree: &mut CallTree, id: u32) -> usize {
+        if let Some(&node) = self.nodes.get(id as usize).filter(|&&node| node != NONE) {
+            return node as usize;
+        }
+
+        let entry = unsafe { self.paths.get(id) };
+        let parent = (entry.parent != NONE).then(|| self.node(tree, entry.parent));
+        let node = tree.child(parent, entry.region);
+
+        if self.nodes.len() <= id as usize {
+            self.nodes.resize(id as usize + 1, NONE);
+        }
+        self.nodes[id as usize] = node as u32;
+
+        node
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    sources: Vec<Source>,
+    registry: FxHashMap<&'static str, Statistics>,
+    tree: CallTree,
+}
+
+impl Collector {
+    /// Merges the buffered samples, the call tree is only built here so recording threads never allocate for it
+    fn collect(&mut self) {
+        let Self { sources, registry, tree } = self;
+
+        sources.extend(SOURCES.take());
+
+        sources.retain_mut(|source| {
+            // Checked first, so samples pushed right before the thread exited are still taken
+            let abandoned = source.samples.is_abandoned();
+
+            while let Some(record) = source.samples.pop() {
+                let region = unsafe { source.paths.get(record.path) }.region;
+                let sample = record.sample;
+                let stats = registry.entry(region).or_default();
+                let peak_usage = stats.memory.peak_usage.max(sample.exclusive.peak_usage);
+
+                stats.timer.last_elapsed = sample.inclusive_time;
+                stats.timer.instant = record.ended;
+                stats.memory = Memory {
+                    peak_usage,
+                    ..sample.exclusive
+                };
+
+                let node = source.node(tree, record.path);
+                tree.record(node, sample);
+            }
+
+            !abandoned
+        });
+    }
+}
+
+/// Locks the collector after merging what every thread recorded since the last time
+fn collector() -> std::sync::MutexGuard<'static, Collector> {
+    let mut collector = COLLECTOR
+        .get_or_init(|| Mutex::new(Collector::default()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    collector.collect();
+    collector
+}
+
+pub fn init() {
+    COLLECTOR.get_or_init(|| Mutex::new(Collector::default()));
+    trace::init_from_env();
+    leaks::init_from_env();
+}
+
+/// Merges the samples every thread buffered into the statistics and call tree and moves their trace events into the
+/// trace. Threads drop what they record once their buffers are full, so this should be called regularly, e.g. once
+/// per frame, and never on a real-time thread.
+pub fn collect() {
+    guarded(|| drop(collector()));
+    trace::collect();
+}
+
+/// Number of samples that were dropped because they were not collected in time
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Sets up the sample and trace buffers of the current thread, which otherwise happens in its first region and
+/// allocates. Real-time threads call it before they process anything, e.g. at the start of the first audio callback,
+/// so their regions never allocate.
+pub fn register_thread() {
+    let _ = with_recorder(|_| ());
+    trace::register_thread();
+}
+
+/// Beings a perf session for a region on the current thread
+pub fn begin(region: &'static str) {
+    trace::record(region, trace::Phase::Begin);
+
+    let _ = REGION_STACK.try_with(|stack| {
+        let mut stack = stack.borrow_mut();
+
+        if stack.len == MAX_DEPTH {
+            stack.overflow += 1;
+            return;
+        }
+
+        let parent = match stack.current() {
+            Some(frame) => frame.path,
+            None => Some(NONE),
+        };
+        let path = parent.and_then(|parent| with_recorder(|recorder| recorder.intern(parent, region)).flatten());
+
+        let len = stack.len;
+        stack.frames[len] = Frame {
+            region,
+            start: Some(Instant::now()),
+            path,
+            ..Frame::EMPTY
+        };
+        stack.len += 1;
+    });
+}
+
+/// Ends a perf session for a region on the current thread and buffers its statistics until they are collected.
+/// Neither locks nor allocates, so regions can be used on real-time threads.
+pub fn end(region: &'static str) {
+    trace::record(region, trace::Phase::End);
+
+    let ended = REGION_STACK.try_with(|stack| {
+        let mut stack = stack.borrow_mut();
+
+        if stack.overflow > 0 {
+            stack.overflow -= 1;
+            return None;
+        }
+
+        let frame = stack.current().copied()?;
+
+        let elapsed = frame.start.map_or(0, |start| start.elapsed().as_micros());
+        let sample = Sample {
+            inclusive_time: elapsed,
+            exclusive_time: elapsed.saturating_sub(frame.child_time),
+            inclusive: Memory {
+                peak_usage: frame.inclusive_peak.max(0) as usize,
+                ..frame.inclusive
+            },
+            exclusive: Memory {
+                peak_usage: frame.exclusive_peak.max(0) as usize,
+                ..frame.exclusive
+            },
+        };
+
+        stack.len -= 1;
+
+        if let Some(parent) = stack.current() {
+            parent.child_time += sample.inclusive_time;
+            parent.inclusive.merge(&frame.inclusive);
+            // The peak of the child happened on top of what the parent had live when it started
+            parent.inclusive_peak = parent
+                .inclusive_peak
+                .max(parent.inclusive_live + frame.inclusive_peak);
+            parent.inclusive_live += frame.inclusive_live;
+        }
+
+        Some((frame.region, frame.path, sample))
+    });
+
+    let Ok(ended) = ended else {
+        return;
+    };
+
+    let popped = ended.map(|e| e.0);
+    assert_eq!(
+        popped,
+        Some(region),
+        "Unmatched regions! Current region is: {:?}, trying to end region: {:?}",
+        popped,
+        region
+    );
+
+    let Some((_, path, sample)) = ended else {
+        return;
+    };
+
+    let pushed = path.is_some_and(|path| {
+        let record = Record {
+            path,
+            sample,
+            ended: Instant::now(),
+        };
+
+        with_recorder(|recorder| recorder.samples.push(record)).unwrap_or(false)
+    });
+
+    if !pushed {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Gets the currently tracked region of the current thread
+pub fn region() -> Option<&'static str> {
+    REGION_STACK
+        .try_with(|stack| stack.try_borrow_mut().ok()?.current().map(|f| f.region))
+        .ok()
+        .flatten()
+}
+
+/// Snapshot of the statistics of every region
+pub fn registry() -> FxHashMap<&'static str, Statistics> {
+    guarded(|| collector().registry.clone()).unwrap_or_default()
+}
+
+pub fn get(region: &'static str) -> Option<Statistics> {
+    guarded(|| collector().registry.get(region).copied()).flatten()
+}
+
+/// Snapshot of the call tree, one entry per outermost region
+pub fn tree() -> Vec<NodeSnapshot> {
+    guarded(|| collector().tree.snapshot()).unwrap_or_default()
+}
+
+/// Clears the call tree, e.g. to start measuring from a known point
+pub fn reset_tree() {
+    guarded(|| {
+        let mut collector = collector();
+        collector.tree.clear();
+
+        for source in &mut collector.sources {
+            source.nodes.clear();
+        }
+    });
+}
+
+/// Records an allocation in the totals and attributes it to the current region of the current thread
+pub fn alloc(ptr: *mut u8, size: usize) {
+    leaks::alloc(ptr, size);
+
+    if IN_PERF.try_with(Cell::get).unwrap_or(true) {
+        return;
+    }
+
+    let _ = REGION_STACK.try_with(|stack| {
+        if let Ok(mut stack) = stack.try_borrow_mut() {
+            if let Some(frame) = stack.current() {
+                frame.exclusive.alloc(size);
+                frame.inclusive.alloc(size);
+
+                frame.exclusive_live += size as isize;
+                frame.exclusive_peak = frame.exclusive_peak.max(frame.exclusive_live);
+                frame.inclusive_live += size as isize;
+                frame.inclusive_peak = frame.inclusive_peak.max(frame.inclusive_live);
+            }
+        }
+    });
+}
+
+/// Records a deallocation in the totals and attributes it to the current region of the current thread
+pub fn dealloc(ptr: *mut u8, size: usize) {
+    leaks::dealloc(ptr, size);
+
+    if IN_PERF.try_with(Cell::get).unwrap_or(true) {
+        return;
+    }
+
+    let _ = REGION_STACK.try_with(|stack| {
+        if let Ok(mut stack) = stack.try_borrow_mut() {
+            if let Some(frame) = stack.current() {
+                frame.exclusive.dealloc(size);
+                frame.inclusive.dealloc(size);
+
+                frame.exclusive_live -= size as isize;
+                frame.inclusive_live -= size as isize;
+            }
+        }
+    });
+}
+
+pub fn total_memory() -> (usize, usize) {
+    if let Some(stats) = memory_stats::memory_stats() {
+        (stats.physical_mem, stats.virtual_mem)
+    } else {
+        (0, 0)
+    }
+}
+
+/// Struct that starts a perf session when created and automatically ends it when it is dropped.
+pub struct PerfTrace(&'static str);
+
+impl PerfTrace {
+    pub fn new(region: &'static str) -> Self {
+        perf::begin(region);
+        Self(region)
+    }
+}
+
+impl Drop for PerfTrace {
+    fn drop(&mut self) {
+        perf::end(self.0);
+    }
+}
+
+#[macro_export]
+macro_rules! begin_perf {
+    () => {
+        let _guard = PerfTrace::new({
+            fn f() {}
+            fn type_name_of<T>(_: T) -> &'static str {
+                std::any::type_name::<T>()
+            }
+            let name = type_name_of(f);
+            name.strip_suffix("::f").unwrap()
+        });
+    };
+
+    ($region: tt) => {
+        let _guard = PerfTrace::new($region);
+    };
+}
+
+pub use begin_perf;
@@ -0,0 +1,339 @@
+use crate::perf::guarded;
+use crate::ring::{self, Consumer, Handoff, Producer};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Environment variable with the path to write a trace to, recording starts at [`crate::perf::init`]
+pub const TRACE_ENV: &str = "VOXEA_TRACE";
+
+/// Number of events kept by default, older events are overwritten
+pub const DEFAULT_CAPACITY: usize = 1 << 18;
+
+/// Events a thread buffers until they are collected into the ring, later ones are dropped
+const THREAD_CAPACITY: usize = 1 << 13;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+static BUFFER: OnceLock<Mutex<TraceBuffer>> = OnceLock::new();
+
+/// Event buffers of the threads that recorded their first event since they were last collected
+static SOURCES: Handoff<Source> = Handoff::new();
+
+thread_local! {
+    /// Event buffer of the current thread, created when it records its first event
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Phase {
+    Begin,
+    End,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TraceEvent {
+    pub region: &'static str,
+    pub phase: Phase,
+    /// Time since the first event was recorded, in nanoseconds
+    pub timestamp: u64,
+    pub thread: u64,
+}
+
+/// Recording side of the event buffer of a thread, with its id and name looked up once
+struct Tracer {
+    thread: u64,
+    events: Producer<TraceEvent>,
+}
+
+impl Tracer {
+    fn new() -> Self {
+        let thread = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+        let name = std::thread::current()
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Thread {thread}"));
+
+        let (events, consumer) = ring::ring(THREAD_CAPACITY);
+        SOURCES.push(Source {
+            thread,
+            name,
+            events: consumer,
+        });
+
+        Self { thread, events }
+    }
+}
+
+/// Event buffer of a thread as seen by the [`TraceBuffer`]
+struct Source {
+    thread: u64,
+    name: String,
+    events: Consumer<TraceEvent>,
+}
+
+/// Bounded ring of trace events, allocated once when recording starts, and the buffers of the threads that
+/// record into it
+#[derive(Default)]
+struct TraceBuffer {
+    events: Vec<TraceEvent>,
+    capacity: usize,
+    next: usize,
+    threads: FxHashMap<u64, String>,
+    sources: Vec<Source>,
+}
+
+impl TraceBuffer {
+    fn push(&mut self, event: TraceEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.events.len() < self.capacity {
+            self.events.push(event);
+        } else {
+            self.events[self.next] = event;
+        }
+
+        self.next = (self.next + 1) % self.capacity;
+    }
+
+    /// Moves the events every thread buffered into the ring
+    fn collect(&mut self) {
+        for source in SOURCES.take() {
+            self.threads.insert(source.thread, source.name.clone());
+            self.sources.push(source);
+        }
+
+        let mut sources = std::mem::take(&mut self.sources);
+
+        sources.retain_mut(|source| {
+            // Checked first, so events pushed right before the thread exited are still taken
+            let abandoned = source.events.is_abandoned();
+
+            while let Some(event) = source.events.pop() {
+                self.push(event);
+            }
+
+            !abandoned
+        });
+
+        self.sources = sources;
+    }
+
+    /// Events from oldest to newest
+    fn ordered(&self) -> impl Iterator<Item = &TraceEvent> {
+        let split = if self.events.len() < self.capacity { 0 } else { self.next };
+        self.events[split..].iter().chain(&self.events[..split])
+    }
+}
+
+/// Locks the ring after moving the events every thread buffered since the last time into it
+fn buffer_lock() -> std::sync::MutexGuard<'static, TraceBuffer> {
+    let mut buffer = BUFFER
+        .get_or_init(|| Mutex::new(TraceBuffer::default()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    buffer.collect();
+    buffer
+}
+
+/// Moves the buffered events of every thread into the ring, see [`crate::perf::collect`]
+pub(crate) fn collect() {
+    guarded(|| drop(buffer_lock()));
+}
+
+/// Starts recording if [`TRACE_ENV`] is set
+pub(crate) fn init_from_env() {
+    if std::env::var_os(TRACE_ENV).is_some() {
+        start(DEFAULT_CAPACITY);
+    }
+}
+
+/// Starts recording into a ring of `capacity` events, clearing any previous recording
+pub fn start(capacity: usize) {
+    guarded(|| {
+        // Events buffered before the start are dropped with the old ring
+        let mut buffer = buffer_lock();
+        buffer.events = Vec::with_capacity(capacity);
+        buffer.capacity = capacity;
+        buffer.next = 0;
+    });
+
+    EPOCH.get_or_init(Instant::now);
+    RECORDING.store(true, Ordering::Release);
+}
+
+/// Stops recording, the recorded events are kept until the next [`start`]
+pub fn stop() {
+    RECORDING.store(false, Ordering::Release);
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Number of events that were dropped because their thread's buffer was not collected in time
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Number of events currently held in the ring
+pub fn len() -> usize {
+    guarded(|| buffer_lock().events.len()).unwrap_or(0)
+}
+
+/// Copy of the recorded events from oldest to newest
+pub fn events() -> Vec<TraceEvent> {
+    guarded(|| buffer_lock().ordered().copied().collect()).unwrap_or_default()
+}
+
+/// Sets up the event buffer of the current thread if it has none yet, see [`crate::perf::register_thread`]
+pub(crate) fn register_thread() {
+    let _ = TRACER.try_with(|tracer| {
+        if let Ok(mut tracer) = tracer.try_borrow_mut() {
+            if tracer.is_none() {
+                *tracer = guarded(Tracer::new);
+            }
+        }
+    });
+}
+
+/// Buffers an event on the current thread, which neither locks nor allocates after the thread's first event
+pub(crate) fn record(region: &'static str, phase: Phase) {
+    if !is_recording() {
+        return;
+    }
+
+    let Some(epoch) = EPOCH.get() else {
+        return;
+    };
+
+    let timestamp = epoch.elapsed().as_nanos() as u64;
+
+    let pushed = TRACER
+        .try_with(|tracer| {
+            let mut tracer = tracer.try_borrow_mut().ok()?;
+
+            if tracer.is_none() {
+                *tracer = Some(guarded(Tracer::new)?);
+            }
+
+            let tracer = tracer.as_mut()?;
+            Some(tracer.events.push(TraceEvent {
+                region,
+                phase,
+                timestamp,
+                thread: tracer.thread,
+            }))
+        })
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+
+    if !pushed {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Serializes the recorded events as Chrome Trace Event JSON, which can be opened in Perfetto or `chrome://tracing`
+pub fn to_json() -> String {
+    guarded(|| {
+        let buffer = buffer_lock();
+        let pid = std::process::id();
+        let mut json = String::from("{\"displayTimeUnit\":\"ms\",\"traceEvents\":[");
+        let mut first = true;
+
+        let mut separator = |json: &mut String| {
+            if !first {
+                json.push(',');
+            }
+            first = false;
+        };
+
+        for (thread, name) in &buffer.threads {
+            separator(&mut json);
+            let _ = write!(
+                json,
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{pid},\"tid\":{thread},\"args\":{{\"name\":\"{}\"}}}}",
+                escape(name)
+            );
+        }
+
+        // The ring may have overwritten the beginning of regions that are still in it
+        let mut depths = FxHashMap::<u64, usize>::default();
+
+        for event in buffer.ordered() {
+            let depth = depths.entry(event.thread).or_default();
+            let phase = match event.phase {
+                Phase::Begin => {
+                    *depth += 1;
+                    "B"
+                }
+                Phase::End if *depth == 0 => continue,
+                Phase::End => {
+                    *depth -= 1;
+                    "E"
+                }
+            };
+
+            separator(&mut json);
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"cat\":\"perf\",\"ph\":\"{phase}\",\"ts\":{}.{:03},\"pid\":{pid},\"tid\":{}}}",
+                escape(event.region),
+                event.timestamp / 1000,
+                event.timestamp % 1000,
+                event.thread
+            );
+        }
+
+        json.push_str("]}");
+        json
+    })
+    .unwrap_or_default()
+}
+
+/// Writes the recorded events to a file, see [`to_json`]
+pub fn write(path: impl AsRef<Path>) -> std::io::Result<()> {
+    let json = to_json();
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(json.as_bytes())
+}
+
+/// Stops recording and writes the trace to the path in [`TRACE_ENV`], if it is set
+pub fn finish() -> std::io::Result<Option<PathBuf>> {
+    let Some(path) = std::env::var_os(TRACE_ENV).map(PathBuf::from) else {
+        return Ok(None);
+    };
+
+    stop();
+    write(&path)?;
+
+    Ok(Some(path))
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,214 @@
+use crate::perf::Memory;
+use rustc_hash::FxHashMap;
+
+/// Number of samples kept per node for min/max/mean and percentiles
+pub const WINDOW: usize = 128;
+
+/// Measurements of a single finished region, already split into inclusive and exclusive parts
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct Sample {
+    pub(crate) inclusive_time: u128,
+    pub(crate) exclusive_time: u128,
+    pub(crate) inclusive: Memory,
+    pub(crate) exclusive: Memory,
+}
+
+/// Fixed size ring of the most recent inclusive times of a node, in microseconds
+#[derive(Debug, Clone)]
+struct Window {
+    samples: [u64; WINDOW],
+    len: usize,
+    next: usize,
+}
+
+impl Window {
+    const fn new() -> Self {
+        Self {
+            samples: [0; WINDOW],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    fn push(&mut self, sample: u64) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % WINDOW;
+        self.len = (self.len + 1).min(WINDOW);
+    }
+
+    fn timing(&self, last: u64) -> Timing {
+        if self.len == 0 {
+            return Timing::default();
+        }
+
+        let mut sorted = self.samples[..self.len].to_vec();
+        sorted.sort_unstable();
+
+        let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+
+        Timing {
+            last,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<u64>() as f64 / sorted.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        }
+    }
+}
+
+/// Timing of a node over the last [`WINDOW`] calls, in microseconds
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Timing {
+    pub last: u64,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+}
+
+/// A region at a specific position in the call tree
+#[derive(Debug, Clone)]
+struct Node {
+    region: &'static str,
+    children: Vec<usize>,
+    calls: u64,
+    last: u64,
+    /// Totals since the tree was created or reset, in microseconds
+    inclusive_time: u128,
+    exclusive_time: u128,
+    inclusive: Memory,
+    exclusive: Memory,
+    window: Window,
+}
+
+impl Node {
+    fn new(region: &'static str) -> Self {
+        Self {
+            region,
+            children: Vec::new(),
+            calls: 0,
+            last: 0,
+            inclusive_time: 0,
+            exclusive_time: 0,
+            inclusive: Memory::default(),
+            exclusive: Memory::default(),
+            window: Window::new(),
+        }
+    }
+}
+
+/// Call tree of every region on every thread.
+/// Nodes are identified by their path from a root, so the same region called from two places gets two nodes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CallTree {
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+    /// Child lookup by parent node (`None` for roots) and region
+    lookup: FxHashMap<(Option<usize>, &'static str), usize>,
+}
+
+impl CallTree {
+    /// Records a finished region in the node returned by [`CallTree::child`] for its path
+    pub(crate) fn record(&mut self, id: usize, sample: Sample) {
+        let node = &mut self.nodes[id];
+        let last = sample.inclusive_time.min(u64::MAX as u128) as u64;
+
+        node.calls += 1;
+        node.last = last;
+        node.inclusive_time += sample.inclusive_time;
+        node.exclusive_time += sample.exclusive_time;
+        node.inclusive.merge(&sample.inclusive);
+        node.exclusive.merge(&sample.exclusive);
+        node.window.push(last);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.nodes.clear();
+        self.roots.clear();
+        self.lookup.clear();
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<NodeSnapshot> {
+        self.roots.iter().map(|&id| self.snapshot_node(id, 0)).collect()
+    }
+
+    fn snapshot_node(&self, id: usize, depth: usize) -> NodeSnapshot {
+        let node = &self.nodes[id];
+
+        NodeSnapshot {
+            region: node.region,
+            depth,
+            calls: node.calls,
+            inclusive_time: node.inclusive_time,
+            exclusive_time: node.exclusive_time,
+            inclusive: node.inclusive,
+            exclusive: node.exclusive,
+            timing: node.window.timing(node.last),
+            children: node
+                .children
+                .iter()
+                .map(|&child| self.snapshot_node(child, depth + 1))
+                .collect(),
+        }
+    }
+
+    /// Node of `region` below `parent`, `None` for the roots, added if it is new
+    pub(crate) fn child(&mut self, parent: Option<usize>, region: &'static str) -> usize {
+        if let Some(&id) = self.lookup.get(&(parent, region)) {
+            return id;
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Node::new(region));
+        self.lookup.insert((parent, region), id);
+
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(id),
+            None => self.roots.push(id),
+        }
+
+        id
+    }
+}
+
+/// Owned copy of a node and its children, for display
+#[derive(Debug, Clone)]
+pub struct NodeSnapshot {
+    pub region: &'static str,
+    pub depth: usize,
+    pub calls: u64,
+    /// Total time spent in this node including its children, in microseconds
+    pub inclusive_time: u128,
+    /// Total time spent in this node excluding its children, in microseconds
+    pub exclusive_time: u128,
+    pub inclusive: Memory,
+    pub exclusive: Memory,
+    pub timing: Timing,
+    pub children: Vec<NodeSnapshot>,
+}
+
+impl NodeSnapshot {
+    /// Mean inclusive time per call since the tree was created or reset, in microseconds
+    pub fn mean_time(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.inclusive_time as f64 / self.calls as f64
+        }
+    }
+
+    /// Iterates this node and all of its descendants depth first
+    pub fn iter(&self) -> impl Iterator<Item = &NodeSnapshot> {
+        let mut stack = vec![self];
+
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
@@ -0,0 +1,228 @@
+//! Lock-free buffers to hand values from one thread to another, e.g. from and to real-time threads
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Creates a bounded single producer single consumer ring.
+/// Neither side allocates or blocks after creation, so either side can be used on real-time threads.
+/// Values still in the ring when both sides are gone are dropped with it.
+pub fn ring<T: Send>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    let capacity = capacity.max(1);
+
+    let shared = Arc::new(Shared {
+        buffer: (0..capacity + 1)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        read: AtomicUsize::new(0),
+        write: AtomicUsize::new(0),
+    });
+
+    (
+        Producer {
+            shared: shared.clone(),
+        },
+        Consumer { shared },
+    )
+}
+
+/// Ring of `capacity + 1` slots, one slot is always left empty to tell a full ring from an empty one
+struct Shared<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    read: AtomicUsize,
+    write: AtomicUsize,
+}
+
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut read = *self.read.get_mut();
+        let write = *self.write.get_mut();
+
+        while read != write {
+            unsafe { self.buffer[read].get_mut().assume_init_drop() };
+            read = self.next(read);
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    fn next(&self, index: usize) -> usize {
+        (index + 1) % self.buffer.len()
+    }
+
+    fn len(&self) -> usize {
+        let read = self.read.load(Ordering::Acquire);
+        let write = self.write.load(Ordering::Acquire);
+
+        (write + self.buffer.len() - read) % self.buffer.len()
+    }
+}
+
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Send> Producer<T> {
+    /// Pushes a value, returns `false` and drops the value if the ring is full
+    pub fn push(&mut self, value: T) -> bool {
+        let write = self.shared.write.load(Ordering::Relaxed);
+        let next = self.shared.next(write);
+
+        if next == self.shared.read.load(Ordering::Acquire) {
+            return false;
+        }
+
+        unsafe {
+            (*self.shared.buffer[write].get()).write(value);
+        }
+
+        self.shared.write.store(next, Ordering::Release);
+        true
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.buffer.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Send> Consumer<T> {
+    pub fn pop(&mut self) -> Option<T> {
+        let read = self.shared.read.load(Ordering::Relaxed);
+
+        if read == self.shared.write.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let value = unsafe { (*self.shared.buffer[read].get()).assume_init_read() };
+
+        self.shared.read.store(self.shared.next(read), Ordering::Release);
+        Some(value)
+    }
+
+    /// Pops every value that is currently in the ring
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(move || self.pop())
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the producer is gone, e.g. because its thread exited
+    pub fn is_abandoned(&self) -> bool {
+        Arc::strong_count(&self.shared) == 1
+    }
+}
+
+struct Node<T> {
+    value: T,
+    next: *mut Node<T>,
+}
+
+/// Lock-free stack that threads push to once, e.g. to register their [`Consumer`] when they first record something.
+/// The collecting thread takes everything pushed so far at once.
+pub(crate) struct Handoff<T> {
+    head: AtomicPtr<Node<T>>,
+}
+
+unsafe impl<T: Send> Sync for Handoff<T> {}
+
+impl<T> Handoff<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    pub(crate) fn push(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value,
+            next: ptr::null_mut(),
+        }));
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*node).next = head };
+
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// Takes every value pushed since the last call, oldest first
+    pub(crate) fn take(&self) -> Vec<T> {
+        let mut node = self.head.swap(ptr::null_mut(), Ordering::Acquire);
+        let mut values = Vec::new();
+
+        while !node.is_null() {
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next;
+            values.push(boxed.value);
+        }
+
+        values.reverse();
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ring;
+    use std::sync::Arc;
+
+    #[test]
+    fn bounded() {
+        let (mut producer, mut consumer) = ring(2);
+
+        assert!(producer.push(1));
+        assert!(producer.push(2));
+        assert!(!producer.push(3));
+        assert_eq!((producer.capacity(), producer.len()), (2, 2));
+
+        assert_eq!(consumer.pop(), Some(1));
+        assert!(producer.push(3));
+        assert_eq!(consumer.drain().collect::<Vec<_>>(), [2, 3]);
+        assert!(consumer.is_empty());
+
+        drop(producer);
+        assert!(consumer.is_abandoned());
+    }
+
+    #[test]
+    fn drops_remaining_values() {
+        let value = Arc::new(());
+        let (mut producer, consumer) = ring(4);
+
+        producer.push(value.clone());
+        producer.push(value.clone());
+        assert_eq!(Arc::strong_count(&value), 3);
+
+        drop((producer, consumer));
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::perf;
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::thread::ThreadId;
+
+static MODE: AtomicU8 = AtomicU8::new(Mode::Record as u8);
+static VIOLATIONS: Mutex<Vec<Violation>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// Whether the current thread was marked as real-time with [`set_realtime`]
+    static REALTIME: Cell<bool> = const { Cell::new(false) };
+
+    /// Depth of nested [`NoAllocGuard`]s on the current thread
+    static NO_ALLOC: Cell<u32> = const { Cell::new(0) };
+
+    /// Set while a violation is being recorded, since capturing the backtrace allocates itself
+    static RECORDING: Cell<bool> = const { Cell::new(false) };
+
+    /// Violations on the current thread that have not been raised in [`Mode::Panic`] yet
+    static PENDING: Cell<usize> = const { Cell::new(0) };
+}
+
+/// What happens when memory is allocated or freed on a real-time thread
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Mode {
+    /// Only records the violation, see [`take_violations`]
+    Record,
+    /// Records the violation and panics once the thread leaves the real-time section.
+    /// The allocator itself is not allowed to unwind, so the panic can't happen at the allocation.
+    Panic,
+    /// Prints the violation and aborts the process immediately
+    Abort,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    Alloc,
+    Dealloc,
+}
+
+/// An allocation or deallocation that happened on a real-time thread
+#[derive(Debug)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub size: usize,
+    pub thread: ThreadId,
+    pub thread_name: Option<String>,
+    pub backtrace: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            ViolationKind::Alloc => "Allocation",
+            ViolationKind::Dealloc => "Deallocation",
+        };
+
+        writeln!(
+            f,
+            "{kind} of {} bytes on real-time thread {}",
+            self.size,
+            self.thread_name.as_deref().unwrap_or("<unnamed>")
+        )?;
+        write!(f, "{}", self.backtrace)
+    }
+}
+
+pub fn set_mode(mode: Mode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn mode() -> Mode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => Mode::Panic,
+        2 => Mode::Abort,
+        _ => Mode::Record,
+    }
+}
+
+/// Marks or unmarks the current thread as real-time, e.g. at the start of the audio callback
+pub fn set_realtime(realtime: bool) {
+    let _ = REALTIME.try_with(|r| r.set(realtime));
+
+    if !realtime {
+        raise_pending();
+    }
+}
+
+/// Whether allocations on the current thread are currently violations
+pub fn is_realtime() -> bool {
+    REALTIME.try_with(Cell::get).unwrap_or(false) || NO_ALLOC.try_with(Cell::get).unwrap_or(0) > 0
+}
+
+/// Enters a scope in which the current thread must not allocate, until the guard is dropped
+pub fn no_alloc() -> NoAllocGuard {
+    let _ = NO_ALLOC.try_with(|depth| depth.set(depth.get() + 1));
+    NoAllocGuard(())
+}
+
+/// Runs `f` in a [`no_alloc`] scope and panics with every violation it caused, regardless of the [`Mode`]
+pub fn assert_no_alloc<R>(f: impl FnOnce() -> R) -> R {
+    let before = PENDING.try_with(Cell::get).unwrap_or(0);
+
+    let result = {
+        // Leaves the scope even if `f` panics
+        let _depth = Depth::enter();
+        f()
+    };
+
+    let after = PENDING.try_with(|pending| pending.replace(before)).unwrap_or(0);
+
+    if after > before {
+        panic!("{}", unchecked(|| report(take_thread_violations())));
+    }
+
+    result
+}
+
+/// Nesting of a [`no_alloc`] scope without raising violations when it ends, see [`assert_no_alloc`]
+struct Depth(());
+
+impl Depth {
+    fn enter() -> Self {
+        let _ = NO_ALLOC.try_with(|depth| depth.set(depth.get() + 1));
+        Self(())
+    }
+}
+
+impl Drop for Depth {
+    fn drop(&mut self) {
+        let _ = NO_ALLOC.try_with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+/// Guard returned by [`no_alloc`]
+pub struct NoAllocGuard(());
+
+impl Drop for NoAllocGuard {
+    fn drop(&mut self) {
+        let depth = NO_ALLOC
+            .try_with(|depth| {
+                depth.set(depth.get().saturating_sub(1));
+                depth.get()
+            })
+            .unwrap_or(0);
+
+        if depth == 0 && !REALTIME.try_with(Cell::get).unwrap_or(false) {
+            raise_pending();
+        }
+    }
+}
+
+/// Takes every violation recorded so far on any thread
+pub fn take_violations() -> Vec<Violation> {
+    std::mem::take(&mut *VIOLATIONS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+pub fn violation_count() -> usize {
+    VIOLATIONS.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+/// Called by the allocator for every allocation and deallocation.
+/// The profiler's own allocations are left out, e.g. when a thread sets up its buffers in its first region, and so
+/// are those of a panic, whose hook holds the lock capturing a backtrace needs.
+#[inline]
+pub(crate) fn check(kind: ViolationKind, size: usize) {
+    if !is_realtime() || perf::is_running() || std::thread::panicking() {
+        return;
+    }
+
+    let entered = RECORDING.try_with(|r| !r.replace(true)).unwrap_or(false);
+    if !entered {
+        return;
+    }
+
+    violation(kind, size);
+
+    let _ = RECORDING.try_with(|r| r.set(false));
+}
+
+#[cold]
+fn violation(kind: ViolationKind, size: usize) {
+    let thread = std::thread::current();
+    let violation = Violation {
+        kind,
+        size,
+        thread: thread.id(),
+        thread_name: thread.name().map(str::to_string),
+        backtrace: Backtrace::force_capture().to_string(),
+    };
+
+    if mode() == Mode::Abort {
+        eprintln!("{violation}");
+        std::process::abort();
+    }
+
+    let _ = PENDING.try_with(|pending| pending.set(pending.get() + 1));
+    VIOLATIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(violation);
+}
+
+/// Panics with the violations of the current thread in [`Mode::Panic`]
+fn raise_pending() {
+    let pending = PENDING.try_with(|pending| pending.replace(0)).unwrap_or(0);
+
+    if pending > 0 && mode() == Mode::Panic && !std::thread::panicking() {
+        panic!("{}", unchecked(|| report(take_thread_violations())));
+    }
+}
+
+/// Runs `f` without checking its allocations, e.g. to build a report while still on a real-time thread
+fn unchecked<R>(f: impl FnOnce() -> R) -> R {
+    let previous = RECORDING.try_with(|r| r.replace(true)).unwrap_or(true);
+    let result = f();
+    let _ = RECORDING.try_with(|r| r.set(previous));
+
+    result
+}
+
+/// Takes the violations recorded on the current thread, leaving the ones of other threads
+fn take_thread_violations() -> Vec<Violation> {
+    let thread = std::thread::current().id();
+    let mut violations = VIOLATIONS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let (own, other) = std::mem::take(&mut *violations)
+        .into_iter()
+        .partition(|v| v.thread == thread);
+    *violations = other;
+
+    own
+}
+
+fn report(violations: Vec<Violation>) -> String {
+    let mut report = format!("{} real-time violation(s):\n", violations.len());
+
+    for violation in violations {
+        report.push_str(&violation.to_string());
+        report.push('\n');
+    }
+
+    report
+}
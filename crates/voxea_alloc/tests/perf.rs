@@ -0,0 +1,51 @@
+use voxea_alloc::perf::{self, NodeSnapshot};
+use voxea_alloc::rt;
+
+#[global_allocator]
+static GLOBAL: voxea_alloc::MemAllocator = voxea_alloc::MemAllocator::new();
+
+fn find<'a>(roots: &'a [NodeSnapshot], region: &str) -> Option<&'a NodeSnapshot> {
+    roots.iter().flat_map(NodeSnapshot::iter).find(|node| node.region == region)
+}
+
+#[test]
+fn regions_neither_lock_nor_allocate() {
+    let threads = (0..2)
+        .map(|_| {
+            std::thread::spawn(|| {
+                perf::register_thread();
+
+                rt::assert_no_alloc(|| {
+                    for _ in 0..10 {
+                        perf::begin("perf::outer");
+                        perf::begin("perf::inner");
+                        perf::end("perf::inner");
+                        perf::end("perf::outer");
+                    }
+                });
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // Samples of threads that exited are still collected, the same path on both threads is one node
+    let tree = perf::tree();
+    let outer = find(&tree, "perf::outer").unwrap();
+    assert_eq!(outer.calls, 20);
+    assert_eq!(outer.depth, 0);
+    assert_eq!(outer.children.len(), 1);
+    assert_eq!(outer.children[0].region, "perf::inner");
+    assert_eq!(outer.children[0].calls, 20);
+
+    assert!(perf::get("perf::inner").is_some());
+
+    perf::reset_tree();
+    assert!(find(&perf::tree(), "perf::outer").is_none());
+
+    perf::begin("perf::outer");
+    perf::end("perf::outer");
+    assert_eq!(find(&perf::tree(), "perf::outer").unwrap().calls, 1);
+}
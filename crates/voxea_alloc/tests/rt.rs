@@ -0,0 +1,74 @@
+use std::hint::black_box;
+use voxea_alloc::rt::{self, Mode, ViolationKind};
+
+#[global_allocator]
+static GLOBAL: voxea_alloc::MemAllocator = voxea_alloc::MemAllocator::new();
+
+// The mode is global, so every case runs in a single test
+#[test]
+fn realtime_violations() {
+    let thread = std::thread::current().id();
+    let own = || {
+        rt::take_violations()
+            .into_iter()
+            .filter(|v| v.thread == thread)
+            .collect::<Vec<_>>()
+    };
+
+    rt::set_mode(Mode::Record);
+
+    // Allocating outside of a real-time section is fine
+    black_box(vec![0u8; 64]);
+    assert!(own().is_empty());
+
+    // Touching preallocated memory is fine
+    let mut buffer = vec![0.0f32; 256];
+    {
+        let _guard = rt::no_alloc();
+        buffer.iter_mut().for_each(|s| *s = 1.0);
+    }
+    assert!(own().is_empty());
+
+    {
+        let _guard = rt::no_alloc();
+        black_box(vec![0u8; 128]);
+    }
+    let violations = own();
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].kind, ViolationKind::Alloc);
+    assert_eq!(violations[0].size, 128);
+    assert_eq!(violations[1].kind, ViolationKind::Dealloc);
+    assert!(!violations[0].backtrace.is_empty());
+
+    rt::set_realtime(true);
+    black_box(Box::new(1u64));
+    rt::set_realtime(false);
+    assert_eq!(own().len(), 2);
+
+    rt::set_mode(Mode::Panic);
+    let result = std::panic::catch_unwind(|| {
+        let _guard = rt::no_alloc();
+        black_box(vec![0u8; 32]);
+    });
+    assert!(result.is_err());
+
+    rt::set_mode(Mode::Record);
+    let result = std::panic::catch_unwind(|| rt::assert_no_alloc(|| black_box(vec![0u8; 16]).len()));
+    assert!(result.is_err());
+    assert_eq!(rt::assert_no_alloc(|| buffer.len()), 256);
+
+    // A panic inside leaves the scope
+    let result = std::panic::catch_unwind(|| rt::assert_no_alloc(|| panic!("inside")));
+    assert!(result.is_err());
+    assert!(!rt::is_realtime());
+
+    // The profiler setting up the buffers of a new thread is not a violation
+    std::thread::spawn(|| {
+        rt::assert_no_alloc(|| {
+            voxea_alloc::perf::begin("rt::first_region");
+            voxea_alloc::perf::end("rt::first_region");
+        });
+    })
+    .join()
+    .unwrap();
+}
@@ -0,0 +1,48 @@
+use voxea_alloc::perf::trace::{self, Phase};
+use voxea_alloc::perf::{self, PerfTrace};
+use voxea_alloc::rt;
+
+#[global_allocator]
+static GLOBAL: voxea_alloc::MemAllocator = voxea_alloc::MemAllocator::new();
+
+#[test]
+fn events_of_every_thread_are_collected() {
+    trace::start(64);
+
+    std::thread::Builder::new()
+        .name("trace worker".to_string())
+        .spawn(|| {
+            // The first event of a thread sets up its buffer
+            perf::begin_perf!("trace::warm_up");
+
+            rt::assert_no_alloc(|| {
+                perf::begin_perf!("trace::worker");
+            });
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    perf::collect();
+    trace::stop();
+
+    let events = trace::events();
+    let worker = events
+        .iter()
+        .filter(|event| event.region == "trace::worker")
+        .collect::<Vec<_>>();
+
+    assert_eq!(worker.len(), 2);
+    assert_eq!(worker[0].phase, Phase::Begin);
+    assert_eq!(worker[1].phase, Phase::End);
+    assert!(worker[0].timestamp <= worker[1].timestamp);
+
+    let json = trace::to_json();
+    assert!(json.contains("\"trace worker\""));
+    assert!(json.contains("\"trace::worker\""));
+
+    // Starting again clears the previous recording
+    trace::start(64);
+    assert_eq!(trace::len(), 0);
+    trace::stop();
+}
@@ -0,0 +1,419 @@
+use voxea_alloc::ring::{self, Consumer, Producer};
+use crate::fft::Fft;
+use std::f32::consts::PI;
+use std::time::Duration;
+
+/// Samples that can be buffered between two updates of the [`Analyzer`]
+const RING_CAPACITY: usize = 1 << 16;
+
+/// Lowest level of the spectrum, silence is clamped to it
+pub const MIN_DB: f32 = -120.0;
+
+/// Analyses older than this many hops are skipped when the UI falls behind
+const MAX_HOPS_PER_UPDATE: usize = 8;
+
+/// Window function applied before every FFT
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WindowFunction {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl WindowFunction {
+    pub const ALL: [WindowFunction; 4] = [Self::Rectangular, Self::Hann, Self::Hamming, Self::Blackman];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rectangular => "Rectangular",
+            Self::Hann => "Hann",
+            Self::Hamming => "Hamming",
+            Self::Blackman => "Blackman",
+        }
+    }
+
+    pub fn coefficients(&self, size: usize) -> Vec<f32> {
+        let n = (size.max(2) - 1) as f32;
+
+        (0..size)
+            .map(|i| {
+                let x = 2.0 * PI * i as f32 / n;
+                match self {
+                    Self::Rectangular => 1.0,
+                    Self::Hann => 0.5 - 0.5 * x.cos(),
+                    Self::Hamming => 0.54 - 0.46 * x.cos(),
+                    Self::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AnalyzerConfig {
+    /// Power of two number of samples per FFT, consecutive FFTs overlap by half
+    pub fft_size: usize,
+    pub window: WindowFunction,
+    /// Share of the previous spectrum kept in every analysis, 0 disables averaging
+    pub averaging: f32,
+    /// How long peaks are held before they fall, zero disables peak hold
+    pub peak_hold: Duration,
+    /// Speed at which peaks fall after the hold time, in dB per second
+    pub peak_decay: f32,
+    /// Samples shown by the oscilloscope
+    pub scope_len: usize,
+}
+
+impl Default for AnalyzerConfig {
+    fn default() -> Self {
+        Self {
+            fft_size: 4096,
+            window: WindowFunction::Hann,
+            averaging: 0.7,
+            peak_hold: Duration::from_secs(1),
+            peak_decay: 20.0,
+            scope_len: 1024,
+        }
+    }
+}
+
+/// Creates the audio thread and UI sides of an analyzer
+pub fn analyzer(sample_rate: u32, config: AnalyzerConfig) -> (AnalyzerNode, Analyzer) {
+    let (sender, receiver) = ring::ring(RING_CAPACITY);
+
+    (AnalyzerNode { sender }, Analyzer::new(receiver, sample_rate, config))
+}
+
+/// Audio thread side of the analyzer, taps the signal without changing it
+pub struct AnalyzerNode {
+    sender: Producer<f32>,
+}
+
+impl AnalyzerNode {
+    /// Pushes the mono mix of an interleaved buffer into the ring, samples are dropped while it is full
+    pub fn process(&mut self, buffer: &[f32], channels: usize) {
+        let channels = channels.max(1);
+
+        for frame in buffer.chunks_exact(channels) {
+            let _ = self.sender.push(frame.iter().sum::<f32>() / channels as f32);
+        }
+    }
+}
+
+/// UI side of the analyzer, computes the spectrum and oscilloscope from the tapped samples
+pub struct Analyzer {
+    receiver: Consumer<f32>,
+    sample_rate: u32,
+    config: AnalyzerConfig,
+
+    /// Ring of the latest samples, `written` counts every sample ever received
+    history: Vec<f32>,
+    written: u64,
+    /// Samples received since the last analysis
+    pending: usize,
+
+    fft: Fft,
+    window: Vec<f32>,
+    /// Scales a bin so a full scale sine reads 0 dB
+    normalization: f32,
+    re: Vec<f32>,
+    im: Vec<f32>,
+
+    /// Averaged power per bin
+    power: Vec<f32>,
+    spectrum: Vec<f32>,
+    peaks: Vec<f32>,
+    peak_ages: Vec<f32>,
+    scope: Vec<f32>,
+    triggered: bool,
+}
+
+impl Analyzer {
+    fn new(receiver: Consumer<f32>, sample_rate: u32, config: AnalyzerConfig) -> Self {
+        let mut analyzer = Self {
+            receiver,
+            sample_rate,
+            config,
+
+            history: Vec::new(),
+            written: 0,
+            pending: 0,
+
+            fft: Fft::new(config.fft_size),
+            window: Vec::new(),
+            normalization: 0.0,
+            re: Vec::new(),
+            im: Vec::new(),
+
+            power: Vec::new(),
+            spectrum: Vec::new(),
+            peaks: Vec::new(),
+            peak_ages: Vec::new(),
+            scope: Vec::new(),
+            triggered: false,
+        };
+
+        analyzer.set_config(config);
+        analyzer
+    }
+
+    pub fn config(&self) -> &AnalyzerConfig {
+        &self.config
+    }
+
+    /// Applies a new config, resets the spectrum when the FFT size or window changes
+    pub fn set_config(&mut self, config: AnalyzerConfig) {
+        let size = config.fft_size;
+        let resize = self.window.is_empty() || size != self.config.fft_size || config.window != self.config.window;
+        let scope_len = config.scope_len.max(2);
+
+        self.config = config;
+        self.config.scope_len = scope_len;
+
+        // Enough for a window, the hops of one update and a triggered scope
+        let history = (size * 2 + MAX_HOPS_PER_UPDATE * size / 2).max(scope_len * 4);
+        if history != self.history.len() {
+            self.history = vec![0.0; history];
+            self.written = 0;
+            self.pending = 0;
+        }
+
+        if resize {
+            self.fft = Fft::new(size);
+            self.window = config.window.coefficients(size);
+            self.normalization = 2.0 / self.window.iter().sum::<f32>().max(f32::EPSILON);
+            self.re = vec![0.0; size];
+            self.im = vec![0.0; size];
+
+            let bins = size / 2 + 1;
+            self.power = vec![0.0; bins];
+            self.spectrum = vec![MIN_DB; bins];
+            self.peaks = vec![MIN_DB; bins];
+            self.peak_ages = vec![0.0; bins];
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Spectrum in dB per bin, see [`Analyzer::bin_frequency`]
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+
+    /// Highest level of every bin within the hold time, in dB
+    pub fn peaks(&self) -> &[f32] {
+        &self.peaks
+    }
+
+    /// Latest [`AnalyzerConfig::scope_len`] samples, starting at a rising zero crossing when one was found
+    pub fn scope(&self) -> &[f32] {
+        &self.scope
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate as f32 / self.config.fft_size as f32
+    }
+
+    pub fn reset_peaks(&mut self) {
+        self.peaks.copy_from_slice(&self.spectrum);
+        self.peak_ages.fill(0.0);
+    }
+
+    /// Drains the ring and runs an analysis for every half window of new samples
+    pub fn update(&mut self) {
+        let len = self.history.len();
+
+        for sample in self.receiver.drain() {
+            self.history[(self.written % len as u64) as usize] = sample;
+            self.written += 1;
+            self.pending += 1;
+        }
+
+        let size = self.config.fft_size;
+        let hop = size / 2;
+        self.pending = self.pending.min(hop * MAX_HOPS_PER_UPDATE);
+
+        // The first window ends once a full window was received, e.g. right after the history was reset
+        if let Some(complete) = self.written.checked_sub(size as u64) {
+            self.pending = self.pending.min(complete as usize + hop);
+        }
+
+        while self.pending >= hop && self.written >= size as u64 {
+            self.pending -= hop;
+            self.analyse(self.written - self.pending as u64, hop);
+        }
+
+        self.trigger();
+    }
+
+    /// Sample `index` of the stream, which must still be in the history
+    fn sample(&self, index: u64) -> f32 {
+        self.history[(index % self.history.len() as u64) as usize]
+    }
+
+    /// Runs an FFT over the window ending at sample `end`
+    fn analyse(&mut self, end: u64, hop: usize) {
+        let size = self.config.fft_size;
+        let start = end - size as u64;
+
+        for i in 0..size {
+            self.re[i] = self.sample(start + i as u64) * self.window[i];
+            self.im[i] = 0.0;
+        }
+
+        self.fft.process(&mut self.re, &mut self.im);
+
+        let dt = hop as f32 / self.sample_rate.max(1) as f32;
+        let averaging = self.config.averaging.clamp(0.0, 0.99);
+        let hold = self.config.peak_hold.as_secs_f32();
+
+        for bin in 0..self.power.len() {
+            let re = self.re[bin] * self.normalization;
+            let im = self.im[bin] * self.normalization;
+
+            self.power[bin] = self.power[bin] * averaging + (re * re + im * im) * (1.0 - averaging);
+
+            let db = (10.0 * self.power[bin].max(f32::MIN_POSITIVE).log10()).max(MIN_DB);
+            self.spectrum[bin] = db;
+
+            if hold <= 0.0 || db >= self.peaks[bin] {
+                self.peaks[bin] = db;
+                self.peak_ages[bin] = 0.0;
+            } else {
+                self.peak_ages[bin] += dt;
+
+                if self.peak_ages[bin] > hold {
+                    self.peaks[bin] = (self.peaks[bin] - self.config.peak_decay * dt).max(db);
+                }
+            }
+        }
+    }
+
+    /// Finds the latest rising zero crossing that still has a full scope of samples after it
+    fn trigger(&mut self) {
+        let scope_len = self.config.scope_len;
+        let available = self.written.min(self.history.len() as u64);
+
+        self.scope.clear();
+        if available < scope_len as u64 {
+            self.triggered = false;
+            return;
+        }
+
+        let latest = self.written - scope_len as u64;
+        let earliest = self.written - available + 1;
+
+        let crossing = (earliest..=latest)
+            .rev()
+            .take(scope_len)
+            .find(|&i| self.sample(i - 1) < 0.0 && self.sample(i) >= 0.0);
+
+        self.triggered = crossing.is_some();
+
+        let start = crossing.unwrap_or(latest);
+        let len = self.history.len() as u64;
+        self.scope
+            .extend((start..start + scope_len as u64).map(|i| self.history[(i % len) as usize]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48000;
+    const SIZE: usize = 256;
+
+    fn config() -> AnalyzerConfig {
+        AnalyzerConfig {
+            fft_size: SIZE,
+            averaging: 0.0,
+            scope_len: 64,
+            ..Default::default()
+        }
+    }
+
+    /// Full scale sine at the center frequency of `bin`, starting at phase `offset`
+    fn sine(bin: usize, len: usize, offset: usize) -> Vec<f32> {
+        (offset..offset + len)
+            .map(|i| (2.0 * PI * bin as f32 * i as f32 / SIZE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn first_full_window() {
+        let (mut node, mut analyzer) = analyzer(SAMPLE_RATE, config());
+
+        node.process(&sine(8, SIZE - 1, 0), 1);
+        analyzer.update();
+        assert!(analyzer.spectrum().iter().all(|&db| db == MIN_DB));
+
+        node.process(&sine(8, 1, SIZE - 1), 1);
+        analyzer.update();
+        assert!(analyzer.spectrum()[8] > -1.0);
+        assert!(analyzer.spectrum()[40] < -60.0);
+    }
+
+    #[test]
+    fn resized_history() {
+        let (mut node, mut analyzer) = analyzer(SAMPLE_RATE, config());
+
+        node.process(&sine(8, SIZE * 8, 0), 1);
+        analyzer.update();
+
+        // A larger FFT starts over with an empty history, bin 8 of the smaller one is bin 16 of it
+        analyzer.set_config(AnalyzerConfig {
+            fft_size: SIZE * 2,
+            ..config()
+        });
+        node.process(&sine(8, SIZE * 2, 0), 1);
+        analyzer.update();
+
+        assert_eq!(analyzer.spectrum().len(), SIZE + 1);
+        assert!(analyzer.spectrum()[16] > -1.0);
+    }
+
+    #[test]
+    fn mono_mix() {
+        let (mut node, mut analyzer) = analyzer(SAMPLE_RATE, config());
+
+        // Opposite channels cancel out
+        let stereo = sine(8, SIZE, 0).iter().flat_map(|&s| [s, -s]).collect::<Vec<_>>();
+        node.process(&stereo, 2);
+        analyzer.update();
+
+        assert!(analyzer.spectrum().iter().all(|&db| db == MIN_DB));
+    }
+
+    #[test]
+    fn trigger() {
+        let (mut node, mut analyzer) = analyzer(SAMPLE_RATE, config());
+
+        node.process(&[0.5; 63], 1);
+        analyzer.update();
+        assert!(analyzer.scope().is_empty());
+        assert!(!analyzer.is_triggered());
+
+        // No zero crossing, the scope shows the latest samples
+        node.process(&[0.5; 64], 1);
+        analyzer.update();
+        assert_eq!(analyzer.scope(), &[0.5; 64]);
+        assert!(!analyzer.is_triggered());
+
+        node.process(&sine(4, SIZE, 3), 1);
+        analyzer.update();
+
+        let scope = analyzer.scope();
+        assert!(analyzer.is_triggered());
+        assert_eq!(scope.len(), 64);
+        assert!(scope[0] >= 0.0 && scope[0] < 0.1);
+        assert!(scope[1] > scope[0]);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::analyzer::{self, Analyzer, AnalyzerConfig, AnalyzerNode};
+use crate::monitor::{self, StatsReceiver};
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{BufferSize, FromSample, SizedSample, Stream, StreamConfig, SupportedBufferSize};
+use log::{error, info};
+use voxea_alloc::perf;
+
+/// Largest block the engine processes at once, larger buffers are processed in blocks of this size
+const MAX_BLOCK: usize = 8192;
+
+/// Callback that fills an interleaved `f32` buffer with the given number of channels
+pub type Process = Box<dyn FnMut(&mut [f32], usize) + Send + 'static>;
+
+/// A running output stream whose callbacks are measured by a [`monitor::CallbackMonitor`]
+/// and whose output is tapped by an [`Analyzer`]
+pub struct Engine {
+    stream: Stream,
+    stats: StatsReceiver,
+    analyzer: Analyzer,
+    config: StreamConfig,
+}
+
+impl Engine {
+    /// Starts an output stream on the default device of the default host
+    pub fn start_default(process: Process) -> Result<Self> {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or_else(|| anyhow!("No default output device"))?;
+
+        Self::start(&device, process)
+    }
+
+    /// Starts an output stream on a device with its default config
+    pub fn start(device: &cpal::Device, process: Process) -> Result<Self> {
+        let supported = device.default_output_config()?;
+        let sample_format = supported.sample_format();
+        let max_frames = match supported.buffer_size() {
+            SupportedBufferSize::Range { max, .. } => (*max as usize).clamp(1, MAX_BLOCK),
+            SupportedBufferSize::Unknown => MAX_BLOCK,
+        };
+        let config: StreamConfig = supported.into();
+
+        info!("Starting audio engine on {} with {:?}", device.name()?, config);
+
+        let (node, analyzer) = analyzer::analyzer(config.sample_rate.0, AnalyzerConfig::default());
+
+        let (stream, stats) = match sample_format {
+            cpal::SampleFormat::I16 => build::<i16>(device, &config, max_frames, process, node)?,
+            cpal::SampleFormat::I32 => build::<i32>(device, &config, max_frames, process, node)?,
+            cpal::SampleFormat::U16 => build::<u16>(device, &config, max_frames, process, node)?,
+            cpal::SampleFormat::F32 => build::<f32>(device, &config, max_frames, process, node)?,
+            cpal::SampleFormat::F64 => build::<f64>(device, &config, max_frames, process, node)?,
+            sample_format => return Err(anyhow!("Unsupported sample format '{sample_format}'")),
+        };
+
+        stream.play()?;
+
+        Ok(Self {
+            stream,
+            stats,
+            analyzer,
+            config,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.config.sample_rate.0
+    }
+
+    pub fn channels(&self) -> usize {
+        self.config.channels as usize
+    }
+
+    pub fn stats(&mut self) -> &mut StatsReceiver {
+        &mut self.stats
+    }
+
+    /// Analyzer of the output, see [`Analyzer::update`]
+    pub fn analyzer(&mut self) -> &mut Analyzer {
+        &mut self.analyzer
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        Ok(self.stream.pause()?)
+    }
+}
+
+/// Builds the output stream, blocks are at most `max_frames` long so the scratch buffer never grows on the audio
+/// thread
+fn build<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    max_frames: usize,
+    mut process: Process,
+    mut analyzer: AnalyzerNode,
+) -> Result<(Stream, StatsReceiver)>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let (mut monitor, stats) = monitor::monitor(config.sample_rate.0);
+    let xruns = monitor.xrun_counter();
+
+    let max_frames = match config.buffer_size {
+        BufferSize::Fixed(frames) => (frames as usize).clamp(1, MAX_BLOCK),
+        BufferSize::Default => max_frames,
+    };
+    let mut scratch = vec![0.0f32; max_frames * channels.max(1)];
+    let mut registered = false;
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            let frames = data.len() / channels.max(1);
+
+            // The callback runs on a thread of the audio backend, which can not be set up before the stream starts
+            if !registered {
+                perf::register_thread();
+                registered = true;
+            }
+
+            monitor.measure(info.timestamp().callback, frames, || {
+                // Buffers larger than the device reported are split instead of growing the scratch buffer
+                for block in data.chunks_mut(scratch.len()) {
+                    let scratch = &mut scratch[..block.len()];
+                    scratch.fill(0.0);
+
+                    process(scratch, channels);
+                    analyzer.process(scratch, channels);
+
+                    for (sample, &value) in block.iter_mut().zip(scratch.iter()) {
+                        *sample = T::from_sample(value);
+                    }
+                }
+            });
+        },
+        move |err| {
+            error!("an error occurred on stream: {}", err);
+            xruns.report();
+        },
+        None,
+    )?;
+
+    Ok((stream, stats))
+}
@@ -0,0 +1,71 @@
+use std::f32::consts::PI;
+
+/// In-place radix-2 FFT of a fixed power of two size, with twiddles and bit reversal computed up front
+pub struct Fft {
+    size: usize,
+    twiddles: Vec<(f32, f32)>,
+    reversed: Vec<usize>,
+}
+
+impl Fft {
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two(), "FFT size {size} is not a power of two");
+
+        let bits = size.trailing_zeros();
+        let reversed = (0..size)
+            .map(|i| if bits == 0 { 0 } else { i.reverse_bits() >> (usize::BITS - bits) })
+            .collect();
+
+        let twiddles = (0..size / 2)
+            .map(|i| {
+                let angle = -2.0 * PI * i as f32 / size as f32;
+                (angle.cos(), angle.sin())
+            })
+            .collect();
+
+        Self {
+            size,
+            twiddles,
+            reversed,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Transforms the complex signal in `re` and `im`, both must be [`Fft::size`] long
+    pub fn process(&self, re: &mut [f32], im: &mut [f32]) {
+        assert!(re.len() == self.size && im.len() == self.size);
+
+        for (i, &j) in self.reversed.iter().enumerate() {
+            if i < j {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= self.size {
+            let half = len / 2;
+            let stride = self.size / len;
+
+            for start in (0..self.size).step_by(len) {
+                for k in 0..half {
+                    let (cos, sin) = self.twiddles[k * stride];
+                    let (a, b) = (start + k, start + k + half);
+
+                    let t_re = re[b] * cos - im[b] * sin;
+                    let t_im = re[b] * sin + im[b] * cos;
+
+                    re[b] = re[a] - t_re;
+                    im[b] = im[a] - t_im;
+                    re[a] += t_re;
+                    im[a] += t_im;
+                }
+            }
+
+            len *= 2;
+        }
+    }
+}
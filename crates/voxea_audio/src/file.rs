@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// Decoded audio file with interleaved `f32` samples
+#[derive(Debug, Clone)]
+pub struct AudioFile {
+    pub path: PathBuf,
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub samples: Vec<f32>,
+}
+
+impl AudioFile {
+    /// Decodes a WAV file in any of its integer or float formats
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut wav = hound::WavReader::open(path)?;
+        let spec = wav.spec();
+
+        if spec.channels == 0 {
+            return Err(anyhow!("{} has no channels", path.display()));
+        }
+
+        let samples = match spec.sample_format {
+            hound::SampleFormat::Float => wav.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => {
+                // Integer samples are normalized to [-1, 1] by their bit depth
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                wav.samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            sample_rate: spec.sample_rate,
+            channels: spec.channels as usize,
+            samples,
+        })
+    }
+
+    /// Writes the samples as a 32 bit float WAV file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let spec = hound::WavSpec {
+            channels: self.channels as u16,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+
+        let mut wav = hound::WavWriter::create(path, spec)?;
+        for &sample in &self.samples {
+            wav.write_sample(sample)?;
+        }
+        wav.finalize()?;
+
+        Ok(())
+    }
+
+    /// Number of samples per channel
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1)
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.frames() as f64 / self.sample_rate.max(1) as f64
+    }
+}
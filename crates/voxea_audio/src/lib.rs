@@ -1,3 +1,10 @@
+pub mod analyzer;
+pub mod engine;
+pub mod fft;
+pub mod file;
+pub mod mixer;
+pub mod monitor;
+
 use std::fmt::Debug;
 use anyhow::Result;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
@@ -304,9 +311,3 @@ where
         }
     }
 }
-
-fn decode_wav_file(file: String) -> Result<()>{
-    let mut wav = hound::WavReader::open(file)?;
-
-    Ok(())
-}
\ No newline at end of file
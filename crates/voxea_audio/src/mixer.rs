@@ -0,0 +1,1387 @@
+//! Mixer with one strip per track or bus and a master strip. The UI owns a [`Mixer`] and the audio thread a
+//! [`MixerNode`], changes are sent to the node and meter readings back over lock-free channels.
+
+use voxea_alloc::ring::{self, Consumer, Producer};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use voxea_alloc::perf::metrics;
+
+pub const MAX_STRIPS: usize = 64;
+pub const MAX_INSERTS: usize = 8;
+pub const MAX_SENDS: usize = 8;
+
+/// Lowest level of faders and meters, anything below is silence
+pub const MIN_DB: f32 = -60.0;
+pub const MAX_DB: f32 = 6.0;
+
+/// Samples every strip buffer holds up front, larger buffers grow them once on the audio thread
+const BUFFER_CAPACITY: usize = 8192 * 2;
+
+const COMMAND_CAPACITY: usize = 256;
+const METER_CAPACITY: usize = 4096;
+const LOAD_CAPACITY: usize = 4096;
+const GARBAGE_CAPACITY: usize = 256;
+
+/// Removed strips and inserts kept with their processors so [`Mixer::restore`] can put them back, the oldest
+/// are freed first
+const DETACHED_STRIPS: usize = 16;
+const DETACHED_INSERTS: usize = 32;
+
+/// How long meter peaks are held before they fall
+const PEAK_HOLD: f32 = 1.5;
+/// Speed at which meters fall, in dB per second
+const METER_DECAY: f32 = 24.0;
+
+/// Audio processing run on the audio thread, either generating into a silent buffer or changing it in place.
+/// Buffers are interleaved with the given number of channels.
+pub trait Processor: Send {
+    fn process(&mut self, buffer: &mut [f32], channels: usize);
+}
+
+impl<F: FnMut(&mut [f32], usize) + Send> Processor for F {
+    fn process(&mut self, buffer: &mut [f32], channels: usize) {
+        self(buffer, channels)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StripId(u32);
+
+impl StripId {
+    pub const MASTER: StripId = StripId(0);
+}
+
+/// Identifies an insert for as long as the mixer exists, unlike its index which changes as inserts are removed
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InsertId(u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StripKind {
+    /// Plays a source, e.g. the clips of a track
+    Track,
+    /// Mixes tracks routed or sent to it
+    Bus,
+    /// Mixes everything into the output, there is exactly one
+    Master,
+}
+
+/// Copy of a strip's signal to a bus
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuxSend {
+    pub target: StripId,
+    /// Level in dB
+    pub level: f32,
+    /// Taken before the fader and pan instead of after them
+    pub pre_fader: bool,
+}
+
+/// Where the processor of an insert comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InsertKind {
+    /// A WASM plugin by id
+    Wasm(String),
+    /// A class of a VST3 bundle
+    Vst3 { path: PathBuf, cid: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InsertSlot {
+    /// Assigned by [`Mixer::add_insert`]
+    pub id: InsertId,
+    pub name: String,
+    pub kind: InsertKind,
+    pub bypassed: bool,
+}
+
+/// Peak and RMS levels of the first two channels of a strip in dB, with falloff
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Meter {
+    pub peak: [f32; 2],
+    pub rms: [f32; 2],
+    pub hold: [f32; 2],
+    /// Seconds since the held peaks were reached
+    held_for: [f32; 2],
+    /// Whether the signal went above 0 dB, see [`Mixer::reset_clip`]
+    pub clipped: bool,
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self {
+            peak: [MIN_DB; 2],
+            rms: [MIN_DB; 2],
+            hold: [MIN_DB; 2],
+            held_for: [0.0; 2],
+            clipped: false,
+        }
+    }
+}
+
+impl Meter {
+    fn decay(&mut self, seconds: f32) {
+        let fall = METER_DECAY * seconds;
+
+        for channel in 0..2 {
+            self.peak[channel] = (self.peak[channel] - fall).max(MIN_DB);
+            self.rms[channel] = (self.rms[channel] - fall).max(MIN_DB);
+
+            self.held_for[channel] += seconds;
+            if self.held_for[channel] > PEAK_HOLD {
+                self.hold[channel] = (self.hold[channel] - fall).max(MIN_DB);
+            }
+        }
+    }
+
+    fn apply(&mut self, reading: &MeterReading) {
+        for channel in 0..2 {
+            let peak = to_db(reading.peak[channel]);
+            let rms = to_db(reading.rms[channel]);
+
+            self.peak[channel] = self.peak[channel].max(peak);
+            self.rms[channel] = self.rms[channel].max(rms);
+
+            if peak >= self.hold[channel] {
+                self.hold[channel] = peak;
+                self.held_for[channel] = 0.0;
+            }
+
+            self.clipped |= reading.peak[channel] > 1.0;
+        }
+    }
+}
+
+/// Linear levels of one strip for one buffer
+#[derive(Debug, Copy, Clone)]
+struct MeterReading {
+    strip: StripId,
+    peak: [f32; 2],
+    rms: [f32; 2],
+}
+
+/// Processing time of one strip and each of its inserts for one buffer
+#[derive(Debug, Copy, Clone)]
+struct LoadReading {
+    strip: StripId,
+    frames: usize,
+    elapsed: Duration,
+    inserts: [Duration; MAX_INSERTS],
+}
+
+/// UI side of a strip, changed through the [`Mixer`] so the audio thread stays in sync
+pub struct Strip {
+    id: StripId,
+    name: String,
+    kind: StripKind,
+    gain: f32,
+    pan: f32,
+    mute: bool,
+    solo: bool,
+    arm: bool,
+    output: StripId,
+    inserts: Vec<InsertSlot>,
+    sends: Vec<AuxSend>,
+    meter: Meter,
+}
+
+impl Strip {
+    fn new(id: StripId, name: &str, kind: StripKind) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            kind,
+            gain: 0.0,
+            pan: 0.0,
+            mute: false,
+            solo: false,
+            arm: false,
+            output: StripId::MASTER,
+            inserts: Vec::new(),
+            sends: Vec::new(),
+            meter: Meter::default(),
+        }
+    }
+
+    pub fn id(&self) -> StripId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> StripKind {
+        self.kind
+    }
+
+    /// Fader level in dB
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Balance from -1 (left) to 1 (right)
+    pub fn pan(&self) -> f32 {
+        self.pan
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.mute
+    }
+
+    pub fn is_soloed(&self) -> bool {
+        self.solo
+    }
+
+    /// Armed for recording
+    pub fn is_armed(&self) -> bool {
+        self.arm
+    }
+
+    /// Strip the signal is mixed into after the fader, the master has none
+    pub fn output(&self) -> Option<StripId> {
+        (self.kind != StripKind::Master).then_some(self.output)
+    }
+
+    pub fn inserts(&self) -> &[InsertSlot] {
+        &self.inserts
+    }
+
+    pub fn sends(&self) -> &[AuxSend] {
+        &self.sends
+    }
+
+    pub fn meter(&self) -> &Meter {
+        &self.meter
+    }
+}
+
+/// Settings of a strip and its inserts, enough to put a removed strip back with [`Mixer::restore`]. The processors
+/// of the source and inserts are not part of it, the mixer keeps them for a while after they are removed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StripState {
+    pub id: StripId,
+    pub name: String,
+    pub kind: StripKind,
+    pub gain: f32,
+    pub pan: f32,
+    pub mute: bool,
+    pub solo: bool,
+    pub arm: bool,
+    pub output: StripId,
+    pub sends: Vec<AuxSend>,
+    #[serde(default)]
+    pub inserts: Vec<InsertSlot>,
+}
+
+impl From<&Strip> for StripState {
+    fn from(strip: &Strip) -> Self {
+        Self {
+            id: strip.id,
+            name: strip.name.clone(),
+            kind: strip.kind,
+            gain: strip.gain,
+            pan: strip.pan,
+            mute: strip.mute,
+            solo: strip.solo,
+            arm: strip.arm,
+            output: strip.output,
+            sends: strip.sends.clone(),
+            inserts: strip.inserts.clone(),
+        }
+    }
+}
+
+enum MixerCommand {
+    AddStrip(Box<StripNode>),
+    /// Detaches a strip, see [`MixerCommand::ReviveStrip`]
+    RemoveStrip(StripId),
+    /// Puts a detached strip back with its source and inserts
+    ReviveStrip(StripId),
+    SetGain(StripId, f32),
+    SetPan(StripId, f32),
+    SetMute(StripId, bool),
+    SetSolo(StripId, bool),
+    SetOutput(StripId, StripId),
+    SetSends(StripId, [Option<AuxSend>; MAX_SENDS]),
+    SetSource(StripId, Option<Box<dyn Processor>>),
+    AddInsert(StripId, usize, InsertNode),
+    /// Detaches an insert, see [`MixerCommand::ReviveInsert`]
+    RemoveInsert(StripId, usize),
+    ReviveInsert(StripId, usize, InsertId),
+    SetBypass(StripId, usize, bool),
+    /// Frees every detached strip and insert
+    Forget,
+}
+
+/// Everything the audio thread lets go of, only kept to be dropped on the UI thread instead of freeing
+/// memory during processing
+#[allow(dead_code)]
+enum Garbage {
+    Strip(Box<StripNode>),
+    Processor(Box<dyn Processor>),
+}
+
+/// Creates the UI and audio thread sides of a mixer with only a master strip
+pub fn mixer() -> (Mixer, MixerNode) {
+    with_capacity(COMMAND_CAPACITY)
+}
+
+/// Mixer processed on the calling thread instead of the audio thread, e.g. to bounce faster than real time.
+/// Starts out with the strips of a [`Mixer::state`], their inserts get new processors from `instantiate`.
+pub fn offline(
+    states: &[StripState],
+    mut instantiate: impl FnMut(&InsertSlot) -> Result<Box<dyn Processor>>,
+) -> Result<(Mixer, MixerNode)> {
+    let strips = states
+        .iter()
+        .map(|state| StripState {
+            inserts: Vec::new(),
+            ..state.clone()
+        })
+        .collect::<Vec<_>>();
+
+    // All changes are queued before the node processes anything
+    let (mut mixer, node) = with_capacity(MAX_STRIPS * (16 + MAX_INSERTS));
+    mixer.restore(&strips)?;
+
+    for state in states {
+        for slot in &state.inserts {
+            mixer.add_insert(state.id, slot.clone(), instantiate(slot)?)?;
+        }
+    }
+
+    Ok((mixer, node))
+}
+
+fn with_capacity(command_capacity: usize) -> (Mixer, MixerNode) {
+    let (commands, command_receiver) = ring::ring(command_capacity);
+    let (meter_sender, meters) = ring::ring(METER_CAPACITY);
+    let (load_sender, loads) = ring::ring(LOAD_CAPACITY);
+    let (garbage_sender, garbage) = ring::ring(GARBAGE_CAPACITY);
+
+    let mut strips = Vec::with_capacity(MAX_STRIPS);
+    strips.push(Box::new(StripNode::new(StripId::MASTER, StripKind::Master)));
+
+    (
+        Mixer {
+            strips: vec![Strip::new(StripId::MASTER, "Master", StripKind::Master)],
+            next_id: 1,
+            next_insert: 1,
+            revision: 0,
+            detached: VecDeque::with_capacity(DETACHED_STRIPS),
+            detached_inserts: VecDeque::with_capacity(DETACHED_INSERTS),
+            commands,
+            meters,
+            loads,
+            garbage,
+            last_update: Instant::now(),
+        },
+        MixerNode {
+            strips,
+            detached: Vec::with_capacity(DETACHED_STRIPS),
+            detached_inserts: Vec::with_capacity(DETACHED_INSERTS),
+            commands: command_receiver,
+            meters: meter_sender,
+            loads: load_sender,
+            garbage: garbage_sender,
+        },
+    )
+}
+
+/// UI side of the mixer
+pub struct Mixer {
+    strips: Vec<Strip>,
+    next_id: u32,
+    next_insert: u32,
+    /// Counts changes to the state, see [`Mixer::revision`]
+    revision: u64,
+    /// Strips and inserts the node keeps detached, in the same order so both drop the same ones when full
+    detached: VecDeque<Strip>,
+    detached_inserts: VecDeque<InsertSlot>,
+    commands: Producer<MixerCommand>,
+    meters: Consumer<MeterReading>,
+    loads: Consumer<LoadReading>,
+    garbage: Consumer<Garbage>,
+    last_update: Instant,
+}
+
+impl Mixer {
+    /// Strips in processing order within their kind, the master is always first
+    pub fn strips(&self) -> &[Strip] {
+        &self.strips
+    }
+
+    pub fn strip(&self, id: StripId) -> Option<&Strip> {
+        self.strips.iter().find(|strip| strip.id == id)
+    }
+
+    fn strip_mut(&mut self, id: StripId) -> Result<&mut Strip> {
+        self.strips
+            .iter_mut()
+            .find(|strip| strip.id == id)
+            .ok_or_else(|| anyhow!("No mixer strip {id:?}"))
+    }
+
+    /// Fails if fewer than `count` commands fit in the queue. Called before the UI side changes, so a full queue
+    /// leaves both sides as they were instead of letting them disagree.
+    fn reserve(&self, count: usize) -> Result<()> {
+        if self.commands.capacity() - self.commands.len() < count {
+            return Err(anyhow!("The mixer is busy, try again"));
+        }
+        Ok(())
+    }
+
+    /// Queues a command there is room for, see [`Mixer::reserve`]
+    fn send(&mut self, command: MixerCommand) {
+        let queued = self.commands.push(command);
+        debug_assert!(queued, "mixer command sent without reserving room for it");
+        self.revision += 1;
+    }
+
+    /// Changes whenever [`Mixer::state`] may have changed, to find out without building the state
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Keeps a removed strip the way the node does, see [`MixerCommand::RemoveStrip`]
+    fn detach(&mut self, strip: Strip) {
+        if self.detached.len() == DETACHED_STRIPS {
+            self.detached.pop_front();
+        }
+        self.detached.push_back(strip);
+    }
+
+    fn detach_insert(&mut self, slot: InsertSlot) {
+        if self.detached_inserts.len() == DETACHED_INSERTS {
+            self.detached_inserts.pop_front();
+        }
+        self.detached_inserts.push_back(slot);
+    }
+
+    /// Inserts that were removed, on their own or with their strip, and can still be put back by [`Mixer::restore`]
+    pub fn detached_inserts(&self) -> impl Iterator<Item = &InsertSlot> {
+        self.detached
+            .iter()
+            .flat_map(|strip| &strip.inserts)
+            .chain(&self.detached_inserts)
+    }
+
+    /// Whether any track is soloed, which silences the others
+    pub fn is_soloed(&self) -> bool {
+        self.strips.iter().any(|strip| strip.kind == StripKind::Track && strip.solo)
+    }
+
+    pub fn add_strip(&mut self, name: &str, kind: StripKind) -> Result<StripId> {
+        if kind == StripKind::Master {
+            return Err(anyhow!("There can only be one master strip"));
+        }
+        if self.strips.len() >= MAX_STRIPS {
+            return Err(anyhow!("The mixer is limited to {MAX_STRIPS} strips"));
+        }
+
+        self.reserve(1)?;
+
+        let id = StripId(self.next_id);
+        self.next_id += 1;
+
+        self.strips.push(Strip::new(id, name, kind));
+        self.send(MixerCommand::AddStrip(Box::new(StripNode::new(id, kind))));
+
+        Ok(id)
+    }
+
+    /// Removes a track or bus, strips routed to it go to the master and sends to it are removed
+    pub fn remove_strip(&mut self, id: StripId) -> Result<()> {
+        if id == StripId::MASTER {
+            return Err(anyhow!("The master strip can not be removed"));
+        }
+
+        let index = self
+            .strips
+            .iter()
+            .position(|strip| strip.id == id)
+            .ok_or_else(|| anyhow!("No mixer strip {id:?}"))?;
+
+        let routed = self
+            .strips
+            .iter()
+            .filter(|strip| strip.id != id)
+            .filter(|strip| strip.output == id || strip.sends.iter().any(|send| send.target == id))
+            .map(|strip| strip.id)
+            .collect::<Vec<_>>();
+
+        // Each routed strip may need a new output and new sends
+        self.reserve(1 + 2 * routed.len())?;
+
+        let strip = self.strips.remove(index);
+        self.send(MixerCommand::RemoveStrip(id));
+        self.detach(strip);
+
+        for strip in routed {
+            if self.strip_mut(strip)?.output == id {
+                self.set_output(strip, StripId::MASTER)?;
+            }
+
+            let sends = &mut self.strip_mut(strip)?.sends;
+            sends.retain(|send| send.target != id);
+            self.sync_sends(strip)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_name(&mut self, id: StripId, name: &str) -> Result<()> {
+        self.strip_mut(id)?.name = name.to_string();
+        self.revision += 1;
+        Ok(())
+    }
+
+    pub fn set_gain(&mut self, id: StripId, db: f32) -> Result<()> {
+        let db = db.clamp(MIN_DB, MAX_DB);
+        self.reserve(1)?;
+        self.strip_mut(id)?.gain = db;
+        self.send(MixerCommand::SetGain(id, db));
+        Ok(())
+    }
+
+    pub fn set_pan(&mut self, id: StripId, pan: f32) -> Result<()> {
+        let pan = pan.clamp(-1.0, 1.0);
+        self.reserve(1)?;
+        self.strip_mut(id)?.pan = pan;
+        self.send(MixerCommand::SetPan(id, pan));
+        Ok(())
+    }
+
+    pub fn set_mute(&mut self, id: StripId, mute: bool) -> Result<()> {
+        self.reserve(1)?;
+        self.strip_mut(id)?.mute = mute;
+        self.send(MixerCommand::SetMute(id, mute));
+        Ok(())
+    }
+
+    pub fn set_solo(&mut self, id: StripId, solo: bool) -> Result<()> {
+        self.reserve(1)?;
+        self.strip_mut(id)?.solo = solo;
+        self.send(MixerCommand::SetSolo(id, solo));
+        Ok(())
+    }
+
+    pub fn set_arm(&mut self, id: StripId, arm: bool) -> Result<()> {
+        self.strip_mut(id)?.arm = arm;
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Routes a track to a bus or the master, buses can only go to the master
+    pub fn set_output(&mut self, id: StripId, output: StripId) -> Result<()> {
+        let target = self.strip(output).ok_or_else(|| anyhow!("No mixer strip {output:?}"))?.kind;
+        self.reserve(1)?;
+        let strip = self.strip_mut(id)?;
+
+        match (strip.kind, target) {
+            (StripKind::Master, _) => return Err(anyhow!("The master strip has no output")),
+            (StripKind::Bus, StripKind::Bus) | (_, StripKind::Track) => {
+                return Err(anyhow!("{:?} strips can not be routed to {target:?} strips", strip.kind))
+            }
+            _ => {}
+        }
+
+        strip.output = output;
+        self.send(MixerCommand::SetOutput(id, output));
+        Ok(())
+    }
+
+    /// Adds a post fader send from a track to a bus
+    pub fn add_send(&mut self, id: StripId, target: StripId) -> Result<()> {
+        if self.strip(target).map(|strip| strip.kind) != Some(StripKind::Bus) {
+            return Err(anyhow!("Sends can only go to buses"));
+        }
+        self.reserve(1)?;
+
+        let strip = self.strip_mut(id)?;
+        if strip.kind != StripKind::Track {
+            return Err(anyhow!("Only tracks have sends"));
+        }
+        if strip.sends.len() >= MAX_SENDS {
+            return Err(anyhow!("Strips are limited to {MAX_SENDS} sends"));
+        }
+
+        strip.sends.push(AuxSend {
+            target,
+            level: 0.0,
+            pre_fader: false,
+        });
+        self.sync_sends(id)
+    }
+
+    pub fn set_send(&mut self, id: StripId, index: usize, level: f32, pre_fader: bool) -> Result<()> {
+        self.reserve(1)?;
+        let send = self
+            .strip_mut(id)?
+            .sends
+            .get_mut(index)
+            .ok_or_else(|| anyhow!("No send {index}"))?;
+
+        send.level = level.clamp(MIN_DB, MAX_DB);
+        send.pre_fader = pre_fader;
+        self.sync_sends(id)
+    }
+
+    pub fn remove_send(&mut self, id: StripId, index: usize) -> Result<()> {
+        self.reserve(1)?;
+        let sends = &mut self.strip_mut(id)?.sends;
+        if index >= sends.len() {
+            return Err(anyhow!("No send {index}"));
+        }
+
+        sends.remove(index);
+        self.sync_sends(id)
+    }
+
+    fn sync_sends(&mut self, id: StripId) -> Result<()> {
+        let mut sends = [None; MAX_SENDS];
+        for (slot, send) in sends.iter_mut().zip(&self.strip_mut(id)?.sends) {
+            *slot = Some(*send);
+        }
+
+        self.send(MixerCommand::SetSends(id, sends));
+        Ok(())
+    }
+
+    /// Sets what a track plays, `None` makes it silent
+    pub fn set_source(&mut self, id: StripId, source: Option<Box<dyn Processor>>) -> Result<()> {
+        if self.strip_mut(id)?.kind != StripKind::Track {
+            return Err(anyhow!("Only tracks have a source"));
+        }
+
+        self.reserve(1)?;
+        self.send(MixerCommand::SetSource(id, source));
+        Ok(())
+    }
+
+    /// Appends an insert to the chain of a strip
+    pub fn add_insert(&mut self, id: StripId, mut slot: InsertSlot, processor: Box<dyn Processor>) -> Result<InsertId> {
+        let insert = InsertId(self.next_insert);
+        self.reserve(1)?;
+        let inserts = &mut self.strip_mut(id)?.inserts;
+        if inserts.len() >= MAX_INSERTS {
+            return Err(anyhow!("Strips are limited to {MAX_INSERTS} inserts"));
+        }
+
+        let index = inserts.len();
+        let node = InsertNode {
+            id: insert,
+            processor,
+            bypassed: slot.bypassed,
+        };
+        slot.id = insert;
+        inserts.push(slot);
+        self.next_insert += 1;
+
+        self.send(MixerCommand::AddInsert(id, index, node));
+        Ok(insert)
+    }
+
+    pub fn remove_insert(&mut self, id: StripId, index: usize) -> Result<InsertSlot> {
+        self.reserve(1)?;
+        let inserts = &mut self.strip_mut(id)?.inserts;
+        if index >= inserts.len() {
+            return Err(anyhow!("No insert {index}"));
+        }
+
+        let slot = inserts.remove(index);
+        self.send(MixerCommand::RemoveInsert(id, index));
+        self.detach_insert(slot.clone());
+        Ok(slot)
+    }
+
+    /// Puts a detached insert back at `index`, false when it was freed already
+    fn revive_insert(&mut self, id: StripId, index: usize, slot: &InsertSlot) -> Result<bool> {
+        let Some(detached) = self.detached_inserts.iter().position(|detached| detached.id == slot.id) else {
+            return Ok(false);
+        };
+
+        let inserts = &self.strip_mut(id)?.inserts;
+        if inserts.len() >= MAX_INSERTS {
+            return Err(anyhow!("Strips are limited to {MAX_INSERTS} inserts"));
+        }
+        let index = index.min(inserts.len());
+        self.reserve(1)?;
+
+        let revived = self.detached_inserts.remove(detached).expect("detached insert");
+        self.strip_mut(id)?.inserts.insert(index, revived);
+        self.send(MixerCommand::ReviveInsert(id, index, slot.id));
+        Ok(true)
+    }
+
+    /// Brings the inserts of a strip to `slots`: inserts missing from them or out of order are detached, the
+    /// rest are put back from the detached ones. Names of the inserts that were freed already end up in `lost`.
+    fn restore_inserts(&mut self, id: StripId, slots: &[InsertSlot], lost: &mut Vec<String>) -> Result<()> {
+        loop {
+            let inserts = &self.strip_mut(id)?.inserts;
+            let kept = inserts
+                .iter()
+                .zip(slots)
+                .take_while(|(insert, slot)| insert.id == slot.id)
+                .count();
+
+            if kept == inserts.len() {
+                break;
+            }
+            self.remove_insert(id, kept)?;
+        }
+
+        let mut index = self.strip_mut(id)?.inserts.len();
+        for slot in &slots[index..] {
+            if self.revive_insert(id, index, slot)? {
+                index += 1;
+            } else {
+                lost.push(slot.name.clone());
+            }
+        }
+
+        for slot in slots {
+            let inserts = &mut self.strip_mut(id)?.inserts;
+            let Some(index) = inserts.iter().position(|insert| insert.id == slot.id) else {
+                continue;
+            };
+            inserts[index].name.clone_from(&slot.name);
+
+            if inserts[index].bypassed != slot.bypassed {
+                self.set_bypass(id, index, slot.bypassed)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_bypass(&mut self, id: StripId, index: usize, bypassed: bool) -> Result<()> {
+        self.reserve(1)?;
+        self.strip_mut(id)?
+            .inserts
+            .get_mut(index)
+            .ok_or_else(|| anyhow!("No insert {index}"))?
+            .bypassed = bypassed;
+
+        self.send(MixerCommand::SetBypass(id, index, bypassed));
+        Ok(())
+    }
+
+    /// Settings of every strip, the master first
+    pub fn state(&self) -> Vec<StripState> {
+        self.strips.iter().map(StripState::from).collect()
+    }
+
+    /// Brings the strips back to a [`Mixer::state`]: strips missing from it are removed, removed ones are put back
+    /// in their old order with their source and inserts, and only settings that differ are sent to the audio thread.
+    /// Strips and inserts removed too long ago are added again without their processors, or fail to be put back.
+    pub fn restore(&mut self, states: &[StripState]) -> Result<()> {
+        let removed = self
+            .strips
+            .iter()
+            .filter(|strip| strip.kind != StripKind::Master && !states.iter().any(|state| state.id == strip.id))
+            .map(|strip| strip.id)
+            .collect::<Vec<_>>();
+
+        for id in removed {
+            self.remove_strip(id)?;
+        }
+
+        // Every strip has to exist before outputs and sends can point to it
+        for state in states {
+            if self.strip(state.id).is_some() {
+                continue;
+            }
+            if state.kind == StripKind::Master {
+                return Err(anyhow!("There can only be one master strip"));
+            }
+            if self.strips.len() >= MAX_STRIPS {
+                return Err(anyhow!("The mixer is limited to {MAX_STRIPS} strips"));
+            }
+            self.reserve(1)?;
+
+            self.next_id = self.next_id.max(state.id.0 + 1);
+
+            match self.detached.iter().position(|strip| strip.id == state.id) {
+                Some(index) => {
+                    let mut strip = self.detached.remove(index).expect("detached strip");
+                    strip.meter = Meter::default();
+                    self.strips.push(strip);
+                    self.send(MixerCommand::ReviveStrip(state.id));
+                }
+                None => {
+                    self.strips.push(Strip::new(state.id, &state.name, state.kind));
+                    self.send(MixerCommand::AddStrip(Box::new(StripNode::new(state.id, state.kind))));
+                }
+            }
+        }
+
+        // Only the UI shows the order, the node processes by kind
+        self.strips
+            .sort_by_key(|strip| states.iter().position(|state| state.id == strip.id));
+
+        let mut lost = Vec::new();
+        for state in states {
+            let current = StripState::from(self.strip(state.id).ok_or_else(|| anyhow!("No mixer strip {:?}", state.id))?);
+            if current == *state {
+                continue;
+            }
+
+            let id = state.id;
+            if current.name != state.name {
+                self.set_name(id, &state.name)?;
+            }
+            if current.gain != state.gain {
+                self.set_gain(id, state.gain)?;
+            }
+            if current.pan != state.pan {
+                self.set_pan(id, state.pan)?;
+            }
+            if current.mute != state.mute {
+                self.set_mute(id, state.mute)?;
+            }
+            if current.solo != state.solo {
+                self.set_solo(id, state.solo)?;
+            }
+            if current.arm != state.arm {
+                self.set_arm(id, state.arm)?;
+            }
+            if current.output != state.output && state.kind != StripKind::Master {
+                self.set_output(id, state.output)?;
+            }
+            if current.sends != state.sends {
+                self.reserve(1)?;
+                self.strip_mut(id)?.sends = state.sends.clone();
+                self.sync_sends(id)?;
+            }
+            if current.inserts != state.inserts {
+                self.restore_inserts(id, &state.inserts, &mut lost)?;
+            }
+        }
+
+        if !lost.is_empty() {
+            return Err(anyhow!("{} removed too long ago to be put back", lost.join(", ")));
+        }
+        Ok(())
+    }
+
+    /// Removes every strip but the master and resets the master, e.g. before another project is loaded
+    pub fn clear(&mut self) -> Result<()> {
+        let master = Strip::new(StripId::MASTER, "Master", StripKind::Master);
+        self.restore(&[StripState::from(&master)])?;
+
+        self.reserve(1)?;
+        self.detached.clear();
+        self.detached_inserts.clear();
+        self.send(MixerCommand::Forget);
+        Ok(())
+    }
+
+    pub fn reset_clip(&mut self, id: StripId) -> Result<()> {
+        self.strip_mut(id)?.meter.clipped = false;
+        Ok(())
+    }
+
+    /// Applies the meter readings of the audio thread and frees what it no longer uses, call once per frame
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        for strip in &mut self.strips {
+            strip.meter.decay(elapsed);
+        }
+
+        while let Some(reading) = self.meters.pop() {
+            if let Some(strip) = self.strips.iter_mut().find(|strip| strip.id == reading.strip) {
+                strip.meter.apply(&reading);
+            }
+        }
+
+        self.garbage.drain().for_each(drop);
+    }
+
+    /// Publishes the DSP load of every strip and insert as perf metrics, in percent of the buffer period at
+    /// `sample_rate`, see [`metrics::DSP_NODE`] and [`metrics::DSP_PLUGIN`]
+    pub fn publish_load(&mut self, sample_rate: u32) {
+        let load = |elapsed: Duration, frames: usize| {
+            let deadline = frames as f64 / sample_rate.max(1) as f64;
+            if deadline > 0.0 {
+                elapsed.as_secs_f64() / deadline * 100.0
+            } else {
+                0.0
+            }
+        };
+
+        while let Some(reading) = self.loads.pop() {
+            let Some(strip) = self.strip(reading.strip) else {
+                continue;
+            };
+
+            metrics::record(&node_metric(strip), load(reading.elapsed, reading.frames));
+
+            for (slot, &elapsed) in strip.inserts.iter().zip(&reading.inserts) {
+                metrics::record(&plugin_metric(strip, slot), load(elapsed, reading.frames));
+            }
+        }
+
+        // Strips and inserts that are gone, or were renamed, leave the profiler
+        let current = self
+            .strips
+            .iter()
+            .flat_map(|strip| {
+                std::iter::once(node_metric(strip)).chain(strip.inserts.iter().map(|slot| plugin_metric(strip, slot)))
+            })
+            .collect::<Vec<_>>();
+
+        for name in metrics::names(metrics::DSP_NODE)
+            .into_iter()
+            .chain(metrics::names(metrics::DSP_PLUGIN))
+        {
+            if !current.contains(&name) {
+                metrics::remove(&name);
+            }
+        }
+    }
+}
+
+fn node_metric(strip: &Strip) -> String {
+    format!("{}{}", metrics::DSP_NODE, strip.name)
+}
+
+fn plugin_metric(strip: &Strip, slot: &InsertSlot) -> String {
+    format!("{}{} / {}", metrics::DSP_PLUGIN, strip.name, slot.name)
+}
+
+struct InsertNode {
+    id: InsertId,
+    processor: Box<dyn Processor>,
+    bypassed: bool,
+}
+
+/// Audio thread side of a strip
+pub struct StripNode {
+    id: StripId,
+    kind: StripKind,
+    gain: f32,
+    pan: f32,
+    mute: bool,
+    solo: bool,
+    output: StripId,
+    sends: [Option<AuxSend>; MAX_SENDS],
+    source: Option<Box<dyn Processor>>,
+    inserts: Vec<InsertNode>,
+    /// Signal of the strip, buses and the master mix their inputs into it before they are processed
+    buffer: Vec<f32>,
+}
+
+impl StripNode {
+    fn new(id: StripId, kind: StripKind) -> Self {
+        Self {
+            id,
+            kind,
+            gain: 1.0,
+            pan: 0.0,
+            mute: false,
+            solo: false,
+            output: StripId::MASTER,
+            sends: [None; MAX_SENDS],
+            source: None,
+            inserts: Vec::with_capacity(MAX_INSERTS),
+            buffer: vec![0.0; BUFFER_CAPACITY],
+        }
+    }
+}
+
+/// Audio thread side of the mixer, neither allocates nor frees while processing unless a buffer grows
+pub struct MixerNode {
+    /// Boxed so strips move in and out without the audio thread allocating or freeing them
+    #[allow(clippy::vec_box)]
+    strips: Vec<Box<StripNode>>,
+    /// Removed strips and inserts, oldest first, see [`MixerCommand::RemoveStrip`]
+    #[allow(clippy::vec_box)]
+    detached: Vec<Box<StripNode>>,
+    detached_inserts: Vec<InsertNode>,
+    commands: Consumer<MixerCommand>,
+    meters: Producer<MeterReading>,
+    loads: Producer<LoadReading>,
+    garbage: Producer<Garbage>,
+}
+
+impl MixerNode {
+    fn strip_mut(&mut self, id: StripId) -> Option<&mut StripNode> {
+        self.strips.iter_mut().find(|strip| strip.id == id).map(|strip| &mut **strip)
+    }
+
+    fn discard(&mut self, garbage: Garbage) {
+        // Only dropped here when the UI stops collecting
+        let _ = self.garbage.push(garbage);
+    }
+
+    /// Keeps a removed strip, the UI detaches the same ones so both free the oldest when full
+    fn detach(&mut self, strip: Box<StripNode>) {
+        if self.detached.len() == DETACHED_STRIPS {
+            let oldest = self.detached.remove(0);
+            self.discard(Garbage::Strip(oldest));
+        }
+        self.detached.push(strip);
+    }
+
+    fn detach_insert(&mut self, insert: InsertNode) {
+        if self.detached_inserts.len() == DETACHED_INSERTS {
+            let oldest = self.detached_inserts.remove(0);
+            self.discard(Garbage::Processor(oldest.processor));
+        }
+        self.detached_inserts.push(insert);
+    }
+
+    fn apply(&mut self, command: MixerCommand) {
+        match command {
+            MixerCommand::AddStrip(strip) => {
+                if self.strips.len() < self.strips.capacity() {
+                    self.strips.push(strip);
+                } else {
+                    self.discard(Garbage::Strip(strip));
+                }
+            }
+            MixerCommand::RemoveStrip(id) => {
+                if let Some(index) = self.strips.iter().position(|strip| strip.id == id) {
+                    let strip = self.strips.remove(index);
+                    self.detach(strip);
+                }
+            }
+            MixerCommand::ReviveStrip(id) => {
+                if let Some(index) = self.detached.iter().position(|strip| strip.id == id) {
+                    let strip = self.detached.remove(index);
+                    self.apply(MixerCommand::AddStrip(strip));
+                }
+            }
+            MixerCommand::SetGain(id, db) => {
+                if let Some(strip) = self.strip_mut(id) {
+                    strip.gain = to_gain(db);
+                }
+            }
+            MixerCommand::SetPan(id, pan) => {
+                if let Some(strip) = self.strip_mut(id) {
+                    strip.pan = pan;
+                }
+            }
+            MixerCommand::SetMute(id, mute) => {
+                if let Some(strip) = self.strip_mut(id) {
+                    strip.mute = mute;
+                }
+            }
+            MixerCommand::SetSolo(id, solo) => {
+                if let Some(strip) = self.strip_mut(id) {
+                    strip.solo = solo;
+                }
+            }
+            MixerCommand::SetOutput(id, output) => {
+                if let Some(strip) = self.strip_mut(id) {
+                    strip.output = output;
+                }
+            }
+            MixerCommand::SetSends(id, sends) => {
+                if let Some(strip) = self.strip_mut(id) {
+                    strip.sends = sends;
+                }
+            }
+            MixerCommand::SetSource(id, source) => {
+                let previous = match self.strip_mut(id) {
+                    Some(strip) => std::mem::replace(&mut strip.source, source),
+                    None => source,
+                };
+
+                if let Some(previous) = previous {
+                    self.discard(Garbage::Processor(previous));
+                }
+            }
+            MixerCommand::AddInsert(id, index, insert) => {
+                let rejected = match self.strip_mut(id) {
+                    Some(strip) if strip.inserts.len() < strip.inserts.capacity() => {
+                        let index = index.min(strip.inserts.len());
+                        strip.inserts.insert(index, insert);
+                        None
+                    }
+                    _ => Some(insert),
+                };
+
+                if let Some(insert) = rejected {
+                    self.discard(Garbage::Processor(insert.processor));
+                }
+            }
+            MixerCommand::RemoveInsert(id, index) => {
+                let removed = self
+                    .strip_mut(id)
+                    .filter(|strip| index < strip.inserts.len())
+                    .map(|strip| strip.inserts.remove(index));
+
+                if let Some(insert) = removed {
+                    self.detach_insert(insert);
+                }
+            }
+            MixerCommand::ReviveInsert(id, index, insert) => {
+                if let Some(detached) = self.detached_inserts.iter().position(|detached| detached.id == insert) {
+                    let insert = self.detached_inserts.remove(detached);
+                    self.apply(MixerCommand::AddInsert(id, index, insert));
+                }
+            }
+            MixerCommand::SetBypass(id, index, bypassed) => {
+                if let Some(insert) = self.strip_mut(id).and_then(|strip| strip.inserts.get_mut(index)) {
+                    insert.bypassed = bypassed;
+                }
+            }
+            MixerCommand::Forget => {
+                while let Some(strip) = self.detached.pop() {
+                    self.discard(Garbage::Strip(strip));
+                }
+                while let Some(insert) = self.detached_inserts.pop() {
+                    self.discard(Garbage::Processor(insert.processor));
+                }
+            }
+        }
+    }
+
+    /// Adds `level` times `signal` to the buffer of a strip, falling back to the master for unknown strips
+    fn mix_into(&mut self, target: StripId, signal: &[f32], level: f32) {
+        let index = self
+            .strips
+            .iter()
+            .position(|strip| strip.id == target)
+            .or_else(|| self.strips.iter().position(|strip| strip.id == StripId::MASTER));
+
+        if let Some(index) = index {
+            for (sample, &value) in self.strips[index].buffer.iter_mut().zip(signal) {
+                *sample += value * level;
+            }
+        }
+    }
+
+    fn send_all(&mut self, sends: &[Option<AuxSend>; MAX_SENDS], signal: &[f32], pre_fader: bool) {
+        for send in sends.iter().flatten().filter(|send| send.pre_fader == pre_fader) {
+            self.mix_into(send.target, signal, to_gain(send.level));
+        }
+    }
+
+    /// Mixes all strips into `output`, an interleaved buffer with `channels` channels
+    pub fn process(&mut self, output: &mut [f32], channels: usize) {
+        while let Some(command) = self.commands.pop() {
+            self.apply(command);
+        }
+
+        let len = output.len();
+        let channels = channels.max(1);
+
+        for strip in &mut self.strips {
+            if strip.buffer.len() < len {
+                strip.buffer.resize(len, 0.0);
+            }
+            strip.buffer[..len].fill(0.0);
+        }
+
+        let soloed = self
+            .strips
+            .iter()
+            .any(|strip| strip.kind == StripKind::Track && strip.solo);
+
+        // Everything routed to a strip is mixed before the strip itself is processed
+        for kind in [StripKind::Track, StripKind::Bus, StripKind::Master] {
+            for index in 0..self.strips.len() {
+                if self.strips[index].kind != kind {
+                    continue;
+                }
+
+                // Taken out so the signal can be mixed into other strips
+                let mut buffer = std::mem::take(&mut self.strips[index].buffer);
+                let signal = &mut buffer[..len];
+
+                let strip = &mut self.strips[index];
+                let start = Instant::now();
+                let mut inserts = [Duration::ZERO; MAX_INSERTS];
+
+                if let Some(source) = &mut strip.source {
+                    source.process(signal, channels);
+                }
+                for (insert, elapsed) in strip.inserts.iter_mut().zip(&mut inserts) {
+                    if !insert.bypassed {
+                        let start = Instant::now();
+                        insert.processor.process(signal, channels);
+                        *elapsed = start.elapsed();
+                    }
+                }
+
+                // Dropped when the UI is not publishing the load
+                let _ = self.loads.push(LoadReading {
+                    strip: strip.id,
+                    frames: len / channels,
+                    elapsed: start.elapsed(),
+                    inserts,
+                });
+
+                let silent = strip.mute || (soloed && strip.kind == StripKind::Track && !strip.solo);
+                let gain = if silent { 0.0 } else { strip.gain };
+                let (left, right) = balance(strip.pan);
+                let (id, output_id, sends) = (strip.id, strip.output, strip.sends);
+
+                self.send_all(&sends, signal, true);
+
+                for frame in signal.chunks_exact_mut(channels) {
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        *sample *= match (channels, channel) {
+                            (1, _) => gain,
+                            (_, 0) => gain * left,
+                            (_, 1) => gain * right,
+                            _ => gain,
+                        };
+                    }
+                }
+
+                self.send_all(&sends, signal, false);
+
+                let _ = self.meters.push(measure(id, signal, channels));
+
+                if kind == StripKind::Master {
+                    output.copy_from_slice(signal);
+                } else {
+                    self.mix_into(output_id, signal, 1.0);
+                }
+
+                self.strips[index].buffer = buffer;
+            }
+        }
+    }
+}
+
+fn measure(strip: StripId, signal: &[f32], channels: usize) -> MeterReading {
+    let mut peak = [0.0f32; 2];
+    let mut sum = [0.0f32; 2];
+    let frames = (signal.len() / channels).max(1);
+
+    for frame in signal.chunks_exact(channels) {
+        for channel in 0..2 {
+            // Mono signals show the same level on both sides
+            let sample = frame[channel.min(channels - 1)];
+            peak[channel] = peak[channel].max(sample.abs());
+            sum[channel] += sample * sample;
+        }
+    }
+
+    MeterReading {
+        strip,
+        peak,
+        rms: sum.map(|sum| (sum / frames as f32).sqrt()),
+    }
+}
+
+/// Gains of the left and right channel, panning turns one side down and leaves the other at unity
+fn balance(pan: f32) -> (f32, f32) {
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+pub fn to_gain(db: f32) -> f32 {
+    if db <= MIN_DB {
+        0.0
+    } else {
+        10f32.powf(db / 20.0)
+    }
+}
+
+pub fn to_db(gain: f32) -> f32 {
+    if gain <= 0.0 {
+        MIN_DB
+    } else {
+        (20.0 * gain.log10()).max(MIN_DB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Insert replacing the signal with a constant level
+    fn level(level: f32) -> Box<dyn Processor> {
+        Box::new(move |buffer: &mut [f32], _channels: usize| buffer.fill(level))
+    }
+
+    fn slot(name: &str) -> InsertSlot {
+        InsertSlot {
+            id: InsertId::default(),
+            name: name.to_string(),
+            kind: InsertKind::Wasm(name.to_string()),
+            bypassed: false,
+        }
+    }
+
+    /// First sample of the next block of the master
+    fn output(node: &mut MixerNode) -> f32 {
+        let mut buffer = [0.0; 64];
+        node.process(&mut buffer, 2);
+        buffer[0]
+    }
+
+    fn ids(mixer: &Mixer) -> Vec<StripId> {
+        mixer.strips().iter().map(|strip| strip.id()).collect()
+    }
+
+    #[test]
+    fn restore_removed_strip() {
+        let (mut mixer, mut node) = mixer();
+        let first = mixer.add_strip("First", StripKind::Track).unwrap();
+        let track = mixer.add_strip("Track", StripKind::Track).unwrap();
+        let last = mixer.add_strip("Last", StripKind::Bus).unwrap();
+        mixer.add_insert(track, slot("Level"), level(0.5)).unwrap();
+        let before = mixer.state();
+        assert_ne!(output(&mut node), 0.0);
+
+        mixer.remove_strip(track).unwrap();
+        assert_eq!(output(&mut node), 0.0);
+
+        // Put back in its old place with the processor of its insert
+        mixer.restore(&before).unwrap();
+        assert_eq!(mixer.state(), before);
+        assert_eq!(ids(&mixer), [StripId::MASTER, first, track, last]);
+        assert_ne!(output(&mut node), 0.0);
+    }
+
+    #[test]
+    fn restore_removed_insert() {
+        let (mut mixer, mut node) = mixer();
+        let track = mixer.add_strip("Track", StripKind::Track).unwrap();
+        mixer.add_insert(track, slot("Loud"), level(0.5)).unwrap();
+        mixer.add_insert(track, slot("Quiet"), level(0.25)).unwrap();
+        mixer.set_bypass(track, 1, true).unwrap();
+        let before = mixer.state();
+        let loud = output(&mut node);
+
+        mixer.remove_insert(track, 0).unwrap();
+        assert_eq!(output(&mut node), 0.0);
+
+        mixer.restore(&before).unwrap();
+        assert_eq!(mixer.state(), before);
+        assert_eq!(output(&mut node), loud);
+
+        // The last insert wins once it is no longer bypassed
+        let mut after = before.clone();
+        after[1].inserts[1].bypassed = false;
+        mixer.restore(&after).unwrap();
+        assert!(output(&mut node) < loud);
+    }
+
+    #[test]
+    fn clear_forgets_removed_strips() {
+        let (mut mixer, mut node) = mixer();
+        let track = mixer.add_strip("Track", StripKind::Track).unwrap();
+        mixer.add_insert(track, slot("Level"), level(0.5)).unwrap();
+        let before = mixer.state();
+
+        mixer.clear().unwrap();
+        output(&mut node);
+
+        // The strip comes back without the insert whose processor is gone
+        assert!(mixer.restore(&before).is_err());
+        assert!(mixer.strip(track).is_some_and(|strip| strip.inserts().is_empty()));
+        assert_eq!(output(&mut node), 0.0);
+    }
+}
@@ -0,0 +1,141 @@
+use voxea_alloc::ring::{self, Consumer, Producer};
+use cpal::StreamInstant;
+use log::warn;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use voxea_alloc::perf::metrics;
+
+/// Number of callbacks that can be buffered before the UI drains them
+const STATS_CAPACITY: usize = 1024;
+
+/// A callback is counted as an xrun when the time since the previous one exceeds its period by this factor
+const GAP_TOLERANCE: f64 = 1.5;
+
+/// Measurements of a single audio callback
+#[derive(Debug, Copy, Clone)]
+pub struct CallbackStats {
+    pub frames: u32,
+    /// Time spent processing the buffer
+    pub elapsed: Duration,
+    /// Time the buffer covers, processing must finish before it to avoid an underrun
+    pub deadline: Duration,
+    /// Total number of xruns since the stream was started
+    pub xruns: u64,
+}
+
+impl CallbackStats {
+    /// Processing time in percent of the deadline
+    pub fn load(&self) -> f64 {
+        if self.deadline.is_zero() {
+            0.0
+        } else {
+            self.elapsed.as_secs_f64() / self.deadline.as_secs_f64() * 100.0
+        }
+    }
+}
+
+/// Creates the audio thread and UI sides of a callback monitor
+pub fn monitor(sample_rate: u32) -> (CallbackMonitor, StatsReceiver) {
+    let (sender, receiver) = ring::ring(STATS_CAPACITY);
+    let xruns = Arc::new(AtomicU64::new(0));
+
+    (
+        CallbackMonitor {
+            sender,
+            sample_rate,
+            xruns: xruns.clone(),
+            last_callback: None,
+            last_period: Duration::ZERO,
+        },
+        StatsReceiver {
+            receiver,
+            xruns,
+            published_xruns: 0,
+        },
+    )
+}
+
+/// Audio thread side of the monitor, measures every callback without allocating or locking
+pub struct CallbackMonitor {
+    sender: Producer<CallbackStats>,
+    sample_rate: u32,
+    xruns: Arc<AtomicU64>,
+    last_callback: Option<StreamInstant>,
+    last_period: Duration,
+}
+
+impl CallbackMonitor {
+    /// Handle for the stream's error callback to report xruns from cpal
+    pub fn xrun_counter(&self) -> XrunCounter {
+        XrunCounter(self.xruns.clone())
+    }
+
+    /// Runs `process` for a buffer of `frames` frames and publishes how long it took compared to the deadline.
+    /// `callback` is the timestamp cpal reports for the callback, used to detect gaps between callbacks.
+    pub fn measure<R>(&mut self, callback: StreamInstant, frames: usize, process: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let deadline = Duration::from_secs_f64(frames as f64 / self.sample_rate.max(1) as f64);
+
+        // A late callback means the device ran out of samples in between
+        if let Some(gap) = self.last_callback.and_then(|last| callback.duration_since(&last)) {
+            if !self.last_period.is_zero() && gap.as_secs_f64() > self.last_period.as_secs_f64() * GAP_TOLERANCE {
+                self.xruns.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        self.last_callback = Some(callback);
+        self.last_period = deadline;
+
+        let result = process();
+        let elapsed = start.elapsed();
+
+        // Dropped when the UI is not draining, the totals are kept in the xrun counter
+        let _ = self.sender.push(CallbackStats {
+            frames: frames as u32,
+            elapsed,
+            deadline,
+            xruns: self.xruns.load(Ordering::Relaxed),
+        });
+
+        result
+    }
+}
+
+/// Counts stream errors reported by cpal as xruns, since cpal does not tell them apart
+#[derive(Clone)]
+pub struct XrunCounter(Arc<AtomicU64>);
+
+impl XrunCounter {
+    pub fn report(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// UI side of the monitor
+pub struct StatsReceiver {
+    receiver: Consumer<CallbackStats>,
+    xruns: Arc<AtomicU64>,
+    published_xruns: u64,
+}
+
+impl StatsReceiver {
+    pub fn xruns(&self) -> u64 {
+        self.xruns.load(Ordering::Relaxed)
+    }
+
+    /// Drains the measured callbacks into the perf metrics, see [`metrics::AUDIO_CALLBACK`]
+    pub fn publish(&mut self) {
+        for stats in self.receiver.drain() {
+            metrics::record(metrics::AUDIO_CALLBACK, stats.elapsed.as_secs_f64() * 1000.0);
+            metrics::record(metrics::AUDIO_DEADLINE, stats.deadline.as_secs_f64() * 1000.0);
+            metrics::record(metrics::AUDIO_XRUNS, stats.xruns as f64);
+        }
+
+        let xruns = self.xruns();
+        if xruns > self.published_xruns {
+            warn!("{} audio xrun(s), {} in total", xruns - self.published_xruns, xruns);
+            self.published_xruns = xruns;
+        }
+    }
+}
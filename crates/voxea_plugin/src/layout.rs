@@ -0,0 +1,56 @@
+use crate::ui::{Image, Layout, Node, Widget};
+
+/// Builds a [`Layout`] by nesting widgets, so node parents do not have to be tracked by hand
+pub struct LayoutBuilder {
+    layout: Layout,
+    containers: Vec<u32>,
+}
+
+impl LayoutBuilder {
+    pub fn new(title: &str, width: u32, height: u32) -> Self {
+        Self {
+            layout: Layout {
+                title: title.to_string(),
+                width,
+                height,
+                nodes: Vec::new(),
+                images: Vec::new(),
+            },
+            containers: Vec::new(),
+        }
+    }
+
+    /// Adds a widget to the innermost open container
+    pub fn add(&mut self, widget: Widget) -> &mut Self {
+        self.layout.nodes.push(Node {
+            widget,
+            parent: self.containers.last().copied(),
+        });
+        self
+    }
+
+    /// Adds a row, column or group, widgets are added to it until [`LayoutBuilder::end`]
+    pub fn begin(&mut self, container: Widget) -> &mut Self {
+        self.add(container);
+        self.containers.push((self.layout.nodes.len() - 1) as u32);
+        self
+    }
+
+    pub fn end(&mut self) -> &mut Self {
+        self.containers.pop();
+        self
+    }
+
+    /// Bundles an encoded image, shown by `Widget::Image(name)`
+    pub fn image(&mut self, name: &str, data: Vec<u8>) -> &mut Self {
+        self.layout.images.push(Image {
+            name: name.to_string(),
+            data,
+        });
+        self
+    }
+
+    pub fn build(&self) -> Layout {
+        self.layout.clone()
+    }
+}
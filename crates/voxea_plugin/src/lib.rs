@@ -5,7 +5,12 @@ pub mod bindings {
     generate!({path: "./wit/world.wit", pub_export_macro: true, export_macro_name: "export"  });
 }
 
+pub mod layout;
+
 // Reexport the Guest trait as a different name. entirely optional
 pub use crate::bindings::{
-    export, exports::sdk::component::plugin_api::Guest as VoxeaPlugin, sdk::component::*,
+    export,
+    exports::sdk::component::plugin_api::{Action, Guest as VoxeaPlugin},
+    sdk::component::*,
 };
+pub use crate::layout::LayoutBuilder;
@@ -0,0 +1,462 @@
+//! A minimal VST3 plugin which halves its input and aborts the process after a number of processed blocks,
+//! used to test that a crashing plugin hosted out of process only bypasses itself.
+//! The number of blocks is read from `VOXEA_CRASH_AFTER` and defaults to 3.
+
+#![allow(non_snake_case)]
+
+use std::ffi::{c_char, c_void};
+use std::sync::atomic::{AtomicU32, Ordering};
+use voxea_vst::{FUID, TUID};
+
+const K_RESULT_OK: i32 = 0;
+const K_RESULT_FALSE: i32 = 1;
+#[cfg(windows)]
+const K_NO_INTERFACE: i32 = 0x80004002_u32 as i32;
+#[cfg(not(windows))]
+const K_NO_INTERFACE: i32 = -1;
+#[cfg(windows)]
+const K_NOT_IMPLEMENTED: i32 = 0x80004001_u32 as i32;
+#[cfg(not(windows))]
+const K_NOT_IMPLEMENTED: i32 = 3;
+
+const K_AUDIO: i32 = 0;
+const CHANNELS: i32 = 2;
+
+const fn uid(l1: u32, l2: u32, l3: u32, l4: u32) -> TUID {
+    FUID::new(l1, l2, l3, l4).to_tuid()
+}
+
+const FUNKNOWN_IID: TUID = uid(0x00000000, 0x00000000, 0xC0000000, 0x00000046);
+const FACTORY_IID: TUID = uid(0x7A4D811C, 0x52114A1F, 0xAED9D2EE, 0x0B43BF9F);
+const PLUGIN_BASE_IID: TUID = uid(0x22888DDB, 0x156E45AE, 0x8358B348, 0x08190625);
+const COMPONENT_IID: TUID = uid(0xE831FF31, 0xF2D54301, 0x928EBBEE, 0x25697802);
+const AUDIO_PROCESSOR_IID: TUID = uid(0x42043F99, 0xB7DA453C, 0xA569E79D, 0x9AAEC33D);
+
+/// Class id of the crash plugin, `5658454143524153485052434F4E4F4E` in hex
+pub const CLASS_ID: TUID = uid(0x56584541, 0x43524153, 0x48505243, 0x4F4E4F4E);
+
+#[repr(C)]
+struct PFactoryInfo {
+    vendor: [c_char; 64],
+    url: [c_char; 256],
+    email: [c_char; 128],
+    flags: i32,
+}
+
+#[repr(C)]
+struct PClassInfo {
+    cid: TUID,
+    cardinality: i32,
+    category: [c_char; 32],
+    name: [c_char; 64],
+}
+
+#[repr(C)]
+struct BusInfo {
+    media_type: i32,
+    direction: i32,
+    channel_count: i32,
+    name: [u16; 128],
+    bus_type: i32,
+    flags: u32,
+}
+
+#[repr(C)]
+struct AudioBusBuffers {
+    num_channels: i32,
+    silence_flags: u64,
+    channel_buffers_32: *mut *mut f32,
+}
+
+#[repr(C)]
+struct ProcessData {
+    process_mode: i32,
+    symbolic_sample_size: i32,
+    num_samples: i32,
+    num_inputs: i32,
+    num_outputs: i32,
+    inputs: *mut AudioBusBuffers,
+    outputs: *mut AudioBusBuffers,
+    input_parameter_changes: *mut c_void,
+    output_parameter_changes: *mut c_void,
+    input_events: *mut c_void,
+    output_events: *mut c_void,
+    process_context: *mut c_void,
+}
+
+fn copy_str<const N: usize>(target: &mut [c_char; N], value: &str) {
+    for (t, b) in target.iter_mut().zip(value.bytes().take(N - 1)) {
+        *t = b as c_char;
+    }
+}
+
+#[repr(C)]
+struct FactoryVTable {
+    queryInterface: unsafe extern "system" fn(*mut Factory, *const TUID, *mut *mut c_void) -> i32,
+    addRef: unsafe extern "system" fn(*mut Factory) -> u32,
+    release: unsafe extern "system" fn(*mut Factory) -> u32,
+    getFactoryInfo: unsafe extern "system" fn(*mut Factory, *mut PFactoryInfo) -> i32,
+    countClasses: unsafe extern "system" fn(*mut Factory) -> i32,
+    getClassInfo: unsafe extern "system" fn(*mut Factory, i32, *mut PClassInfo) -> i32,
+    createInstance: unsafe extern "system" fn(*mut Factory, *const c_char, *const c_char, *mut *mut c_void) -> i32,
+}
+
+#[repr(C)]
+struct Factory {
+    vtable: *const FactoryVTable,
+}
+
+unsafe impl Sync for Factory {}
+
+static FACTORY: Factory = Factory {
+    vtable: &FactoryVTable {
+        queryInterface: Factory::query_interface,
+        addRef: Factory::add_ref,
+        release: Factory::release,
+        getFactoryInfo: Factory::get_factory_info,
+        countClasses: Factory::count_classes,
+        getClassInfo: Factory::get_class_info,
+        createInstance: Factory::create_instance,
+    },
+};
+
+impl Factory {
+    unsafe extern "system" fn query_interface(this: *mut Factory, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUNKNOWN_IID || *iid == FACTORY_IID {
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut Factory) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut Factory) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn get_factory_info(_this: *mut Factory, info: *mut PFactoryInfo) -> i32 {
+        let info = &mut *info;
+        copy_str(&mut info.vendor, "Voxea");
+        copy_str(&mut info.url, "");
+        copy_str(&mut info.email, "");
+        info.flags = 0;
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn count_classes(_this: *mut Factory) -> i32 {
+        1
+    }
+
+    unsafe extern "system" fn get_class_info(_this: *mut Factory, index: i32, info: *mut PClassInfo) -> i32 {
+        if index != 0 {
+            return K_RESULT_FALSE;
+        }
+
+        let info = &mut *info;
+        info.cid = CLASS_ID;
+        info.cardinality = 0x7FFFFFFF;
+        copy_str(&mut info.category, "Audio Module Class");
+        copy_str(&mut info.name, "Crash Plugin");
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn create_instance(_this: *mut Factory, cid: *const c_char, iid: *const c_char, obj: *mut *mut c_void) -> i32 {
+        *obj = std::ptr::null_mut();
+
+        if *(cid as *const TUID) != CLASS_ID {
+            return K_NO_INTERFACE;
+        }
+
+        let crash_after = std::env::var("VOXEA_CRASH_AFTER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+
+        let component = Box::into_raw(Box::new(Component {
+            component_vtable: &Component::COMPONENT_VTABLE,
+            processor_vtable: &Component::PROCESSOR_VTABLE,
+            ref_count: AtomicU32::new(1),
+            processed: 0,
+            crash_after,
+        }));
+
+        let result = Component::query_interface(component, iid as *const TUID, obj);
+        Component::release(component);
+
+        result
+    }
+}
+
+#[repr(C)]
+struct ComponentVTable {
+    queryInterface: unsafe extern "system" fn(*mut Component, *const TUID, *mut *mut c_void) -> i32,
+    addRef: unsafe extern "system" fn(*mut Component) -> u32,
+    release: unsafe extern "system" fn(*mut Component) -> u32,
+    initialize: unsafe extern "system" fn(*mut Component, *mut c_void) -> i32,
+    terminate: unsafe extern "system" fn(*mut Component) -> i32,
+    getControllerClassId: unsafe extern "system" fn(*mut Component, *mut TUID) -> i32,
+    setIoMode: unsafe extern "system" fn(*mut Component, i32) -> i32,
+    getBusCount: unsafe extern "system" fn(*mut Component, i32, i32) -> i32,
+    getBusInfo: unsafe extern "system" fn(*mut Component, i32, i32, i32, *mut BusInfo) -> i32,
+    getRoutingInfo: unsafe extern "system" fn(*mut Component, *mut c_void, *mut c_void) -> i32,
+    activateBus: unsafe extern "system" fn(*mut Component, i32, i32, i32, u8) -> i32,
+    setActive: unsafe extern "system" fn(*mut Component, u8) -> i32,
+    setState: unsafe extern "system" fn(*mut Component, *mut c_void) -> i32,
+    getState: unsafe extern "system" fn(*mut Component, *mut c_void) -> i32,
+}
+
+#[repr(C)]
+struct ProcessorVTable {
+    queryInterface: unsafe extern "system" fn(*mut *const ProcessorVTable, *const TUID, *mut *mut c_void) -> i32,
+    addRef: unsafe extern "system" fn(*mut *const ProcessorVTable) -> u32,
+    release: unsafe extern "system" fn(*mut *const ProcessorVTable) -> u32,
+    setBusArrangements: unsafe extern "system" fn(*mut *const ProcessorVTable, *mut u64, i32, *mut u64, i32) -> i32,
+    getBusArrangement: unsafe extern "system" fn(*mut *const ProcessorVTable, i32, i32, *mut u64) -> i32,
+    canProcessSampleSize: unsafe extern "system" fn(*mut *const ProcessorVTable, i32) -> i32,
+    getLatencySamples: unsafe extern "system" fn(*mut *const ProcessorVTable) -> u32,
+    setupProcessing: unsafe extern "system" fn(*mut *const ProcessorVTable, *mut c_void) -> i32,
+    setProcessing: unsafe extern "system" fn(*mut *const ProcessorVTable, u8) -> i32,
+    process: unsafe extern "system" fn(*mut *const ProcessorVTable, *mut ProcessData) -> i32,
+    getTailSamples: unsafe extern "system" fn(*mut *const ProcessorVTable) -> u32,
+}
+
+/// The component and audio processor, implemented by one object with a vtable per interface
+#[repr(C)]
+struct Component {
+    component_vtable: *const ComponentVTable,
+    processor_vtable: *const ProcessorVTable,
+    ref_count: AtomicU32,
+    processed: u32,
+    crash_after: u32,
+}
+
+impl Component {
+    const COMPONENT_VTABLE: ComponentVTable = ComponentVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        initialize: Self::initialize,
+        terminate: Self::terminate,
+        getControllerClassId: Self::get_controller_class_id,
+        setIoMode: Self::set_io_mode,
+        getBusCount: Self::get_bus_count,
+        getBusInfo: Self::get_bus_info,
+        getRoutingInfo: Self::get_routing_info,
+        activateBus: Self::activate_bus,
+        setActive: Self::set_active,
+        setState: Self::set_state,
+        getState: Self::get_state,
+    };
+
+    const PROCESSOR_VTABLE: ProcessorVTable = ProcessorVTable {
+        queryInterface: Self::processor_query_interface,
+        addRef: Self::processor_add_ref,
+        release: Self::processor_release,
+        setBusArrangements: Self::set_bus_arrangements,
+        getBusArrangement: Self::get_bus_arrangement,
+        canProcessSampleSize: Self::can_process_sample_size,
+        getLatencySamples: Self::get_zero_samples,
+        setupProcessing: Self::setup_processing,
+        setProcessing: Self::set_processing,
+        process: Self::process,
+        getTailSamples: Self::get_zero_samples,
+    };
+
+    unsafe fn from_processor(this: *mut *const ProcessorVTable) -> *mut Component {
+        (this as *mut u8).sub(std::mem::offset_of!(Component, processor_vtable)) as *mut Component
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut Component, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        let iid = *iid;
+
+        if iid == FUNKNOWN_IID || iid == PLUGIN_BASE_IID || iid == COMPONENT_IID {
+            *obj = this as *mut c_void;
+        } else if iid == AUDIO_PROCESSOR_IID {
+            *obj = &mut (*this).processor_vtable as *mut *const ProcessorVTable as *mut c_void;
+        } else {
+            *obj = std::ptr::null_mut();
+            return K_NO_INTERFACE;
+        }
+
+        Self::add_ref(this);
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut Component) -> u32 {
+        (*this).ref_count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    unsafe extern "system" fn release(this: *mut Component) -> u32 {
+        let count = (*this).ref_count.fetch_sub(1, Ordering::AcqRel) - 1;
+        if count == 0 {
+            drop(Box::from_raw(this));
+        }
+        count
+    }
+
+    unsafe extern "system" fn initialize(_this: *mut Component, _context: *mut c_void) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn terminate(_this: *mut Component) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn get_controller_class_id(_this: *mut Component, _class_id: *mut TUID) -> i32 {
+        K_NOT_IMPLEMENTED
+    }
+
+    unsafe extern "system" fn set_io_mode(_this: *mut Component, _mode: i32) -> i32 {
+        K_NOT_IMPLEMENTED
+    }
+
+    unsafe extern "system" fn get_bus_count(_this: *mut Component, media_type: i32, _direction: i32) -> i32 {
+        (media_type == K_AUDIO) as i32
+    }
+
+    unsafe extern "system" fn get_bus_info(_this: *mut Component, media_type: i32, direction: i32, index: i32, info: *mut BusInfo) -> i32 {
+        if media_type != K_AUDIO || index != 0 {
+            return K_RESULT_FALSE;
+        }
+
+        let info = &mut *info;
+        info.media_type = media_type;
+        info.direction = direction;
+        info.channel_count = CHANNELS;
+        info.bus_type = 0;
+        info.flags = 1;
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn get_routing_info(_this: *mut Component, _in_info: *mut c_void, _out_info: *mut c_void) -> i32 {
+        K_NOT_IMPLEMENTED
+    }
+
+    unsafe extern "system" fn activate_bus(_this: *mut Component, _media_type: i32, _direction: i32, _index: i32, _state: u8) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn set_active(_this: *mut Component, _state: u8) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn set_state(_this: *mut Component, _state: *mut c_void) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn get_state(_this: *mut Component, _state: *mut c_void) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn processor_query_interface(this: *mut *const ProcessorVTable, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        Self::query_interface(Self::from_processor(this), iid, obj)
+    }
+
+    unsafe extern "system" fn processor_add_ref(this: *mut *const ProcessorVTable) -> u32 {
+        Self::add_ref(Self::from_processor(this))
+    }
+
+    unsafe extern "system" fn processor_release(this: *mut *const ProcessorVTable) -> u32 {
+        Self::release(Self::from_processor(this))
+    }
+
+    unsafe extern "system" fn set_bus_arrangements(_this: *mut *const ProcessorVTable, _inputs: *mut u64, _num_ins: i32, _outputs: *mut u64, _num_outs: i32) -> i32 {
+        K_RESULT_FALSE
+    }
+
+    unsafe extern "system" fn get_bus_arrangement(_this: *mut *const ProcessorVTable, _direction: i32, index: i32, arrangement: *mut u64) -> i32 {
+        if index != 0 {
+            return K_RESULT_FALSE;
+        }
+
+        // Stereo, left and right speaker
+        *arrangement = 0b11;
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn can_process_sample_size(_this: *mut *const ProcessorVTable, symbolic_sample_size: i32) -> i32 {
+        if symbolic_sample_size == 0 {
+            K_RESULT_OK
+        } else {
+            K_RESULT_FALSE
+        }
+    }
+
+    unsafe extern "system" fn get_zero_samples(_this: *mut *const ProcessorVTable) -> u32 {
+        0
+    }
+
+    unsafe extern "system" fn setup_processing(_this: *mut *const ProcessorVTable, _setup: *mut c_void) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn set_processing(_this: *mut *const ProcessorVTable, _state: u8) -> i32 {
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn process(this: *mut *const ProcessorVTable, data: *mut ProcessData) -> i32 {
+        let component = &mut *Self::from_processor(this);
+
+        component.processed += 1;
+        if component.processed > component.crash_after {
+            std::process::abort();
+        }
+
+        let data = &mut *data;
+        if data.num_inputs < 1 || data.num_outputs < 1 {
+            return K_RESULT_OK;
+        }
+
+        let input = &*data.inputs;
+        let output = &*data.outputs;
+        let samples = data.num_samples as usize;
+
+        for channel in 0..input.num_channels.min(output.num_channels) as usize {
+            let input = std::slice::from_raw_parts(*input.channel_buffers_32.add(channel), samples);
+            let output = std::slice::from_raw_parts_mut(*output.channel_buffers_32.add(channel), samples);
+
+            for (o, i) in output.iter_mut().zip(input) {
+                *o = i * 0.5;
+            }
+        }
+
+        K_RESULT_OK
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn GetPluginFactory() -> *mut c_void {
+    &FACTORY as *const Factory as *mut c_void
+}
+
+#[cfg(not(windows))]
+#[no_mangle]
+pub extern "system" fn ModuleEntry(_handle: *mut c_void) -> bool {
+    true
+}
+
+#[cfg(not(windows))]
+#[no_mangle]
+pub extern "system" fn ModuleExit() -> bool {
+    true
+}
+
+#[cfg(windows)]
+#[no_mangle]
+pub extern "system" fn InitDll() -> bool {
+    true
+}
+
+#[cfg(windows)]
+#[no_mangle]
+pub extern "system" fn ExitDll() -> bool {
+    true
+}
@@ -1,5 +1,9 @@
 use voxea_vst::load_vst;
 
 fn main() {
-    load_vst();
-}
\ No newline at end of file
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or("../../../vst3/Archetype Nolly.vst3".to_string());
+
+    load_vst(path).unwrap();
+}
@@ -0,0 +1,601 @@
+//! Hosting of a plugin in a separate `voxea_vst` helper process.
+//!
+//! Audio, parameter changes and the handshake of every block are exchanged through shared memory. A unix socket carries
+//! the setup and wakes the helper for a block, the audio thread never waits on it. If the helper misses the deadline
+//! of a block the block is silent, if it crashes or stops responding a watchdog thread marks the plugin as failed and
+//! it is bypassed from then on.
+
+pub mod ring;
+pub mod shm;
+
+use crate::bridge::ring::Ring;
+use crate::bridge::shm::SharedMemory;
+use crate::events::{Event, DATA_EVENT, MAX_EVENTS};
+use crate::module::Module;
+use crate::node::Deadline;
+use crate::parameters::ParameterChange;
+use crate::plugin::Plugin;
+use anyhow::{anyhow, Context, Result};
+use log::{error, info, warn};
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Maximum number of channels passed in each direction
+pub const MAX_CHANNELS: usize = 8;
+/// Maximum number of parameter changes queued for one block
+pub const PARAMETER_CAPACITY: usize = 1024;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const SETUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a block may stay unfinished before the helper counts as hung
+const PROCESS_TIMEOUT: Duration = Duration::from_secs(1);
+/// How often the watchdog checks on the helper
+const WATCH_INTERVAL: Duration = Duration::from_millis(20);
+
+static NEXT_SOCKET: AtomicU32 = AtomicU32::new(0);
+
+const OP_SETUP: u32 = 0;
+const OP_PROCESS: u32 = 1;
+const OP_SHUTDOWN: u32 = 2;
+const OP_READY: u32 = 3;
+const OP_ERROR: u32 = 5;
+
+/// Handshake of a block at the start of the shared memory.
+/// The host only requests a block once the previous one is processed, so the two sides never touch the buffers at once.
+#[repr(C, align(64))]
+struct Control {
+    /// Number of the last block the host requested
+    requested: AtomicU32,
+    /// Number of the last block the helper finished
+    processed: AtomicU32,
+    num_samples: AtomicU32,
+    /// Set by the helper if the plugin failed to process the last block
+    error: AtomicBool,
+}
+
+impl Control {
+    /// # Safety
+    /// `memory` must start with a [`Control`] and outlive the returned reference
+    unsafe fn get<'a>(memory: &SharedMemory) -> &'a Self {
+        &*(memory.as_ptr() as *const Self)
+    }
+
+    fn is_pending(&self) -> bool {
+        self.requested.load(Ordering::Acquire) != self.processed.load(Ordering::Acquire)
+    }
+}
+
+/// State shared between the audio thread and the watchdog of a bridged plugin
+#[derive(Default)]
+struct Status {
+    failed: AtomicBool,
+    /// Set when the plugin is dropped, the watchdog then shuts the helper down
+    stopped: AtomicBool,
+    /// Blocks that were silent because the helper missed their deadline
+    missed: AtomicU64,
+    /// When the pending block was requested, in microseconds since the plugin was spawned
+    requested_at: AtomicU64,
+}
+
+/// A setup message, always sent as 16 bytes. Blocks are requested with the single byte [`OP_PROCESS`] instead.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    op: u32,
+    a: u32,
+    b: f64,
+}
+
+impl Frame {
+    fn new(op: u32, a: u32, b: f64) -> Self {
+        Self { op, a, b }
+    }
+
+    fn send(&self, stream: &mut UnixStream) -> std::io::Result<()> {
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&self.op.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.a.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.b.to_le_bytes());
+
+        stream.write_all(&bytes)
+    }
+
+    fn receive(stream: &mut UnixStream) -> std::io::Result<Self> {
+        let mut bytes = [0u8; 16];
+        stream.read_exact(&mut bytes)?;
+
+        Ok(Self {
+            op: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            a: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            b: f64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+        })
+    }
+}
+
+/// Location of the parameter ring and the audio buffers within the shared memory
+struct Layout {
+    max_block: usize,
+}
+
+impl Layout {
+    fn control(&self) -> usize {
+        0
+    }
+
+    fn parameters(&self) -> usize {
+        self.control() + std::mem::size_of::<Control>()
+    }
+
+    fn events(&self) -> usize {
+        self.parameters() + Ring::<ParameterChange>::size(PARAMETER_CAPACITY).next_multiple_of(64)
+    }
+
+    fn inputs(&self) -> usize {
+        self.events() + Ring::<Event>::size(MAX_EVENTS).next_multiple_of(64)
+    }
+
+    fn outputs(&self) -> usize {
+        self.inputs() + MAX_CHANNELS * self.max_block * std::mem::size_of::<f32>()
+    }
+
+    fn len(&self) -> usize {
+        self.outputs() + MAX_CHANNELS * self.max_block * std::mem::size_of::<f32>()
+    }
+
+    /// Channel buffer within a direction starting at `offset`
+    unsafe fn channel<'a>(&self, memory: &SharedMemory, offset: usize, channel: usize) -> &'a mut [f32] {
+        let ptr = memory.as_ptr().add(offset) as *mut f32;
+        std::slice::from_raw_parts_mut(ptr.add(channel * self.max_block), self.max_block)
+    }
+}
+
+/// A plugin running in a helper process
+pub struct BridgedPlugin {
+    stream: UnixStream,
+    memory: Arc<SharedMemory>,
+    parameters: Ring<ParameterChange>,
+    events: Ring<Event>,
+    layout: Layout,
+    sample_rate: f64,
+    input_channels: usize,
+    output_channels: usize,
+    /// How long the audio thread waits for a block
+    deadline: Deadline,
+    status: Arc<Status>,
+    spawned: Instant,
+    /// Owns the helper process once it is set up
+    watchdog: Option<JoinHandle<()>>,
+}
+
+impl BridgedPlugin {
+    /// Spawns `helper` to host the class `cid` of `bundle` and sets it up for processing
+    pub fn spawn(helper: &Path, bundle: &Path, cid: &str, sample_rate: f64, max_block: usize) -> Result<Self> {
+        let layout = Layout { max_block };
+        let memory = Arc::new(SharedMemory::create(layout.len())?);
+        let parameters = unsafe { Ring::init(memory.as_ptr().add(layout.parameters()), PARAMETER_CAPACITY) };
+        let events = unsafe { Ring::init(memory.as_ptr().add(layout.events()), MAX_EVENTS) };
+
+        let socket = std::env::temp_dir().join(format!(
+            "voxea-{}-{}.sock",
+            std::process::id(),
+            NEXT_SOCKET.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket)?;
+
+        info!("Hosting {cid} of {} out of process", bundle.display());
+
+        let child = Command::new(helper)
+            .arg("host")
+            .arg(bundle)
+            .arg(cid)
+            .arg(memory.name())
+            .arg(max_block.to_string())
+            .arg(&socket)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Could not spawn plugin helper {}", helper.display()));
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = std::fs::remove_file(&socket);
+                return Err(e);
+            }
+        };
+
+        let stream = accept(&listener, &mut child);
+        let _ = std::fs::remove_file(&socket);
+
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+
+        let mut plugin = Self {
+            stream,
+            memory,
+            parameters,
+            events,
+            layout,
+            sample_rate,
+            input_channels: 0,
+            output_channels: 0,
+            deadline: Deadline::default(),
+            status: Arc::new(Status::default()),
+            spawned: Instant::now(),
+            watchdog: None,
+        };
+
+        if let Err(e) = plugin.setup() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+
+        let status = plugin.status.clone();
+        let memory = plugin.memory.clone();
+        let spawned = plugin.spawned;
+        plugin.watchdog = Some(
+            std::thread::Builder::new()
+                .name("Plugin Watchdog".to_string())
+                .spawn(move || watch(child, &memory, &status, spawned))?,
+        );
+
+        Ok(plugin)
+    }
+
+    fn setup(&mut self) -> Result<()> {
+        self.stream.set_read_timeout(Some(SETUP_TIMEOUT))?;
+
+        let reply = Frame::new(OP_SETUP, self.layout.max_block as u32, self.sample_rate)
+            .send(&mut self.stream)
+            .and_then(|_| Frame::receive(&mut self.stream));
+
+        match reply {
+            Ok(Frame { op: OP_READY, a, .. }) => {
+                self.input_channels = (a & 0xFFFF) as usize;
+                self.output_channels = (a >> 16) as usize;
+            }
+            Ok(_) => return Err(anyhow!("Plugin helper could not set up processing")),
+            Err(e) => return Err(anyhow!("Plugin helper did not respond: {e}")),
+        }
+
+        // Requesting a block must never block the audio thread
+        self.stream.set_nonblocking(true)?;
+
+        Ok(())
+    }
+
+    /// How long to wait for every block, see [`Deadline`]
+    pub fn set_deadline(&mut self, deadline: Deadline) {
+        self.deadline = deadline;
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.input_channels
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.output_channels
+    }
+
+    /// Whether the helper crashed, failed to process or stopped responding
+    pub fn is_failed(&self) -> bool {
+        self.status.failed.load(Ordering::Acquire)
+    }
+
+    /// Number of blocks that were silent because the helper missed their deadline
+    pub fn missed_blocks(&self) -> u64 {
+        self.status.missed.load(Ordering::Relaxed)
+    }
+
+    /// Processes one block in the helper, waiting at most until the deadline and never blocking on the socket.
+    /// A block the helper misses the deadline of is silent, once the helper failed inputs are copied to the outputs.
+    /// Failures are reported by the watchdog, so this never logs, allocates or waits on the helper process.
+    /// Data events such as sysex point into host memory and can not be passed to the helper, so they are dropped.
+    pub fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        changes: &[ParameterChange],
+        events: &[Event],
+    ) -> Result<()> {
+        if self.is_failed() {
+            bypass(inputs, outputs);
+            return Ok(());
+        }
+
+        let control = unsafe { Control::get(&self.memory) };
+
+        let num_samples = outputs
+            .iter()
+            .map(|o| o.len())
+            .chain(inputs.iter().map(|i| i.len()))
+            .min()
+            .unwrap_or(0)
+            .min(self.layout.max_block);
+
+        // Changes and events are queued even if the helper is still busy, so they apply to the next block it gets
+        for change in changes {
+            self.parameters.push(*change);
+        }
+
+        for event in events.iter().filter(|e| e.event_type != DATA_EVENT) {
+            self.events.push(*event);
+        }
+
+        // Still working on a block that missed its deadline
+        if control.is_pending() {
+            self.missed(outputs);
+            return Ok(());
+        }
+
+        for (channel, input) in inputs.iter().take(MAX_CHANNELS).enumerate() {
+            let buffer = unsafe { self.layout.channel(&self.memory, self.layout.inputs(), channel) };
+            buffer[..num_samples].copy_from_slice(&input[..num_samples]);
+        }
+
+        let block = control.processed.load(Ordering::Acquire).wrapping_add(1);
+        control.num_samples.store(num_samples as u32, Ordering::Relaxed);
+        control.error.store(false, Ordering::Relaxed);
+        self.status
+            .requested_at
+            .store(self.spawned.elapsed().as_micros() as u64, Ordering::Relaxed);
+        control.requested.store(block, Ordering::Release);
+
+        // A full socket or a helper that is gone shows up as a missed block, the watchdog tells them apart
+        let _ = (&self.stream).write(&[OP_PROCESS as u8]);
+
+        let deadline = Instant::now() + self.deadline.duration(num_samples, self.sample_rate);
+
+        while control.processed.load(Ordering::Acquire) != block {
+            if Instant::now() >= deadline {
+                self.missed(outputs);
+                return Ok(());
+            }
+
+            std::thread::yield_now();
+        }
+
+        if control.error.load(Ordering::Acquire) {
+            self.status.failed.store(true, Ordering::Release);
+            bypass(inputs, outputs);
+            return Err(anyhow!("Plugin helper failed to process"));
+        }
+
+        // Channels the plugin does not have would replay whatever an earlier block left in the buffers
+        let channels = self.output_channels.min(MAX_CHANNELS);
+        for (channel, output) in outputs.iter_mut().enumerate() {
+            if channel < channels {
+                let buffer = unsafe { self.layout.channel(&self.memory, self.layout.outputs(), channel) };
+                output[..num_samples].copy_from_slice(&buffer[..num_samples]);
+                output[num_samples..].fill(0.0);
+            } else {
+                output.fill(0.0);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn missed(&self, outputs: &mut [&mut [f32]]) {
+        self.status.missed.fetch_add(1, Ordering::Relaxed);
+
+        for output in outputs {
+            output.fill(0.0);
+        }
+    }
+}
+
+impl Drop for BridgedPlugin {
+    /// Leaves shutting down the helper to the watchdog, so dropping never waits for the helper process
+    fn drop(&mut self) {
+        let _ = (&self.stream).write(&[OP_SHUTDOWN as u8]);
+        self.status.stopped.store(true, Ordering::Release);
+
+        if let Some(watchdog) = self.watchdog.take() {
+            watchdog.thread().unpark();
+        }
+    }
+}
+
+/// Owns the helper process of a bridged plugin and reports on it from outside the audio thread.
+/// Kills the helper if it crashed, failed to process or did not finish a block within [`PROCESS_TIMEOUT`].
+fn watch(mut child: Child, memory: &SharedMemory, status: &Status, spawned: Instant) {
+    let control = unsafe { Control::get(memory) };
+    let mut reported = 0;
+
+    loop {
+        std::thread::park_timeout(WATCH_INTERVAL);
+
+        if status.stopped.load(Ordering::Acquire) {
+            break;
+        }
+
+        let missed = status.missed.load(Ordering::Relaxed);
+        if missed > reported {
+            warn!("Plugin helper missed the deadline of {} blocks", missed - reported);
+            reported = missed;
+        }
+
+        let pending = Duration::from_micros(status.requested_at.load(Ordering::Relaxed));
+        let reason = match child.try_wait() {
+            Ok(Some(exit)) => Some(format!("exited with {exit}")),
+            _ if status.failed.load(Ordering::Acquire) => Some("failed to process".to_string()),
+            _ if control.is_pending() && spawned.elapsed().saturating_sub(pending) > PROCESS_TIMEOUT => {
+                Some("stopped responding".to_string())
+            }
+            _ => None,
+        };
+
+        if let Some(reason) = reason {
+            error!("Plugin helper {reason}, bypassing plugin");
+            status.failed.store(true, Ordering::Release);
+            break;
+        }
+    }
+
+    // The socket closes with the plugin, which makes a healthy helper shut down by itself
+    let deadline = Instant::now() + PROCESS_TIMEOUT;
+    while !status.failed.load(Ordering::Acquire) && Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Waits for the helper to connect, giving up if it exits or takes too long
+fn accept(listener: &UnixListener, child: &mut Child) -> Result<UnixStream> {
+    listener.set_nonblocking(true)?;
+
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(stream);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        if let Some(status) = child.try_wait()? {
+            return Err(anyhow!("Plugin helper exited before connecting: {status}"));
+        }
+
+        if Instant::now() > deadline {
+            return Err(anyhow!("Timed out waiting for the plugin helper"));
+        }
+
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Copies the inputs to the outputs, silencing outputs without a matching input
+fn bypass(inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+    for (channel, output) in outputs.iter_mut().enumerate() {
+        match inputs.get(channel) {
+            Some(input) => {
+                let len = input.len().min(output.len());
+                output[..len].copy_from_slice(&input[..len]);
+                output[len..].fill(0.0);
+            }
+            None => output.fill(0.0),
+        }
+    }
+}
+
+/// Runs the helper side of the bridge: loads the plugin, connects to the host, sets up processing and processes the
+/// blocks the host requests until shutdown.
+/// This is what `voxea_vst host` runs.
+pub fn run_helper(bundle: &Path, cid: &str, memory: &str, max_block: usize, socket: &Path) -> Result<()> {
+    let module = Arc::new(Module::load(bundle)?);
+    let class = module
+        .classes()
+        .into_iter()
+        .find(|c| c.cid.eq_ignore_ascii_case(cid))
+        .ok_or_else(|| anyhow!("{} has no class {cid}", bundle.display()))?;
+
+    let mut plugin = Plugin::new(module, &class)?;
+
+    let layout = Layout { max_block };
+    let memory = SharedMemory::open(memory, layout.len())?;
+    let parameters: Ring<ParameterChange> = unsafe { Ring::attach(memory.as_ptr().add(layout.parameters())) };
+    let events: Ring<Event> = unsafe { Ring::attach(memory.as_ptr().add(layout.events())) };
+
+    let control = unsafe { Control::get(&memory) };
+
+    let mut stream = UnixStream::connect(socket)?;
+
+    let mut changes = Vec::with_capacity(PARAMETER_CAPACITY);
+    let mut block_events = Vec::with_capacity(MAX_EVENTS);
+    let mut inputs: Vec<&[f32]> = Vec::with_capacity(MAX_CHANNELS);
+    let mut outputs: Vec<&mut [f32]> = Vec::with_capacity(MAX_CHANNELS);
+
+    let frame = match Frame::receive(&mut stream) {
+        Ok(frame) => frame,
+        // The host went away
+        Err(_) => return Ok(()),
+    };
+
+    if frame.op != OP_SETUP {
+        return Err(anyhow!("Expected a setup frame, got {}", frame.op));
+    }
+
+    if let Err(e) = plugin.setup_processing(frame.b, frame.a as usize) {
+        Frame::new(OP_ERROR, 0, 0.0).send(&mut stream)?;
+        return Err(e);
+    }
+
+    let input_channels = plugin.input_channels().min(MAX_CHANNELS);
+    let output_channels = plugin.output_channels().min(MAX_CHANNELS);
+
+    let channels = input_channels as u32 | (output_channels as u32) << 16;
+    Frame::new(OP_READY, channels, 0.0).send(&mut stream)?;
+
+    loop {
+        let mut op = [0u8];
+        if stream.read_exact(&mut op).is_err() {
+            // The host went away
+            return Ok(());
+        }
+
+        match op[0] as u32 {
+            OP_PROCESS => {
+                let block = control.requested.load(Ordering::Acquire);
+                if block == control.processed.load(Ordering::Relaxed) {
+                    continue;
+                }
+
+                let num_samples = (control.num_samples.load(Ordering::Relaxed) as usize).min(max_block);
+
+                changes.clear();
+                while let Some(change) = parameters.pop() {
+                    changes.push(change);
+                }
+
+                block_events.clear();
+                while let Some(event) = events.pop() {
+                    block_events.push(event);
+                }
+
+                inputs.clear();
+                outputs.clear();
+                unsafe {
+                    for channel in 0..input_channels {
+                        inputs.push(&layout.channel(&memory, layout.inputs(), channel)[..num_samples]);
+                    }
+                    for channel in 0..output_channels {
+                        outputs.push(&mut layout.channel(&memory, layout.outputs(), channel)[..num_samples]);
+                    }
+                }
+
+                if let Err(e) = plugin.process(&inputs, &mut outputs, &changes, &block_events) {
+                    error!("{e}");
+                    control.error.store(true, Ordering::Relaxed);
+                }
+
+                control.processed.store(block, Ordering::Release);
+            }
+            OP_SHUTDOWN => return Ok(()),
+            op => return Err(anyhow!("Unknown request {op}")),
+        }
+    }
+}
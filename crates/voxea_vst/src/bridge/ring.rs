@@ -0,0 +1,96 @@
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Header of a ring, padded so the two indices do not share a cache line with the data
+#[repr(C, align(64))]
+struct RingHeader {
+    head: AtomicU32,
+    tail: AtomicU32,
+    capacity: u32,
+}
+
+/// A single producer, single consumer ring buffer living in memory shared between two processes.
+/// One side pushes and the other pops, neither side ever blocks or allocates.
+pub struct Ring<T: Copy> {
+    header: *const RingHeader,
+    data: *mut T,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Copy> Ring<T> {
+    /// Number of bytes a ring with the given capacity needs
+    pub const fn size(capacity: usize) -> usize {
+        std::mem::size_of::<RingHeader>() + capacity * std::mem::size_of::<T>()
+    }
+
+    /// Initializes an empty ring at `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must be 64 byte aligned, valid for [`Ring::size`] bytes and outlive the ring.
+    pub unsafe fn init(ptr: *mut u8, capacity: usize) -> Self {
+        (ptr as *mut RingHeader).write(RingHeader {
+            head: AtomicU32::new(0),
+            tail: AtomicU32::new(0),
+            capacity: capacity as u32,
+        });
+
+        Self::attach(ptr)
+    }
+
+    /// Attaches to a ring another process initialized at `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must point to a ring created with [`Ring::init`] which outlives this one.
+    pub unsafe fn attach(ptr: *mut u8) -> Self {
+        Self {
+            header: ptr as *const RingHeader,
+            data: ptr.add(std::mem::size_of::<RingHeader>()) as *mut T,
+            _marker: PhantomData,
+        }
+    }
+
+    fn header(&self) -> &RingHeader {
+        unsafe { &*self.header }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.header().capacity as usize
+    }
+
+    /// Appends a value, returns false if the ring is full
+    pub fn push(&self, value: T) -> bool {
+        let header = self.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Acquire);
+
+        if head.wrapping_sub(tail) as usize >= self.capacity() {
+            return false;
+        }
+
+        unsafe {
+            self.data.add(head as usize % self.capacity()).write(value);
+        }
+        header.head.store(head.wrapping_add(1), Ordering::Release);
+
+        true
+    }
+
+    /// Takes the oldest value out of the ring
+    pub fn pop(&self) -> Option<T> {
+        let header = self.header();
+        let tail = header.tail.load(Ordering::Relaxed);
+        let head = header.head.load(Ordering::Acquire);
+
+        if head == tail {
+            return None;
+        }
+
+        let value = unsafe { self.data.add(tail as usize % self.capacity()).read() };
+        header.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        Some(value)
+    }
+}
+
+// Values are copied in and out of the shared memory, the ring itself holds no references
+unsafe impl<T: Copy> Send for Ring<T> {}
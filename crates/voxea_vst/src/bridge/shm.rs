@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use std::ffi::CString;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A POSIX shared memory object mapped into the current process.
+/// The process that created it unlinks it again when it is dropped.
+pub struct SharedMemory {
+    name: String,
+    ptr: *mut u8,
+    len: usize,
+    owner: bool,
+}
+
+impl SharedMemory {
+    /// Creates a new zeroed shared memory object with a unique name
+    pub fn create(len: usize) -> Result<Self> {
+        let name = format!(
+            "/voxea-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+
+        Self::map(name, len, true)
+    }
+
+    /// Maps a shared memory object created by another process
+    pub fn open(name: &str, len: usize) -> Result<Self> {
+        Self::map(name.to_string(), len, false)
+    }
+
+    fn map(name: String, len: usize, create: bool) -> Result<Self> {
+        let c_name = CString::new(name.as_str())?;
+
+        unsafe {
+            let flags = if create {
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR
+            } else {
+                libc::O_RDWR
+            };
+
+            let fd = libc::shm_open(c_name.as_ptr(), flags, 0o600);
+            if fd < 0 {
+                return Err(anyhow!(
+                    "Could not open shared memory {name}: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            if create && libc::ftruncate(fd, len as libc::off_t) != 0 {
+                let error = std::io::Error::last_os_error();
+                libc::close(fd);
+                libc::shm_unlink(c_name.as_ptr());
+                return Err(anyhow!("Could not resize shared memory {name}: {error}"));
+            }
+
+            let ptr = libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+            libc::close(fd);
+
+            if ptr == libc::MAP_FAILED {
+                let error = std::io::Error::last_os_error();
+                if create {
+                    libc::shm_unlink(c_name.as_ptr());
+                }
+                return Err(anyhow!("Could not map shared memory {name}: {error}"));
+            }
+
+            Ok(Self {
+                name,
+                ptr: ptr as *mut u8,
+                len,
+                owner: create,
+            })
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+}
+
+// The mapping stays valid until drop, synchronization of its contents is up to the user
+unsafe impl Send for SharedMemory {}
+unsafe impl Sync for SharedMemory {}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+
+            if self.owner {
+                if let Ok(name) = CString::new(self.name.as_str()) {
+                    libc::shm_unlink(name.as_ptr());
+                }
+            }
+        }
+    }
+}
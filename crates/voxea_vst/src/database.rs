@@ -0,0 +1,119 @@
+use crate::node::HostingMode;
+use crate::scanner::{self, PluginInfo, Scanner};
+use anyhow::Result;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cache of scanned VST3 bundles persisted as json, so plugins only need to be rescanned when they change.
+/// Bundles that failed to scan are blacklisted and skipped until they are removed from the blacklist.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PluginDatabase {
+    pub(crate) plugins: BTreeMap<PathBuf, PluginInfo>,
+    /// Blacklisted bundles with the reason they were blacklisted
+    pub(crate) blacklist: BTreeMap<PathBuf, String>,
+    /// Bundles the user chose a non default hosting mode for
+    #[serde(default)]
+    pub(crate) hosting: BTreeMap<PathBuf, HostingMode>,
+}
+
+impl PluginDatabase {
+    /// Default location of the database in the user's config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("Voxea").join("vst3_plugins.json"))
+    }
+
+    /// Loads the database from disk, or returns an empty one if it does not exist yet
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// Scans every bundle under the given directories. Bundles that are cached and unchanged are skipped,
+    /// bundles that fail to scan are blacklisted and bundles that no longer exist are removed.
+    pub fn scan(&mut self, scanner: &Scanner, paths: &[PathBuf]) {
+        let bundles = scanner::find_bundles(paths);
+
+        self.plugins.retain(|path, _| bundles.contains(path));
+
+        for bundle in bundles {
+            if self.is_blacklisted(&bundle) {
+                continue;
+            }
+
+            let modified = scanner::modified_time(&bundle);
+            if self.plugins.get(&bundle).is_some_and(|p| p.modified == modified) {
+                continue;
+            }
+
+            match scanner.scan(&bundle) {
+                Ok(plugin) => {
+                    info!("Found {} with {} classes", bundle.display(), plugin.classes.len());
+                    self.plugins.insert(bundle, plugin);
+                }
+                Err(e) => {
+                    warn!("Blacklisting {}: {e}", bundle.display());
+                    self.plugins.remove(&bundle);
+                    self.blacklist.insert(bundle, e.to_string());
+                }
+            }
+        }
+    }
+
+    pub fn plugins(&self) -> impl Iterator<Item = &PluginInfo> {
+        self.plugins.values()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&PluginInfo> {
+        self.plugins.get(path)
+    }
+
+    pub fn blacklist(&mut self, path: impl Into<PathBuf>, reason: impl Into<String>) {
+        let path = path.into();
+        self.plugins.remove(&path);
+        self.blacklist.insert(path, reason.into());
+    }
+
+    /// Removes a bundle from the blacklist so it is scanned again on the next [`PluginDatabase::scan`]
+    pub fn unblacklist(&mut self, path: &Path) -> bool {
+        self.blacklist.remove(path).is_some()
+    }
+
+    pub fn is_blacklisted(&self, path: &Path) -> bool {
+        self.blacklist.contains_key(path)
+    }
+
+    pub fn blacklisted(&self) -> impl Iterator<Item = (&PathBuf, &String)> {
+        self.blacklist.iter()
+    }
+
+    /// How the plugins of a bundle should be hosted
+    pub fn hosting_mode(&self, path: &Path) -> HostingMode {
+        self.hosting.get(path).copied().unwrap_or_default()
+    }
+
+    pub fn set_hosting_mode(&mut self, path: impl Into<PathBuf>, mode: HostingMode) {
+        let path = path.into();
+
+        if mode == HostingMode::default() {
+            self.hosting.remove(&path);
+        } else {
+            self.hosting.insert(path, mode);
+        }
+    }
+}
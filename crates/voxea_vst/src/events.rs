@@ -0,0 +1,492 @@
+use crate::{FUnknown, IEventList, IEventListVTable, K_INVALID_ARGUMENT, K_NO_INTERFACE, K_RESULT_FALSE, K_RESULT_OK, TUID};
+use std::ffi::c_void;
+
+/// Maximum number of events passed to or received from a plugin within one block
+pub const MAX_EVENTS: usize = 512;
+
+pub const NOTE_ON_EVENT: u16 = 0;
+pub const NOTE_OFF_EVENT: u16 = 1;
+pub const DATA_EVENT: u16 = 2;
+pub const POLY_PRESSURE_EVENT: u16 = 3;
+pub const NOTE_EXPRESSION_VALUE_EVENT: u16 = 4;
+
+/// The event is played live and not read from the timeline
+pub const EVENT_IS_LIVE: u16 = 1 << 0;
+
+/// Type of a [`DataEvent`] holding a MIDI system exclusive message
+pub const DATA_MIDI_SYSEX: u32 = 0;
+
+/// Note id of notes the host does not track individually
+pub const NO_NOTE_ID: i32 = -1;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NoteOnEvent {
+    pub channel: i16,
+    pub pitch: i16,
+    /// Tuning in cents relative to the pitch
+    pub tuning: f32,
+    /// Normalized velocity from 0 to 1
+    pub velocity: f32,
+    /// Length in samples, 0 if unknown
+    pub length: i32,
+    pub note_id: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NoteOffEvent {
+    pub channel: i16,
+    pub pitch: i16,
+    pub velocity: f32,
+    pub note_id: i32,
+    pub tuning: f32,
+}
+
+/// Raw data such as a sysex message. The bytes are borrowed and have to outlive the block they are sent in.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataEvent {
+    pub size: u32,
+    pub data_type: u32,
+    pub bytes: *const u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PolyPressureEvent {
+    pub channel: i16,
+    pub pitch: i16,
+    pub pressure: f32,
+    pub note_id: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NoteExpressionValueEvent {
+    pub type_id: u32,
+    pub note_id: i32,
+    /// Normalized value from 0 to 1
+    pub value: f64,
+}
+
+/// Payload of an [`Event`], which member is valid depends on [`Event::event_type`]
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union EventData {
+    pub note_on: NoteOnEvent,
+    pub note_off: NoteOffEvent,
+    pub data: DataEvent,
+    pub poly_pressure: PolyPressureEvent,
+    pub note_expression_value: NoteExpressionValueEvent,
+    /// Size of the largest event in the SDK, the text based note expression event
+    _size: [u64; 3],
+}
+
+/// A VST3 event with the same layout as `Steinberg::Vst::Event`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Event {
+    pub bus_index: i32,
+    /// Position within the block
+    pub sample_offset: i32,
+    /// Position in quarter notes within the project
+    pub ppq_position: f64,
+    pub flags: u16,
+    pub event_type: u16,
+    pub data: EventData,
+}
+
+impl Event {
+    fn new(sample_offset: i32, event_type: u16, data: EventData) -> Self {
+        Self {
+            bus_index: 0,
+            sample_offset,
+            ppq_position: 0.0,
+            flags: EVENT_IS_LIVE,
+            event_type,
+            data,
+        }
+    }
+
+    pub fn note_on(sample_offset: i32, note_on: NoteOnEvent) -> Self {
+        Self::new(sample_offset, NOTE_ON_EVENT, EventData { note_on })
+    }
+
+    pub fn note_off(sample_offset: i32, note_off: NoteOffEvent) -> Self {
+        Self::new(sample_offset, NOTE_OFF_EVENT, EventData { note_off })
+    }
+
+    pub fn poly_pressure(sample_offset: i32, poly_pressure: PolyPressureEvent) -> Self {
+        Self::new(sample_offset, POLY_PRESSURE_EVENT, EventData { poly_pressure })
+    }
+
+    pub fn note_expression_value(sample_offset: i32, note_expression_value: NoteExpressionValueEvent) -> Self {
+        Self::new(sample_offset, NOTE_EXPRESSION_VALUE_EVENT, EventData { note_expression_value })
+    }
+
+    /// A sysex message, `bytes` has to outlive processing of the block the event is sent in
+    pub fn sysex(sample_offset: i32, bytes: &[u8]) -> Self {
+        let data = DataEvent {
+            size: bytes.len() as u32,
+            data_type: DATA_MIDI_SYSEX,
+            bytes: bytes.as_ptr(),
+        };
+
+        Self::new(sample_offset, DATA_EVENT, EventData { data })
+    }
+
+    /// Translates a MIDI message. Only notes, poly pressure and sysex are events in VST3,
+    /// controllers, channel pressure and pitch bend are mapped to parameters by the plugin instead.
+    pub fn from_midi(sample_offset: i32, message: &MidiMessage) -> Option<Self> {
+        match *message {
+            MidiMessage::NoteOn { channel, key, velocity } if velocity > 0 => Some(Self::note_on(
+                sample_offset,
+                NoteOnEvent {
+                    channel: channel as i16,
+                    pitch: key as i16,
+                    tuning: 0.0,
+                    velocity: velocity as f32 / 127.0,
+                    length: 0,
+                    note_id: NO_NOTE_ID,
+                },
+            )),
+            // A note on with zero velocity is a note off with the default release velocity
+            MidiMessage::NoteOn { channel, key, .. } => Some(Self::note_off(
+                sample_offset,
+                NoteOffEvent {
+                    channel: channel as i16,
+                    pitch: key as i16,
+                    velocity: 64.0 / 127.0,
+                    note_id: NO_NOTE_ID,
+                    tuning: 0.0,
+                },
+            )),
+            MidiMessage::NoteOff { channel, key, velocity } => Some(Self::note_off(
+                sample_offset,
+                NoteOffEvent {
+                    channel: channel as i16,
+                    pitch: key as i16,
+                    velocity: velocity as f32 / 127.0,
+                    note_id: NO_NOTE_ID,
+                    tuning: 0.0,
+                },
+            )),
+            MidiMessage::PolyPressure { channel, key, pressure } => Some(Self::poly_pressure(
+                sample_offset,
+                PolyPressureEvent {
+                    channel: channel as i16,
+                    pitch: key as i16,
+                    pressure: pressure as f32 / 127.0,
+                    note_id: NO_NOTE_ID,
+                },
+            )),
+            MidiMessage::SysEx(bytes) => Some(Self::sysex(sample_offset, bytes)),
+            _ => None,
+        }
+    }
+
+    pub fn as_note_on(&self) -> Option<&NoteOnEvent> {
+        (self.event_type == NOTE_ON_EVENT).then_some(unsafe { &self.data.note_on })
+    }
+
+    pub fn as_note_off(&self) -> Option<&NoteOffEvent> {
+        (self.event_type == NOTE_OFF_EVENT).then_some(unsafe { &self.data.note_off })
+    }
+
+    pub fn as_data(&self) -> Option<&DataEvent> {
+        (self.event_type == DATA_EVENT).then_some(unsafe { &self.data.data })
+    }
+
+    pub fn as_poly_pressure(&self) -> Option<&PolyPressureEvent> {
+        (self.event_type == POLY_PRESSURE_EVENT).then_some(unsafe { &self.data.poly_pressure })
+    }
+
+    pub fn as_note_expression_value(&self) -> Option<&NoteExpressionValueEvent> {
+        (self.event_type == NOTE_EXPRESSION_VALUE_EVENT).then_some(unsafe { &self.data.note_expression_value })
+    }
+}
+
+impl std::fmt::Debug for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("Event");
+        debug
+            .field("bus_index", &self.bus_index)
+            .field("sample_offset", &self.sample_offset)
+            .field("ppq_position", &self.ppq_position)
+            .field("flags", &self.flags);
+
+        match self.event_type {
+            NOTE_ON_EVENT => debug.field("note_on", unsafe { &self.data.note_on }),
+            NOTE_OFF_EVENT => debug.field("note_off", unsafe { &self.data.note_off }),
+            DATA_EVENT => debug.field("data", unsafe { &self.data.data }),
+            POLY_PRESSURE_EVENT => debug.field("poly_pressure", unsafe { &self.data.poly_pressure }),
+            NOTE_EXPRESSION_VALUE_EVENT => debug.field("note_expression_value", unsafe { &self.data.note_expression_value }),
+            event_type => debug.field("event_type", &event_type),
+        };
+
+        debug.finish()
+    }
+}
+
+/// A MIDI 1.0 channel voice or sysex message. Channels are 0 based and all values are 7 bit except pitch bend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage<'a> {
+    NoteOff { channel: u8, key: u8, velocity: u8 },
+    NoteOn { channel: u8, key: u8, velocity: u8 },
+    PolyPressure { channel: u8, key: u8, pressure: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    ProgramChange { channel: u8, program: u8 },
+    ChannelPressure { channel: u8, pressure: u8 },
+    /// 14 bit value centered at 8192
+    PitchBend { channel: u8, value: u16 },
+    /// A complete sysex message including the leading `0xF0` and trailing `0xF7`
+    SysEx(&'a [u8]),
+}
+
+impl<'a> MidiMessage<'a> {
+    /// Parses a single message from its wire bytes, returns `None` for incomplete or unsupported messages
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let status = *bytes.first()?;
+
+        if status == 0xF0 {
+            return (bytes.last() == Some(&0xF7)).then_some(Self::SysEx(bytes));
+        }
+
+        let channel = status & 0x0F;
+        let data = |i: usize| bytes.get(i).copied().filter(|b| b & 0x80 == 0);
+
+        Some(match status & 0xF0 {
+            0x80 => Self::NoteOff { channel, key: data(1)?, velocity: data(2)? },
+            0x90 => Self::NoteOn { channel, key: data(1)?, velocity: data(2)? },
+            0xA0 => Self::PolyPressure { channel, key: data(1)?, pressure: data(2)? },
+            0xB0 => Self::ControlChange { channel, controller: data(1)?, value: data(2)? },
+            0xC0 => Self::ProgramChange { channel, program: data(1)? },
+            0xD0 => Self::ChannelPressure { channel, pressure: data(1)? },
+            0xE0 => Self::PitchBend {
+                channel,
+                value: data(1)? as u16 | (data(2)? as u16) << 7,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// An `IEventList` used both to pass events to a plugin and to receive its output events.
+/// The storage is allocated up front so it can be cleared and refilled on the audio thread.
+#[repr(C)]
+pub struct EventList {
+    vtable: *const IEventListVTable,
+    events: Box<[Event]>,
+    len: usize,
+}
+
+impl EventList {
+    const VTABLE: IEventListVTable = IEventListVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        getEventCount: Self::get_event_count,
+        getEvent: Self::get_event,
+        addEvent: Self::add_event,
+    };
+
+    pub fn new() -> Self {
+        let empty = Event::note_off(0, NoteOffEvent::default());
+
+        Self {
+            vtable: &Self::VTABLE,
+            events: vec![empty; MAX_EVENTS].into_boxed_slice(),
+            len: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events[..self.len]
+    }
+
+    /// Adds an event, returns false if the list is full
+    pub fn push(&mut self, event: Event) -> bool {
+        if self.len == MAX_EVENTS {
+            return false;
+        }
+
+        self.events[self.len] = event;
+        self.len += 1;
+
+        true
+    }
+
+    pub(crate) fn as_ptr(&mut self) -> *mut IEventList {
+        self as *mut Self as *mut IEventList
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IEventList) -> &'a mut Self {
+        &mut *(this as *mut Self)
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IEventList, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IEventList::iid() {
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut IEventList) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut IEventList) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn get_event_count(this: *mut IEventList) -> i32 {
+        Self::from_ptr(this).len as i32
+    }
+
+    unsafe extern "system" fn get_event(this: *mut IEventList, index: i32, event: *mut Event) -> i32 {
+        let Some(e) = Self::from_ptr(this).events().get(index as usize) else {
+            return K_INVALID_ARGUMENT;
+        };
+
+        *event = *e;
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn add_event(this: *mut IEventList, event: *mut Event) -> i32 {
+        if event.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        if Self::from_ptr(this).push(*event) {
+            K_RESULT_OK
+        } else {
+            K_RESULT_FALSE
+        }
+    }
+}
+
+impl Default for EventList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, EventList, MidiMessage, NoteOffEvent, MAX_EVENTS};
+    use crate::{K_INVALID_ARGUMENT, K_RESULT_FALSE, K_RESULT_OK};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            MidiMessage::parse(&[0x93, 60, 100]),
+            Some(MidiMessage::NoteOn { channel: 3, key: 60, velocity: 100 })
+        );
+        assert_eq!(
+            MidiMessage::parse(&[0x80, 60, 0]),
+            Some(MidiMessage::NoteOff { channel: 0, key: 60, velocity: 0 })
+        );
+        assert_eq!(
+            MidiMessage::parse(&[0xCF, 5]),
+            Some(MidiMessage::ProgramChange { channel: 15, program: 5 })
+        );
+
+        // Least significant 7 bits first
+        assert_eq!(MidiMessage::parse(&[0xE0, 0x00, 0x40]), Some(MidiMessage::PitchBend { channel: 0, value: 8192 }));
+        assert_eq!(MidiMessage::parse(&[0xE1, 0x7F, 0x7F]), Some(MidiMessage::PitchBend { channel: 1, value: 16383 }));
+        assert_eq!(MidiMessage::parse(&[0xE0, 0x01, 0x00]), Some(MidiMessage::PitchBend { channel: 0, value: 1 }));
+
+        let sysex = [0xF0, 0x7E, 0x00, 0xF7];
+        assert_eq!(MidiMessage::parse(&sysex), Some(MidiMessage::SysEx(&sysex)));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(MidiMessage::parse(&[]), None);
+        // Incomplete
+        assert_eq!(MidiMessage::parse(&[0x90, 60]), None);
+        assert_eq!(MidiMessage::parse(&[0xF0, 0x7E]), None);
+        // Data bytes have the high bit clear
+        assert_eq!(MidiMessage::parse(&[0x90, 0x90, 100]), None);
+        // System realtime
+        assert_eq!(MidiMessage::parse(&[0xF8]), None);
+    }
+
+    #[test]
+    fn from_midi() {
+        let event = Event::from_midi(12, &MidiMessage::NoteOn { channel: 2, key: 64, velocity: 127 }).unwrap();
+        let note_on = event.as_note_on().unwrap();
+        assert_eq!(event.sample_offset, 12);
+        assert_eq!((note_on.channel, note_on.pitch, note_on.velocity), (2, 64, 1.0));
+
+        let event = Event::from_midi(0, &MidiMessage::NoteOff { channel: 0, key: 64, velocity: 0 }).unwrap();
+        assert_eq!(event.as_note_off().unwrap().velocity, 0.0);
+
+        let event = Event::from_midi(0, &MidiMessage::PolyPressure { channel: 0, key: 64, pressure: 127 }).unwrap();
+        assert_eq!(event.as_poly_pressure().unwrap().pressure, 1.0);
+
+        let sysex = [0xF0, 0x01, 0xF7];
+        let event = Event::from_midi(0, &MidiMessage::SysEx(&sysex)).unwrap();
+        let data = event.as_data().unwrap();
+        assert_eq!((data.size, data.bytes), (3, sysex.as_ptr()));
+    }
+
+    #[test]
+    fn note_on_without_velocity_is_note_off() {
+        let event = Event::from_midi(0, &MidiMessage::NoteOn { channel: 1, key: 60, velocity: 0 }).unwrap();
+
+        assert!(event.as_note_on().is_none());
+        let note_off = event.as_note_off().unwrap();
+        assert_eq!((note_off.channel, note_off.pitch), (1, 60));
+        assert_eq!(note_off.velocity, 64.0 / 127.0);
+    }
+
+    #[test]
+    fn controllers_are_no_events() {
+        let messages = [
+            MidiMessage::ControlChange { channel: 0, controller: 1, value: 64 },
+            MidiMessage::ProgramChange { channel: 0, program: 1 },
+            MidiMessage::ChannelPressure { channel: 0, pressure: 64 },
+            MidiMessage::PitchBend { channel: 0, value: 8192 },
+        ];
+
+        for message in messages {
+            assert!(Event::from_midi(0, &message).is_none(), "{message:?}");
+        }
+    }
+
+    #[test]
+    fn full_list() {
+        let mut list = EventList::new();
+        let mut event = Event::note_off(0, NoteOffEvent::default());
+
+        for offset in 0..MAX_EVENTS as i32 {
+            event.sample_offset = offset;
+            assert_eq!(unsafe { EventList::add_event(list.as_ptr(), &mut event) }, K_RESULT_OK);
+        }
+
+        assert_eq!(unsafe { EventList::add_event(list.as_ptr(), &mut event) }, K_RESULT_FALSE);
+        assert!(!list.push(event));
+        assert_eq!(unsafe { EventList::get_event_count(list.as_ptr()) }, MAX_EVENTS as i32);
+
+        let mut last = Event::note_off(0, NoteOffEvent::default());
+        let index = MAX_EVENTS as i32 - 1;
+        assert_eq!(unsafe { EventList::get_event(list.as_ptr(), index, &mut last) }, K_RESULT_OK);
+        assert_eq!(last.sample_offset, index);
+        assert_eq!(unsafe { EventList::get_event(list.as_ptr(), MAX_EVENTS as i32, &mut last) }, K_INVALID_ARGUMENT);
+        assert_eq!(unsafe { EventList::add_event(list.as_ptr(), std::ptr::null_mut()) }, K_INVALID_ARGUMENT);
+
+        list.clear();
+        assert!(list.events().is_empty());
+        assert!(list.push(event));
+    }
+}
@@ -0,0 +1,197 @@
+//! The host side of `IComponentHandler`, through which the edit controller reports the parameter edits made in its
+//! editor. Edits are passed on to the processor and kept for the host, e.g. to record undo steps.
+
+use crate::parameters::ParameterChange;
+use crate::{
+    FUnknown, IComponentHandler, IComponentHandlerVTable, IEditController, K_NO_INTERFACE, K_RESULT_FALSE, K_RESULT_OK,
+    TUID,
+};
+use std::ffi::c_void;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+
+/// Maximum number of edits queued for the processor or the host, further edits are dropped until they are taken
+pub const EDIT_CAPACITY: usize = 1024;
+
+/// A parameter edit made in the plugin's editor, values are normalized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// The user grabbed a control, `value` is the value before the gesture
+    Begin { id: u32, value: f64 },
+    Perform { id: u32, value: f64 },
+    /// The user let go of a control
+    End { id: u32 },
+}
+
+impl Edit {
+    pub fn id(&self) -> u32 {
+        match *self {
+            Self::Begin { id, .. } | Self::Perform { id, .. } | Self::End { id } => id,
+        }
+    }
+}
+
+/// Given to the edit controller through `setComponentHandler`. It is called on the UI thread and lives as long as
+/// the plugin, so reference counting is a no-op.
+#[repr(C)]
+pub(crate) struct ComponentHandler {
+    vtable: *const IComponentHandlerVTable,
+    /// Asked for the value before a gesture
+    controller: *mut IEditController,
+    /// Values the processor applies at the start of its next block
+    changes: SyncSender<ParameterChange>,
+    /// Edits the host did not take yet
+    edits: Mutex<Vec<Edit>>,
+}
+
+// The controller is only called on the UI thread, the processor side only receives through the channel
+unsafe impl Send for ComponentHandler {}
+unsafe impl Sync for ComponentHandler {}
+
+impl ComponentHandler {
+    const VTABLE: IComponentHandlerVTable = IComponentHandlerVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        beginEdit: Self::begin_edit,
+        performEdit: Self::perform_edit,
+        endEdit: Self::end_edit,
+        restartComponent: Self::restart_component,
+    };
+
+    /// A handler for `controller` and the receiving end the processor takes its changes from
+    pub(crate) fn new(controller: *mut IEditController) -> (Self, Receiver<ParameterChange>) {
+        let (changes, receiver) = mpsc::sync_channel(EDIT_CAPACITY);
+
+        let handler = Self {
+            vtable: &Self::VTABLE,
+            controller,
+            changes,
+            edits: Mutex::new(Vec::new()),
+        };
+
+        (handler, receiver)
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut IComponentHandler {
+        self as *const Self as *mut IComponentHandler
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IComponentHandler) -> &'a Self {
+        &*(this as *const Self)
+    }
+
+    /// Queues a value for the processor, false if the queue is full
+    pub(crate) fn send(&self, id: u32, value: f64) -> bool {
+        let change = ParameterChange {
+            id,
+            sample_offset: 0,
+            value,
+        };
+
+        !matches!(self.changes.try_send(change), Err(TrySendError::Full(_)))
+    }
+
+    /// Edits since the last call, oldest first
+    pub(crate) fn take_edits(&self) -> Vec<Edit> {
+        std::mem::take(&mut *self.edits.lock().unwrap())
+    }
+
+    fn push(&self, edit: Edit) -> i32 {
+        let mut edits = self.edits.lock().unwrap();
+
+        if edits.len() == EDIT_CAPACITY {
+            return K_RESULT_FALSE;
+        }
+
+        edits.push(edit);
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IComponentHandler, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IComponentHandler::iid() {
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut IComponentHandler) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut IComponentHandler) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn begin_edit(this: *mut IComponentHandler, id: u32) -> i32 {
+        let handler = Self::from_ptr(this);
+        let value = (*handler.controller).get_param_normalized(id);
+
+        handler.push(Edit::Begin { id, value })
+    }
+
+    unsafe extern "system" fn perform_edit(this: *mut IComponentHandler, id: u32, value: f64) -> i32 {
+        let handler = Self::from_ptr(this);
+
+        if !handler.send(id, value) {
+            return K_RESULT_FALSE;
+        }
+
+        handler.push(Edit::Perform { id, value })
+    }
+
+    unsafe extern "system" fn end_edit(this: *mut IComponentHandler, id: u32) -> i32 {
+        Self::from_ptr(this).push(Edit::End { id })
+    }
+
+    /// Parameter values are read from the controller whenever they are needed, so there is nothing to reload
+    unsafe extern "system" fn restart_component(_this: *mut IComponentHandler, _flags: i32) -> i32 {
+        K_RESULT_OK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentHandler, Edit, EDIT_CAPACITY};
+    use crate::{IComponentHandler, K_RESULT_FALSE, K_RESULT_OK};
+
+    /// Calls a method of the handler through its vtable, like a plugin does
+    macro_rules! call {
+        ($this:expr, $method:ident $(, $arg:expr)*) => {
+            unsafe { ((*(*$this).vtable).$method)($this $(, $arg)*) }
+        };
+    }
+
+    #[test]
+    fn edits() {
+        let (handler, changes) = ComponentHandler::new(std::ptr::null_mut());
+        let this: *mut IComponentHandler = handler.as_ptr();
+
+        assert_eq!(call!(this, performEdit, 3, 0.25), K_RESULT_OK);
+        assert_eq!(call!(this, endEdit, 3), K_RESULT_OK);
+
+        assert_eq!(handler.take_edits(), [Edit::Perform { id: 3, value: 0.25 }, Edit::End { id: 3 }]);
+        assert!(handler.take_edits().is_empty());
+
+        let change = changes.try_recv().unwrap();
+        assert_eq!((change.id, change.sample_offset, change.value), (3, 0, 0.25));
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn full_queue() {
+        let (handler, _changes) = ComponentHandler::new(std::ptr::null_mut());
+        let this: *mut IComponentHandler = handler.as_ptr();
+
+        for _ in 0..EDIT_CAPACITY {
+            assert!(handler.send(1, 0.5));
+        }
+
+        assert!(!handler.send(1, 0.5));
+        assert_eq!(call!(this, performEdit, 1, 0.5), K_RESULT_FALSE);
+        assert!(handler.take_edits().is_empty());
+    }
+}
@@ -1,9 +1,47 @@
-#![feature(fn_traits)]
-
-use std::ffi::{c_void, CStr};
-use std::fmt::Formatter;
+#[cfg(unix)]
+pub mod bridge;
+pub mod database;
+pub mod events;
+pub mod handler;
+pub mod message;
+pub mod module;
+pub mod node;
+pub mod parameters;
+pub mod plugin;
+pub mod preset;
+pub mod scanner;
+pub mod stream;
+pub mod uid;
+pub mod view;
+
+use crate::events::Event;
+use crate::message::{AttributeList, HostMessage};
+use crate::module::Module;
+pub use crate::uid::FUID;
+use anyhow::Result;
 use libc::c_char;
-use libloading::{Library, Symbol};
+use std::ffi::c_void;
+use std::path::Path;
+
+pub const K_RESULT_OK: i32 = 0;
+pub const K_RESULT_TRUE: i32 = K_RESULT_OK;
+pub const K_RESULT_FALSE: i32 = 1;
+
+#[cfg(windows)]
+pub const K_NO_INTERFACE: i32 = 0x80004002_u32 as i32;
+#[cfg(windows)]
+pub const K_INVALID_ARGUMENT: i32 = 0x80070057_u32 as i32;
+#[cfg(windows)]
+pub const K_NOT_IMPLEMENTED: i32 = 0x80004001_u32 as i32;
+
+#[cfg(not(windows))]
+pub const K_NO_INTERFACE: i32 = -1;
+#[cfg(not(windows))]
+pub const K_INVALID_ARGUMENT: i32 = 2;
+#[cfg(not(windows))]
+pub const K_NOT_IMPLEMENTED: i32 = 3;
+
+pub type TUID = [c_char; 16];
 
 #[repr(C)]
 #[derive(Debug, Default, Clone, Copy)]
@@ -35,6 +73,34 @@ struct PClassInfo {
     name: [c_char; 64]
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PClassInfo2 {
+    cid: [c_char; 16],
+    cardinality: i32,
+    category: [c_char; 32],
+    name: [c_char; 64],
+    class_flags: u32,
+    sub_categories: [c_char; 128],
+    vendor: [c_char; 64],
+    version: [c_char; 64],
+    sdk_version: [c_char; 64],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PClassInfoW {
+    cid: [c_char; 16],
+    cardinality: i32,
+    category: [c_char; 32],
+    name: [u16; 64],
+    class_flags: u32,
+    sub_categories: [c_char; 128],
+    vendor: [u16; 64],
+    version: [u16; 64],
+    sdk_version: [u16; 64],
+}
+
 impl Default for PClassInfo {
     fn default() -> Self {
         Self {
@@ -57,53 +123,44 @@ impl Default for PFactoryInfo {
     }
 }
 
-impl std::fmt::Display for PFactoryInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        unsafe {
-            write!(f, "PFactoryInfo {{ vendor: {:?}, url: {:?}, email: {:?}, flags: {:?} }}",
-                   CStr::from_ptr(self.vendor.as_ptr()),
-                   CStr::from_ptr(self.url.as_ptr()),
-                   CStr::from_ptr(self.email.as_ptr()),
-                   self.flags
-            )
+impl Default for PClassInfo2 {
+    fn default() -> Self {
+        Self {
+            cid: [0; 16],
+            cardinality: 0,
+            category: [0; 32],
+            name: [0; 64],
+            class_flags: 0,
+            sub_categories: [0; 128],
+            vendor: [0; 64],
+            version: [0; 64],
+            sdk_version: [0; 64],
         }
     }
 }
 
-impl std::fmt::Display for PClassInfo {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        unsafe {
-            write!(f, "PClassInfo {{ cid: {:?}, cardinality: {:?}, category: {:?}, name: {:?} }}",
-                   self.cid,
-                   self.cardinality,
-                   CStr::from_ptr(self.category.as_ptr()),
-                   CStr::from_ptr(self.name.as_ptr()),
-            )
+impl Default for PClassInfoW {
+    fn default() -> Self {
+        Self {
+            cid: [0; 16],
+            cardinality: 0,
+            category: [0; 32],
+            name: [0; 64],
+            class_flags: 0,
+            sub_categories: [0; 128],
+            vendor: [0; 64],
+            version: [0; 64],
+            sdk_version: [0; 64],
         }
     }
 }
 
-#[repr(C)]
-#[derive(Debug, Default, Copy, Clone)]
-pub struct FUID(u32, u32, u32, u32);
-
-impl FUID {
-    pub fn to_hex(&self) -> String {
-        let mut hex_string = String::new();
-
-        // Convert each u32 field into hexadecimal and append to the string
-        let hex_string = format!("{:08X}{:08X}{:08X}{:08X}", self.0, self.1, self.2, self.3);
-
-        hex_string
-    }
-}
-
 #[allow(non_snake_case)]
 #[repr(C)]
 struct FUnknownVTable {
-    pub queryInterface: unsafe extern "thiscall" fn(this: *mut FUnknown, _iid: [c_char; 16], obj: *mut c_void) -> i32,
-    pub addRef: unsafe extern "thiscall" fn(this: *mut FUnknown) -> u32,
-    pub release: unsafe extern "thiscall" fn(this: *mut FUnknown) -> u32,
+    pub queryInterface: unsafe extern "system" fn(this: *mut FUnknown, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut FUnknown) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut FUnknown) -> u32,
 }
 
 #[repr(C)]
@@ -112,30 +169,24 @@ pub struct FUnknown {
 }
 
 impl FUnknown {
-    pub fn iid() -> [c_char; 16] {
+    pub fn iid() -> TUID {
         inline_uid(0x00000000, 0x00000000, 0xC0000000, 0x00000046)
     }
 }
 
-impl Into<[c_char; 16]> for FUID {
-    fn into(self) -> [c_char; 16] {
-        unsafe { std::mem::transmute::<FUID, [c_char; 16]>(self) }
-    }
-}
-
 type FIDString = *const c_char;
 
 #[allow(non_snake_case)]
 #[repr(C)]
 struct IPluginFactoryVTable {
-    pub queryInterface: unsafe extern "thiscall" fn(this: *mut IPluginFactory, _iid: [c_char; 16], obj: *mut c_void) -> i32,
-    pub addRef: unsafe extern "thiscall" fn(this: *mut IPluginFactory) -> u32,
-    pub release: unsafe extern "thiscall" fn(this: *mut IPluginFactory) -> u32,
-
-    pub getFactoryInfo: unsafe extern "thiscall" fn(this: *mut IPluginFactory, factory_info: *mut PFactoryInfo) -> i32,
-    pub countClasses: unsafe extern "thiscall" fn(this: *mut IPluginFactory) -> i32,
-    pub getClassInfo: unsafe extern "thiscall" fn(this: *mut IPluginFactory, index: i32, info: *mut PClassInfo) -> i32,
-    pub createInstance: unsafe extern "thiscall" fn(this: *mut IPluginFactory, cid: [c_char; 16], iid: [c_char; 16], obj: *mut *mut c_void) -> i32,
+    pub queryInterface: unsafe extern "system" fn(this: *mut IPluginFactory, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IPluginFactory) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IPluginFactory) -> u32,
+
+    pub getFactoryInfo: unsafe extern "system" fn(this: *mut IPluginFactory, factory_info: *mut PFactoryInfo) -> i32,
+    pub countClasses: unsafe extern "system" fn(this: *mut IPluginFactory) -> i32,
+    pub getClassInfo: unsafe extern "system" fn(this: *mut IPluginFactory, index: i32, info: *mut PClassInfo) -> i32,
+    pub createInstance: unsafe extern "system" fn(this: *mut IPluginFactory, cid: FIDString, iid: FIDString, obj: *mut *mut c_void) -> i32,
 }
 
 #[repr(C)]
@@ -144,6 +195,10 @@ struct IPluginFactory {
 }
 
 impl IPluginFactory {
+    unsafe fn query_interface(&mut self, iid: &TUID, obj: *mut *mut c_void) -> i32 {
+        ((*(self.vtable)).queryInterface)(self, iid, obj)
+    }
+
     unsafe fn add_ref(&mut self) -> u32 {
         ((*(self.vtable)).addRef)(self)
     }
@@ -164,21 +219,76 @@ impl IPluginFactory {
         ((*(self.vtable)).countClasses)(self)
     }
 
-    unsafe fn create_instance(&mut self, cid: [c_char; 16], iid: [c_char; 16], obj: *mut *mut c_void) -> i32 {
+    unsafe fn create_instance(&mut self, cid: FIDString, iid: FIDString, obj: *mut *mut c_void) -> i32 {
         ((*(self.vtable)).createInstance)(self, cid, iid, obj)
     }
 }
 
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IPluginFactory2VTable {
+    pub base: IPluginFactoryVTable,
+
+    pub getClassInfo2: unsafe extern "system" fn(this: *mut IPluginFactory2, index: i32, info: *mut PClassInfo2) -> i32,
+}
+
+#[repr(C)]
+struct IPluginFactory2 {
+    vtable: *const IPluginFactory2VTable
+}
+
+impl IPluginFactory2 {
+    pub fn iid() -> TUID {
+        inline_uid(0x0007B650, 0xF24B4C0B, 0xA464EDB9, 0xF00B2ABB)
+    }
+
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).base.release)(self as *mut Self as *mut IPluginFactory)
+    }
+
+    unsafe fn get_class_info2(&mut self, index: i32, class_info: *mut PClassInfo2) -> i32 {
+        ((*(self.vtable)).getClassInfo2)(self, index, class_info)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IPluginFactory3VTable {
+    pub base: IPluginFactory2VTable,
+
+    pub getClassInfoUnicode: unsafe extern "system" fn(this: *mut IPluginFactory3, index: i32, info: *mut PClassInfoW) -> i32,
+    pub setHostContext: unsafe extern "system" fn(this: *mut IPluginFactory3, context: *mut FUnknown) -> i32,
+}
+
+#[repr(C)]
+struct IPluginFactory3 {
+    vtable: *const IPluginFactory3VTable
+}
+
+impl IPluginFactory3 {
+    pub fn iid() -> TUID {
+        inline_uid(0x4555A2AB, 0xC1234E57, 0x9B122910, 0x36878931)
+    }
+
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).base.base.release)(self as *mut Self as *mut IPluginFactory)
+    }
+
+    unsafe fn get_class_info_unicode(&mut self, index: i32, class_info: *mut PClassInfoW) -> i32 {
+        ((*(self.vtable)).getClassInfoUnicode)(self, index, class_info)
+    }
+}
+
 
 #[allow(non_snake_case)]
 #[repr(C)]
 struct IPluginBaseVTable {
-    pub queryInterface: unsafe extern "thiscall" fn(this: *mut IPluginBase, _iid: [c_char; 16], obj: *mut c_void) -> i32,
-    pub addRef: unsafe extern "thiscall" fn(this: *mut IPluginBase) -> u32,
-    pub release: unsafe extern "thiscall" fn(this: *mut IPluginBase) -> u32,
+    pub queryInterface: unsafe extern "system" fn(this: *mut IPluginBase, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IPluginBase) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IPluginBase) -> u32,
 
-    pub initialize: unsafe extern "thiscall" fn(this: *mut IPluginBase, context: *mut IHostApplication) -> i32,
-    pub terminate: unsafe extern "thiscall" fn(this: *mut IPluginBase) -> i32,
+    pub initialize: unsafe extern "system" fn(this: *mut IPluginBase, context: *mut IHostApplication) -> i32,
+    pub terminate: unsafe extern "system" fn(this: *mut IPluginBase) -> i32,
 }
 
 #[repr(C)]
@@ -187,6 +297,10 @@ struct IPluginBase {
 }
 
 impl IPluginBase {
+    unsafe fn query_interface(&mut self, iid: &TUID, obj: *mut *mut c_void) -> i32 {
+        ((*(self.vtable)).queryInterface)(self, iid, obj)
+    }
+
     unsafe fn add_ref(&mut self) -> u32 {
         ((*(self.vtable)).addRef)(self)
     }
@@ -204,150 +318,782 @@ impl IPluginBase {
     }
 }
 
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IBStreamVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IBStream, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IBStream) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IBStream) -> u32,
+
+    pub read: unsafe extern "system" fn(this: *mut IBStream, buffer: *mut c_void, num_bytes: i32, num_bytes_read: *mut i32) -> i32,
+    pub write: unsafe extern "system" fn(this: *mut IBStream, buffer: *mut c_void, num_bytes: i32, num_bytes_written: *mut i32) -> i32,
+    pub seek: unsafe extern "system" fn(this: *mut IBStream, pos: i64, mode: i32, result: *mut i64) -> i32,
+    pub tell: unsafe extern "system" fn(this: *mut IBStream, pos: *mut i64) -> i32,
+}
+
+#[repr(C)]
+struct IBStream {
+    vtable: *const IBStreamVTable
+}
+
+impl IBStream {
+    pub fn iid() -> TUID {
+        inline_uid(0xC3BF6EA2, 0x30994752, 0x9B6BF990, 0x1EE33E9B)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct BusInfo {
+    pub media_type: i32,
+    pub direction: i32,
+    pub channel_count: i32,
+    pub name: [u16; 128],
+    pub bus_type: i32,
+    pub flags: u32,
+}
+
+impl Default for BusInfo {
+    fn default() -> Self {
+        Self {
+            media_type: 0,
+            direction: 0,
+            channel_count: 0,
+            name: [0; 128],
+            bus_type: 0,
+            flags: 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RoutingInfo {
+    pub media_type: i32,
+    pub bus_index: i32,
+    pub channel: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ParameterInfo {
+    pub id: u32,
+    pub title: [u16; 128],
+    pub short_title: [u16; 128],
+    pub units: [u16; 128],
+    pub step_count: i32,
+    pub default_normalized_value: f64,
+    pub unit_id: i32,
+    pub flags: i32,
+}
+
+impl Default for ParameterInfo {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            title: [0; 128],
+            short_title: [0; 128],
+            units: [0; 128],
+            step_count: 0,
+            default_normalized_value: 0.0,
+            unit_id: 0,
+            flags: 0,
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IComponentVTable {
+    pub base: IPluginBaseVTable,
+
+    pub getControllerClassId: unsafe extern "system" fn(this: *mut IComponent, class_id: *mut TUID) -> i32,
+    pub setIoMode: unsafe extern "system" fn(this: *mut IComponent, mode: i32) -> i32,
+    pub getBusCount: unsafe extern "system" fn(this: *mut IComponent, media_type: i32, direction: i32) -> i32,
+    pub getBusInfo: unsafe extern "system" fn(this: *mut IComponent, media_type: i32, direction: i32, index: i32, info: *mut BusInfo) -> i32,
+    pub getRoutingInfo: unsafe extern "system" fn(this: *mut IComponent, in_info: *mut RoutingInfo, out_info: *mut RoutingInfo) -> i32,
+    pub activateBus: unsafe extern "system" fn(this: *mut IComponent, media_type: i32, direction: i32, index: i32, state: u8) -> i32,
+    pub setActive: unsafe extern "system" fn(this: *mut IComponent, state: u8) -> i32,
+    pub setState: unsafe extern "system" fn(this: *mut IComponent, state: *mut IBStream) -> i32,
+    pub getState: unsafe extern "system" fn(this: *mut IComponent, state: *mut IBStream) -> i32,
+}
+
+#[repr(C)]
+struct IComponent {
+    vtable: *const IComponentVTable
+}
+
+impl IComponent {
+    pub fn iid() -> TUID {
+        inline_uid(0xE831FF31, 0xF2D54301, 0x928EBBEE, 0x25697802)
+    }
+
+    fn base(&mut self) -> &mut IPluginBase {
+        unsafe { &mut *(self as *mut Self as *mut IPluginBase) }
+    }
+
+    unsafe fn get_controller_class_id(&mut self, class_id: *mut TUID) -> i32 {
+        ((*(self.vtable)).getControllerClassId)(self, class_id)
+    }
+
+    unsafe fn get_bus_count(&mut self, media_type: i32, direction: i32) -> i32 {
+        ((*(self.vtable)).getBusCount)(self, media_type, direction)
+    }
+
+    unsafe fn get_bus_info(&mut self, media_type: i32, direction: i32, index: i32, info: *mut BusInfo) -> i32 {
+        ((*(self.vtable)).getBusInfo)(self, media_type, direction, index, info)
+    }
+
+    unsafe fn activate_bus(&mut self, media_type: i32, direction: i32, index: i32, state: bool) -> i32 {
+        ((*(self.vtable)).activateBus)(self, media_type, direction, index, state as u8)
+    }
+
+    unsafe fn set_active(&mut self, state: bool) -> i32 {
+        ((*(self.vtable)).setActive)(self, state as u8)
+    }
+
+    unsafe fn set_state(&mut self, state: *mut IBStream) -> i32 {
+        ((*(self.vtable)).setState)(self, state)
+    }
+
+    unsafe fn get_state(&mut self, state: *mut IBStream) -> i32 {
+        ((*(self.vtable)).getState)(self, state)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IEditControllerVTable {
+    pub base: IPluginBaseVTable,
+
+    pub setComponentState: unsafe extern "system" fn(this: *mut IEditController, state: *mut IBStream) -> i32,
+    pub setState: unsafe extern "system" fn(this: *mut IEditController, state: *mut IBStream) -> i32,
+    pub getState: unsafe extern "system" fn(this: *mut IEditController, state: *mut IBStream) -> i32,
+    pub getParameterCount: unsafe extern "system" fn(this: *mut IEditController) -> i32,
+    pub getParameterInfo: unsafe extern "system" fn(this: *mut IEditController, index: i32, info: *mut ParameterInfo) -> i32,
+    pub getParamStringByValue: unsafe extern "system" fn(this: *mut IEditController, id: u32, value: f64, string: *mut [u16; 128]) -> i32,
+    pub getParamValueByString: unsafe extern "system" fn(this: *mut IEditController, id: u32, string: *const u16, value: *mut f64) -> i32,
+    pub normalizedParamToPlain: unsafe extern "system" fn(this: *mut IEditController, id: u32, value: f64) -> f64,
+    pub plainParamToNormalized: unsafe extern "system" fn(this: *mut IEditController, id: u32, value: f64) -> f64,
+    pub getParamNormalized: unsafe extern "system" fn(this: *mut IEditController, id: u32) -> f64,
+    pub setParamNormalized: unsafe extern "system" fn(this: *mut IEditController, id: u32, value: f64) -> i32,
+    pub setComponentHandler: unsafe extern "system" fn(this: *mut IEditController, handler: *mut IComponentHandler) -> i32,
+    pub createView: unsafe extern "system" fn(this: *mut IEditController, name: FIDString) -> *mut IPlugView,
+}
+
+#[repr(C)]
+struct IEditController {
+    vtable: *const IEditControllerVTable
+}
+
+impl IEditController {
+    pub fn iid() -> TUID {
+        inline_uid(0xDCD7BBE3, 0x7742448D, 0xA874AACC, 0x979C759E)
+    }
+
+    fn base(&mut self) -> &mut IPluginBase {
+        unsafe { &mut *(self as *mut Self as *mut IPluginBase) }
+    }
+
+    unsafe fn set_component_state(&mut self, state: *mut IBStream) -> i32 {
+        ((*(self.vtable)).setComponentState)(self, state)
+    }
+
+    unsafe fn set_state(&mut self, state: *mut IBStream) -> i32 {
+        ((*(self.vtable)).setState)(self, state)
+    }
+
+    unsafe fn get_state(&mut self, state: *mut IBStream) -> i32 {
+        ((*(self.vtable)).getState)(self, state)
+    }
+
+    unsafe fn get_param_normalized(&mut self, id: u32) -> f64 {
+        ((*(self.vtable)).getParamNormalized)(self, id)
+    }
+
+    unsafe fn set_param_normalized(&mut self, id: u32, value: f64) -> i32 {
+        ((*(self.vtable)).setParamNormalized)(self, id, value)
+    }
+
+    unsafe fn set_component_handler(&mut self, handler: *mut IComponentHandler) -> i32 {
+        ((*(self.vtable)).setComponentHandler)(self, handler)
+    }
+
+    unsafe fn create_view(&mut self, name: FIDString) -> *mut IPlugView {
+        ((*(self.vtable)).createView)(self, name)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IComponentHandlerVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IComponentHandler, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IComponentHandler) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IComponentHandler) -> u32,
+
+    pub beginEdit: unsafe extern "system" fn(this: *mut IComponentHandler, id: u32) -> i32,
+    pub performEdit: unsafe extern "system" fn(this: *mut IComponentHandler, id: u32, value: f64) -> i32,
+    pub endEdit: unsafe extern "system" fn(this: *mut IComponentHandler, id: u32) -> i32,
+    pub restartComponent: unsafe extern "system" fn(this: *mut IComponentHandler, flags: i32) -> i32,
+}
+
+/// Implemented by the host, the edit controller reports the edits made in its editor through it
+#[repr(C)]
+struct IComponentHandler {
+    vtable: *const IComponentHandlerVTable
+}
+
+impl IComponentHandler {
+    pub fn iid() -> TUID {
+        inline_uid(0x93A0BEA3, 0x0BD045DB, 0x8E890B0C, 0xC1E46AC6)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IConnectionPointVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IConnectionPoint, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IConnectionPoint) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IConnectionPoint) -> u32,
+
+    pub connect: unsafe extern "system" fn(this: *mut IConnectionPoint, other: *mut IConnectionPoint) -> i32,
+    pub disconnect: unsafe extern "system" fn(this: *mut IConnectionPoint, other: *mut IConnectionPoint) -> i32,
+    pub notify: unsafe extern "system" fn(this: *mut IConnectionPoint, message: *mut IMessage) -> i32,
+}
+
+/// Lets the component and the edit controller of a plugin send each other messages
+#[repr(C)]
+struct IConnectionPoint {
+    vtable: *const IConnectionPointVTable
+}
+
+impl IConnectionPoint {
+    pub fn iid() -> TUID {
+        inline_uid(0x70A4156F, 0x6E6E4026, 0x989148BF, 0xAA60D8D1)
+    }
+
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).release)(self)
+    }
+
+    unsafe fn connect(&mut self, other: *mut IConnectionPoint) -> i32 {
+        ((*(self.vtable)).connect)(self, other)
+    }
+
+    unsafe fn disconnect(&mut self, other: *mut IConnectionPoint) -> i32 {
+        ((*(self.vtable)).disconnect)(self, other)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IMessageVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IMessage, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IMessage) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IMessage) -> u32,
+
+    pub getMessageID: unsafe extern "system" fn(this: *mut IMessage) -> FIDString,
+    pub setMessageID: unsafe extern "system" fn(this: *mut IMessage, id: FIDString),
+    pub getAttributes: unsafe extern "system" fn(this: *mut IMessage) -> *mut IAttributeList,
+}
+
+/// A message sent through an [`IConnectionPoint`], created by the plugin through [`IHostApplication`]
+#[repr(C)]
+struct IMessage {
+    vtable: *const IMessageVTable
+}
+
+impl IMessage {
+    pub fn iid() -> TUID {
+        inline_uid(0x936F033B, 0xC6C047DB, 0xBB0882F8, 0x13C1E613)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IAttributeListVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IAttributeList, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IAttributeList) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IAttributeList) -> u32,
+
+    pub setInt: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, value: i64) -> i32,
+    pub getInt: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, value: *mut i64) -> i32,
+    pub setFloat: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, value: f64) -> i32,
+    pub getFloat: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, value: *mut f64) -> i32,
+    pub setString: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, string: *const u16) -> i32,
+    pub getString: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, string: *mut u16, size: u32) -> i32,
+    pub setBinary: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, data: *const c_void, size: u32) -> i32,
+    pub getBinary: unsafe extern "system" fn(this: *mut IAttributeList, id: FIDString, data: *mut *const c_void, size: *mut u32) -> i32,
+}
+
+/// Values of an [`IMessage`] by name
+#[repr(C)]
+struct IAttributeList {
+    vtable: *const IAttributeListVTable
+}
+
+impl IAttributeList {
+    pub fn iid() -> TUID {
+        inline_uid(0x1E5F0AEB, 0xCC7F4533, 0xA2544011, 0x38AD5EE4)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ViewRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl ViewRect {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IPlugViewVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IPlugView, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IPlugView) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IPlugView) -> u32,
+
+    pub isPlatformTypeSupported: unsafe extern "system" fn(this: *mut IPlugView, platform_type: FIDString) -> i32,
+    pub attached: unsafe extern "system" fn(this: *mut IPlugView, parent: *mut c_void, platform_type: FIDString) -> i32,
+    pub removed: unsafe extern "system" fn(this: *mut IPlugView) -> i32,
+    pub onWheel: unsafe extern "system" fn(this: *mut IPlugView, distance: f32) -> i32,
+    pub onKeyDown: unsafe extern "system" fn(this: *mut IPlugView, key: u16, key_code: i16, modifiers: i16) -> i32,
+    pub onKeyUp: unsafe extern "system" fn(this: *mut IPlugView, key: u16, key_code: i16, modifiers: i16) -> i32,
+    pub getSize: unsafe extern "system" fn(this: *mut IPlugView, size: *mut ViewRect) -> i32,
+    pub onSize: unsafe extern "system" fn(this: *mut IPlugView, new_size: *mut ViewRect) -> i32,
+    pub onFocus: unsafe extern "system" fn(this: *mut IPlugView, state: u8) -> i32,
+    pub setFrame: unsafe extern "system" fn(this: *mut IPlugView, frame: *mut IPlugFrame) -> i32,
+    pub canResize: unsafe extern "system" fn(this: *mut IPlugView) -> i32,
+    pub checkSizeConstraint: unsafe extern "system" fn(this: *mut IPlugView, rect: *mut ViewRect) -> i32,
+}
+
+#[repr(C)]
+struct IPlugView {
+    vtable: *const IPlugViewVTable
+}
+
+impl IPlugView {
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).release)(self)
+    }
+
+    unsafe fn is_platform_type_supported(&mut self, platform_type: FIDString) -> i32 {
+        ((*(self.vtable)).isPlatformTypeSupported)(self, platform_type)
+    }
+
+    unsafe fn attached(&mut self, parent: *mut c_void, platform_type: FIDString) -> i32 {
+        ((*(self.vtable)).attached)(self, parent, platform_type)
+    }
+
+    unsafe fn removed(&mut self) -> i32 {
+        ((*(self.vtable)).removed)(self)
+    }
+
+    unsafe fn get_size(&mut self, size: *mut ViewRect) -> i32 {
+        ((*(self.vtable)).getSize)(self, size)
+    }
+
+    unsafe fn on_size(&mut self, new_size: *mut ViewRect) -> i32 {
+        ((*(self.vtable)).onSize)(self, new_size)
+    }
+
+    unsafe fn on_focus(&mut self, state: bool) -> i32 {
+        ((*(self.vtable)).onFocus)(self, state as u8)
+    }
+
+    unsafe fn set_frame(&mut self, frame: *mut IPlugFrame) -> i32 {
+        ((*(self.vtable)).setFrame)(self, frame)
+    }
+
+    unsafe fn can_resize(&mut self) -> i32 {
+        ((*(self.vtable)).canResize)(self)
+    }
+
+    unsafe fn check_size_constraint(&mut self, rect: *mut ViewRect) -> i32 {
+        ((*(self.vtable)).checkSizeConstraint)(self, rect)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IPlugFrameVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IPlugFrame, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IPlugFrame) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IPlugFrame) -> u32,
+
+    pub resizeView: unsafe extern "system" fn(this: *mut IPlugFrame, view: *mut IPlugView, new_size: *mut ViewRect) -> i32,
+}
+
+#[repr(C)]
+struct IPlugFrame {
+    vtable: *const IPlugFrameVTable
+}
+
+impl IPlugFrame {
+    pub fn iid() -> TUID {
+        inline_uid(0x367FAF01, 0xAFA94693, 0x8D4DA2A0, 0xED0882A3)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IRunLoopVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IRunLoop, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IRunLoop) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IRunLoop) -> u32,
+
+    pub registerEventHandler: unsafe extern "system" fn(this: *mut IRunLoop, handler: *mut IEventHandler, fd: i32) -> i32,
+    pub unregisterEventHandler: unsafe extern "system" fn(this: *mut IRunLoop, handler: *mut IEventHandler) -> i32,
+    pub registerTimer: unsafe extern "system" fn(this: *mut IRunLoop, handler: *mut ITimerHandler, milliseconds: u64) -> i32,
+    pub unregisterTimer: unsafe extern "system" fn(this: *mut IRunLoop, handler: *mut ITimerHandler) -> i32,
+}
+
+/// Lets plugins on Linux hook file descriptors and timers into the host's UI thread
+#[repr(C)]
+struct IRunLoop {
+    vtable: *const IRunLoopVTable
+}
+
+impl IRunLoop {
+    pub fn iid() -> TUID {
+        inline_uid(0x18C35366, 0x97764F1A, 0x9C5B8385, 0x7A871389)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IEventHandlerVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IEventHandler, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IEventHandler) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IEventHandler) -> u32,
+
+    pub onFDIsSet: unsafe extern "system" fn(this: *mut IEventHandler, fd: i32),
+}
+
+#[repr(C)]
+struct IEventHandler {
+    vtable: *const IEventHandlerVTable
+}
+
+impl IEventHandler {
+    unsafe fn add_ref(&mut self) -> u32 {
+        ((*(self.vtable)).addRef)(self)
+    }
+
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).release)(self)
+    }
+
+    unsafe fn on_fd_is_set(&mut self, fd: i32) {
+        ((*(self.vtable)).onFDIsSet)(self, fd)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct ITimerHandlerVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut ITimerHandler, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut ITimerHandler) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut ITimerHandler) -> u32,
+
+    pub onTimer: unsafe extern "system" fn(this: *mut ITimerHandler),
+}
+
+#[repr(C)]
+struct ITimerHandler {
+    vtable: *const ITimerHandlerVTable
+}
+
+impl ITimerHandler {
+    unsafe fn add_ref(&mut self) -> u32 {
+        ((*(self.vtable)).addRef)(self)
+    }
+
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).release)(self)
+    }
+
+    unsafe fn on_timer(&mut self) {
+        ((*(self.vtable)).onTimer)(self)
+    }
+}
+
+pub const K_AUDIO: i32 = 0;
+pub const K_EVENT: i32 = 1;
+
+pub const K_INPUT: i32 = 0;
+pub const K_OUTPUT: i32 = 1;
+
+pub const K_REALTIME: i32 = 0;
+pub const K_SAMPLE_32: i32 = 0;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProcessSetup {
+    pub process_mode: i32,
+    pub symbolic_sample_size: i32,
+    pub max_samples_per_block: i32,
+    pub sample_rate: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioBusBuffers {
+    pub num_channels: i32,
+    pub silence_flags: u64,
+    pub channel_buffers_32: *mut *mut f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ProcessData {
+    pub process_mode: i32,
+    pub symbolic_sample_size: i32,
+    pub num_samples: i32,
+    pub num_inputs: i32,
+    pub num_outputs: i32,
+    pub inputs: *mut AudioBusBuffers,
+    pub outputs: *mut AudioBusBuffers,
+    pub input_parameter_changes: *mut IParameterChanges,
+    pub output_parameter_changes: *mut IParameterChanges,
+    pub input_events: *mut IEventList,
+    pub output_events: *mut IEventList,
+    pub process_context: *mut c_void,
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IAudioProcessorVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IAudioProcessor, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IAudioProcessor) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IAudioProcessor) -> u32,
+
+    pub setBusArrangements: unsafe extern "system" fn(this: *mut IAudioProcessor, inputs: *mut u64, num_ins: i32, outputs: *mut u64, num_outs: i32) -> i32,
+    pub getBusArrangement: unsafe extern "system" fn(this: *mut IAudioProcessor, dir: i32, index: i32, arr: *mut u64) -> i32,
+    pub canProcessSampleSize: unsafe extern "system" fn(this: *mut IAudioProcessor, symbolic_sample_size: i32) -> i32,
+    pub getLatencySamples: unsafe extern "system" fn(this: *mut IAudioProcessor) -> u32,
+    pub setupProcessing: unsafe extern "system" fn(this: *mut IAudioProcessor, setup: *mut ProcessSetup) -> i32,
+    pub setProcessing: unsafe extern "system" fn(this: *mut IAudioProcessor, state: u8) -> i32,
+    pub process: unsafe extern "system" fn(this: *mut IAudioProcessor, data: *mut ProcessData) -> i32,
+    pub getTailSamples: unsafe extern "system" fn(this: *mut IAudioProcessor) -> u32,
+}
+
+#[repr(C)]
+struct IAudioProcessor {
+    vtable: *const IAudioProcessorVTable
+}
+
+impl IAudioProcessor {
+    pub fn iid() -> TUID {
+        inline_uid(0x42043F99, 0xB7DA453C, 0xA569E79D, 0x9AAEC33D)
+    }
+
+    unsafe fn release(&mut self) -> u32 {
+        ((*(self.vtable)).release)(self)
+    }
+
+    unsafe fn setup_processing(&mut self, setup: *mut ProcessSetup) -> i32 {
+        ((*(self.vtable)).setupProcessing)(self, setup)
+    }
+
+    unsafe fn set_processing(&mut self, state: bool) -> i32 {
+        ((*(self.vtable)).setProcessing)(self, state as u8)
+    }
+
+    unsafe fn process(&mut self, data: *mut ProcessData) -> i32 {
+        ((*(self.vtable)).process)(self, data)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IParamValueQueueVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IParamValueQueue, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IParamValueQueue) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IParamValueQueue) -> u32,
+
+    pub getParameterId: unsafe extern "system" fn(this: *mut IParamValueQueue) -> u32,
+    pub getPointCount: unsafe extern "system" fn(this: *mut IParamValueQueue) -> i32,
+    pub getPoint: unsafe extern "system" fn(this: *mut IParamValueQueue, index: i32, sample_offset: *mut i32, value: *mut f64) -> i32,
+    pub addPoint: unsafe extern "system" fn(this: *mut IParamValueQueue, sample_offset: i32, value: f64, index: *mut i32) -> i32,
+}
+
+#[repr(C)]
+struct IParamValueQueue {
+    vtable: *const IParamValueQueueVTable
+}
+
+impl IParamValueQueue {
+    pub fn iid() -> TUID {
+        inline_uid(0x01263A18, 0xED074F6F, 0x98C9D356, 0x4686F9BA)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IParameterChangesVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IParameterChanges, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IParameterChanges) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IParameterChanges) -> u32,
+
+    pub getParameterCount: unsafe extern "system" fn(this: *mut IParameterChanges) -> i32,
+    pub getParameterData: unsafe extern "system" fn(this: *mut IParameterChanges, index: i32) -> *mut IParamValueQueue,
+    pub addParameterData: unsafe extern "system" fn(this: *mut IParameterChanges, id: *const u32, index: *mut i32) -> *mut IParamValueQueue,
+}
+
+#[repr(C)]
+struct IParameterChanges {
+    vtable: *const IParameterChangesVTable
+}
+
+impl IParameterChanges {
+    pub fn iid() -> TUID {
+        inline_uid(0xA4779663, 0x0BB64A56, 0xB44384A8, 0x466FEB9D)
+    }
+}
+
+#[allow(non_snake_case)]
+#[repr(C)]
+struct IEventListVTable {
+    pub queryInterface: unsafe extern "system" fn(this: *mut IEventList, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IEventList) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IEventList) -> u32,
+
+    pub getEventCount: unsafe extern "system" fn(this: *mut IEventList) -> i32,
+    pub getEvent: unsafe extern "system" fn(this: *mut IEventList, index: i32, event: *mut Event) -> i32,
+    pub addEvent: unsafe extern "system" fn(this: *mut IEventList, event: *mut Event) -> i32,
+}
+
+#[repr(C)]
+struct IEventList {
+    vtable: *const IEventListVTable
+}
+
+impl IEventList {
+    pub fn iid() -> TUID {
+        inline_uid(0x3A2C4214, 0x346349FE, 0xB2C4F397, 0xB9695A44)
+    }
+}
+
 #[allow(non_snake_case)]
 #[repr(C)]
 struct IHostApplicationVTable {
-    pub queryInterface: fn(this: *mut IHostApplication, _iid: [c_char; 16], obj: *mut c_void) -> i32,
-    pub addRef: fn(this: *mut IHostApplication) -> u32,
-    pub release: fn(this: *mut IHostApplication) -> u32,
+    pub queryInterface: unsafe extern "system" fn(this: *mut IHostApplication, _iid: *const TUID, obj: *mut *mut c_void) -> i32,
+    pub addRef: unsafe extern "system" fn(this: *mut IHostApplication) -> u32,
+    pub release: unsafe extern "system" fn(this: *mut IHostApplication) -> u32,
 
-    pub getName: fn(this: *mut IHostApplication, name: &mut [u16; 128]) -> i32,
-    pub createInstance: fn(this: *mut IHostApplication, cid: [c_char; 16], iid: [c_char; 16], obj: *mut *mut c_void) -> i32,
+    pub getName: unsafe extern "system" fn(this: *mut IHostApplication, name: *mut [u16; 128]) -> i32,
+    pub createInstance: unsafe extern "system" fn(this: *mut IHostApplication, cid: *const TUID, iid: *const TUID, obj: *mut *mut c_void) -> i32,
 }
 
+/// The host context passed to [`IPluginBase::initialize`]. It lives as long as the plugins it is given to,
+/// so reference counting is a no-op.
 #[repr(C)]
 struct IHostApplication {
     vtable: &'static IHostApplicationVTable
 }
 
 impl IHostApplication {
+    pub fn iid() -> TUID {
+        inline_uid(0x58E595CC, 0xDB2D4969, 0x8B6AAF8C, 0x36A664E5)
+    }
+
     pub fn new() -> Self {
         Self {
             vtable: &IHostApplicationVTable {
-                queryInterface: |this, iid, obj| {
-                    -1
-                },
+                queryInterface: Self::query_interface,
+                addRef: Self::add_ref,
+                release: Self::release,
+                getName: Self::get_name,
+                createInstance: Self::create_instance,
+            }
+        }
+    }
 
-                addRef: |this| {
-                    1000
-                },
+    unsafe extern "system" fn query_interface(this: *mut IHostApplication, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == Self::iid() {
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
 
-                release: |this| {
-                    1000
-                },
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
 
-                getName: |this, name| {
-                    name[0] = u16::try_from('N').unwrap();
-                    name[1] = 0;
+    unsafe extern "system" fn add_ref(_this: *mut IHostApplication) -> u32 {
+        1
+    }
 
-                    0
-                },
+    unsafe extern "system" fn release(_this: *mut IHostApplication) -> u32 {
+        1
+    }
 
-                createInstance: |this, cid, iid, obj| {
-                    0
-                }
-            }
+    unsafe extern "system" fn get_name(_this: *mut IHostApplication, name: *mut [u16; 128]) -> i32 {
+        let name = &mut *name;
+
+        for (i, c) in "Voxea".encode_utf16().enumerate() {
+            name[i] = c;
         }
+        name["Voxea".len()] = 0;
+
+        K_RESULT_OK
     }
-}
 
-type InitDllProc = fn() -> bool;
-type ExitDllProc = fn() -> bool;
-type GetPluginFactoryProc = fn() -> *mut IPluginFactory;
-
-fn inline_uid(l1: u32, l2: u32, l3: u32, l4: u32) -> [c_char; 16] {
-    // [
-    //     ((l1 & 0x000000FF)) as c_char,
-    //     ((l1 & 0x0000FF00) >> 8) as c_char,
-    //     ((l1 & 0x00FF0000) >> 16) as c_char,
-    //     ((l1 & 0xFF000000) >> 24) as c_char,
-    //
-    //     ((l2 & 0x00FF0000) >> 16) as c_char,
-    //     ((l2 & 0xFF000000) >> 24) as c_char,
-    //     ((l2 & 0x000000FF)) as c_char,
-    //     ((l2 & 0x0000FF00) >> 8) as c_char,
-    //
-    //     ((l3 & 0xFF000000) >> 24) as c_char,
-    //     ((l3 & 0x00FF0000) >> 16) as c_char,
-    //     ((l3 & 0x0000FF00) >> 8) as c_char,
-    //     ((l3 & 0x000000FF)) as c_char,
-    //
-    //     ((l4 & 0xFF000000) >> 24) as c_char,
-    //     ((l4 & 0x00FF0000) >> 16) as c_char,
-    //     ((l4 & 0x0000FF00) >> 8) as c_char,
-    //     ((l4 & 0x000000FF)) as c_char,
-    // ]
-
-    [
-        ((l1 & 0xFF000000) >> 24) as c_char,
-        ((l1 & 0x00FF0000) >> 16) as c_char,
-        ((l1 & 0x0000FF00) >>  8) as c_char,
-        ((l1 & 0x000000FF)      ) as c_char,
-
-        ((l2 & 0xFF000000) >> 24) as c_char,
-        ((l2 & 0x00FF0000) >> 16) as c_char,
-        ((l2 & 0x0000FF00) >>  8) as c_char,
-        ((l2 & 0x000000FF)      ) as c_char,
-
-        ((l3 & 0xFF000000) >> 24) as c_char,
-        ((l3 & 0x00FF0000) >> 16) as c_char,
-        ((l3 & 0x0000FF00) >>  8) as c_char,
-        ((l3 & 0x000000FF)      ) as c_char,
-
-        ((l4 & 0xFF000000) >> 24) as c_char,
-        ((l4 & 0x00FF0000) >> 16) as c_char,
-        ((l4 & 0x0000FF00) >>  8) as c_char,
-        ((l4 & 0x000000FF)      ) as c_char,
-    ]
-}
-
-fn uid_to_ascii(uid: [c_char; 16]) -> [u8; 37] {
-    // Step 1: Convert [u8; 16] to a hex string (32 characters long)
-    let hex_string = uid.iter()
-        .map(|byte| format!("{:02X}", byte))  // Format each byte as 2 hex digits
-        .collect::<String>();
-
-    let formatted_uid = format!(
-        "{}{}{}{}{}{}{}{}{}",
-        &hex_string[0..8],
-        "-",
-        &hex_string[8..12],
-        "-",
-        &hex_string[12..16],
-        "-",
-        &hex_string[16..20],
-        "-",
-        &hex_string[20..32]
-    );
-
-    // Step 2: Convert the hex string into [u8; 32] of ASCII values
-    let mut ascii_array = [0u8; 37];
-    for (i, c) in formatted_uid.chars().enumerate() {
-        ascii_array[i] = c as u8;  // Convert each char to its ASCII value
-    }
-
-    ascii_array[36] = 0;
-
-    ascii_array
-}
-pub fn load_vst() {
-    unsafe {
-        let lib = Library::new("../../../vst3/Archetype Nolly.vst3").unwrap();
-        let init: Symbol<InitDllProc> = lib.get(b"InitDll").unwrap();
-        init.call(());
+    /// Plugins create the messages the component and the controller send each other through this
+    unsafe extern "system" fn create_instance(_this: *mut IHostApplication, cid: *const TUID, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if cid.is_null() || iid.is_null() || obj.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
 
-        let raw_factory: Symbol<GetPluginFactoryProc> = lib.get::<GetPluginFactoryProc>(b"GetPluginFactory").unwrap();
-        let raw_factory: *mut IPluginFactory = raw_factory.call(());
-        let factory = &mut *raw_factory;
+        if *cid == IMessage::iid() && *iid == IMessage::iid() {
+            *obj = HostMessage::create() as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        if *cid == IAttributeList::iid() && *iid == IAttributeList::iid() {
+            *obj = AttributeList::create() as *mut c_void;
+            return K_RESULT_OK;
+        }
 
-        factory.add_ref();
+        *obj = std::ptr::null_mut();
+        K_RESULT_FALSE
+    }
+}
 
-        println!("{}", factory.count_classes());
+/// Byte layout of an id for the current platform, see [`FUID`]
+const fn inline_uid(l1: u32, l2: u32, l3: u32, l4: u32) -> TUID {
+    FUID::new(l1, l2, l3, l4).to_tuid()
+}
 
-        let mut factory_info = PFactoryInfo::default();
-        factory.get_factory_info(&mut factory_info);
+pub fn load_vst(path: impl AsRef<Path>) -> Result<()> {
+    let module = Module::load(path)?;
 
-        let mut host = IHostApplication::new();
+    println!("{:?}", module.factory_info());
+
+    let mut host = IHostApplication::new();
+
+    unsafe {
+        let factory = &mut *module.factory;
 
         for i in 0..factory.count_classes() {
             let mut class_info = PClassInfo::default();
@@ -357,32 +1103,23 @@ pub fn load_vst() {
 
             let fid1 = class_info.cid;
             let fid2 = FUnknown::iid();
-            let fid3 = inline_uid(0xE831FF31, 0xF2D54301, 0x928EBBEE, 0x25697802);
 
-            println!("{:?}", fid1);
-            println!("{:?}", fid2);
-            println!("{:?}", fid3);
+            let res = factory.create_instance(fid1.as_ptr(), fid2.as_ptr(), &mut object);
 
-            let res = factory.create_instance(fid1, fid2, &mut object);
+            if object.is_null() {
+                continue;
+            }
 
             let object = &mut *(object as *mut IPluginBase);
 
             let initres = object.initialize(&mut host);
 
             object.terminate();
+            object.release();
 
-            println!("{:?} {:?} {} {:p} {} {}", class_info.cid, FUnknown::iid(), class_info, object, res, initres);
+            println!("{:?} {:p} {} {}", module.class_info(i), object, res, initres);
         }
-
-
-
-        factory.release();
-
-        println!("{:?}, {}", raw_factory, factory_info);
-
-        let exit: Symbol<ExitDllProc> = lib.get(b"ExitDll").unwrap();
-        exit.call(());
-
-        lib.close().unwrap();
     }
-}
\ No newline at end of file
+
+    Ok(())
+}
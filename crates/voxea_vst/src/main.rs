@@ -0,0 +1,53 @@
+//! Helper process for loading untrusted VST3 modules outside of the main application.
+//! A crashing plugin only takes this process down.
+
+use std::path::Path;
+use std::process::ExitCode;
+#[cfg(unix)]
+use voxea_vst::bridge;
+use voxea_vst::scanner::{self, SCAN_RESULT_PREFIX};
+
+fn main() -> ExitCode {
+    // Stdout carries the scan result, the host shows stderr in its own log
+    tracing_subscriber::fmt().with_writer(std::io::stderr).with_ansi(false).init();
+
+    let args = std::env::args().collect::<Vec<String>>();
+
+    match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("scan"), Some(path)) => match scanner::scan_in_process(Path::new(path)) {
+            Ok(info) => {
+                println!("{SCAN_RESULT_PREFIX}{}", serde_json::to_string(&info).unwrap());
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Could not scan {path}: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        #[cfg(unix)]
+        (Some("host"), Some(path)) => match args.get(3..7) {
+            Some([cid, memory, max_block, socket]) => {
+                let result = max_block
+                    .parse()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|max_block| bridge::run_helper(Path::new(path), cid, memory, max_block, Path::new(socket)));
+
+                match result {
+                    Ok(()) => ExitCode::SUCCESS,
+                    Err(e) => {
+                        eprintln!("Could not host {path}: {e}");
+                        ExitCode::FAILURE
+                    }
+                }
+            }
+            _ => usage(),
+        },
+        _ => usage(),
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("Usage: voxea_vst scan <path to .vst3>");
+    eprintln!("       voxea_vst host <path to .vst3> <class id> <shared memory> <max block size> <socket>");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,381 @@
+//! The `IMessage` and `IAttributeList` a plugin creates through the host to send messages from its component to
+//! its edit controller and back. Unlike the other host objects they are owned by the plugin, so they are reference
+//! counted and freed by their last release.
+
+use crate::{
+    FUnknown, IAttributeList, IAttributeListVTable, IMessage, IMessageVTable, K_INVALID_ARGUMENT, K_NO_INTERFACE,
+    K_RESULT_FALSE, K_RESULT_OK, TUID,
+};
+use libc::c_char;
+use std::collections::HashMap;
+use std::ffi::{c_void, CStr, CString};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+/// Value of an attribute, see [`AttributeList`]
+#[derive(Debug, Clone, PartialEq)]
+enum Attribute {
+    Int(i64),
+    Float(f64),
+    /// UTF-16 without the terminator
+    String(Vec<u16>),
+    Binary(Vec<u8>),
+}
+
+#[repr(C)]
+pub(crate) struct HostMessage {
+    vtable: *const IMessageVTable,
+    ref_count: AtomicU32,
+    id: Mutex<Option<CString>>,
+    attributes: *mut IAttributeList,
+}
+
+impl HostMessage {
+    const VTABLE: IMessageVTable = IMessageVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        getMessageID: Self::get_message_id,
+        setMessageID: Self::set_message_id,
+        getAttributes: Self::get_attributes,
+    };
+
+    /// A new message with a reference count of one, the caller releases it
+    pub(crate) fn create() -> *mut IMessage {
+        let message = Box::new(Self {
+            vtable: &Self::VTABLE,
+            ref_count: AtomicU32::new(1),
+            id: Mutex::new(None),
+            attributes: AttributeList::create(),
+        });
+
+        Box::into_raw(message) as *mut IMessage
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IMessage) -> &'a Self {
+        &*(this as *const Self)
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IMessage, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IMessage::iid() {
+            Self::add_ref(this);
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut IMessage) -> u32 {
+        Self::from_ptr(this).ref_count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    unsafe extern "system" fn release(this: *mut IMessage) -> u32 {
+        let count = Self::from_ptr(this).ref_count.fetch_sub(1, Ordering::AcqRel) - 1;
+
+        if count == 0 {
+            let message = Box::from_raw(this as *mut Self);
+            AttributeList::release(message.attributes);
+        }
+
+        count
+    }
+
+    unsafe extern "system" fn get_message_id(this: *mut IMessage) -> *const c_char {
+        // The string lives as long as the id is not replaced, which is what the SDK promises as well
+        let id = Self::from_ptr(this).id.lock().unwrap();
+        id.as_ref().map_or(std::ptr::null(), |id| id.as_ptr())
+    }
+
+    unsafe extern "system" fn set_message_id(this: *mut IMessage, id: *const c_char) {
+        let id = (!id.is_null()).then(|| CStr::from_ptr(id).to_owned());
+        *Self::from_ptr(this).id.lock().unwrap() = id;
+    }
+
+    /// The list stays owned by the message, so no reference is added
+    unsafe extern "system" fn get_attributes(this: *mut IMessage) -> *mut IAttributeList {
+        Self::from_ptr(this).attributes
+    }
+}
+
+#[repr(C)]
+pub(crate) struct AttributeList {
+    vtable: *const IAttributeListVTable,
+    ref_count: AtomicU32,
+    attributes: Mutex<HashMap<CString, Attribute>>,
+}
+
+impl AttributeList {
+    const VTABLE: IAttributeListVTable = IAttributeListVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        setInt: Self::set_int,
+        getInt: Self::get_int,
+        setFloat: Self::set_float,
+        getFloat: Self::get_float,
+        setString: Self::set_string,
+        getString: Self::get_string,
+        setBinary: Self::set_binary,
+        getBinary: Self::get_binary,
+    };
+
+    /// A new list with a reference count of one, the caller releases it
+    pub(crate) fn create() -> *mut IAttributeList {
+        let list = Box::new(Self {
+            vtable: &Self::VTABLE,
+            ref_count: AtomicU32::new(1),
+            attributes: Mutex::new(HashMap::new()),
+        });
+
+        Box::into_raw(list) as *mut IAttributeList
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IAttributeList) -> &'a Self {
+        &*(this as *const Self)
+    }
+
+    unsafe fn set(this: *mut IAttributeList, id: *const c_char, attribute: Attribute) -> i32 {
+        if id.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let mut attributes = Self::from_ptr(this).attributes.lock().unwrap();
+        attributes.insert(CStr::from_ptr(id).to_owned(), attribute);
+
+        K_RESULT_OK
+    }
+
+    /// Runs `f` on the attribute `id`, [`K_RESULT_FALSE`] if it does not exist or `f` returns false
+    unsafe fn get(this: *mut IAttributeList, id: *const c_char, f: impl FnOnce(&Attribute) -> bool) -> i32 {
+        if id.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let attributes = Self::from_ptr(this).attributes.lock().unwrap();
+        match attributes.get(CStr::from_ptr(id)) {
+            Some(attribute) if f(attribute) => K_RESULT_OK,
+            _ => K_RESULT_FALSE,
+        }
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IAttributeList, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IAttributeList::iid() {
+            Self::add_ref(this);
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut IAttributeList) -> u32 {
+        Self::from_ptr(this).ref_count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    unsafe extern "system" fn release(this: *mut IAttributeList) -> u32 {
+        let count = Self::from_ptr(this).ref_count.fetch_sub(1, Ordering::AcqRel) - 1;
+
+        if count == 0 {
+            drop(Box::from_raw(this as *mut Self));
+        }
+
+        count
+    }
+
+    unsafe extern "system" fn set_int(this: *mut IAttributeList, id: *const c_char, value: i64) -> i32 {
+        Self::set(this, id, Attribute::Int(value))
+    }
+
+    unsafe extern "system" fn get_int(this: *mut IAttributeList, id: *const c_char, value: *mut i64) -> i32 {
+        if value.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        Self::get(this, id, |attribute| match *attribute {
+            Attribute::Int(int) => {
+                *value = int;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    unsafe extern "system" fn set_float(this: *mut IAttributeList, id: *const c_char, value: f64) -> i32 {
+        Self::set(this, id, Attribute::Float(value))
+    }
+
+    unsafe extern "system" fn get_float(this: *mut IAttributeList, id: *const c_char, value: *mut f64) -> i32 {
+        if value.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        Self::get(this, id, |attribute| match *attribute {
+            Attribute::Float(float) => {
+                *value = float;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    unsafe extern "system" fn set_string(this: *mut IAttributeList, id: *const c_char, string: *const u16) -> i32 {
+        if string.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let len = (0..).take_while(|&i| *string.add(i) != 0).count();
+        let string = std::slice::from_raw_parts(string, len).to_vec();
+
+        Self::set(this, id, Attribute::String(string))
+    }
+
+    /// `size` is in bytes, the string is cut to fit and always terminated
+    unsafe extern "system" fn get_string(this: *mut IAttributeList, id: *const c_char, string: *mut u16, size: u32) -> i32 {
+        let capacity = size as usize / size_of::<u16>();
+        if string.is_null() || capacity == 0 {
+            return K_INVALID_ARGUMENT;
+        }
+
+        Self::get(this, id, |attribute| match attribute {
+            Attribute::String(chars) => {
+                let len = chars.len().min(capacity - 1);
+                std::ptr::copy_nonoverlapping(chars.as_ptr(), string, len);
+                *string.add(len) = 0;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    unsafe extern "system" fn set_binary(this: *mut IAttributeList, id: *const c_char, data: *const c_void, size: u32) -> i32 {
+        if data.is_null() && size > 0 {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let bytes = match size {
+            0 => Vec::new(),
+            size => std::slice::from_raw_parts(data as *const u8, size as usize).to_vec(),
+        };
+
+        Self::set(this, id, Attribute::Binary(bytes))
+    }
+
+    /// The data stays owned by the list and is valid until the attribute is replaced or the list is freed
+    unsafe extern "system" fn get_binary(
+        this: *mut IAttributeList,
+        id: *const c_char,
+        data: *mut *const c_void,
+        size: *mut u32,
+    ) -> i32 {
+        if data.is_null() || size.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        Self::get(this, id, |attribute| match attribute {
+            Attribute::Binary(bytes) => {
+                *data = bytes.as_ptr() as *const c_void;
+                *size = bytes.len() as u32;
+                true
+            }
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AttributeList, HostMessage};
+    use crate::{FUnknown, IAttributeList, IHostApplication, IMessage, K_RESULT_FALSE, K_RESULT_OK};
+    use std::ffi::c_void;
+
+    /// Calls a method of an interface through its vtable, like a plugin does
+    macro_rules! call {
+        ($this:expr, $method:ident $(, $arg:expr)*) => {
+            unsafe { ((*(*$this).vtable).$method)($this $(, $arg)*) }
+        };
+    }
+
+    #[test]
+    fn attributes() {
+        let list = AttributeList::create();
+
+        assert_eq!(call!(list, setInt, c"int".as_ptr(), -3), K_RESULT_OK);
+        assert_eq!(call!(list, setFloat, c"float".as_ptr(), 0.5), K_RESULT_OK);
+
+        let text = "Gain".encode_utf16().chain([0]).collect::<Vec<_>>();
+        assert_eq!(call!(list, setString, c"string".as_ptr(), text.as_ptr()), K_RESULT_OK);
+
+        let bytes = [1u8, 2, 3];
+        assert_eq!(call!(list, setBinary, c"binary".as_ptr(), bytes.as_ptr() as *const c_void, 3), K_RESULT_OK);
+
+        let mut int = 0;
+        assert_eq!(call!(list, getInt, c"int".as_ptr(), &mut int), K_RESULT_OK);
+        assert_eq!(int, -3);
+
+        let mut float = 0.0;
+        assert_eq!(call!(list, getFloat, c"float".as_ptr(), &mut float), K_RESULT_OK);
+        assert_eq!(float, 0.5);
+
+        let mut string = [0xFFFFu16; 8];
+        assert_eq!(call!(list, getString, c"string".as_ptr(), string.as_mut_ptr(), 16), K_RESULT_OK);
+        assert_eq!(string[..5], text[..]);
+
+        // Cut to the buffer and still terminated
+        let mut short = [0xFFFFu16; 3];
+        assert_eq!(call!(list, getString, c"string".as_ptr(), short.as_mut_ptr(), 6), K_RESULT_OK);
+        assert_eq!(short, [b'G' as u16, b'a' as u16, 0]);
+
+        let mut data = std::ptr::null();
+        let mut size = 0;
+        assert_eq!(call!(list, getBinary, c"binary".as_ptr(), &mut data, &mut size), K_RESULT_OK);
+        assert_eq!(unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) }, bytes);
+
+        // Missing or of another type
+        assert_eq!(call!(list, getInt, c"missing".as_ptr(), &mut int), K_RESULT_FALSE);
+        assert_eq!(call!(list, getInt, c"float".as_ptr(), &mut int), K_RESULT_FALSE);
+
+        assert_eq!(call!(list, release), 0);
+    }
+
+    #[test]
+    fn messages_from_the_host() {
+        let mut host = IHostApplication::new();
+        let host = &mut host as *mut IHostApplication;
+
+        let mut object = std::ptr::null_mut();
+        assert_eq!(call!(host, createInstance, &IMessage::iid(), &IMessage::iid(), &mut object), K_RESULT_OK);
+        let message = object as *mut IMessage;
+
+        call!(message, setMessageID, c"Meter".as_ptr());
+        let id = call!(message, getMessageID);
+        assert_eq!(unsafe { std::ffi::CStr::from_ptr(id) }, c"Meter");
+
+        let attributes = call!(message, getAttributes);
+        assert_eq!(call!(attributes, setInt, c"level".as_ptr(), 7), K_RESULT_OK);
+
+        // Kept alive by the receiver while the sender lets go of it
+        assert_eq!(call!(message, addRef), 2);
+        assert_eq!(call!(message, release), 1);
+        let mut level = 0;
+        assert_eq!(call!(attributes, getInt, c"level".as_ptr(), &mut level), K_RESULT_OK);
+        assert_eq!(level, 7);
+        assert_eq!(call!(message, release), 0);
+
+        assert_eq!(call!(host, createInstance, &IAttributeList::iid(), &IAttributeList::iid(), &mut object), K_RESULT_OK);
+        assert_eq!(call!(object as *mut IAttributeList, release), 0);
+
+        assert_eq!(call!(host, createInstance, &FUnknown::iid(), &FUnknown::iid(), &mut object), K_RESULT_FALSE);
+        assert!(object.is_null());
+    }
+
+    #[test]
+    fn message_without_id() {
+        let message = HostMessage::create();
+
+        assert!(call!(message, getMessageID).is_null());
+        assert_eq!(call!(message, release), 0);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::{
+    IPluginFactory, IPluginFactory2, IPluginFactory3, PClassInfo, PClassInfo2, PClassInfoW,
+    PFactoryInfo, FUID, K_RESULT_OK, TUID,
+};
+use anyhow::{anyhow, Result};
+use libc::c_char;
+use libloading::{Library, Symbol};
+use serde::{Deserialize, Serialize};
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+
+type GetPluginFactoryProc = unsafe extern "system" fn() -> *mut IPluginFactory;
+
+#[cfg(windows)]
+type InitDllProc = unsafe extern "system" fn() -> bool;
+#[cfg(windows)]
+type ExitDllProc = unsafe extern "system" fn() -> bool;
+
+#[cfg(not(windows))]
+type ModuleEntryProc = unsafe extern "system" fn(handle: *mut std::ffi::c_void) -> bool;
+#[cfg(not(windows))]
+type ModuleExitProc = unsafe extern "system" fn() -> bool;
+
+/// A loaded VST3 module, i.e. the shared library inside a `.vst3` bundle together with its plugin factory.
+/// The module entry point is called when loading and the exit point is called when dropped.
+pub struct Module {
+    pub(crate) path: PathBuf,
+    pub(crate) factory: *mut IPluginFactory,
+    pub(crate) factory2: Option<*mut IPluginFactory2>,
+    pub(crate) factory3: Option<*mut IPluginFactory3>,
+    library: Option<Library>,
+}
+
+impl Module {
+    /// Loads the module from a `.vst3` bundle directory or a single file `.vst3` library
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let binary = binary_path(&path)?;
+
+        unsafe {
+            let library = open_library(&binary)?;
+
+            let get_factory: Symbol<GetPluginFactoryProc> = library
+                .get(b"GetPluginFactory")
+                .map_err(|e| anyhow!("{} does not export GetPluginFactory: {e}", binary.display()))?;
+
+            let factory = get_factory();
+            if factory.is_null() {
+                return Err(anyhow!("{} returned a null plugin factory", binary.display()));
+            }
+
+            (*factory).add_ref();
+
+            // Newer factories expose extended class info through these interfaces
+            let mut factory2: *mut c_void = std::ptr::null_mut();
+            let mut factory3: *mut c_void = std::ptr::null_mut();
+
+            let has_factory2 = (*factory).query_interface(&IPluginFactory2::iid(), &mut factory2) == K_RESULT_OK;
+            let has_factory3 = (*factory).query_interface(&IPluginFactory3::iid(), &mut factory3) == K_RESULT_OK;
+
+            Ok(Self {
+                path,
+                factory,
+                factory2: (has_factory2 && !factory2.is_null()).then_some(factory2 as *mut IPluginFactory2),
+                factory3: (has_factory3 && !factory3.is_null()).then_some(factory3 as *mut IPluginFactory3),
+                library: Some(library),
+            })
+        }
+    }
+
+    /// Path of the bundle this module was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the vendor, url and e-mail reported by the plugin factory
+    pub fn factory_info(&self) -> FactoryInfo {
+        let mut info = PFactoryInfo::default();
+
+        unsafe {
+            (*self.factory).get_factory_info(&mut info);
+        }
+
+        FactoryInfo {
+            vendor: string_from_chars(&info.vendor),
+            url: string_from_chars(&info.url),
+            email: string_from_chars(&info.email),
+            flags: info.flags as i32,
+        }
+    }
+
+    /// Creates an instance of a class and queries the given interface on it
+    pub(crate) fn create_instance(&self, cid: &TUID, iid: &TUID) -> Option<*mut c_void> {
+        let mut object: *mut c_void = std::ptr::null_mut();
+
+        unsafe {
+            let result = (*self.factory).create_instance(cid.as_ptr(), iid.as_ptr(), &mut object);
+            (result == K_RESULT_OK && !object.is_null()).then_some(object)
+        }
+    }
+
+    pub fn class_count(&self) -> i32 {
+        unsafe { (*self.factory).count_classes() }
+    }
+
+    /// Reads the class info at an index using the most detailed interface the factory supports,
+    /// i.e. [`PClassInfoW`] through `IPluginFactory3`, [`PClassInfo2`] through `IPluginFactory2` or [`PClassInfo`]
+    pub fn class_info(&self, index: i32) -> Option<ClassInfo> {
+        unsafe {
+            if let Some(factory) = self.factory3 {
+                let mut info = PClassInfoW::default();
+                if (*factory).get_class_info_unicode(index, &mut info) == K_RESULT_OK {
+                    return Some(ClassInfo::from(&info));
+                }
+            }
+
+            if let Some(factory) = self.factory2 {
+                let mut info = PClassInfo2::default();
+                if (*factory).get_class_info2(index, &mut info) == K_RESULT_OK {
+                    return Some(ClassInfo::from(&info));
+                }
+            }
+
+            let mut info = PClassInfo::default();
+            ((*self.factory).get_class_info(index, &mut info) == K_RESULT_OK).then(|| ClassInfo::from(&info))
+        }
+    }
+
+    /// Reads the class info of every class exported by the plugin factory.
+    /// Classes without a vendor inherit the vendor of the factory.
+    pub fn classes(&self) -> Vec<ClassInfo> {
+        let vendor = self.factory_info().vendor;
+
+        (0..self.class_count())
+            .filter_map(|i| self.class_info(i))
+            .map(|mut class| {
+                if class.vendor.is_empty() {
+                    class.vendor = vendor.clone();
+                }
+                class
+            })
+            .collect()
+    }
+}
+
+// The plugin factory is shared by every plugin created from the module and may be used from any thread
+unsafe impl Send for Module {}
+unsafe impl Sync for Module {}
+
+impl Drop for Module {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(factory) = self.factory3 {
+                (*factory).release();
+            }
+
+            if let Some(factory) = self.factory2 {
+                (*factory).release();
+            }
+
+            (*self.factory).release();
+        }
+
+        if let Some(library) = self.library.take() {
+            unsafe {
+                close_library(&library);
+            }
+
+            let _ = library.close();
+        }
+    }
+}
+
+/// Factory level information shared by every class in a module
+#[derive(Debug, Clone, Default)]
+pub struct FactoryInfo {
+    pub vendor: String,
+    pub url: String,
+    pub email: String,
+    pub flags: i32,
+}
+
+/// Class info unified over [`PClassInfo`], [`PClassInfo2`] and [`PClassInfoW`] with UTF-8 strings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassInfo {
+    /// Class id as 32 hex characters
+    pub cid: String,
+    pub cardinality: i32,
+    pub category: String,
+    pub name: String,
+    pub class_flags: u32,
+    pub sub_categories: Vec<String>,
+    pub vendor: String,
+    pub version: String,
+    pub sdk_version: String,
+}
+
+impl From<&PClassInfo> for ClassInfo {
+    fn from(info: &PClassInfo) -> Self {
+        Self {
+            cid: FUID::from_tuid(&info.cid).to_hex(),
+            cardinality: info.cardinality,
+            category: string_from_chars(&info.category),
+            name: string_from_chars(&info.name),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&PClassInfo2> for ClassInfo {
+    fn from(info: &PClassInfo2) -> Self {
+        Self {
+            cid: FUID::from_tuid(&info.cid).to_hex(),
+            cardinality: info.cardinality,
+            category: string_from_chars(&info.category),
+            name: string_from_chars(&info.name),
+            class_flags: info.class_flags,
+            sub_categories: split_sub_categories(&string_from_chars(&info.sub_categories)),
+            vendor: string_from_chars(&info.vendor),
+            version: string_from_chars(&info.version),
+            sdk_version: string_from_chars(&info.sdk_version),
+        }
+    }
+}
+
+impl From<&PClassInfoW> for ClassInfo {
+    fn from(info: &PClassInfoW) -> Self {
+        Self {
+            cid: FUID::from_tuid(&info.cid).to_hex(),
+            cardinality: info.cardinality,
+            category: string_from_chars(&info.category),
+            name: string_from_wide_chars(&info.name),
+            class_flags: info.class_flags,
+            sub_categories: split_sub_categories(&string_from_chars(&info.sub_categories)),
+            vendor: string_from_wide_chars(&info.vendor),
+            version: string_from_wide_chars(&info.version),
+            sdk_version: string_from_wide_chars(&info.sdk_version),
+        }
+    }
+}
+
+/// Sub categories are reported as a single `|` separated string, e.g. `Fx|Delay`
+fn split_sub_categories(sub_categories: &str) -> Vec<String> {
+    sub_categories
+        .split('|')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Converts a fixed size, null terminated C string buffer into a [`String`]
+pub(crate) fn string_from_chars(chars: &[c_char]) -> String {
+    let bytes = chars
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8)
+        .collect::<Vec<u8>>();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Converts a fixed size, null terminated UTF-16 buffer into a [`String`]
+pub(crate) fn string_from_wide_chars(chars: &[u16]) -> String {
+    let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+
+    String::from_utf16_lossy(&chars[..len])
+}
+
+/// Name of the architecture folder inside `Contents` for the current platform
+fn architecture() -> &'static str {
+    #[cfg(windows)]
+    return match std::env::consts::ARCH {
+        "x86" => "x86-win",
+        "aarch64" => "arm64-win",
+        _ => "x86_64-win",
+    };
+
+    #[cfg(not(windows))]
+    return match std::env::consts::ARCH {
+        "x86" => "i386-linux",
+        "aarch64" => "aarch64-linux",
+        _ => "x86_64-linux",
+    };
+}
+
+/// Resolves the shared library inside a bundle
+pub(crate) fn binary_path(bundle: &Path) -> Result<PathBuf> {
+    if bundle.is_file() {
+        return Ok(bundle.to_path_buf());
+    }
+
+    let name = bundle
+        .file_stem()
+        .ok_or_else(|| anyhow!("Invalid bundle path {}", bundle.display()))?;
+
+    let extension = if cfg!(windows) { "vst3" } else { "so" };
+
+    // Not `with_extension`, bundle names may contain dots, e.g. "Foo 1.2.vst3"
+    let binary = bundle
+        .join("Contents")
+        .join(architecture())
+        .join(format!("{}.{extension}", name.to_string_lossy()));
+
+    if binary.is_file() {
+        Ok(binary)
+    } else {
+        Err(anyhow!("Could not find module binary {}", binary.display()))
+    }
+}
+
+#[cfg(windows)]
+unsafe fn open_library(binary: &Path) -> Result<Library> {
+    let library = Library::new(binary)?;
+
+    if let Ok(init) = library.get::<InitDllProc>(b"InitDll") {
+        if !init() {
+            return Err(anyhow!("InitDll failed for {}", binary.display()));
+        }
+    }
+
+    Ok(library)
+}
+
+#[cfg(windows)]
+unsafe fn close_library(library: &Library) {
+    if let Ok(exit) = library.get::<ExitDllProc>(b"ExitDll") {
+        exit();
+    }
+}
+
+#[cfg(not(windows))]
+unsafe fn open_library(binary: &Path) -> Result<Library> {
+    use libloading::os::unix;
+
+    // ModuleEntry expects the raw handle returned by dlopen
+    let handle = unix::Library::new(binary)?.into_raw();
+    let library = Library::from(unix::Library::from_raw(handle));
+
+    let entry = library
+        .get::<ModuleEntryProc>(b"ModuleEntry")
+        .map_err(|e| anyhow!("{} does not export ModuleEntry: {e}", binary.display()))?;
+
+    if !entry(handle) {
+        return Err(anyhow!("ModuleEntry failed for {}", binary.display()));
+    }
+
+    Ok(library)
+}
+
+#[cfg(not(windows))]
+unsafe fn close_library(library: &Library) {
+    if let Ok(exit) = library.get::<ModuleExitProc>(b"ModuleExit") {
+        exit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{architecture, binary_path, split_sub_categories, string_from_chars, string_from_wide_chars};
+    use libc::c_char;
+    use std::fs;
+
+    #[test]
+    fn strings() {
+        let mut chars = [0 as c_char; 8];
+        chars[..3].copy_from_slice(&[b'F' as c_char, b'x' as c_char, 0]);
+        chars[4] = b'!' as c_char;
+        assert_eq!(string_from_chars(&chars), "Fx");
+
+        let mut wide = [0u16; 8];
+        for (c, w) in "Gäin".encode_utf16().zip(&mut wide) {
+            *w = c;
+        }
+        assert_eq!(string_from_wide_chars(&wide), "Gäin");
+
+        // A full buffer has no terminator
+        let full = "Vendor".encode_utf16().collect::<Vec<_>>();
+        assert_eq!(string_from_wide_chars(&full), "Vendor");
+        assert_eq!(string_from_wide_chars(&[]), "");
+    }
+
+    #[test]
+    fn sub_categories() {
+        assert_eq!(split_sub_categories("Fx|Delay|Stereo"), ["Fx", "Delay", "Stereo"]);
+        assert_eq!(split_sub_categories("Instrument"), ["Instrument"]);
+        assert_eq!(split_sub_categories("Fx||Dynamics|"), ["Fx", "Dynamics"]);
+        assert!(split_sub_categories("").is_empty());
+    }
+
+    #[test]
+    fn dotted_bundle_name() {
+        let bundle = std::env::temp_dir().join(format!("voxea_module_{} 1.2.vst3", std::process::id()));
+        let binaries = bundle.join("Contents").join(architecture());
+        let extension = if cfg!(windows) { "vst3" } else { "so" };
+        let binary = binaries.join(format!("voxea_module_{} 1.2.{extension}", std::process::id()));
+
+        fs::create_dir_all(&binaries).unwrap();
+        fs::write(&binary, []).unwrap();
+
+        let result = binary_path(&bundle);
+        fs::remove_dir_all(&bundle).unwrap();
+
+        assert_eq!(result.unwrap(), binary);
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(unix)]
+use crate::bridge::BridgedPlugin;
+use crate::events::Event;
+use crate::module::Module;
+use crate::parameters::ParameterChange;
+use crate::plugin::{Plugin, PluginHandle};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Where a plugin runs
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HostingMode {
+    /// Loaded into the host process, cheapest but a crash takes down the host
+    #[default]
+    InProcess,
+    /// Sandboxed in a helper process, a crash only bypasses the plugin
+    Bridged,
+}
+
+/// How long [`PluginNode::process`] waits for a bridged plugin to finish a block before the block is silent
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Deadline {
+    /// [`Deadline::SHARE`] of the block, at most [`Deadline::MAX_SHARE`], so one slow plugin leaves the rest of
+    /// the audio callback to the other strips
+    #[default]
+    Share,
+    /// The whole duration of the block, for a plugin that is the only thing processed in the callback
+    Block,
+    /// A fixed time regardless of the block, e.g. when rendering faster or slower than real time
+    Fixed(Duration),
+}
+
+impl Deadline {
+    /// Share of the block [`Deadline::Share`] waits for
+    pub const SHARE: f64 = 0.25;
+    /// Longest wait of [`Deadline::Share`], however large the block
+    pub const MAX_SHARE: Duration = Duration::from_micros(750);
+
+    /// How long to wait for a block of `num_samples` at `sample_rate`
+    pub fn duration(self, num_samples: usize, sample_rate: f64) -> Duration {
+        let block = Duration::from_secs_f64(num_samples as f64 / sample_rate.max(1.0));
+
+        match self {
+            Self::Share => block.mul_f64(Self::SHARE).min(Self::MAX_SHARE),
+            Self::Block => block,
+            Self::Fixed(deadline) => deadline,
+        }
+    }
+}
+
+/// A plugin instance set up for processing, hosted in one of the [`HostingMode`]s
+pub enum PluginNode {
+    InProcess(Box<Plugin>),
+    #[cfg(unix)]
+    Bridged(Box<BridgedPlugin>),
+}
+
+impl PluginNode {
+    /// Instantiates the class `cid` of `bundle` and sets it up for processing.
+    /// `helper` is the `voxea_vst` executable used for bridged plugins.
+    pub fn new(
+        mode: HostingMode,
+        helper: &Path,
+        bundle: &Path,
+        cid: &str,
+        sample_rate: f64,
+        max_block: usize,
+    ) -> Result<Self> {
+        match mode {
+            HostingMode::InProcess => {
+                let module = Arc::new(Module::load(bundle)?);
+                let class = module
+                    .classes()
+                    .into_iter()
+                    .find(|c| c.cid.eq_ignore_ascii_case(cid))
+                    .ok_or_else(|| anyhow!("{} has no class {cid}", bundle.display()))?;
+
+                let mut plugin = Plugin::new(module, &class)?;
+                plugin.setup_processing(sample_rate, max_block)?;
+
+                Ok(Self::InProcess(Box::new(plugin)))
+            }
+            #[cfg(unix)]
+            HostingMode::Bridged => Ok(Self::Bridged(Box::new(BridgedPlugin::spawn(
+                helper,
+                bundle,
+                cid,
+                sample_rate,
+                max_block,
+            )?))),
+            #[cfg(not(unix))]
+            HostingMode::Bridged => {
+                let _ = helper;
+                Err(anyhow!("Bridged plugins are not supported on this platform"))
+            }
+        }
+    }
+
+    pub fn mode(&self) -> HostingMode {
+        match self {
+            Self::InProcess(_) => HostingMode::InProcess,
+            #[cfg(unix)]
+            Self::Bridged(_) => HostingMode::Bridged,
+        }
+    }
+
+    /// A reference for the UI thread, to open the editor. Bridged plugins have none since they live in another process.
+    pub fn handle(&self) -> Option<PluginHandle> {
+        match self {
+            Self::InProcess(plugin) => Some(plugin.handle()),
+            #[cfg(unix)]
+            Self::Bridged(_) => None,
+        }
+    }
+
+    /// How long [`PluginNode::process`] may wait for a bridged plugin.
+    /// In-process plugins are processed on the calling thread and never wait.
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    pub fn set_deadline(&mut self, deadline: Deadline) {
+        match self {
+            Self::InProcess(_) => {}
+            #[cfg(unix)]
+            Self::Bridged(plugin) => plugin.set_deadline(deadline),
+        }
+    }
+
+    /// Whether the plugin failed and is bypassed
+    pub fn is_failed(&self) -> bool {
+        match self {
+            Self::InProcess(_) => false,
+            #[cfg(unix)]
+            Self::Bridged(plugin) => plugin.is_failed(),
+        }
+    }
+
+    pub fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        changes: &[ParameterChange],
+        events: &[Event],
+    ) -> Result<()> {
+        match self {
+            Self::InProcess(plugin) => plugin.process(inputs, outputs, changes, events),
+            #[cfg(unix)]
+            Self::Bridged(plugin) => plugin.process(inputs, outputs, changes, events),
+        }
+    }
+}
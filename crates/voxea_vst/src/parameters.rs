@@ -0,0 +1,299 @@
+use crate::{
+    FUnknown, IParamValueQueue, IParamValueQueueVTable, IParameterChanges, IParameterChangesVTable,
+    K_INVALID_ARGUMENT, K_NO_INTERFACE, K_RESULT_FALSE, K_RESULT_OK, TUID,
+};
+use serde::{Deserialize, Serialize};
+use std::ffi::c_void;
+
+/// Maximum number of parameters that can change within one block
+pub const MAX_PARAMETERS: usize = 64;
+/// Maximum number of automation points per parameter within one block
+pub const MAX_POINTS: usize = 32;
+
+/// A change of a normalized parameter value at a sample offset within the block
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParameterChange {
+    pub id: u32,
+    pub sample_offset: i32,
+    pub value: f64,
+}
+
+/// An `IParamValueQueue` holding the points of a single parameter. The storage is fixed, so adding points never allocates.
+#[repr(C)]
+pub struct ParamValueQueue {
+    vtable: *const IParamValueQueueVTable,
+    id: u32,
+    points: [(i32, f64); MAX_POINTS],
+    len: usize,
+}
+
+impl ParamValueQueue {
+    const VTABLE: IParamValueQueueVTable = IParamValueQueueVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        getParameterId: Self::get_parameter_id,
+        getPointCount: Self::get_point_count,
+        getPoint: Self::get_point,
+        addPoint: Self::add_point,
+    };
+
+    fn new() -> Self {
+        Self {
+            vtable: &Self::VTABLE,
+            id: 0,
+            points: [(0, 0.0); MAX_POINTS],
+            len: 0,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn points(&self) -> &[(i32, f64)] {
+        &self.points[..self.len]
+    }
+
+    /// Adds a point after every point at the same or an earlier offset, VST3 requires them sorted by offset.
+    /// Returns the index of the point, or `None` if the queue is full.
+    fn insert(&mut self, sample_offset: i32, value: f64) -> Option<usize> {
+        if self.len == MAX_POINTS {
+            return None;
+        }
+
+        let index = self.points().partition_point(|&(offset, _)| offset <= sample_offset);
+        self.points.copy_within(index..self.len, index + 1);
+        self.points[index] = (sample_offset, value);
+        self.len += 1;
+
+        Some(index)
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IParamValueQueue) -> &'a mut Self {
+        &mut *(this as *mut Self)
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IParamValueQueue, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IParamValueQueue::iid() {
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut IParamValueQueue) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut IParamValueQueue) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn get_parameter_id(this: *mut IParamValueQueue) -> u32 {
+        Self::from_ptr(this).id
+    }
+
+    unsafe extern "system" fn get_point_count(this: *mut IParamValueQueue) -> i32 {
+        Self::from_ptr(this).len as i32
+    }
+
+    unsafe extern "system" fn get_point(this: *mut IParamValueQueue, index: i32, sample_offset: *mut i32, value: *mut f64) -> i32 {
+        let queue = Self::from_ptr(this);
+
+        let Some(&(offset, point)) = queue.points().get(index as usize) else {
+            return K_INVALID_ARGUMENT;
+        };
+
+        *sample_offset = offset;
+        *value = point;
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn add_point(this: *mut IParamValueQueue, sample_offset: i32, value: f64, index: *mut i32) -> i32 {
+        let Some(inserted) = Self::from_ptr(this).insert(sample_offset, value) else {
+            return K_RESULT_FALSE;
+        };
+
+        if !index.is_null() {
+            *index = inserted as i32;
+        }
+
+        K_RESULT_OK
+    }
+}
+
+/// An `IParameterChanges` used both to pass parameter changes to a plugin and to receive its output changes.
+/// All queues are allocated up front so it can be cleared and refilled on the audio thread.
+#[repr(C)]
+pub struct ParameterChanges {
+    vtable: *const IParameterChangesVTable,
+    queues: Box<[ParamValueQueue]>,
+    len: usize,
+}
+
+impl ParameterChanges {
+    const VTABLE: IParameterChangesVTable = IParameterChangesVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        getParameterCount: Self::get_parameter_count,
+        getParameterData: Self::get_parameter_data,
+        addParameterData: Self::add_parameter_data,
+    };
+
+    pub fn new() -> Self {
+        Self {
+            vtable: &Self::VTABLE,
+            queues: (0..MAX_PARAMETERS).map(|_| ParamValueQueue::new()).collect(),
+            len: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn queues(&self) -> &[ParamValueQueue] {
+        &self.queues[..self.len]
+    }
+
+    /// Finds the queue of a parameter or takes the next unused one for it
+    fn queue_index(&mut self, id: u32) -> Option<usize> {
+        if let Some(index) = self.queues().iter().position(|q| q.id == id) {
+            return Some(index);
+        }
+
+        if self.len == MAX_PARAMETERS {
+            return None;
+        }
+
+        let queue = &mut self.queues[self.len];
+        queue.id = id;
+        queue.len = 0;
+        self.len += 1;
+
+        Some(self.len - 1)
+    }
+
+    /// Adds a change to the queue of its parameter in order of sample offset.
+    /// Returns false if there is no room left for it.
+    pub fn push(&mut self, change: ParameterChange) -> bool {
+        let Some(index) = self.queue_index(change.id) else {
+            return false;
+        };
+
+        self.queues[index].insert(change.sample_offset, change.value).is_some()
+    }
+
+    /// Iterates over every point of every parameter
+    pub fn changes(&self) -> impl Iterator<Item = ParameterChange> + '_ {
+        self.queues().iter().flat_map(|queue| {
+            queue.points().iter().map(|&(sample_offset, value)| ParameterChange {
+                id: queue.id,
+                sample_offset,
+                value,
+            })
+        })
+    }
+
+    pub(crate) fn as_ptr(&mut self) -> *mut IParameterChanges {
+        self as *mut Self as *mut IParameterChanges
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IParameterChanges) -> &'a mut Self {
+        &mut *(this as *mut Self)
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IParameterChanges, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IParameterChanges::iid() {
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut IParameterChanges) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut IParameterChanges) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn get_parameter_count(this: *mut IParameterChanges) -> i32 {
+        Self::from_ptr(this).len as i32
+    }
+
+    unsafe extern "system" fn get_parameter_data(this: *mut IParameterChanges, index: i32) -> *mut IParamValueQueue {
+        let changes = Self::from_ptr(this);
+
+        if index < 0 || index as usize >= changes.len {
+            return std::ptr::null_mut();
+        }
+
+        &mut changes.queues[index as usize] as *mut ParamValueQueue as *mut IParamValueQueue
+    }
+
+    unsafe extern "system" fn add_parameter_data(this: *mut IParameterChanges, id: *const u32, index: *mut i32) -> *mut IParamValueQueue {
+        let changes = Self::from_ptr(this);
+
+        let Some(position) = changes.queue_index(*id) else {
+            return std::ptr::null_mut();
+        };
+
+        if !index.is_null() {
+            *index = position as i32;
+        }
+
+        &mut changes.queues[position] as *mut ParamValueQueue as *mut IParamValueQueue
+    }
+}
+
+impl Default for ParameterChanges {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParameterChange, ParameterChanges, MAX_POINTS};
+
+    fn change(id: u32, sample_offset: i32, value: f64) -> ParameterChange {
+        ParameterChange { id, sample_offset, value }
+    }
+
+    #[test]
+    fn points_are_sorted_by_offset() {
+        let mut changes = ParameterChanges::new();
+
+        assert!(changes.push(change(1, 32, 0.5)));
+        assert!(changes.push(change(2, 0, 1.0)));
+        assert!(changes.push(change(1, 0, 0.0)));
+        assert!(changes.push(change(1, 32, 0.75)));
+        assert!(changes.push(change(1, 16, 0.25)));
+
+        assert_eq!(changes.queues().len(), 2);
+        assert_eq!(changes.queues()[0].points(), &[(0, 0.0), (16, 0.25), (32, 0.5), (32, 0.75)]);
+        assert_eq!(changes.queues()[1].points(), &[(0, 1.0)]);
+    }
+
+    #[test]
+    fn full_queue() {
+        let mut changes = ParameterChanges::new();
+
+        for offset in 0..MAX_POINTS as i32 {
+            assert!(changes.push(change(1, offset, 0.0)));
+        }
+
+        assert!(!changes.push(change(1, 0, 0.0)));
+        assert_eq!(changes.queues()[0].points().len(), MAX_POINTS);
+    }
+}
@@ -0,0 +1,649 @@
+use crate::events::{Event, EventList};
+use crate::handler::{ComponentHandler, Edit};
+use crate::module::{ClassInfo, Module};
+use crate::parameters::{ParameterChange, ParameterChanges};
+use crate::stream::MemoryStream;
+use crate::view::PlugView;
+use crate::{
+    AudioBusBuffers, BusInfo, IAudioProcessor, IComponent, IConnectionPoint, IEditController, IHostApplication,
+    ProcessData, ProcessSetup, FUID, K_AUDIO, K_EVENT, K_INPUT, K_OUTPUT, K_REALTIME, K_RESULT_OK, K_SAMPLE_32,
+    TUID,
+};
+use anyhow::{anyhow, Result};
+use std::ffi::c_void;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+
+/// An instantiated and initialized plugin class consisting of its component and, if it has one, its edit controller
+pub struct Plugin {
+    pub(crate) module: Arc<Module>,
+    pub(crate) class: ClassInfo,
+    pub(crate) component: *mut IComponent,
+    pub(crate) controller: Option<*mut IEditController>,
+    pub(crate) processor: Option<*mut IAudioProcessor>,
+    processing: Option<ProcessState>,
+    /// Whether the controller was queried from the component instead of being created separately
+    single_component: bool,
+    /// Connection points of the component and the controller, if they are separate objects and both have one
+    connection: Option<(*mut IConnectionPoint, *mut IConnectionPoint)>,
+    /// Receives the edits made in the editor, the processor takes them from `edits`
+    handler: Option<Arc<ComponentHandler>>,
+    edits: Option<Receiver<ParameterChange>>,
+    host: Box<IHostApplication>,
+}
+
+impl Plugin {
+    pub fn new(module: Arc<Module>, class: &ClassInfo) -> Result<Self> {
+        let cid = class.cid.parse::<FUID>()?.to_tuid();
+
+        let component = module
+            .create_instance(&cid, &IComponent::iid())
+            .ok_or_else(|| anyhow!("Could not create component for {}", class.name))?
+            as *mut IComponent;
+
+        let mut host = Box::new(IHostApplication::new());
+
+        unsafe {
+            if (*component).base().initialize(host.as_mut()) != K_RESULT_OK {
+                (*component).base().release();
+                return Err(anyhow!("Could not initialize component for {}", class.name));
+            }
+
+            let mut plugin = Self {
+                module,
+                class: class.clone(),
+                component,
+                controller: None,
+                processor: None,
+                processing: None,
+                single_component: false,
+                connection: None,
+                handler: None,
+                edits: None,
+                host,
+            };
+
+            plugin.create_controller();
+            plugin.connect();
+
+            // Synchronizes the controller with the initial state of the component
+            if let Ok(state) = plugin.component_state() {
+                let _ = plugin.set_controller_component_state(&state);
+            }
+
+            Ok(plugin)
+        }
+    }
+
+    pub fn class(&self) -> &ClassInfo {
+        &self.class
+    }
+
+    pub fn module(&self) -> &Arc<Module> {
+        &self.module
+    }
+
+    /// Creates the edit controller from the class id the component reports,
+    /// or falls back to querying it from the component itself
+    unsafe fn create_controller(&mut self) {
+        let mut controller_cid: TUID = [0; 16];
+
+        if (*self.component).get_controller_class_id(&mut controller_cid) == K_RESULT_OK {
+            if let Some(controller) = self.module.create_instance(&controller_cid, &IEditController::iid()) {
+                let controller = controller as *mut IEditController;
+
+                if (*controller).base().initialize(self.host.as_mut()) == K_RESULT_OK {
+                    self.controller = Some(controller);
+                    return;
+                }
+
+                (*controller).base().release();
+            }
+        }
+
+        let mut controller: *mut c_void = std::ptr::null_mut();
+        if (*self.component).base().query_interface(&IEditController::iid(), &mut controller) == K_RESULT_OK
+            && !controller.is_null()
+        {
+            self.controller = Some(controller as *mut IEditController);
+            self.single_component = true;
+        }
+    }
+
+    /// Gives the controller a component handler, so edits made in the editor reach the processor, and connects
+    /// the controller to the component so they can exchange messages. Messages are delivered on the thread that
+    /// sends them.
+    unsafe fn connect(&mut self) {
+        let Some(controller) = self.controller else {
+            return;
+        };
+
+        let (handler, edits) = ComponentHandler::new(controller);
+        let handler = Arc::new(handler);
+        (*controller).set_component_handler(handler.as_ptr());
+        self.handler = Some(handler);
+        self.edits = Some(edits);
+
+        if self.single_component {
+            return;
+        }
+
+        let mut component_point: *mut c_void = std::ptr::null_mut();
+        let mut controller_point: *mut c_void = std::ptr::null_mut();
+        (*self.component).base().query_interface(&IConnectionPoint::iid(), &mut component_point);
+        (*controller).base().query_interface(&IConnectionPoint::iid(), &mut controller_point);
+
+        let component_point = component_point as *mut IConnectionPoint;
+        let controller_point = controller_point as *mut IConnectionPoint;
+
+        match (component_point.is_null(), controller_point.is_null()) {
+            (false, false) => {
+                (*component_point).connect(controller_point);
+                (*controller_point).connect(component_point);
+                self.connection = Some((component_point, controller_point));
+            }
+            (false, true) => {
+                (*component_point).release();
+            }
+            (true, false) => {
+                (*controller_point).release();
+            }
+            (true, true) => {}
+        }
+    }
+
+    /// Undoes [`Plugin::connect`] before the component and the controller are terminated
+    unsafe fn disconnect(&mut self) {
+        if let Some((component_point, controller_point)) = self.connection.take() {
+            (*component_point).disconnect(controller_point);
+            (*controller_point).disconnect(component_point);
+            (*component_point).release();
+            (*controller_point).release();
+        }
+
+        if let (Some(controller), Some(_)) = (self.controller, self.handler.take()) {
+            (*controller).set_component_handler(std::ptr::null_mut());
+        }
+    }
+
+    /// Serializes the state of the component through `IComponent::getState`
+    pub fn component_state(&mut self) -> Result<Vec<u8>> {
+        unsafe { component_state(self.component, &self.class) }
+    }
+
+    /// Restores the state of the component through `IComponent::setState`
+    pub fn set_component_state(&mut self, state: &[u8]) -> Result<()> {
+        unsafe { set_component_state(self.component, &self.class, state) }
+    }
+
+    /// Creates the plugin's editor
+    pub fn create_view(&mut self) -> Result<PlugView> {
+        let Some(controller) = self.controller else {
+            return Err(anyhow!("{} has no edit controller", self.class.name));
+        };
+
+        unsafe { PlugView::new(controller, self.module.clone()) }
+    }
+
+    /// A reference to the component and controller for the UI thread, which stays usable while the plugin is
+    /// moved to the audio thread
+    pub fn handle(&self) -> PluginHandle {
+        unsafe {
+            (*self.component).base().add_ref();
+            if let Some(controller) = self.controller {
+                (*controller).base().add_ref();
+            }
+        }
+
+        PluginHandle {
+            module: self.module.clone(),
+            class: self.class.clone(),
+            component: self.component,
+            controller: self.controller,
+            handler: self.handler.clone(),
+        }
+    }
+
+    /// Serializes the state of the edit controller, if the plugin has one
+    pub fn controller_state(&mut self) -> Result<Option<Vec<u8>>> {
+        unsafe { controller_state(self.controller, &self.class) }
+    }
+
+    pub fn set_controller_state(&mut self, state: &[u8]) -> Result<()> {
+        unsafe { set_controller_state(self.controller, &self.class, state) }
+    }
+
+    /// Passes the component state to the controller through `IEditController::setComponentState`
+    /// so its parameters reflect the state of the component
+    pub fn set_controller_component_state(&mut self, state: &[u8]) -> Result<()> {
+        unsafe { set_controller_component_state(self.controller, &self.class, state) }
+    }
+}
+
+unsafe fn component_state(component: *mut IComponent, class: &ClassInfo) -> Result<Vec<u8>> {
+    let mut stream = MemoryStream::default();
+
+    if (*component).get_state(stream.as_ptr()) != K_RESULT_OK {
+        return Err(anyhow!("Could not get component state of {}", class.name));
+    }
+
+    Ok(stream.into_bytes())
+}
+
+unsafe fn set_component_state(component: *mut IComponent, class: &ClassInfo, state: &[u8]) -> Result<()> {
+    let mut stream = MemoryStream::from_bytes(state.to_vec());
+
+    if (*component).set_state(stream.as_ptr()) != K_RESULT_OK {
+        return Err(anyhow!("Could not set component state of {}", class.name));
+    }
+
+    Ok(())
+}
+
+unsafe fn controller_state(controller: Option<*mut IEditController>, class: &ClassInfo) -> Result<Option<Vec<u8>>> {
+    let Some(controller) = controller else {
+        return Ok(None);
+    };
+
+    let mut stream = MemoryStream::default();
+
+    if (*controller).get_state(stream.as_ptr()) != K_RESULT_OK {
+        return Err(anyhow!("Could not get controller state of {}", class.name));
+    }
+
+    Ok(Some(stream.into_bytes()))
+}
+
+unsafe fn set_controller_state(controller: Option<*mut IEditController>, class: &ClassInfo, state: &[u8]) -> Result<()> {
+    let Some(controller) = controller else {
+        return Ok(());
+    };
+
+    let mut stream = MemoryStream::from_bytes(state.to_vec());
+
+    if (*controller).set_state(stream.as_ptr()) != K_RESULT_OK {
+        return Err(anyhow!("Could not set controller state of {}", class.name));
+    }
+
+    Ok(())
+}
+
+unsafe fn set_controller_component_state(
+    controller: Option<*mut IEditController>,
+    class: &ClassInfo,
+    state: &[u8],
+) -> Result<()> {
+    let Some(controller) = controller else {
+        return Ok(());
+    };
+
+    let mut stream = MemoryStream::from_bytes(state.to_vec());
+
+    if (*controller).set_component_state(stream.as_ptr()) != K_RESULT_OK {
+        return Err(anyhow!("Could not set component state of the controller of {}", class.name));
+    }
+
+    Ok(())
+}
+
+/// UI thread side of a [`Plugin`] processed elsewhere, see [`Plugin::handle`]. It only keeps the component and
+/// controller alive, the plugin terminates them when it is dropped.
+pub struct PluginHandle {
+    module: Arc<Module>,
+    class: ClassInfo,
+    component: *mut IComponent,
+    controller: Option<*mut IEditController>,
+    handler: Option<Arc<ComponentHandler>>,
+}
+
+impl PluginHandle {
+    pub fn class(&self) -> &ClassInfo {
+        &self.class
+    }
+
+    /// Creates the plugin's editor
+    pub fn create_view(&mut self) -> Result<PlugView> {
+        let Some(controller) = self.controller else {
+            return Err(anyhow!("{} has no edit controller", self.class.name));
+        };
+
+        unsafe { PlugView::new(controller, self.module.clone()) }
+    }
+
+    /// Same as [`Plugin::component_state`], called while the plugin processes elsewhere
+    pub fn component_state(&mut self) -> Result<Vec<u8>> {
+        unsafe { component_state(self.component, &self.class) }
+    }
+
+    pub fn set_component_state(&mut self, state: &[u8]) -> Result<()> {
+        unsafe { set_component_state(self.component, &self.class, state) }
+    }
+
+    pub fn controller_state(&mut self) -> Result<Option<Vec<u8>>> {
+        unsafe { controller_state(self.controller, &self.class) }
+    }
+
+    pub fn set_controller_state(&mut self, state: &[u8]) -> Result<()> {
+        unsafe { set_controller_state(self.controller, &self.class, state) }
+    }
+
+    pub fn set_controller_component_state(&mut self, state: &[u8]) -> Result<()> {
+        unsafe { set_controller_component_state(self.controller, &self.class, state) }
+    }
+
+    /// Parameter edits made in the editor since the last call, oldest first
+    pub fn take_edits(&mut self) -> Vec<Edit> {
+        self.handler.as_ref().map(|handler| handler.take_edits()).unwrap_or_default()
+    }
+
+    /// Normalized value of a parameter as the controller has it
+    pub fn parameter(&mut self, id: u32) -> Option<f64> {
+        let controller = self.controller?;
+
+        Some(unsafe { (*controller).get_param_normalized(id) })
+    }
+
+    /// Sets a normalized parameter value in the controller and passes it to the processor with its next block,
+    /// e.g. to undo an edit made in the editor
+    pub fn set_parameter(&mut self, id: u32, value: f64) -> Result<()> {
+        let (Some(controller), Some(handler)) = (self.controller, &self.handler) else {
+            return Err(anyhow!("{} has no edit controller", self.class.name));
+        };
+
+        if unsafe { (*controller).set_param_normalized(id, value) } != K_RESULT_OK {
+            return Err(anyhow!("{} has no parameter {id}", self.class.name));
+        }
+
+        if !handler.send(id, value) {
+            return Err(anyhow!("Too many parameter changes are queued for {}", self.class.name));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for PluginHandle {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(controller) = self.controller.take() {
+                (*controller).base().release();
+            }
+
+            (*self.component).base().release();
+        }
+    }
+}
+
+/// Channel buffers of one bus. The channel pointers handed to the plugin point into `channels`.
+struct BusBuffers {
+    channels: Vec<Vec<f32>>,
+    pointers: Vec<*mut f32>,
+}
+
+impl BusBuffers {
+    fn new(channel_count: usize, max_block: usize) -> Self {
+        let mut channels = vec![vec![0.0; max_block]; channel_count];
+        let pointers = channels.iter_mut().map(|c| c.as_mut_ptr()).collect();
+
+        Self { channels, pointers }
+    }
+}
+
+/// Everything needed to process a block, allocated in [`Plugin::setup_processing`] so processing itself does not allocate
+struct ProcessState {
+    max_block: usize,
+    input_buses: Vec<BusBuffers>,
+    output_buses: Vec<BusBuffers>,
+    inputs: Vec<AudioBusBuffers>,
+    outputs: Vec<AudioBusBuffers>,
+    input_changes: ParameterChanges,
+    output_changes: ParameterChanges,
+    input_events: EventList,
+    output_events: EventList,
+}
+
+impl Plugin {
+    /// Reads the channel counts of every audio bus in a direction
+    unsafe fn bus_channels(&mut self, direction: i32) -> Vec<usize> {
+        let component = &mut *self.component;
+
+        (0..component.get_bus_count(K_AUDIO, direction))
+            .map(|index| {
+                let mut info = BusInfo::default();
+                component.get_bus_info(K_AUDIO, direction, index, &mut info);
+                info.channel_count.max(0) as usize
+            })
+            .collect()
+    }
+
+    /// Number of channels of the main input bus
+    pub fn input_channels(&mut self) -> usize {
+        unsafe { self.bus_channels(K_INPUT).first().copied().unwrap_or(0) }
+    }
+
+    /// Number of channels of the main output bus
+    pub fn output_channels(&mut self) -> usize {
+        unsafe { self.bus_channels(K_OUTPUT).first().copied().unwrap_or(0) }
+    }
+
+    /// Prepares the plugin for processing blocks of up to `max_block` samples and activates it
+    pub fn setup_processing(&mut self, sample_rate: f64, max_block: usize) -> Result<()> {
+        self.stop_processing();
+
+        unsafe {
+            if self.processor.is_none() {
+                let mut processor: *mut c_void = std::ptr::null_mut();
+                if (*self.component).base().query_interface(&IAudioProcessor::iid(), &mut processor) != K_RESULT_OK
+                    || processor.is_null()
+                {
+                    return Err(anyhow!("{} is not an audio processor", self.class.name));
+                }
+
+                self.processor = Some(processor as *mut IAudioProcessor);
+            }
+
+            let processor = &mut *self.processor.unwrap();
+
+            let mut setup = ProcessSetup {
+                process_mode: K_REALTIME,
+                symbolic_sample_size: K_SAMPLE_32,
+                max_samples_per_block: max_block as i32,
+                sample_rate,
+            };
+
+            if processor.setup_processing(&mut setup) != K_RESULT_OK {
+                return Err(anyhow!("Could not set up processing for {}", self.class.name));
+            }
+
+            let mut input_buses = self
+                .bus_channels(K_INPUT)
+                .into_iter()
+                .map(|channels| BusBuffers::new(channels, max_block))
+                .collect::<Vec<_>>();
+            let mut output_buses = self
+                .bus_channels(K_OUTPUT)
+                .into_iter()
+                .map(|channels| BusBuffers::new(channels, max_block))
+                .collect::<Vec<_>>();
+
+            let bus_buffers = |bus: &mut BusBuffers| AudioBusBuffers {
+                num_channels: bus.pointers.len() as i32,
+                silence_flags: 0,
+                channel_buffers_32: bus.pointers.as_mut_ptr(),
+            };
+
+            let inputs = input_buses.iter_mut().map(bus_buffers).collect();
+            let outputs = output_buses.iter_mut().map(bus_buffers).collect();
+
+            let component = &mut *self.component;
+            for index in 0..input_buses.len() as i32 {
+                component.activate_bus(K_AUDIO, K_INPUT, index, true);
+            }
+            for index in 0..output_buses.len() as i32 {
+                component.activate_bus(K_AUDIO, K_OUTPUT, index, true);
+            }
+            for direction in [K_INPUT, K_OUTPUT] {
+                for index in 0..component.get_bus_count(K_EVENT, direction) {
+                    component.activate_bus(K_EVENT, direction, index, true);
+                }
+            }
+
+            component.set_active(true);
+            processor.set_processing(true);
+
+            self.processing = Some(ProcessState {
+                max_block,
+                input_buses,
+                output_buses,
+                inputs,
+                outputs,
+                input_changes: ParameterChanges::new(),
+                output_changes: ParameterChanges::new(),
+                input_events: EventList::new(),
+                output_events: EventList::new(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Deactivates the plugin if it is processing
+    pub fn stop_processing(&mut self) {
+        if self.processing.take().is_none() {
+            return;
+        }
+
+        unsafe {
+            if let Some(processor) = self.processor {
+                (*processor).set_processing(false);
+            }
+
+            (*self.component).set_active(false);
+        }
+    }
+
+    /// Processes one block. The inputs are copied to the main input bus and the main output bus is copied to the outputs,
+    /// missing channels are treated as silence. The block is truncated to the maximum block size given at setup.
+    pub fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+        changes: &[ParameterChange],
+        events: &[Event],
+    ) -> Result<()> {
+        let Some(processor) = self.processor else {
+            return Err(anyhow!("{} is not set up for processing", self.class.name));
+        };
+
+        let Some(state) = self.processing.as_mut() else {
+            return Err(anyhow!("{} is not set up for processing", self.class.name));
+        };
+
+        let num_samples = outputs
+            .iter()
+            .map(|o| o.len())
+            .chain(inputs.iter().map(|i| i.len()))
+            .min()
+            .unwrap_or(0)
+            .min(state.max_block);
+
+        for (index, bus) in state.input_buses.iter_mut().enumerate() {
+            for (channel, buffer) in bus.channels.iter_mut().enumerate() {
+                match inputs.get(channel).filter(|_| index == 0) {
+                    Some(input) => buffer[..num_samples].copy_from_slice(&input[..num_samples]),
+                    None => buffer[..num_samples].fill(0.0),
+                }
+            }
+        }
+
+        state.input_changes.clear();
+        state.output_changes.clear();
+        for change in changes {
+            state.input_changes.push(*change);
+        }
+
+        // Edits made in the editor since the last block
+        if let Some(edits) = &self.edits {
+            while let Ok(change) = edits.try_recv() {
+                state.input_changes.push(change);
+            }
+        }
+
+        state.input_events.clear();
+        state.output_events.clear();
+        for event in events {
+            state.input_events.push(*event);
+        }
+
+        let mut data = ProcessData {
+            process_mode: K_REALTIME,
+            symbolic_sample_size: K_SAMPLE_32,
+            num_samples: num_samples as i32,
+            num_inputs: state.inputs.len() as i32,
+            num_outputs: state.outputs.len() as i32,
+            inputs: state.inputs.as_mut_ptr(),
+            outputs: state.outputs.as_mut_ptr(),
+            input_parameter_changes: state.input_changes.as_ptr(),
+            output_parameter_changes: state.output_changes.as_ptr(),
+            input_events: state.input_events.as_ptr(),
+            output_events: state.output_events.as_ptr(),
+            process_context: std::ptr::null_mut(),
+        };
+
+        let result = unsafe { (*processor).process(&mut data) };
+
+        if let Some(bus) = state.output_buses.first() {
+            for (channel, output) in outputs.iter_mut().enumerate() {
+                match bus.channels.get(channel) {
+                    Some(buffer) => output[..num_samples].copy_from_slice(&buffer[..num_samples]),
+                    None => output[..num_samples].fill(0.0),
+                }
+            }
+        }
+
+        if result != K_RESULT_OK {
+            return Err(anyhow!("{} failed to process", self.class.name));
+        }
+
+        Ok(())
+    }
+
+    /// Events the plugin sent during the last processed block
+    pub fn output_events(&self) -> &[Event] {
+        self.processing.as_ref().map_or(&[], |state| state.output_events.events())
+    }
+
+    /// Parameter changes the plugin reported during the last processed block
+    pub fn output_parameter_changes(&self) -> impl Iterator<Item = ParameterChange> + '_ {
+        self.processing
+            .iter()
+            .flat_map(|state| state.output_changes.changes())
+    }
+}
+
+// VST3 plugins may be moved to another thread, e.g. the audio thread, as long as they are not called concurrently
+unsafe impl Send for Plugin {}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        self.stop_processing();
+
+        unsafe {
+            self.disconnect();
+
+            if let Some(processor) = self.processor.take() {
+                (*processor).release();
+            }
+
+            if let Some(controller) = self.controller.take() {
+                if !self.single_component {
+                    (*controller).base().terminate();
+                }
+                (*controller).base().release();
+            }
+
+            (*self.component).base().terminate();
+            (*self.component).base().release();
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::module::ClassInfo;
+use crate::plugin::{Plugin, PluginHandle};
+use anyhow::{anyhow, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const HEADER_ID: &[u8; 4] = b"VST3";
+const LIST_ID: &[u8; 4] = b"List";
+const FORMAT_VERSION: i32 = 1;
+
+/// Size of the header: id, version, class id and the offset of the chunk list
+const HEADER_SIZE: u64 = 4 + 4 + 32 + 8;
+
+/// Chunk holding the state of the component
+pub const COMPONENT_STATE: [u8; 4] = *b"Comp";
+/// Chunk holding the state of the edit controller
+pub const CONTROLLER_STATE: [u8; 4] = *b"Cont";
+/// Chunk holding program data
+pub const PROGRAM_DATA: [u8; 4] = *b"Prog";
+/// Chunk holding xml meta info
+pub const META_INFO: [u8; 4] = *b"Info";
+
+/// A plugin whose state a [`Preset`] can capture and restore, either the plugin itself or a handle to it
+pub trait PluginState {
+    fn class(&self) -> &ClassInfo;
+    fn component_state(&mut self) -> Result<Vec<u8>>;
+    fn set_component_state(&mut self, state: &[u8]) -> Result<()>;
+    fn controller_state(&mut self) -> Result<Option<Vec<u8>>>;
+    fn set_controller_state(&mut self, state: &[u8]) -> Result<()>;
+    fn set_controller_component_state(&mut self, state: &[u8]) -> Result<()>;
+}
+
+macro_rules! plugin_state {
+    ($plugin:ty) => {
+        impl PluginState for $plugin {
+            fn class(&self) -> &ClassInfo {
+                <$plugin>::class(self)
+            }
+
+            fn component_state(&mut self) -> Result<Vec<u8>> {
+                <$plugin>::component_state(self)
+            }
+
+            fn set_component_state(&mut self, state: &[u8]) -> Result<()> {
+                <$plugin>::set_component_state(self, state)
+            }
+
+            fn controller_state(&mut self) -> Result<Option<Vec<u8>>> {
+                <$plugin>::controller_state(self)
+            }
+
+            fn set_controller_state(&mut self, state: &[u8]) -> Result<()> {
+                <$plugin>::set_controller_state(self, state)
+            }
+
+            fn set_controller_component_state(&mut self, state: &[u8]) -> Result<()> {
+                <$plugin>::set_controller_component_state(self, state)
+            }
+        }
+    };
+}
+
+plugin_state!(Plugin);
+plugin_state!(PluginHandle);
+
+/// A chunk of a preset file identified by a four character id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+/// The contents of a `.vstpreset` file.
+///
+/// The file starts with a header holding the class id of the plugin and the offset of the chunk list,
+/// followed by the chunk data and finally the chunk list with the id, offset and size of every chunk.
+/// All integers are little endian.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preset {
+    /// Class id of the processor as 32 hex characters
+    pub class_id: String,
+    pub chunks: Vec<Chunk>,
+}
+
+impl Preset {
+    pub fn new(class_id: impl Into<String>) -> Self {
+        Self {
+            class_id: class_id.into(),
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Captures the component and controller state of a plugin
+    pub fn from_plugin(plugin: &mut impl PluginState) -> Result<Self> {
+        let mut preset = Self::new(plugin.class().cid.clone());
+
+        preset.set_chunk(COMPONENT_STATE, plugin.component_state()?);
+
+        if let Some(state) = plugin.controller_state()? {
+            preset.set_chunk(CONTROLLER_STATE, state);
+        }
+
+        Ok(preset)
+    }
+
+    /// Restores the component state, synchronizes the controller with it and then restores the controller state
+    pub fn apply(&self, plugin: &mut impl PluginState) -> Result<()> {
+        if !self.class_id.eq_ignore_ascii_case(&plugin.class().cid) {
+            return Err(anyhow!(
+                "Preset is for class {} but the plugin is {}",
+                self.class_id,
+                plugin.class().cid
+            ));
+        }
+
+        if let Some(state) = self.chunk(COMPONENT_STATE) {
+            plugin.set_component_state(state)?;
+            plugin.set_controller_component_state(state)?;
+        }
+
+        if let Some(state) = self.chunk(CONTROLLER_STATE) {
+            plugin.set_controller_state(state)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn chunk(&self, id: [u8; 4]) -> Option<&[u8]> {
+        self.chunks
+            .iter()
+            .find(|c| c.id == id)
+            .map(|c| c.data.as_slice())
+    }
+
+    /// Replaces the chunk with the same id or appends it
+    pub fn set_chunk(&mut self, id: [u8; 4], data: Vec<u8>) {
+        match self.chunks.iter_mut().find(|c| c.id == id) {
+            Some(chunk) => chunk.data = data,
+            None => self.chunks.push(Chunk { id, data }),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read(&mut File::open(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.write(&mut File::create(path)?)
+    }
+
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        if &read_id(reader)? != HEADER_ID {
+            return Err(anyhow!("Not a vstpreset file"));
+        }
+
+        let version = read_i32(reader)?;
+        if version > FORMAT_VERSION {
+            return Err(anyhow!("Unsupported vstpreset version {version}"));
+        }
+
+        let mut class_id = [0u8; 32];
+        reader.read_exact(&mut class_id)?;
+        let class_id = String::from_utf8(class_id.to_vec())?;
+
+        let list_offset = read_i64(reader)?;
+        reader.seek(SeekFrom::Start(list_offset as u64))?;
+
+        if &read_id(reader)? != LIST_ID {
+            return Err(anyhow!("Missing chunk list"));
+        }
+
+        let count = read_i32(reader)?;
+        let entries = (0..count)
+            .map(|_| Ok((read_id(reader)?, read_i64(reader)?, read_i64(reader)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let chunks = entries
+            .into_iter()
+            .map(|(id, offset, size)| {
+                let in_bounds = match offset.checked_add(size) {
+                    Some(end) => offset >= 0 && size >= 0 && end as u64 <= len,
+                    None => false,
+                };
+                if !in_bounds {
+                    return Err(anyhow!("Chunk {:?} is out of bounds", String::from_utf8_lossy(&id)));
+                }
+
+                let mut data = vec![0; size as usize];
+                reader.seek(SeekFrom::Start(offset as u64))?;
+                reader.read_exact(&mut data)?;
+
+                Ok(Chunk { id, data })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { class_id, chunks })
+    }
+
+    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+        if self.class_id.len() != 32 {
+            return Err(anyhow!("Invalid class id {}", self.class_id));
+        }
+
+        writer.seek(SeekFrom::Start(0))?;
+
+        writer.write_all(HEADER_ID)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(self.class_id.to_uppercase().as_bytes())?;
+        // The offset of the chunk list is patched in once the chunks are written
+        writer.write_all(&0i64.to_le_bytes())?;
+
+        let mut offset = HEADER_SIZE as i64;
+        let mut entries = Vec::with_capacity(self.chunks.len());
+
+        for chunk in &self.chunks {
+            writer.write_all(&chunk.data)?;
+            entries.push((chunk.id, offset, chunk.data.len() as i64));
+            offset += chunk.data.len() as i64;
+        }
+
+        writer.write_all(LIST_ID)?;
+        writer.write_all(&(entries.len() as i32).to_le_bytes())?;
+        for (id, offset, size) in entries {
+            writer.write_all(&id)?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&size.to_le_bytes())?;
+        }
+
+        writer.seek(SeekFrom::Start(HEADER_SIZE - 8))?;
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.seek(SeekFrom::End(0))?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+fn read_id<R: Read>(reader: &mut R) -> Result<[u8; 4]> {
+    let mut id = [0; 4];
+    reader.read_exact(&mut id)?;
+    Ok(id)
+}
+
+fn read_i32<R: Read>(reader: &mut R) -> Result<i32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(i32::from_le_bytes(bytes))
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> Result<i64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(i64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Preset, COMPONENT_STATE, CONTROLLER_STATE};
+    use std::io::Cursor;
+
+    const CLASS_ID: &str = "E831FF31F2D54301928EBBEE25697802";
+
+    fn bytes(preset: &Preset) -> Vec<u8> {
+        let mut cursor = Cursor::new(Vec::new());
+        preset.write(&mut cursor).unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut preset = Preset::new(CLASS_ID);
+        preset.set_chunk(COMPONENT_STATE, vec![1, 2, 3]);
+        preset.set_chunk(CONTROLLER_STATE, vec![4, 5]);
+
+        let read = Preset::read(&mut Cursor::new(bytes(&preset))).unwrap();
+
+        assert_eq!(read, preset);
+        assert_eq!(read.chunk(CONTROLLER_STATE), Some(&[4, 5][..]));
+    }
+
+    #[test]
+    fn chunk_out_of_bounds() {
+        let mut preset = Preset::new(CLASS_ID);
+        preset.set_chunk(COMPONENT_STATE, vec![1, 2, 3]);
+        let mut data = bytes(&preset);
+
+        // The list is the last 4 + 4 + 20 bytes: id, count and one entry of id, offset and size
+        let size = data.len() - 8;
+        data[size..].copy_from_slice(&i64::MAX.to_le_bytes());
+        assert!(Preset::read(&mut Cursor::new(data.clone())).is_err());
+
+        data[size..].copy_from_slice(&(-1i64).to_le_bytes());
+        assert!(Preset::read(&mut Cursor::new(data)).is_err());
+    }
+}
@@ -0,0 +1,456 @@
+use crate::module::{self, ClassInfo, Module};
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+/// Prefix of the stdout line the helper process writes its result to.
+/// Plugins are free to print to stdout themselves, so everything else is ignored.
+pub const SCAN_RESULT_PREFIX: &str = "VOXEA_SCAN_RESULT:";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Description of a VST3 bundle and the classes it exports
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub path: PathBuf,
+    /// Modification time of the bundle in seconds since the unix epoch, used to invalidate the cache.
+    /// See [`modified_time`].
+    pub modified: u64,
+    pub vendor: String,
+    pub url: String,
+    pub email: String,
+    pub classes: Vec<ClassInfo>,
+}
+
+/// Finds plugin descriptions either through `moduleinfo.json` or by loading the module in a helper process
+pub struct Scanner {
+    pub(crate) helper: PathBuf,
+    pub(crate) timeout: Duration,
+}
+
+impl Scanner {
+    /// Creates a scanner which uses the `voxea_vst` helper next to the current executable
+    pub fn new() -> Self {
+        Self {
+            helper: default_helper(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_helper(mut self, helper: impl Into<PathBuf>) -> Self {
+        self.helper = helper.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Describes a bundle, preferring its `moduleinfo.json` and falling back to an out-of-process scan
+    pub fn scan(&self, bundle: &Path) -> Result<PluginInfo> {
+        match read_module_info(bundle) {
+            Ok(Some(info)) => return Ok(info),
+            Ok(None) => {}
+            Err(e) => warn!("Invalid moduleinfo.json in {}: {e}", bundle.display()),
+        }
+
+        self.scan_out_of_process(bundle)
+    }
+
+    /// Spawns the helper process to load the module and waits for its result.
+    /// The helper is killed if it takes longer than the timeout.
+    pub fn scan_out_of_process(&self, bundle: &Path) -> Result<PluginInfo> {
+        info!("Scanning {} out of process", bundle.display());
+
+        let mut child = Command::new(&self.helper)
+            .arg("scan")
+            .arg(bundle)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Could not spawn scan helper {}", self.helper.display()))?;
+
+        // Reads stdout on another thread so a chatty plugin can not fill the pipe and block the helper
+        let mut stdout = child.stdout.take().unwrap();
+        let reader = std::thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stdout.read_to_string(&mut output);
+            output
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+
+            if Instant::now() > deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(anyhow!("Timed out scanning {}", bundle.display()));
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        };
+
+        let output = reader.join().unwrap_or_default();
+
+        if !status.success() {
+            return Err(anyhow!("Scan helper failed for {}: {status}", bundle.display()));
+        }
+
+        let result = output
+            .lines()
+            .find_map(|line| line.strip_prefix(SCAN_RESULT_PREFIX))
+            .ok_or_else(|| anyhow!("Scan helper returned no result for {}", bundle.display()))?;
+
+        let mut info: PluginInfo = serde_json::from_str(result)?;
+        info.modified = modified_time(bundle);
+
+        Ok(info)
+    }
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Path of the `voxea_vst` helper next to the current executable
+pub fn default_helper() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+        .join(format!("voxea_vst{}", std::env::consts::EXE_SUFFIX))
+}
+
+/// Loads the module in the current process and describes it. This is what the helper process runs.
+pub fn scan_in_process(bundle: &Path) -> Result<PluginInfo> {
+    let module = Module::load(bundle)?;
+    let factory_info = module.factory_info();
+
+    let classes = module.classes();
+
+    Ok(PluginInfo {
+        path: bundle.to_path_buf(),
+        modified: modified_time(bundle),
+        vendor: factory_info.vendor,
+        url: factory_info.url,
+        email: factory_info.email,
+        classes,
+    })
+}
+
+/// Standard VST3 locations for the current platform
+pub fn default_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(home) = dirs::home_dir() {
+            paths.push(home.join(".vst3"));
+        }
+        paths.push(PathBuf::from("/usr/lib/vst3"));
+        paths.push(PathBuf::from("/usr/local/lib/vst3"));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(common) = std::env::var_os("CommonProgramFiles") {
+            paths.push(PathBuf::from(common).join("VST3"));
+        }
+        if let Some(local) = dirs::data_local_dir() {
+            paths.push(local.join("Programs").join("Common").join("VST3"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(home) = dirs::home_dir() {
+            paths.push(home.join("Library/Audio/Plug-Ins/VST3"));
+        }
+        paths.push(PathBuf::from("/Library/Audio/Plug-Ins/VST3"));
+    }
+
+    paths
+}
+
+/// Recursively collects every `.vst3` bundle under the given directories.
+/// Symlinked directories are followed, but each directory is only visited once so link cycles end the walk.
+pub fn find_bundles(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut bundles = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = paths.to_vec();
+
+    while let Some(dir) = pending.pop() {
+        let Ok(canonical) = dir.canonicalize() else {
+            continue;
+        };
+        if !visited.insert(canonical) {
+            continue;
+        }
+
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("vst3")) {
+                bundles.push(path);
+            } else if path.is_dir() {
+                pending.push(path);
+            }
+        }
+    }
+
+    bundles.sort();
+    bundles
+}
+
+/// Latest modification time of a bundle in seconds since the unix epoch, or 0 if unavailable.
+/// Updates usually replace the binary or `moduleinfo.json` without touching the bundle directory, so their
+/// times are taken into account as well.
+pub fn modified_time(bundle: &Path) -> u64 {
+    let mut files = vec![bundle.to_path_buf(), module_info_path(bundle)];
+    files.extend(module::binary_path(bundle).ok());
+
+    files
+        .iter()
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .filter_map(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .max()
+        .unwrap_or(0)
+}
+
+fn module_info_path(bundle: &Path) -> PathBuf {
+    bundle.join("Contents").join("Resources").join("moduleinfo.json")
+}
+
+#[derive(Deserialize)]
+struct ModuleInfo {
+    #[serde(rename = "Factory Info", default)]
+    factory_info: ModuleFactoryInfo,
+    #[serde(rename = "Classes", default)]
+    classes: Vec<ModuleClass>,
+    #[serde(rename = "Compatibility", default)]
+    compatibility: Vec<ModuleCompatibility>,
+}
+
+#[derive(Deserialize, Default)]
+struct ModuleFactoryInfo {
+    #[serde(rename = "Vendor", default)]
+    vendor: String,
+    #[serde(rename = "URL", default)]
+    url: String,
+    #[serde(rename = "E-Mail", default)]
+    email: String,
+}
+
+/// Older classes a class replaces
+#[derive(Deserialize)]
+struct ModuleCompatibility {
+    #[serde(rename = "Old", default)]
+    old: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ModuleClass {
+    #[serde(rename = "CID")]
+    cid: String,
+    #[serde(rename = "Category", default)]
+    category: String,
+    #[serde(rename = "Name", default)]
+    name: String,
+    #[serde(rename = "Vendor", default)]
+    vendor: String,
+    #[serde(rename = "Version", default)]
+    version: String,
+    #[serde(rename = "SDKVersion", default)]
+    sdk_version: String,
+    #[serde(rename = "Sub Categories", default)]
+    sub_categories: Vec<String>,
+    #[serde(rename = "Class Flags", default)]
+    class_flags: u32,
+    #[serde(rename = "Cardinality", default)]
+    cardinality: i32,
+}
+
+/// Reads `Contents/Resources/moduleinfo.json` if the bundle ships one.
+/// Classes replaced by another one in its `Compatibility` section are left out.
+pub fn read_module_info(bundle: &Path) -> Result<Option<PluginInfo>> {
+    let path = module_info_path(bundle);
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    // The file is JSON5, which allows comments and trailing commas
+    let module_info: ModuleInfo = json5::from_str(&fs::read_to_string(&path)?)?;
+
+    let replaced = module_info
+        .compatibility
+        .iter()
+        .flat_map(|compatibility| &compatibility.old)
+        .map(|cid| cid.to_uppercase())
+        .collect::<HashSet<_>>();
+
+    let classes = module_info
+        .classes
+        .into_iter()
+        .filter(|class| !replaced.contains(&class.cid.to_uppercase()))
+        .map(|class| ClassInfo {
+            cid: class.cid.to_uppercase(),
+            cardinality: class.cardinality,
+            category: class.category,
+            name: class.name,
+            class_flags: class.class_flags,
+            sub_categories: class.sub_categories,
+            vendor: class.vendor,
+            version: class.version,
+            sdk_version: class.sdk_version,
+        })
+        .collect();
+
+    Ok(Some(PluginInfo {
+        path: bundle.to_path_buf(),
+        modified: modified_time(bundle),
+        vendor: module_info.factory_info.vendor,
+        url: module_info.factory_info.url,
+        email: module_info.factory_info.email,
+        classes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_bundles, modified_time, read_module_info};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    /// A bundle in the temp directory containing only the given moduleinfo.json
+    fn bundle(name: &str, module_info: &str) -> PathBuf {
+        let bundle = std::env::temp_dir().join(format!("voxea_scanner_{}_{name}.vst3", std::process::id()));
+        let resources = bundle.join("Contents").join("Resources");
+
+        fs::create_dir_all(&resources).unwrap();
+        fs::write(resources.join("moduleinfo.json"), module_info).unwrap();
+
+        bundle
+    }
+
+    #[test]
+    fn json5() {
+        let bundle = bundle(
+            "json5",
+            r#"{
+                // Written by the SDK's moduleinfotool
+                "Name": "Test",
+                "Factory Info": {
+                    "Vendor": "Voxea",
+                    "URL": "https://example.com",
+                    "E-Mail": "mail@example.com",
+                },
+                "Classes": [
+                    {
+                        "CID": "e831ff31f2d54301928ebbee25697802",
+                        "Category": "Audio Module Class",
+                        "Name": "Gain",
+                        "Sub Categories": ["Fx", "Tools",],
+                        "Cardinality": 2147483647,
+                    },
+                ],
+            }"#,
+        );
+
+        let info = read_module_info(&bundle).unwrap().unwrap();
+        fs::remove_dir_all(&bundle).unwrap();
+
+        assert_eq!(info.vendor, "Voxea");
+        assert_eq!(info.email, "mail@example.com");
+        assert_eq!(info.classes.len(), 1);
+        assert_eq!(info.classes[0].cid, "E831FF31F2D54301928EBBEE25697802");
+        assert_eq!(info.classes[0].sub_categories, ["Fx", "Tools"]);
+    }
+
+    #[test]
+    fn replaced_classes_are_left_out() {
+        let bundle = bundle(
+            "compatibility",
+            r#"{
+                "Classes": [
+                    { "CID": "00000000000000000000000000000001", "Name": "Gain" },
+                    { "CID": "00000000000000000000000000000002", "Name": "Gain (Legacy)" },
+                ],
+                "Compatibility": [
+                    { "New": "00000000000000000000000000000001", "Old": ["00000000000000000000000000000002"] },
+                ],
+            }"#,
+        );
+
+        let info = read_module_info(&bundle).unwrap().unwrap();
+        fs::remove_dir_all(&bundle).unwrap();
+
+        let names = info.classes.iter().map(|class| class.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["Gain"]);
+    }
+
+    #[test]
+    fn missing_module_info() {
+        let bundle = std::env::temp_dir().join(format!("voxea_scanner_{}_missing.vst3", std::process::id()));
+
+        assert!(read_module_info(&bundle).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_module_info() {
+        let bundle = bundle("invalid", "{ \"Classes\": [ { \"Name\": \"No CID\" } ] }");
+
+        let result = read_module_info(&bundle);
+        fs::remove_dir_all(&bundle).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles() {
+        let root = std::env::temp_dir().join(format!("voxea_scanner_{}_cycle", std::process::id()));
+        let vendor = root.join("Vendor");
+
+        fs::create_dir_all(vendor.join("Gain.vst3")).unwrap();
+        std::os::unix::fs::symlink(&root, vendor.join("loop")).unwrap();
+
+        let bundles = find_bundles(std::slice::from_ref(&root));
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(bundles, [vendor.join("Gain.vst3")]);
+    }
+
+    #[test]
+    fn modified_with_module_info() {
+        let bundle = bundle("modified", "{}");
+        let module_info = bundle.join("Contents").join("Resources").join("moduleinfo.json");
+        let before = modified_time(&bundle);
+
+        // Replacing a file inside the bundle leaves the bundle directory's time unchanged
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options().write(true).open(&module_info).unwrap().set_modified(later).unwrap();
+
+        let after = modified_time(&bundle);
+        fs::remove_dir_all(&bundle).unwrap();
+
+        assert!(after > before);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    FUnknown, IBStream, IBStreamVTable, K_INVALID_ARGUMENT, K_NO_INTERFACE, K_RESULT_FALSE,
+    K_RESULT_OK, TUID,
+};
+use std::ffi::c_void;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+const SEEK_SET: i32 = 0;
+const SEEK_CUR: i32 = 1;
+const SEEK_END: i32 = 2;
+
+/// An `IBStream` implementation over anything readable, writable and seekable.
+/// Streams are owned by the host and only lent to plugins for the duration of a call,
+/// so reference counting is a no-op.
+#[repr(C)]
+pub struct BStream<T: Read + Write + Seek> {
+    vtable: *const IBStreamVTable,
+    inner: T,
+}
+
+/// Stream over an in-memory buffer
+pub type MemoryStream = BStream<Cursor<Vec<u8>>>;
+
+/// Stream over a file on disk
+pub type FileStream = BStream<File>;
+
+impl<T: Read + Write + Seek> BStream<T> {
+    const VTABLE: IBStreamVTable = IBStreamVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        read: Self::read,
+        write: Self::write,
+        seek: Self::seek,
+        tell: Self::tell,
+    };
+
+    pub fn new(inner: T) -> Self {
+        Self {
+            vtable: &Self::VTABLE,
+            inner,
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Pointer to pass to plugin interfaces, only valid while `self` is not moved
+    pub(crate) fn as_ptr(&mut self) -> *mut IBStream {
+        self as *mut Self as *mut IBStream
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IBStream) -> &'a mut Self {
+        &mut *(this as *mut Self)
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IBStream, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IBStream::iid() {
+            Self::add_ref(this);
+            *obj = this as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut IBStream) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut IBStream) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn read(this: *mut IBStream, buffer: *mut c_void, num_bytes: i32, num_bytes_read: *mut i32) -> i32 {
+        if buffer.is_null() || num_bytes < 0 {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let stream = Self::from_ptr(this);
+        let buffer = std::slice::from_raw_parts_mut(buffer as *mut u8, num_bytes as usize);
+
+        // Keeps reading until the buffer is full or the end of the stream is reached
+        let mut read = 0;
+        while read < buffer.len() {
+            match stream.inner.read(&mut buffer[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => return K_RESULT_FALSE,
+            }
+        }
+
+        if !num_bytes_read.is_null() {
+            *num_bytes_read = read as i32;
+        }
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn write(this: *mut IBStream, buffer: *mut c_void, num_bytes: i32, num_bytes_written: *mut i32) -> i32 {
+        if buffer.is_null() || num_bytes < 0 {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let stream = Self::from_ptr(this);
+        let buffer = std::slice::from_raw_parts(buffer as *const u8, num_bytes as usize);
+
+        if stream.inner.write_all(buffer).is_err() {
+            return K_RESULT_FALSE;
+        }
+
+        if !num_bytes_written.is_null() {
+            *num_bytes_written = num_bytes;
+        }
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn seek(this: *mut IBStream, pos: i64, mode: i32, result: *mut i64) -> i32 {
+        let from = match mode {
+            SEEK_SET if pos >= 0 => SeekFrom::Start(pos as u64),
+            SEEK_CUR => SeekFrom::Current(pos),
+            SEEK_END => SeekFrom::End(pos),
+            _ => return K_INVALID_ARGUMENT,
+        };
+
+        let Ok(new_pos) = Self::from_ptr(this).inner.seek(from) else {
+            return K_RESULT_FALSE;
+        };
+
+        if !result.is_null() {
+            *result = new_pos as i64;
+        }
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn tell(this: *mut IBStream, pos: *mut i64) -> i32 {
+        if pos.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let Ok(current) = Self::from_ptr(this).inner.stream_position() else {
+            return K_RESULT_FALSE;
+        };
+
+        *pos = current as i64;
+
+        K_RESULT_OK
+    }
+}
+
+impl MemoryStream {
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self::new(Cursor::new(data))
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.inner.into_inner()
+    }
+}
+
+impl Default for MemoryStream {
+    fn default() -> Self {
+        Self::from_bytes(Vec::new())
+    }
+}
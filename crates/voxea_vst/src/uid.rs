@@ -0,0 +1,213 @@
+use crate::TUID;
+use anyhow::{anyhow, Error};
+use libc::c_char;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A VST3 class or interface id.
+///
+/// Ids are written as four 32 bit values, but the 16 bytes plugins compare against are laid out differently per platform:
+/// on Windows they are COM compatible GUIDs, so the first value is little endian and both halves of the second are too,
+/// everywhere else all four values are big endian.
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FUID([u8; 16]);
+
+impl FUID {
+    /// Creates an id from its four values, like the SDK's `INLINE_UID`
+    pub const fn new(l1: u32, l2: u32, l3: u32, l4: u32) -> Self {
+        #[cfg(windows)]
+        let [a, b, c, d] = l1.to_le_bytes();
+        #[cfg(not(windows))]
+        let [a, b, c, d] = l1.to_be_bytes();
+
+        #[cfg(windows)]
+        let [e, f, g, h] = {
+            let [e, f, g, h] = l2.to_be_bytes();
+            [f, e, h, g]
+        };
+        #[cfg(not(windows))]
+        let [e, f, g, h] = l2.to_be_bytes();
+
+        let [i, j, k, l] = l3.to_be_bytes();
+        let [m, n, o, p] = l4.to_be_bytes();
+
+        Self([a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p])
+    }
+
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn from_tuid(tuid: &TUID) -> Self {
+        let mut bytes = [0; 16];
+        let mut i = 0;
+        while i < 16 {
+            bytes[i] = tuid[i] as u8;
+            i += 1;
+        }
+
+        Self(bytes)
+    }
+
+    /// Bytes in the platform layout
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    pub const fn to_tuid(self) -> TUID {
+        let mut tuid = [0; 16];
+        let mut i = 0;
+        while i < 16 {
+            tuid[i] = self.0[i] as c_char;
+            i += 1;
+        }
+
+        tuid
+    }
+
+    /// The four values the id was created from
+    pub const fn parts(&self) -> (u32, u32, u32, u32) {
+        let [a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p] = self.0;
+
+        #[cfg(windows)]
+        let l1 = u32::from_le_bytes([a, b, c, d]);
+        #[cfg(not(windows))]
+        let l1 = u32::from_be_bytes([a, b, c, d]);
+
+        #[cfg(windows)]
+        let l2 = u32::from_be_bytes([f, e, h, g]);
+        #[cfg(not(windows))]
+        let l2 = u32::from_be_bytes([e, f, g, h]);
+
+        (l1, l2, u32::from_be_bytes([i, j, k, l]), u32::from_be_bytes([m, n, o, p]))
+    }
+
+    /// The 32 hex characters used by `moduleinfo.json` and `.vstpreset` files
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl From<FUID> for TUID {
+    fn from(uid: FUID) -> Self {
+        uid.to_tuid()
+    }
+}
+
+impl From<TUID> for FUID {
+    fn from(tuid: TUID) -> Self {
+        Self::from_tuid(&tuid)
+    }
+}
+
+/// Formats the id as 32 hex characters, or with `{:#}` in the registry form `{XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX}`
+impl Display for FUID {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (l1, l2, l3, l4) = self.parts();
+
+        if f.alternate() {
+            write!(
+                f,
+                "{{{l1:08X}-{:04X}-{:04X}-{:04X}-{:04X}{l4:08X}}}",
+                l2 >> 16,
+                l2 & 0xFFFF,
+                l3 >> 16,
+                l3 & 0xFFFF
+            )
+        } else {
+            write!(f, "{l1:08X}{l2:08X}{l3:08X}{l4:08X}")
+        }
+    }
+}
+
+/// Parses 32 hex characters, optionally in the registry form with dashes and braces
+impl FromStr for FUID {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.strip_prefix('{').and_then(|s| s.strip_suffix('}')).unwrap_or(s);
+
+        let hex = if trimmed.len() == 36 {
+            let dashes = [8, 13, 18, 23];
+            if !dashes.iter().all(|&i| trimmed.as_bytes()[i] == b'-') {
+                return Err(anyhow!("Invalid uid {s}"));
+            }
+
+            trimmed.replace('-', "")
+        } else {
+            trimmed.to_string()
+        };
+
+        if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(anyhow!("Invalid uid {s}"));
+        }
+
+        let part = |i: usize| u32::from_str_radix(&hex[i * 8..i * 8 + 8], 16).unwrap();
+
+        Ok(Self::new(part(0), part(1), part(2), part(3)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FUID;
+    use crate::{FUnknown, IComponent};
+
+    #[test]
+    fn funknown_layout() {
+        assert_eq!(
+            FUID::from_tuid(&FUnknown::iid()).as_bytes(),
+            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46]
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn icomponent_layout() {
+        assert_eq!(
+            FUID::from_tuid(&IComponent::iid()).as_bytes(),
+            &[0x31, 0xFF, 0x31, 0xE8, 0xD5, 0xF2, 0x01, 0x43, 0x92, 0x8E, 0xBB, 0xEE, 0x25, 0x69, 0x78, 0x02]
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn icomponent_layout() {
+        assert_eq!(
+            FUID::from_tuid(&IComponent::iid()).as_bytes(),
+            &[0xE8, 0x31, 0xFF, 0x31, 0xF2, 0xD5, 0x43, 0x01, 0x92, 0x8E, 0xBB, 0xEE, 0x25, 0x69, 0x78, 0x02]
+        );
+    }
+
+    #[test]
+    fn parts_round_trip() {
+        let uid = FUID::new(0xE831FF31, 0xF2D54301, 0x928EBBEE, 0x25697802);
+
+        assert_eq!(uid.parts(), (0xE831FF31, 0xF2D54301, 0x928EBBEE, 0x25697802));
+        assert_eq!(FUID::from_tuid(&uid.to_tuid()), uid);
+    }
+
+    #[test]
+    fn display() {
+        let uid = FUID::from_tuid(&IComponent::iid());
+
+        assert_eq!(uid.to_string(), "E831FF31F2D54301928EBBEE25697802");
+        assert_eq!(format!("{uid:#}"), "{E831FF31-F2D5-4301-928E-BBEE25697802}");
+        assert_eq!(FUID::from_tuid(&FUnknown::iid()).to_string(), "0000000000000000C000000000000046");
+    }
+
+    #[test]
+    fn parse() {
+        let uid = FUID::from_tuid(&IComponent::iid());
+
+        assert_eq!("E831FF31F2D54301928EBBEE25697802".parse::<FUID>().unwrap(), uid);
+        assert_eq!("e831ff31f2d54301928ebbee25697802".parse::<FUID>().unwrap(), uid);
+        assert_eq!("{E831FF31-F2D5-4301-928E-BBEE25697802}".parse::<FUID>().unwrap(), uid);
+        assert_eq!("E831FF31-F2D5-4301-928E-BBEE25697802".parse::<FUID>().unwrap(), uid);
+
+        assert!("E831FF31F2D54301928EBBEE256978".parse::<FUID>().is_err());
+        assert!("E831FF31F2D54301928EBBEE2569780G".parse::<FUID>().is_err());
+        assert!("{E831FF31F-2D5-4301-928E-BBEE25697802}".parse::<FUID>().is_err());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::module::Module;
+use crate::{
+    FUnknown, IEditController, IEventHandler, IPlugFrame, IPlugFrameVTable, IPlugView, IRunLoop, IRunLoopVTable,
+    ITimerHandler, ViewRect, K_INVALID_ARGUMENT, K_NO_INTERFACE, K_RESULT_OK, K_RESULT_TRUE, TUID,
+};
+use anyhow::{anyhow, Result};
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_void, CStr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const PLATFORM_HWND: &CStr = c"HWND";
+pub const PLATFORM_X11: &CStr = c"X11EmbedWindowID";
+pub const PLATFORM_NSVIEW: &CStr = c"NSView";
+
+/// Native window an editor is embedded into
+#[derive(Debug, Clone, Copy)]
+pub enum ParentWindow {
+    Hwnd(*mut c_void),
+    /// X11 window id
+    X11(u64),
+    NsView(*mut c_void),
+}
+
+impl ParentWindow {
+    pub fn platform_type(&self) -> &'static CStr {
+        match self {
+            Self::Hwnd(_) => PLATFORM_HWND,
+            Self::X11(_) => PLATFORM_X11,
+            Self::NsView(_) => PLATFORM_NSVIEW,
+        }
+    }
+
+    fn as_ptr(&self) -> *mut c_void {
+        match *self {
+            Self::Hwnd(hwnd) => hwnd,
+            Self::X11(window) => window as usize as *mut c_void,
+            Self::NsView(view) => view,
+        }
+    }
+}
+
+struct Timer {
+    handler: *mut ITimerHandler,
+    interval: Duration,
+    next: Instant,
+}
+
+/// The host side of an editor: the `IPlugFrame` the view requests resizes through,
+/// which also hands out the `IRunLoop` Linux plugins use to get called back on the UI thread.
+#[repr(C)]
+struct PlugFrame {
+    frame_vtable: *const IPlugFrameVTable,
+    run_loop_vtable: *const IRunLoopVTable,
+    resize_request: Cell<Option<ViewRect>>,
+    event_handlers: RefCell<Vec<(*mut IEventHandler, i32)>>,
+    timers: RefCell<Vec<Timer>>,
+}
+
+impl PlugFrame {
+    const FRAME_VTABLE: IPlugFrameVTable = IPlugFrameVTable {
+        queryInterface: Self::query_interface,
+        addRef: Self::add_ref,
+        release: Self::release,
+        resizeView: Self::resize_view,
+    };
+
+    const RUN_LOOP_VTABLE: IRunLoopVTable = IRunLoopVTable {
+        queryInterface: Self::run_loop_query_interface,
+        addRef: Self::run_loop_add_ref,
+        release: Self::run_loop_release,
+        registerEventHandler: Self::register_event_handler,
+        unregisterEventHandler: Self::unregister_event_handler,
+        registerTimer: Self::register_timer,
+        unregisterTimer: Self::unregister_timer,
+    };
+
+    fn new() -> Self {
+        Self {
+            frame_vtable: &Self::FRAME_VTABLE,
+            run_loop_vtable: &Self::RUN_LOOP_VTABLE,
+            resize_request: Cell::new(None),
+            event_handlers: RefCell::new(Vec::new()),
+            timers: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn as_ptr(&mut self) -> *mut IPlugFrame {
+        self as *mut Self as *mut IPlugFrame
+    }
+
+    unsafe fn from_ptr<'a>(this: *mut IPlugFrame) -> &'a Self {
+        &*(this as *const Self)
+    }
+
+    unsafe fn from_run_loop<'a>(this: *mut IRunLoop) -> &'a Self {
+        &*((this as *const u8).sub(std::mem::offset_of!(PlugFrame, run_loop_vtable)) as *const Self)
+    }
+
+    /// Calls the handlers of ready file descriptors and due timers
+    fn poll(&self) {
+        #[cfg(unix)]
+        {
+            let mut fds = self
+                .event_handlers
+                .borrow()
+                .iter()
+                .map(|&(_, fd)| libc::pollfd {
+                    fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                })
+                .collect::<Vec<_>>();
+
+            if !fds.is_empty() && unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, 0) } > 0 {
+                for fd in fds.iter().filter(|fd| fd.revents != 0).map(|fd| fd.fd) {
+                    // Handlers may unregister each other while being called
+                    let handler = self.event_handlers.borrow().iter().find(|&&(_, f)| f == fd).map(|&(h, _)| h);
+
+                    if let Some(handler) = handler {
+                        unsafe { (*handler).on_fd_is_set(fd) };
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let due = self
+            .timers
+            .borrow_mut()
+            .iter_mut()
+            .filter(|timer| timer.next <= now)
+            .map(|timer| {
+                timer.next = now + timer.interval;
+                timer.handler
+            })
+            .collect::<Vec<_>>();
+
+        for handler in due {
+            if self.timers.borrow().iter().any(|timer| timer.handler == handler) {
+                unsafe { (*handler).on_timer() };
+            }
+        }
+    }
+
+    unsafe fn query(this: &Self, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        if *iid == FUnknown::iid() || *iid == IPlugFrame::iid() {
+            *obj = &this.frame_vtable as *const _ as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        if *iid == IRunLoop::iid() {
+            *obj = &this.run_loop_vtable as *const _ as *mut c_void;
+            return K_RESULT_OK;
+        }
+
+        *obj = std::ptr::null_mut();
+        K_NO_INTERFACE
+    }
+
+    unsafe extern "system" fn query_interface(this: *mut IPlugFrame, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        Self::query(Self::from_ptr(this), iid, obj)
+    }
+
+    unsafe extern "system" fn add_ref(_this: *mut IPlugFrame) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn release(_this: *mut IPlugFrame) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn resize_view(this: *mut IPlugFrame, _view: *mut IPlugView, new_size: *mut ViewRect) -> i32 {
+        if new_size.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        Self::from_ptr(this).resize_request.set(Some(*new_size));
+
+        K_RESULT_OK
+    }
+
+    unsafe extern "system" fn run_loop_query_interface(this: *mut IRunLoop, iid: *const TUID, obj: *mut *mut c_void) -> i32 {
+        Self::query(Self::from_run_loop(this), iid, obj)
+    }
+
+    unsafe extern "system" fn run_loop_add_ref(_this: *mut IRunLoop) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn run_loop_release(_this: *mut IRunLoop) -> u32 {
+        1
+    }
+
+    unsafe extern "system" fn register_event_handler(this: *mut IRunLoop, handler: *mut IEventHandler, fd: i32) -> i32 {
+        if handler.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        (*handler).add_ref();
+        Self::from_run_loop(this).event_handlers.borrow_mut().push((handler, fd));
+
+        K_RESULT_TRUE
+    }
+
+    unsafe extern "system" fn unregister_event_handler(this: *mut IRunLoop, handler: *mut IEventHandler) -> i32 {
+        let mut handlers = Self::from_run_loop(this).event_handlers.borrow_mut();
+
+        let count = handlers.len();
+        handlers.retain(|&(h, _)| h != handler);
+
+        for _ in handlers.len()..count {
+            (*handler).release();
+        }
+
+        K_RESULT_TRUE
+    }
+
+    unsafe extern "system" fn register_timer(this: *mut IRunLoop, handler: *mut ITimerHandler, milliseconds: u64) -> i32 {
+        if handler.is_null() {
+            return K_INVALID_ARGUMENT;
+        }
+
+        let interval = Duration::from_millis(milliseconds.max(1));
+
+        (*handler).add_ref();
+        Self::from_run_loop(this).timers.borrow_mut().push(Timer {
+            handler,
+            interval,
+            next: Instant::now() + interval,
+        });
+
+        K_RESULT_TRUE
+    }
+
+    unsafe extern "system" fn unregister_timer(this: *mut IRunLoop, handler: *mut ITimerHandler) -> i32 {
+        let mut timers = Self::from_run_loop(this).timers.borrow_mut();
+
+        let count = timers.len();
+        timers.retain(|timer| timer.handler != handler);
+
+        for _ in timers.len()..count {
+            (*handler).release();
+        }
+
+        K_RESULT_TRUE
+    }
+}
+
+impl Drop for PlugFrame {
+    fn drop(&mut self) {
+        unsafe {
+            for (handler, _) in self.event_handlers.take() {
+                (*handler).release();
+            }
+
+            for timer in self.timers.take() {
+                (*timer.handler).release();
+            }
+        }
+    }
+}
+
+/// The editor of a plugin. It keeps the edit controller alive and has to be used on the UI thread.
+pub struct PlugView {
+    view: *mut IPlugView,
+    frame: Box<PlugFrame>,
+    controller: *mut IEditController,
+    _module: Arc<Module>,
+    attached: bool,
+}
+
+impl PlugView {
+    /// Creates the `editor` view of a controller
+    pub(crate) unsafe fn new(controller: *mut IEditController, module: Arc<Module>) -> Result<Self> {
+        let view = (*controller).create_view(c"editor".as_ptr());
+        if view.is_null() {
+            return Err(anyhow!("Plugin has no editor"));
+        }
+
+        (*controller).base().add_ref();
+
+        let mut frame = Box::new(PlugFrame::new());
+        (*view).set_frame(frame.as_ptr());
+
+        Ok(Self {
+            view,
+            frame,
+            controller,
+            _module: module,
+            attached: false,
+        })
+    }
+
+    pub fn is_platform_type_supported(&mut self, platform_type: &CStr) -> bool {
+        unsafe { (*self.view).is_platform_type_supported(platform_type.as_ptr()) == K_RESULT_TRUE }
+    }
+
+    /// Embeds the view into a native window
+    pub fn attach(&mut self, parent: ParentWindow) -> Result<()> {
+        if self.attached {
+            return Err(anyhow!("Editor is already attached"));
+        }
+
+        let platform_type = parent.platform_type();
+
+        if !self.is_platform_type_supported(platform_type) {
+            return Err(anyhow!("Editor does not support {platform_type:?} windows"));
+        }
+
+        unsafe {
+            if (*self.view).attached(parent.as_ptr(), platform_type.as_ptr()) != K_RESULT_OK {
+                return Err(anyhow!("Could not attach editor"));
+            }
+        }
+
+        self.attached = true;
+
+        Ok(())
+    }
+
+    /// Detaches the view from its window, this also happens when dropped
+    pub fn detach(&mut self) {
+        if self.attached {
+            unsafe { (*self.view).removed() };
+            self.attached = false;
+        }
+    }
+
+    pub fn size(&mut self) -> Option<ViewRect> {
+        let mut rect = ViewRect::default();
+
+        unsafe { ((*self.view).get_size(&mut rect) == K_RESULT_OK).then_some(rect) }
+    }
+
+    pub fn can_resize(&mut self) -> bool {
+        unsafe { (*self.view).can_resize() == K_RESULT_TRUE }
+    }
+
+    /// Adjusts a size to the constraints of the view
+    pub fn constrain_size(&mut self, mut rect: ViewRect) -> ViewRect {
+        unsafe { (*self.view).check_size_constraint(&mut rect) };
+        rect
+    }
+
+    /// Tells the view its window was resized
+    pub fn set_size(&mut self, mut rect: ViewRect) {
+        unsafe { (*self.view).on_size(&mut rect) };
+    }
+
+    pub fn set_focus(&mut self, focus: bool) {
+        unsafe { (*self.view).on_focus(focus) };
+    }
+
+    /// Size the view asked its window to be resized to since the last call.
+    /// The host resizes the window and then calls [`PlugView::set_size`].
+    pub fn take_resize_request(&self) -> Option<ViewRect> {
+        self.frame.resize_request.take()
+    }
+
+    /// Runs the timers and file descriptor handlers the plugin registered, should be called regularly on the UI thread
+    pub fn poll(&self) {
+        self.frame.poll();
+    }
+}
+
+impl Drop for PlugView {
+    fn drop(&mut self) {
+        self.detach();
+
+        unsafe {
+            (*self.view).set_frame(std::ptr::null_mut());
+            (*self.view).release();
+            (*self.controller).base().release();
+        }
+    }
+}
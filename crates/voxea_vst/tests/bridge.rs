@@ -0,0 +1,88 @@
+#![cfg(unix)]
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use voxea_vst::bridge::BridgedPlugin;
+use voxea_vst::module::Module;
+use voxea_vst::node::{Deadline, HostingMode, PluginNode};
+
+const BLOCK: usize = 64;
+
+/// Held while a test depends on the environment, which is shared by every test of the process
+static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+/// The crash plugin example, built by cargo together with the tests
+fn crash_plugin() -> PathBuf {
+    let helper = PathBuf::from(env!("CARGO_BIN_EXE_voxea_vst"));
+
+    helper
+        .parent()
+        .unwrap()
+        .join("examples")
+        .join(format!("{}crash_plugin{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX))
+}
+
+fn class_id() -> String {
+    let module = Module::load(crash_plugin()).unwrap();
+    module.classes()[0].cid.clone()
+}
+
+#[test]
+fn bridged_plugin_is_bypassed_after_crashing() {
+    let helper = PathBuf::from(env!("CARGO_BIN_EXE_voxea_vst"));
+    let _environment = ENVIRONMENT.lock().unwrap();
+
+    // The helper inherits the environment, so the plugin aborts in its third block
+    std::env::set_var("VOXEA_CRASH_AFTER", "2");
+    let node = BridgedPlugin::spawn(&helper, &crash_plugin(), &class_id(), 48000.0, BLOCK);
+    std::env::remove_var("VOXEA_CRASH_AFTER");
+
+    let mut node = node.unwrap();
+    // Generous, so a slow machine does not miss blocks
+    node.set_deadline(Deadline::Fixed(Duration::from_secs(5)));
+
+    let left = [1.0; BLOCK];
+    let right = [-1.0; BLOCK];
+    let mut out_left = [0.0; BLOCK];
+    let mut out_right = [0.0; BLOCK];
+
+    for _ in 0..2 {
+        node.process(&[&left, &right], &mut [&mut out_left, &mut out_right], &[], &[]).unwrap();
+
+        assert!(!node.is_failed());
+        assert_eq!(out_left, [0.5; BLOCK]);
+        assert_eq!(out_right, [-0.5; BLOCK]);
+    }
+
+    // The helper dies without finishing the block, which stays silent
+    node.set_deadline(Deadline::Fixed(Duration::from_millis(100)));
+    node.process(&[&left, &right], &mut [&mut out_left, &mut out_right], &[], &[]).unwrap();
+    assert_eq!(out_left, [0.0; BLOCK]);
+    assert_eq!(node.missed_blocks(), 1);
+
+    // The watchdog notices the crash
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !node.is_failed() {
+        assert!(Instant::now() < deadline, "The crash was not noticed");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    node.process(&[&left, &right], &mut [&mut out_left, &mut out_right], &[], &[]).unwrap();
+    assert_eq!(out_left, left);
+    assert_eq!(out_right, right);
+}
+
+#[test]
+fn in_process_plugin_processes_audio() {
+    let _environment = ENVIRONMENT.lock().unwrap();
+    let mut node = PluginNode::new(HostingMode::InProcess, &PathBuf::new(), &crash_plugin(), &class_id(), 48000.0, BLOCK).unwrap();
+
+    let input = [1.0; BLOCK];
+    let mut output = [0.0; BLOCK];
+
+    node.process(&[&input, &input], &mut [&mut output], &[], &[]).unwrap();
+
+    assert_eq!(output, [0.5; BLOCK]);
+    assert!(!node.is_failed());
+}
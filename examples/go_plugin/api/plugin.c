@@ -16,10 +16,47 @@ extern double __wasm_import_sdk_component_registry_get_signal(int64_t);
 __attribute__((__import_module__("sdk:component/registry"), __import_name__("set-signal")))
 extern double __wasm_import_sdk_component_registry_set_signal(int64_t, double);
 
+__attribute__((__import_module__("sdk:component/registry"), __import_name__("get-parameter")))
+extern float __wasm_import_sdk_component_registry_get_parameter(int32_t);
+
+__attribute__((__import_module__("sdk:component/registry"), __import_name__("set-parameter")))
+extern void __wasm_import_sdk_component_registry_set_parameter(int32_t, float);
+
 // Exported Functions from `sdk:component/plugin-api`
 
+__attribute__((__weak__, __export_name__("cabi_post_sdk:component/plugin-api#icon")))
+void __wasm_export_exports_sdk_component_plugin_api_icon_post_return(uint8_t * arg0) {
+  plugin_list_u8_t ret = (plugin_list_u8_t) { (uint8_t*)(*((uint8_t **) (arg0 + 0))), (*((size_t*) (arg0 + 4))) };
+  plugin_list_u8_free(&ret);
+}
+
+__attribute__((__weak__, __export_name__("cabi_post_sdk:component/plugin-api#parameters")))
+void __wasm_export_exports_sdk_component_plugin_api_parameters_post_return(uint8_t * arg0) {
+  exports_sdk_component_plugin_api_list_parameter_t ret = (exports_sdk_component_plugin_api_list_parameter_t) { (exports_sdk_component_plugin_api_parameter_t*)(*((uint8_t **) (arg0 + 0))), (*((size_t*) (arg0 + 4))) };
+  exports_sdk_component_plugin_api_list_parameter_free(&ret);
+}
 
+__attribute__((__weak__, __export_name__("cabi_post_sdk:component/plugin-api#layout")))
+void __wasm_export_exports_sdk_component_plugin_api_layout_post_return(uint8_t * arg0) {
+  switch ((int32_t) (*((uint8_t*) (arg0 + 0)))) {
+    case 0: {
+      break;
+    }
+    case 1: {
+      exports_sdk_component_plugin_api_layout_free((exports_sdk_component_plugin_api_layout_t*) (arg0 + 4));
+      break;
+    }
+  }
+}
+
+__attribute__((__weak__, __export_name__("cabi_post_sdk:component/plugin-api#actions")))
+void __wasm_export_exports_sdk_component_plugin_api_actions_post_return(uint8_t * arg0) {
+  exports_sdk_component_plugin_api_list_action_t ret = (exports_sdk_component_plugin_api_list_action_t) { (exports_sdk_component_plugin_api_action_t*)(*((uint8_t **) (arg0 + 0))), (*((size_t*) (arg0 + 4))) };
+  exports_sdk_component_plugin_api_list_action_free(&ret);
+}
 
+__attribute__((__aligned__(4)))
+static uint8_t RET_AREA[36];
 
 // Canonical ABI intrinsics
 
@@ -34,6 +71,117 @@ void *cabi_realloc(void *ptr, size_t old_size, size_t align, size_t new_size) {
 
 // Helper Functions
 
+void sdk_component_ui_parameter_free(sdk_component_ui_parameter_t *ptr) {
+  plugin_string_free(&ptr->name);
+  plugin_string_free(&ptr->unit);
+}
+
+void sdk_component_ui_widget_free(sdk_component_ui_widget_t *ptr) {
+  switch ((int32_t) ptr->tag) {
+    case 2: {
+      plugin_string_free(&ptr->val.group);
+      break;
+    }
+    case 7: {
+      plugin_string_free(&ptr->val.label);
+      break;
+    }
+    case 8: {
+      plugin_string_free(&ptr->val.image);
+      break;
+    }
+  }
+}
+
+void plugin_option_u32_free(plugin_option_u32_t *ptr) {
+  if (ptr->is_some) {
+  }
+}
+
+void sdk_component_ui_node_free(sdk_component_ui_node_t *ptr) {
+  sdk_component_ui_widget_free(&ptr->widget);
+  plugin_option_u32_free(&ptr->parent);
+}
+
+void plugin_list_u8_free(plugin_list_u8_t *ptr) {
+  if (ptr->len > 0) {
+    free(ptr->ptr);
+  }
+}
+
+void sdk_component_ui_image_free(sdk_component_ui_image_t *ptr) {
+  plugin_string_free(&ptr->name);
+  plugin_list_u8_free(&ptr->data);
+}
+
+void sdk_component_ui_list_node_free(sdk_component_ui_list_node_t *ptr) {
+  for (size_t i = 0; i < ptr->len; i++) {
+    sdk_component_ui_node_free(&ptr->ptr[i]);
+  }
+  if (ptr->len > 0) {
+    free(ptr->ptr);
+  }
+}
+
+void sdk_component_ui_list_image_free(sdk_component_ui_list_image_t *ptr) {
+  for (size_t i = 0; i < ptr->len; i++) {
+    sdk_component_ui_image_free(&ptr->ptr[i]);
+  }
+  if (ptr->len > 0) {
+    free(ptr->ptr);
+  }
+}
+
+void sdk_component_ui_layout_free(sdk_component_ui_layout_t *ptr) {
+  plugin_string_free(&ptr->title);
+  sdk_component_ui_list_node_free(&ptr->nodes);
+  sdk_component_ui_list_image_free(&ptr->images);
+}
+
+void exports_sdk_component_plugin_api_parameter_free(exports_sdk_component_plugin_api_parameter_t *ptr) {
+  sdk_component_ui_parameter_free(ptr);
+}
+
+void exports_sdk_component_plugin_api_layout_free(exports_sdk_component_plugin_api_layout_t *ptr) {
+  sdk_component_ui_layout_free(ptr);
+}
+
+void plugin_option_string_free(plugin_option_string_t *ptr) {
+  if (ptr->is_some) {
+    plugin_string_free(&ptr->val);
+  }
+}
+
+void exports_sdk_component_plugin_api_action_free(exports_sdk_component_plugin_api_action_t *ptr) {
+  plugin_string_free(&ptr->id);
+  plugin_string_free(&ptr->name);
+  plugin_option_string_free(&ptr->shortcut);
+}
+
+void exports_sdk_component_plugin_api_list_parameter_free(exports_sdk_component_plugin_api_list_parameter_t *ptr) {
+  for (size_t i = 0; i < ptr->len; i++) {
+    exports_sdk_component_plugin_api_parameter_free(&ptr->ptr[i]);
+  }
+  if (ptr->len > 0) {
+    free(ptr->ptr);
+  }
+}
+
+void exports_sdk_component_plugin_api_option_layout_free(exports_sdk_component_plugin_api_option_layout_t *ptr) {
+  if (ptr->is_some) {
+    exports_sdk_component_plugin_api_layout_free(&ptr->val);
+  }
+}
+
+void exports_sdk_component_plugin_api_list_action_free(exports_sdk_component_plugin_api_list_action_t *ptr) {
+  for (size_t i = 0; i < ptr->len; i++) {
+    exports_sdk_component_plugin_api_action_free(&ptr->ptr[i]);
+  }
+  if (ptr->len > 0) {
+    free(ptr->ptr);
+  }
+}
+
 void plugin_string_set(plugin_string_t *ret, const char*s) {
   ret->ptr = (uint8_t*) s;
   ret->len = strlen(s);
@@ -69,6 +217,15 @@ double sdk_component_registry_set_signal(uint64_t idx, double val) {
   return ret;
 }
 
+float sdk_component_registry_get_parameter(uint32_t id) {
+  float ret = __wasm_import_sdk_component_registry_get_parameter((int32_t) (id));
+  return ret;
+}
+
+void sdk_component_registry_set_parameter(uint32_t id, float value) {
+  __wasm_import_sdk_component_registry_set_parameter((int32_t) (id), value);
+}
+
 __attribute__((__export_name__("sdk:component/plugin-api#enable")))
 int32_t __wasm_export_exports_sdk_component_plugin_api_enable(void) {
   int32_t ret = exports_sdk_component_plugin_api_enable();
@@ -81,6 +238,69 @@ int32_t __wasm_export_exports_sdk_component_plugin_api_disable(void) {
   return ret;
 }
 
+__attribute__((__export_name__("sdk:component/plugin-api#icon")))
+uint8_t * __wasm_export_exports_sdk_component_plugin_api_icon(void) {
+  plugin_list_u8_t ret;
+  exports_sdk_component_plugin_api_icon(&ret);
+  uint8_t *ptr = (uint8_t *) &RET_AREA;
+  *((size_t*)(ptr + 4)) = (ret).len;
+  *((uint8_t **)(ptr + 0)) = (uint8_t *) (ret).ptr;
+  return ptr;
+}
+
+__attribute__((__export_name__("sdk:component/plugin-api#parameters")))
+uint8_t * __wasm_export_exports_sdk_component_plugin_api_parameters(void) {
+  exports_sdk_component_plugin_api_list_parameter_t ret;
+  exports_sdk_component_plugin_api_parameters(&ret);
+  uint8_t *ptr = (uint8_t *) &RET_AREA;
+  *((size_t*)(ptr + 4)) = (ret).len;
+  *((uint8_t **)(ptr + 0)) = (uint8_t *) (ret).ptr;
+  return ptr;
+}
+
+__attribute__((__export_name__("sdk:component/plugin-api#layout")))
+uint8_t * __wasm_export_exports_sdk_component_plugin_api_layout(void) {
+  exports_sdk_component_plugin_api_option_layout_t ret;
+  ret.is_some = exports_sdk_component_plugin_api_layout(&ret.val);
+  uint8_t *ptr = (uint8_t *) &RET_AREA;
+  if ((ret).is_some) {
+    const exports_sdk_component_plugin_api_layout_t *payload0 = &(ret).val;
+    *((int8_t*)(ptr + 0)) = 1;
+    *((size_t*)(ptr + 8)) = ((*payload0).title).len;
+    *((uint8_t **)(ptr + 4)) = (uint8_t *) ((*payload0).title).ptr;
+    *((int32_t*)(ptr + 12)) = (int32_t) ((*payload0).width);
+    *((int32_t*)(ptr + 16)) = (int32_t) ((*payload0).height);
+    *((size_t*)(ptr + 24)) = ((*payload0).nodes).len;
+    *((uint8_t **)(ptr + 20)) = (uint8_t *) ((*payload0).nodes).ptr;
+    *((size_t*)(ptr + 32)) = ((*payload0).images).len;
+    *((uint8_t **)(ptr + 28)) = (uint8_t *) ((*payload0).images).ptr;
+  } else {
+    *((int8_t*)(ptr + 0)) = 0;
+  }
+  return ptr;
+}
+
+__attribute__((__export_name__("sdk:component/plugin-api#parameter-changed")))
+void __wasm_export_exports_sdk_component_plugin_api_parameter_changed(int32_t arg, float arg0) {
+  exports_sdk_component_plugin_api_parameter_changed((uint32_t) (arg), arg0);
+}
+
+__attribute__((__export_name__("sdk:component/plugin-api#actions")))
+uint8_t * __wasm_export_exports_sdk_component_plugin_api_actions(void) {
+  exports_sdk_component_plugin_api_list_action_t ret;
+  exports_sdk_component_plugin_api_actions(&ret);
+  uint8_t *ptr = (uint8_t *) &RET_AREA;
+  *((size_t*)(ptr + 4)) = (ret).len;
+  *((uint8_t **)(ptr + 0)) = (uint8_t *) (ret).ptr;
+  return ptr;
+}
+
+__attribute__((__export_name__("sdk:component/plugin-api#run-action")))
+void __wasm_export_exports_sdk_component_plugin_api_run_action(uint8_t * arg, size_t arg0) {
+  plugin_string_t arg1 = (plugin_string_t) { (uint8_t*)(arg), (arg0) };
+  exports_sdk_component_plugin_api_run_action(&arg1);
+}
+
 __attribute__((__export_name__("sdk:component/plugin-api#process-signal")))
 void __wasm_export_exports_sdk_component_plugin_api_process_signal(int64_t arg) {
   exports_sdk_component_plugin_api_process_signal((uint64_t) (arg));
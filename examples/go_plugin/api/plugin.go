@@ -4,7 +4,180 @@ package plugin
 // #include "plugin.h"
 // #include <stdlib.h>
 import "C"
+import "fmt"
 import "unsafe"
+
+// Types from sdk:component/ui
+type SdkComponentUiParameter struct {
+  Id uint32
+  Name string
+  Min float32
+  Max float32
+  DefaultValue float32
+  Unit string
+}
+
+type SdkComponentUiWidgetKind int
+
+const (
+SdkComponentUiWidgetKindRow SdkComponentUiWidgetKind = iota
+SdkComponentUiWidgetKindColumn
+SdkComponentUiWidgetKindGroup
+SdkComponentUiWidgetKindKnob
+SdkComponentUiWidgetKindSlider
+SdkComponentUiWidgetKindToggle
+SdkComponentUiWidgetKindMeter
+SdkComponentUiWidgetKindLabel
+SdkComponentUiWidgetKindImage
+)
+
+type SdkComponentUiWidget struct {
+  kind SdkComponentUiWidgetKind
+  val any
+}
+
+func (n SdkComponentUiWidget) Kind() SdkComponentUiWidgetKind {
+  return n.kind
+}
+
+func SdkComponentUiWidgetRow() SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindRow}
+}
+
+func SdkComponentUiWidgetColumn() SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindColumn}
+}
+
+func SdkComponentUiWidgetGroup(v string) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindGroup, val: v}
+}
+
+func (n SdkComponentUiWidget) GetGroup() string {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindGroup; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(string)
+}
+
+func (n *SdkComponentUiWidget) SetGroup(v string) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindGroup
+}
+
+func SdkComponentUiWidgetKnob(v uint32) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindKnob, val: v}
+}
+
+func (n SdkComponentUiWidget) GetKnob() uint32 {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindKnob; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(uint32)
+}
+
+func (n *SdkComponentUiWidget) SetKnob(v uint32) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindKnob
+}
+
+func SdkComponentUiWidgetSlider(v uint32) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindSlider, val: v}
+}
+
+func (n SdkComponentUiWidget) GetSlider() uint32 {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindSlider; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(uint32)
+}
+
+func (n *SdkComponentUiWidget) SetSlider(v uint32) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindSlider
+}
+
+func SdkComponentUiWidgetToggle(v uint32) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindToggle, val: v}
+}
+
+func (n SdkComponentUiWidget) GetToggle() uint32 {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindToggle; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(uint32)
+}
+
+func (n *SdkComponentUiWidget) SetToggle(v uint32) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindToggle
+}
+
+func SdkComponentUiWidgetMeter(v uint32) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindMeter, val: v}
+}
+
+func (n SdkComponentUiWidget) GetMeter() uint32 {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindMeter; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(uint32)
+}
+
+func (n *SdkComponentUiWidget) SetMeter(v uint32) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindMeter
+}
+
+func SdkComponentUiWidgetLabel(v string) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindLabel, val: v}
+}
+
+func (n SdkComponentUiWidget) GetLabel() string {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindLabel; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(string)
+}
+
+func (n *SdkComponentUiWidget) SetLabel(v string) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindLabel
+}
+
+func SdkComponentUiWidgetImage(v string) SdkComponentUiWidget{
+  return SdkComponentUiWidget{kind: SdkComponentUiWidgetKindImage, val: v}
+}
+
+func (n SdkComponentUiWidget) GetImage() string {
+  if g, w := n.Kind(), SdkComponentUiWidgetKindImage; g != w {
+    panic(fmt.Sprintf("Attr kind is %v, not %v", g, w))
+  }
+  return n.val.(string)
+}
+
+func (n *SdkComponentUiWidget) SetImage(v string) {
+  n.val = v
+  n.kind = SdkComponentUiWidgetKindImage
+}
+
+type SdkComponentUiNode struct {
+  Widget SdkComponentUiWidget
+  Parent Option[uint32]
+}
+
+type SdkComponentUiImage struct {
+  Name string
+  Data []uint8
+}
+
+type SdkComponentUiLayout struct {
+  Title string
+  Width uint32
+  Height uint32
+  Nodes []SdkComponentUiNode
+  Images []SdkComponentUiImage
+}
+
 // Import functions from sdk:component/logger
 func SdkComponentLoggerLog(text string) {
   var lower_text C.plugin_string_t
@@ -33,7 +206,29 @@ func SdkComponentRegistrySetSignal(idx uint64, val float64) float64 {
   return lift_ret
 }
 
+func SdkComponentRegistryGetParameter(id uint32) float32 {
+  lower_id := C.uint32_t(id)
+  ret := C.sdk_component_registry_get_parameter(lower_id )
+  var lift_ret float32
+  lift_ret = float32(ret)
+  return lift_ret
+}
+
+func SdkComponentRegistrySetParameter(id uint32, value float32) {
+  lower_id := C.uint32_t(id)
+  lower_value := C.float(value)
+  C.sdk_component_registry_set_parameter(lower_id , lower_value )
+}
+
 // Export functions from sdk:component/plugin-api
+type ExportsSdkComponentPluginApiParameter = SdkComponentUiParameter
+type ExportsSdkComponentPluginApiLayout = SdkComponentUiLayout
+type ExportsSdkComponentPluginApiAction struct {
+  Id string
+  Name string
+  Shortcut Option[string]
+}
+
 var exports_sdk_component_plugin_api ExportsSdkComponentPluginApi = nil
 // `SetExportsSdkComponentPluginApi` sets the `ExportsSdkComponentPluginApi` interface implementation.
 // This function will need to be called by the init() function from the guest application.
@@ -44,6 +239,12 @@ func SetExportsSdkComponentPluginApi(i ExportsSdkComponentPluginApi) {
 type ExportsSdkComponentPluginApi interface {
   Enable() int32 
   Disable() int32 
+  Icon() []uint8 
+  Parameters() []ExportsSdkComponentPluginApiParameter 
+  Layout() Option[ExportsSdkComponentPluginApiLayout] 
+  ParameterChanged(id uint32, value float32) 
+  Actions() []ExportsSdkComponentPluginApiAction 
+  RunAction(id string) 
   ProcessSignal(ptr uint64) 
 }
 //export exports_sdk_component_plugin_api_enable
@@ -59,6 +260,247 @@ func exportsSdkComponentPluginApiDisable() C.int32_t {
   lower_result := C.int32_t(result)
   return lower_result
 
+}
+//export exports_sdk_component_plugin_api_icon
+func exportsSdkComponentPluginApiIcon(ret *C.plugin_list_u8_t) {
+  result := exports_sdk_component_plugin_api.Icon()
+  var lower_result C.plugin_list_u8_t
+  if len(result) == 0 {
+    lower_result.ptr = nil
+    lower_result.len = 0
+  } else {
+    var empty_lower_result C.uint8_t
+    lower_result.ptr = (*C.uint8_t)(C.malloc(C.size_t(len(result)) * C.size_t(unsafe.Sizeof(empty_lower_result))))
+    lower_result.len = C.size_t(len(result))
+    for lower_result_i := range result {
+      lower_result_ptr := (*C.uint8_t)(unsafe.Pointer(uintptr(unsafe.Pointer(lower_result.ptr)) +
+      uintptr(lower_result_i)*unsafe.Sizeof(empty_lower_result)))
+      lower_result_ptr_value := C.uint8_t(result[lower_result_i])
+      *lower_result_ptr = lower_result_ptr_value
+    }
+  }
+  *ret = lower_result
+
+}
+//export exports_sdk_component_plugin_api_parameters
+func exportsSdkComponentPluginApiParameters(ret *C.exports_sdk_component_plugin_api_list_parameter_t) {
+  result := exports_sdk_component_plugin_api.Parameters()
+  var lower_result C.exports_sdk_component_plugin_api_list_parameter_t
+  if len(result) == 0 {
+    lower_result.ptr = nil
+    lower_result.len = 0
+  } else {
+    var empty_lower_result C.exports_sdk_component_plugin_api_parameter_t
+    lower_result.ptr = (*C.exports_sdk_component_plugin_api_parameter_t)(C.malloc(C.size_t(len(result)) * C.size_t(unsafe.Sizeof(empty_lower_result))))
+    lower_result.len = C.size_t(len(result))
+    for lower_result_i := range result {
+      lower_result_ptr := (*C.exports_sdk_component_plugin_api_parameter_t)(unsafe.Pointer(uintptr(unsafe.Pointer(lower_result.ptr)) +
+      uintptr(lower_result_i)*unsafe.Sizeof(empty_lower_result)))
+      var lower_result_ptr_value C.exports_sdk_component_plugin_api_parameter_t
+      lower_result_ptr_value.id = C.uint32_t(result[lower_result_i].Id)
+      var lower_result_ptr_value_name C.plugin_string_t
+      // use unsafe.Pointer to avoid copy
+      lower_result_ptr_value_name.ptr = (*uint8)(unsafe.Pointer(C.CString(result[lower_result_i].Name)))
+      lower_result_ptr_value_name.len = C.size_t(len(result[lower_result_i].Name))
+      lower_result_ptr_value.name = lower_result_ptr_value_name
+      lower_result_ptr_value.min = C.float(result[lower_result_i].Min)
+      lower_result_ptr_value.max = C.float(result[lower_result_i].Max)
+      lower_result_ptr_value.default_value = C.float(result[lower_result_i].DefaultValue)
+      var lower_result_ptr_value_unit C.plugin_string_t
+      // use unsafe.Pointer to avoid copy
+      lower_result_ptr_value_unit.ptr = (*uint8)(unsafe.Pointer(C.CString(result[lower_result_i].Unit)))
+      lower_result_ptr_value_unit.len = C.size_t(len(result[lower_result_i].Unit))
+      lower_result_ptr_value.unit = lower_result_ptr_value_unit
+      *lower_result_ptr = lower_result_ptr_value
+    }
+  }
+  *ret = lower_result
+
+}
+//export exports_sdk_component_plugin_api_layout
+func exportsSdkComponentPluginApiLayout(ret *C.exports_sdk_component_plugin_api_layout_t) C.bool {
+  result := exports_sdk_component_plugin_api.Layout()
+  if result.IsNone() {
+    return false
+  }
+  lower_layout_value := result.Unwrap()
+  var lower_layout C.exports_sdk_component_plugin_api_layout_t
+  var lower_layout_title C.plugin_string_t
+  // use unsafe.Pointer to avoid copy
+  lower_layout_title.ptr = (*uint8)(unsafe.Pointer(C.CString(lower_layout_value.Title)))
+  lower_layout_title.len = C.size_t(len(lower_layout_value.Title))
+  lower_layout.title = lower_layout_title
+  lower_layout.width = C.uint32_t(lower_layout_value.Width)
+  lower_layout.height = C.uint32_t(lower_layout_value.Height)
+  var lower_nodes C.sdk_component_ui_list_node_t
+  if len(lower_layout_value.Nodes) == 0 {
+    lower_nodes.ptr = nil
+    lower_nodes.len = 0
+  } else {
+    var empty_lower_nodes C.sdk_component_ui_node_t
+    lower_nodes.ptr = (*C.sdk_component_ui_node_t)(C.malloc(C.size_t(len(lower_layout_value.Nodes)) * C.size_t(unsafe.Sizeof(empty_lower_nodes))))
+    lower_nodes.len = C.size_t(len(lower_layout_value.Nodes))
+    for lower_nodes_i := range lower_layout_value.Nodes {
+      lower_nodes_ptr := (*C.sdk_component_ui_node_t)(unsafe.Pointer(uintptr(unsafe.Pointer(lower_nodes.ptr)) +
+      uintptr(lower_nodes_i)*unsafe.Sizeof(empty_lower_nodes)))
+      var lower_node C.sdk_component_ui_node_t
+      lower_node_value := lower_layout_value.Nodes[lower_nodes_i].Widget
+      switch lower_node_value.Kind() {
+      case SdkComponentUiWidgetKindRow:
+        lower_node.widget.tag = 0
+
+      case SdkComponentUiWidgetKindColumn:
+        lower_node.widget.tag = 1
+
+      case SdkComponentUiWidgetKindGroup:
+        lower_node.widget.tag = 2
+        var lower_node_group C.plugin_string_t
+        // use unsafe.Pointer to avoid copy
+        lower_node_group.ptr = (*uint8)(unsafe.Pointer(C.CString(lower_node_value.GetGroup())))
+        lower_node_group.len = C.size_t(len(lower_node_value.GetGroup()))
+        *(*C.plugin_string_t)(unsafe.Pointer(&lower_node.widget.val)) = lower_node_group
+
+      case SdkComponentUiWidgetKindKnob:
+        lower_node.widget.tag = 3
+        *(*C.uint32_t)(unsafe.Pointer(&lower_node.widget.val)) = C.uint32_t(lower_node_value.GetKnob())
+
+      case SdkComponentUiWidgetKindSlider:
+        lower_node.widget.tag = 4
+        *(*C.uint32_t)(unsafe.Pointer(&lower_node.widget.val)) = C.uint32_t(lower_node_value.GetSlider())
+
+      case SdkComponentUiWidgetKindToggle:
+        lower_node.widget.tag = 5
+        *(*C.uint32_t)(unsafe.Pointer(&lower_node.widget.val)) = C.uint32_t(lower_node_value.GetToggle())
+
+      case SdkComponentUiWidgetKindMeter:
+        lower_node.widget.tag = 6
+        *(*C.uint32_t)(unsafe.Pointer(&lower_node.widget.val)) = C.uint32_t(lower_node_value.GetMeter())
+
+      case SdkComponentUiWidgetKindLabel:
+        lower_node.widget.tag = 7
+        var lower_node_label C.plugin_string_t
+        // use unsafe.Pointer to avoid copy
+        lower_node_label.ptr = (*uint8)(unsafe.Pointer(C.CString(lower_node_value.GetLabel())))
+        lower_node_label.len = C.size_t(len(lower_node_value.GetLabel()))
+        *(*C.plugin_string_t)(unsafe.Pointer(&lower_node.widget.val)) = lower_node_label
+
+      case SdkComponentUiWidgetKindImage:
+        lower_node.widget.tag = 8
+        var lower_node_image C.plugin_string_t
+        // use unsafe.Pointer to avoid copy
+        lower_node_image.ptr = (*uint8)(unsafe.Pointer(C.CString(lower_node_value.GetImage())))
+        lower_node_image.len = C.size_t(len(lower_node_value.GetImage()))
+        *(*C.plugin_string_t)(unsafe.Pointer(&lower_node.widget.val)) = lower_node_image
+      default:
+        panic("unreachable")
+      }
+      if lower_layout_value.Nodes[lower_nodes_i].Parent.IsSome() {
+        lower_node.parent.is_some = true
+        lower_node.parent.val = C.uint32_t(lower_layout_value.Nodes[lower_nodes_i].Parent.Unwrap())
+      } else {
+        lower_node.parent.is_some = false
+      }
+      *lower_nodes_ptr = lower_node
+    }
+  }
+  lower_layout.nodes = lower_nodes
+  var lower_images C.sdk_component_ui_list_image_t
+  if len(lower_layout_value.Images) == 0 {
+    lower_images.ptr = nil
+    lower_images.len = 0
+  } else {
+    var empty_lower_images C.sdk_component_ui_image_t
+    lower_images.ptr = (*C.sdk_component_ui_image_t)(C.malloc(C.size_t(len(lower_layout_value.Images)) * C.size_t(unsafe.Sizeof(empty_lower_images))))
+    lower_images.len = C.size_t(len(lower_layout_value.Images))
+    for lower_images_i := range lower_layout_value.Images {
+      lower_images_ptr := (*C.sdk_component_ui_image_t)(unsafe.Pointer(uintptr(unsafe.Pointer(lower_images.ptr)) +
+      uintptr(lower_images_i)*unsafe.Sizeof(empty_lower_images)))
+      var lower_image C.sdk_component_ui_image_t
+      var lower_image_name C.plugin_string_t
+      // use unsafe.Pointer to avoid copy
+      lower_image_name.ptr = (*uint8)(unsafe.Pointer(C.CString(lower_layout_value.Images[lower_images_i].Name)))
+      lower_image_name.len = C.size_t(len(lower_layout_value.Images[lower_images_i].Name))
+      lower_image.name = lower_image_name
+      var lower_image_data C.plugin_list_u8_t
+      if len(lower_layout_value.Images[lower_images_i].Data) == 0 {
+        lower_image_data.ptr = nil
+        lower_image_data.len = 0
+      } else {
+        var empty_lower_image_data C.uint8_t
+        lower_image_data.ptr = (*C.uint8_t)(C.malloc(C.size_t(len(lower_layout_value.Images[lower_images_i].Data)) * C.size_t(unsafe.Sizeof(empty_lower_image_data))))
+        lower_image_data.len = C.size_t(len(lower_layout_value.Images[lower_images_i].Data))
+        for lower_image_data_i := range lower_layout_value.Images[lower_images_i].Data {
+          lower_image_data_ptr := (*C.uint8_t)(unsafe.Pointer(uintptr(unsafe.Pointer(lower_image_data.ptr)) +
+          uintptr(lower_image_data_i)*unsafe.Sizeof(empty_lower_image_data)))
+          *lower_image_data_ptr = C.uint8_t(lower_layout_value.Images[lower_images_i].Data[lower_image_data_i])
+        }
+      }
+      lower_image.data = lower_image_data
+      *lower_images_ptr = lower_image
+    }
+  }
+  lower_layout.images = lower_images
+  *ret = lower_layout
+  return true
+
+}
+//export exports_sdk_component_plugin_api_parameter_changed
+func exportsSdkComponentPluginApiParameterChanged(id C.uint32_t, value C.float) {
+  var lift_id uint32
+  lift_id = uint32(id)
+  var lift_value float32
+  lift_value = float32(value)
+  exports_sdk_component_plugin_api.ParameterChanged(lift_id, lift_value)
+
+}
+//export exports_sdk_component_plugin_api_actions
+func exportsSdkComponentPluginApiActions(ret *C.exports_sdk_component_plugin_api_list_action_t) {
+  result := exports_sdk_component_plugin_api.Actions()
+  var lower_result C.exports_sdk_component_plugin_api_list_action_t
+  if len(result) == 0 {
+    lower_result.ptr = nil
+    lower_result.len = 0
+  } else {
+    var empty_lower_result C.exports_sdk_component_plugin_api_action_t
+    lower_result.ptr = (*C.exports_sdk_component_plugin_api_action_t)(C.malloc(C.size_t(len(result)) * C.size_t(unsafe.Sizeof(empty_lower_result))))
+    lower_result.len = C.size_t(len(result))
+    for lower_result_i := range result {
+      lower_result_ptr := (*C.exports_sdk_component_plugin_api_action_t)(unsafe.Pointer(uintptr(unsafe.Pointer(lower_result.ptr)) +
+      uintptr(lower_result_i)*unsafe.Sizeof(empty_lower_result)))
+      var lower_result_ptr_value C.exports_sdk_component_plugin_api_action_t
+      var lower_result_ptr_value_id C.plugin_string_t
+      // use unsafe.Pointer to avoid copy
+      lower_result_ptr_value_id.ptr = (*uint8)(unsafe.Pointer(C.CString(result[lower_result_i].Id)))
+      lower_result_ptr_value_id.len = C.size_t(len(result[lower_result_i].Id))
+      lower_result_ptr_value.id = lower_result_ptr_value_id
+      var lower_result_ptr_value_name C.plugin_string_t
+      // use unsafe.Pointer to avoid copy
+      lower_result_ptr_value_name.ptr = (*uint8)(unsafe.Pointer(C.CString(result[lower_result_i].Name)))
+      lower_result_ptr_value_name.len = C.size_t(len(result[lower_result_i].Name))
+      lower_result_ptr_value.name = lower_result_ptr_value_name
+      if result[lower_result_i].Shortcut.IsSome() {
+        var lower_result_ptr_value_shortcut C.plugin_string_t
+        // use unsafe.Pointer to avoid copy
+        lower_result_ptr_value_shortcut.ptr = (*uint8)(unsafe.Pointer(C.CString(result[lower_result_i].Shortcut.Unwrap())))
+        lower_result_ptr_value_shortcut.len = C.size_t(len(result[lower_result_i].Shortcut.Unwrap()))
+        lower_result_ptr_value.shortcut.is_some = true
+        lower_result_ptr_value.shortcut.val = lower_result_ptr_value_shortcut
+      } else {
+        lower_result_ptr_value.shortcut.is_some = false
+      }
+      *lower_result_ptr = lower_result_ptr_value
+    }
+  }
+  *ret = lower_result
+
+}
+//export exports_sdk_component_plugin_api_run_action
+func exportsSdkComponentPluginApiRunAction(id *C.plugin_string_t) {
+  defer C.plugin_string_free(id)
+  var lift_id string
+  lift_id = C.GoStringN((*C.char)(unsafe.Pointer(id.ptr)), C.int(id.len))
+  exports_sdk_component_plugin_api.RunAction(lift_id)
+
 }
 //export exports_sdk_component_plugin_api_process_signal
 func exportsSdkComponentPluginApiProcessSignal(ptr C.uint64_t) {
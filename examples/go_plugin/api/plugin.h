@@ -13,20 +13,172 @@ typedef struct plugin_string_t {
   size_t len;
 } plugin_string_t;
 
+typedef struct sdk_component_ui_parameter_t {
+  uint32_t   id;
+  plugin_string_t   name;
+  float   min;
+  float   max;
+  float   default_value;
+  // Shown after the value, e.g. "dB"
+  plugin_string_t   unit;
+} sdk_component_ui_parameter_t;
+
+typedef struct sdk_component_ui_widget_t {
+  uint8_t tag;
+  union {
+    plugin_string_t     group;
+    uint32_t     knob;
+    uint32_t     slider;
+    uint32_t     toggle;
+    uint32_t     meter;
+    plugin_string_t     label;
+    plugin_string_t     image;
+  } val;
+} sdk_component_ui_widget_t;
+
+// Lays out its children horizontally
+#define SDK_COMPONENT_UI_WIDGET_ROW 0
+// Lays out its children vertically
+#define SDK_COMPONENT_UI_WIDGET_COLUMN 1
+// Frame with a title around its children
+#define SDK_COMPONENT_UI_WIDGET_GROUP 2
+#define SDK_COMPONENT_UI_WIDGET_KNOB 3
+#define SDK_COMPONENT_UI_WIDGET_SLIDER 4
+// On above the middle of the parameter range
+#define SDK_COMPONENT_UI_WIDGET_TOGGLE 5
+// Read only level of a parameter, usually set by the plugin with `set-parameter`
+#define SDK_COMPONENT_UI_WIDGET_METER 6
+#define SDK_COMPONENT_UI_WIDGET_LABEL 7
+// Name of an image of the layout
+#define SDK_COMPONENT_UI_WIDGET_IMAGE 8
+
+typedef struct plugin_option_u32_t {
+  bool is_some;
+  uint32_t val;
+} plugin_option_u32_t;
+
+// Widgets form a tree, flattened into a list since WIT types can't be recursive
+typedef struct sdk_component_ui_node_t {
+  sdk_component_ui_widget_t   widget;
+  // Index of the row, column or group containing the widget, none for the top level
+  plugin_option_u32_t   parent;
+} sdk_component_ui_node_t;
+
+typedef struct plugin_list_u8_t {
+  uint8_t *ptr;
+  size_t len;
+} plugin_list_u8_t;
+
+typedef struct sdk_component_ui_image_t {
+  plugin_string_t   name;
+  // Encoded in any common image format
+  plugin_list_u8_t   data;
+} sdk_component_ui_image_t;
+
+typedef struct sdk_component_ui_list_node_t {
+  sdk_component_ui_node_t *ptr;
+  size_t len;
+} sdk_component_ui_list_node_t;
+
+typedef struct sdk_component_ui_list_image_t {
+  sdk_component_ui_image_t *ptr;
+  size_t len;
+} sdk_component_ui_list_image_t;
+
+typedef struct sdk_component_ui_layout_t {
+  plugin_string_t   title;
+  uint32_t   width;
+  uint32_t   height;
+  sdk_component_ui_list_node_t   nodes;
+  sdk_component_ui_list_image_t   images;
+} sdk_component_ui_layout_t;
+
+typedef sdk_component_ui_parameter_t exports_sdk_component_plugin_api_parameter_t;
+
+typedef sdk_component_ui_layout_t exports_sdk_component_plugin_api_layout_t;
+
+typedef struct plugin_option_string_t {
+  bool is_some;
+  plugin_string_t val;
+} plugin_option_string_t;
+
+// Command the user can run from a key binding
+typedef struct exports_sdk_component_plugin_api_action_t {
+  plugin_string_t   id;
+  plugin_string_t   name;
+  // Default binding like "Ctrl+Shift+K", the user can change it in the settings
+  plugin_option_string_t   shortcut;
+} exports_sdk_component_plugin_api_action_t;
+
+typedef struct exports_sdk_component_plugin_api_list_parameter_t {
+  exports_sdk_component_plugin_api_parameter_t *ptr;
+  size_t len;
+} exports_sdk_component_plugin_api_list_parameter_t;
+
+typedef struct exports_sdk_component_plugin_api_option_layout_t {
+  bool is_some;
+  exports_sdk_component_plugin_api_layout_t val;
+} exports_sdk_component_plugin_api_option_layout_t;
+
+typedef struct exports_sdk_component_plugin_api_list_action_t {
+  exports_sdk_component_plugin_api_action_t *ptr;
+  size_t len;
+} exports_sdk_component_plugin_api_list_action_t;
+
 // Imported Functions from `sdk:component/logger`
 extern void sdk_component_logger_log(plugin_string_t *text);
 
 // Imported Functions from `sdk:component/registry`
 extern double sdk_component_registry_get_signal(uint64_t idx);
 extern double sdk_component_registry_set_signal(uint64_t idx, double val);
+extern float sdk_component_registry_get_parameter(uint32_t id);
+extern void sdk_component_registry_set_parameter(uint32_t id, float value);
 
 // Exported Functions from `sdk:component/plugin-api`
 int32_t exports_sdk_component_plugin_api_enable(void);
 int32_t exports_sdk_component_plugin_api_disable(void);
+void exports_sdk_component_plugin_api_icon(plugin_list_u8_t *ret);
+void exports_sdk_component_plugin_api_parameters(exports_sdk_component_plugin_api_list_parameter_t *ret);
+bool exports_sdk_component_plugin_api_layout(exports_sdk_component_plugin_api_layout_t *ret);
+void exports_sdk_component_plugin_api_parameter_changed(uint32_t id, float value);
+void exports_sdk_component_plugin_api_actions(exports_sdk_component_plugin_api_list_action_t *ret);
+void exports_sdk_component_plugin_api_run_action(plugin_string_t *id);
 void exports_sdk_component_plugin_api_process_signal(uint64_t ptr);
 
 // Helper Functions
 
+void sdk_component_ui_parameter_free(sdk_component_ui_parameter_t *ptr);
+
+void sdk_component_ui_widget_free(sdk_component_ui_widget_t *ptr);
+
+void plugin_option_u32_free(plugin_option_u32_t *ptr);
+
+void sdk_component_ui_node_free(sdk_component_ui_node_t *ptr);
+
+void plugin_list_u8_free(plugin_list_u8_t *ptr);
+
+void sdk_component_ui_image_free(sdk_component_ui_image_t *ptr);
+
+void sdk_component_ui_list_node_free(sdk_component_ui_list_node_t *ptr);
+
+void sdk_component_ui_list_image_free(sdk_component_ui_list_image_t *ptr);
+
+void sdk_component_ui_layout_free(sdk_component_ui_layout_t *ptr);
+
+void exports_sdk_component_plugin_api_parameter_free(exports_sdk_component_plugin_api_parameter_t *ptr);
+
+void exports_sdk_component_plugin_api_layout_free(exports_sdk_component_plugin_api_layout_t *ptr);
+
+void plugin_option_string_free(plugin_option_string_t *ptr);
+
+void exports_sdk_component_plugin_api_action_free(exports_sdk_component_plugin_api_action_t *ptr);
+
+void exports_sdk_component_plugin_api_list_parameter_free(exports_sdk_component_plugin_api_list_parameter_t *ptr);
+
+void exports_sdk_component_plugin_api_option_layout_free(exports_sdk_component_plugin_api_option_layout_t *ptr);
+
+void exports_sdk_component_plugin_api_list_action_free(exports_sdk_component_plugin_api_list_action_t *ptr);
+
 // Transfers ownership of `s` into the string `ret`
 void plugin_string_set(plugin_string_t *ret, const char*s);
 
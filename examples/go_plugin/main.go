@@ -1,9 +1,21 @@
 package main
 
 import (
+    _ "embed"
+    "fmt"
+
     . "example.com/api"
 )
 
+const (
+    Gain uint32 = iota
+    Bypass
+    Level
+)
+
+//go:embed assets/32.png
+var icon []byte
+
 type PluginApiImpl struct {
 }
 
@@ -15,7 +27,65 @@ func (i PluginApiImpl) Disable() int32 {
     return 10
 }
 
+func (i PluginApiImpl) Icon() []uint8 {
+    return icon
+}
+
+func (i PluginApiImpl) Parameters() []ExportsSdkComponentPluginApiParameter {
+    return []ExportsSdkComponentPluginApiParameter{
+        {Id: Gain, Name: "Gain", Min: -24, Max: 24, DefaultValue: 0, Unit: "dB"},
+        {Id: Bypass, Name: "Bypass", Min: 0, Max: 1, DefaultValue: 0, Unit: ""},
+        {Id: Level, Name: "Level", Min: 0, Max: 1, DefaultValue: 0, Unit: ""},
+    }
+}
+
+func (i PluginApiImpl) Layout() Option[ExportsSdkComponentPluginApiLayout] {
+    // Parents are indices into the node list
+    row := Some[uint32](0)
+    group := Some[uint32](2)
+
+    return Some(ExportsSdkComponentPluginApiLayout{
+        Title:  "Go Plugin",
+        Width:  320,
+        Height: 200,
+        Nodes: []SdkComponentUiNode{
+            {Widget: SdkComponentUiWidgetRow(), Parent: None[uint32]()},
+            {Widget: SdkComponentUiWidgetImage("logo"), Parent: row},
+            {Widget: SdkComponentUiWidgetGroup("Output"), Parent: row},
+            {Widget: SdkComponentUiWidgetKnob(Gain), Parent: group},
+            {Widget: SdkComponentUiWidgetToggle(Bypass), Parent: group},
+            {Widget: SdkComponentUiWidgetMeter(Level), Parent: row},
+        },
+        Images: []SdkComponentUiImage{
+            {Name: "logo", Data: icon},
+        },
+    })
+}
+
+func (i PluginApiImpl) ParameterChanged(id uint32, value float32) {
+    SdkComponentLoggerLog(fmt.Sprintf("Parameter %d changed to %f", id, value))
+}
+
+func (i PluginApiImpl) Actions() []ExportsSdkComponentPluginApiAction {
+    return []ExportsSdkComponentPluginApiAction{
+        {Id: "bypass", Name: "Toggle Bypass", Shortcut: Some("Ctrl+B")},
+    }
+}
+
+func (i PluginApiImpl) RunAction(id string) {
+    if id == "bypass" {
+        bypass := SdkComponentRegistryGetParameter(Bypass)
+        SdkComponentRegistrySetParameter(Bypass, 1-bypass)
+    }
+}
+
 func (i PluginApiImpl) ProcessSignal(ptr uint64) {
+    signal := SdkComponentRegistryGetSignal(0)
+
+    if signal < 0 {
+        signal = -signal
+    }
+    SdkComponentRegistrySetParameter(Level, float32(signal))
 }
 
 func init() {
@@ -23,4 +93,4 @@ func init() {
     SetExportsSdkComponentPluginApi(example)
 }
 
-func main() {}
\ No newline at end of file
+func main() {}
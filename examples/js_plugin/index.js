@@ -12,5 +12,25 @@ export const pluginApi = {
     processSignal(ptr) {
         console.log("Process Signal!!!");
         return 1;
+    },
+
+    parameters() {
+        return [];
+    },
+
+    layout() {
+        return undefined;
+    },
+
+    parameterChanged(id, value) {
+        console.log(`Parameter ${id} changed to ${value}`);
+    },
+
+    actions() {
+        return [];
+    },
+
+    runAction(id) {
+        console.log(`Action ${id}`);
     }
 }
\ No newline at end of file
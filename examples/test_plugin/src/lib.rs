@@ -1,7 +1,12 @@
 use std::collections::HashMap;
-use voxea_plugin::{logger, registry, VoxeaPlugin};
+use voxea_plugin::ui::{Layout, Parameter, Widget};
+use voxea_plugin::{logger, registry, Action, LayoutBuilder, VoxeaPlugin};
 struct MyPlugin;
 
+const GAIN: u32 = 0;
+const BYPASS: u32 = 1;
+const LEVEL: u32 = 2;
+
 impl VoxeaPlugin for MyPlugin {
     fn icon() -> Vec<u8> {
         let file = include_bytes!("../assets/32.png");
@@ -27,6 +32,60 @@ impl VoxeaPlugin for MyPlugin {
             let log = format!("Process Signal at {:?} is: {}", ptr as *const u64, signal);
 
             logger::log(&log);
+
+            registry::set_parameter(LEVEL, signal.abs() as f32);
+        }
+    }
+
+    fn parameters() -> Vec<Parameter> {
+        let parameter = |id, name: &str, min, max, default_value, unit: &str| Parameter {
+            id,
+            name: name.to_string(),
+            min,
+            max,
+            default_value,
+            unit: unit.to_string(),
+        };
+
+        vec![
+            parameter(GAIN, "Gain", -24.0, 24.0, 0.0, "dB"),
+            parameter(BYPASS, "Bypass", 0.0, 1.0, 0.0, ""),
+            parameter(LEVEL, "Level", 0.0, 1.0, 0.0, ""),
+        ]
+    }
+
+    fn layout() -> Option<Layout> {
+        let layout = LayoutBuilder::new("Test Plugin", 320, 200)
+            .image("logo", include_bytes!("../assets/32.png").to_vec())
+            .begin(Widget::Row)
+            .add(Widget::Image("logo".to_string()))
+            .begin(Widget::Group("Output".to_string()))
+            .add(Widget::Knob(GAIN))
+            .add(Widget::Toggle(BYPASS))
+            .end()
+            .add(Widget::Meter(LEVEL))
+            .end()
+            .build();
+
+        Some(layout)
+    }
+
+    fn parameter_changed(id: u32, value: f32) {
+        logger::log(&format!("Parameter {id} changed to {value}"));
+    }
+
+    fn actions() -> Vec<Action> {
+        vec![Action {
+            id: "bypass".to_string(),
+            name: "Toggle Bypass".to_string(),
+            shortcut: Some("Ctrl+B".to_string()),
+        }]
+    }
+
+    fn run_action(id: String) {
+        if id == "bypass" {
+            let bypass = registry::get_parameter(BYPASS);
+            registry::set_parameter(BYPASS, 1.0 - bypass);
         }
     }
 }
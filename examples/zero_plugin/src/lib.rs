@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use voxea_plugin::{logger, registry, VoxeaPlugin};
+use voxea_plugin::ui::{Layout, Parameter};
+use voxea_plugin::{logger, registry, Action, VoxeaPlugin};
 struct MyPlugin;
 
 impl VoxeaPlugin for MyPlugin {
@@ -24,5 +25,21 @@ impl VoxeaPlugin for MyPlugin {
             logger::log(&log);
         }
     }
+
+    fn parameters() -> Vec<Parameter> {
+        Vec::new()
+    }
+
+    fn layout() -> Option<Layout> {
+        None
+    }
+
+    fn parameter_changed(_id: u32, _value: f32) {}
+
+    fn actions() -> Vec<Action> {
+        Vec::new()
+    }
+
+    fn run_action(_id: String) {}
 }
 voxea_plugin::export!(MyPlugin with_types_in voxea_plugin::bindings);